@@ -3,6 +3,7 @@ import {
     InMemoryCache,
     HttpLink,
     ApolloLink,
+    defaultDataIdFromObject,
 } from "@apollo/client";
 import { onError } from "@apollo/client/link/error";
 
@@ -37,6 +38,36 @@ const errorLink = onError(({ graphQLErrors, networkError }) => {
     }
 });
 
+// Entities implementing the Relay `Node` interface carry globally unique ids
+// (base64 of "Type:id"), so the id alone is a stable cache key.
+const cache = new InMemoryCache({
+    possibleTypes: {
+        Node: ["Tour", "Place"],
+    },
+    dataIdFromObject: (object) =>
+        typeof object.id === "string"
+            ? object.id
+            : defaultDataIdFromObject(object),
+    typePolicies: {
+        Query: {
+            fields: {
+                node: {
+                    read: (existing, { args, toReference, canRead }) => {
+                        const reference = toReference(args?.id);
+                        return canRead(reference) ? reference : existing;
+                    },
+                },
+                tour: {
+                    read: (existing, { args, toReference, canRead }) => {
+                        const reference = toReference(args?.id);
+                        return canRead(reference) ? reference : existing;
+                    },
+                },
+            },
+        },
+    },
+});
+
 // Create the Apollo Client instance with the links
 const client = new ApolloClient({
     link: ApolloLink.from([
@@ -44,7 +75,7 @@ const client = new ApolloClient({
         errorLink,
         new HttpLink({ uri: "http://localhost:8000/graphql" }),
     ]),
-    cache: new InMemoryCache(),
+    cache,
 });
 
 export default client;
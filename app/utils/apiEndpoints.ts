@@ -6,11 +6,16 @@ const GET_TOURS = gql`
     query GetTours {
         getTours {
             id
+            databaseId
+            slug
             title
             description
             startDate
             endDate
-            price
+            price {
+                amount
+                currency
+            }
             rating
             createdAt
             updatedAt
@@ -22,36 +27,38 @@ const GET_TOURS = gql`
     }
 `;
 
+// Define the GET_TOUR_BY_SLUG query
+const GET_TOUR_BY_SLUG = gql`
+    query GetTourBySlug($slug: String!) {
+        tourBySlug(slug: $slug) {
+            id
+            databaseId
+            slug
+            title
+            description
+            startDate
+            endDate
+            price {
+                amount
+                currency
+            }
+            rating
+            location
+            imageUrl
+            isActive
+            maxParticipants
+        }
+    }
+`;
+
 // Define the CREATE_TOUR mutation
 const CREATE_TOUR = gql`
-    mutation CreateTour(
-        $title: String!
-        $description: String
-        $start_date: String
-        $end_date: String
-        $price: Float
-        $rating: Float
-        $location: String
-        $image_url: String
-        $is_active: Boolean!
-        $max_participants: Int
-    ) {
-        createTour(
-            title: $title
-            description: $description
-            start_date: $start_date
-            end_date: $end_date
-            price: $price
-            rating: $rating
-            location: $location
-            image_url: $image_url
-            is_active: $is_active
-            max_participants: $max_participants
-        ) {
+    mutation CreateTour($input: CreateTourInput!) {
+        createTour(input: $input) {
             id
             title
-            created_at
-            updated_at
+            createdAt
+            updatedAt
         }
     }
 `;
@@ -68,13 +75,27 @@ export const fetchTours = async () => {
     }
 };
 
+// Fetch a single tour by its slug, or null when it does not exist
+export const fetchTourBySlug = async (slug: string) => {
+    try {
+        const { data } = await client.query({
+            query: GET_TOUR_BY_SLUG,
+            variables: { slug },
+        });
+        return data.tourBySlug;
+    } catch (error) {
+        console.error("Error fetching tour:", error);
+        throw error;
+    }
+};
+
 /*
 // Create tour function
 export const createTour = async (tourInput) => {
     try {
         const { data } = await client.mutate({
             mutation: CREATE_TOUR,
-            variables: tourInput,
+            variables: { input: tourInput },
         });
         console.log("Created tour: ", data.createTour);
         return data.createTour;
@@ -84,3 +105,33 @@ export const createTour = async (tourInput) => {
     }
 };
 */
+
+export interface Suggestion {
+    kind: "place" | "tour";
+    text: string;
+    place_id: number | null;
+    tour_id: number | null;
+    score: number;
+}
+
+// Fetch place and tour title suggestions for autocomplete inputs
+export const fetchSuggestions = async (
+    prefix: string,
+    kind?: Suggestion["kind"],
+    signal?: AbortSignal
+): Promise<Suggestion[]> => {
+    const params = new URLSearchParams({ prefix });
+    if (kind) {
+        params.set("kind", kind);
+    }
+
+    const response = await fetch(
+        `http://localhost:8000/api/suggest?${params.toString()}`,
+        { signal }
+    );
+    if (!response.ok) {
+        throw new Error(`Failed to fetch suggestions: ${response.status}`);
+    }
+
+    return response.json();
+};
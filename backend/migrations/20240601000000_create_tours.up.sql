@@ -0,0 +1,24 @@
+CREATE TABLE tours (
+    id SERIAL PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    start_date DATE,
+    end_date DATE,
+    price DOUBLE PRECISION,
+    rating DOUBLE PRECISION,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    location TEXT,
+    image_url TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    max_participants INTEGER,
+
+    CONSTRAINT tours_title_not_blank CHECK (btrim(title) <> ''),
+    CONSTRAINT tours_date_range CHECK (end_date IS NULL OR start_date IS NULL OR end_date >= start_date),
+    CONSTRAINT tours_price_non_negative CHECK (price IS NULL OR price >= 0),
+    CONSTRAINT tours_rating_range CHECK (rating IS NULL OR rating BETWEEN 0 AND 5),
+    CONSTRAINT tours_max_participants_positive CHECK (max_participants IS NULL OR max_participants > 0)
+);
+
+CREATE INDEX tours_is_active_start_date_idx ON tours (is_active, start_date);
+CREATE INDEX tours_created_at_idx ON tours (created_at);
@@ -0,0 +1,3 @@
+-- The copied rows are removed together with the tours table; public."Tours" is
+-- never modified, so there is nothing to restore.
+SELECT 1;
@@ -0,0 +1,21 @@
+-- Tours used to live in the legacy public."Tours" table. Its rows are copied
+-- over with their ids so existing links keep working; the legacy table is left
+-- in place and can be dropped by hand once the copy has been checked.
+DO $$
+BEGIN
+    IF to_regclass('public."Tours"') IS NOT NULL THEN
+        INSERT INTO tours (
+            id, title, description, start_date, end_date, price, rating,
+            created_at, updated_at, location, image_url, is_active, max_participants
+        )
+        SELECT id, title, description, start_date, end_date, price, rating,
+               COALESCE(created_at, now() AT TIME ZONE 'utc'),
+               COALESCE(updated_at, created_at, now() AT TIME ZONE 'utc'),
+               location, image_url, is_active, max_participants
+        FROM public."Tours";
+
+        PERFORM setval(pg_get_serial_sequence('tours', 'id'), COALESCE(MAX(id), 0) + 1, false)
+        FROM tours;
+    END IF;
+END
+$$;
@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS tours_location_trgm_idx;
+DROP INDEX IF EXISTS tours_end_date_idx;
+DROP INDEX IF EXISTS tours_start_date_id_idx;
+DROP INDEX IF EXISTS tours_rating_id_idx;
+DROP INDEX IF EXISTS tours_price_id_idx;
+DROP INDEX IF EXISTS tours_created_at_id_idx;
+
+CREATE INDEX IF NOT EXISTS tours_created_at_idx ON tours (created_at);
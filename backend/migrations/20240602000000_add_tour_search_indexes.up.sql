@@ -0,0 +1,10 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+DROP INDEX IF EXISTS tours_created_at_idx;
+
+CREATE INDEX IF NOT EXISTS tours_created_at_id_idx ON tours (created_at, id);
+CREATE INDEX IF NOT EXISTS tours_price_id_idx ON tours (price, id);
+CREATE INDEX IF NOT EXISTS tours_rating_id_idx ON tours (rating, id);
+CREATE INDEX IF NOT EXISTS tours_start_date_id_idx ON tours (start_date, id);
+CREATE INDEX IF NOT EXISTS tours_end_date_idx ON tours (end_date);
+CREATE INDEX IF NOT EXISTS tours_location_trgm_idx ON tours USING GIN (location gin_trgm_ops);
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS tours_language_idx;
+DROP INDEX IF EXISTS tours_search_vector_idx;
+
+ALTER TABLE tours DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE tours DROP COLUMN IF EXISTS language;
+
+DROP FUNCTION IF EXISTS tours_text_search_config(TEXT);
@@ -0,0 +1,21 @@
+-- Text search configurations are resolved by name, so casting is only stable as
+-- long as nobody renames a configuration. That is good enough to let the
+-- generated column below depend on a per-tour language.
+CREATE OR REPLACE FUNCTION tours_text_search_config(language TEXT)
+RETURNS regconfig
+LANGUAGE sql
+IMMUTABLE STRICT PARALLEL SAFE
+AS $$ SELECT language::regconfig $$;
+
+ALTER TABLE tours
+    ADD COLUMN language TEXT NOT NULL DEFAULT 'english';
+
+ALTER TABLE tours
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector(tours_text_search_config(language), coalesce(title, '')), 'A') ||
+        setweight(to_tsvector(tours_text_search_config(language), coalesce(description, '')), 'B') ||
+        setweight(to_tsvector(tours_text_search_config(language), coalesce(location, '')), 'C')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS tours_search_vector_idx ON tours USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS tours_language_idx ON tours (language);
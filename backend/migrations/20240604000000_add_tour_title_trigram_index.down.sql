@@ -0,0 +1 @@
+DROP INDEX IF EXISTS tours_title_trgm_idx;
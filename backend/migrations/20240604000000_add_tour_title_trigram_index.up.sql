@@ -0,0 +1,3 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS tours_title_trgm_idx ON tours USING GIN (title gin_trgm_ops);
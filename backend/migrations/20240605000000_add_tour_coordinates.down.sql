@@ -0,0 +1,10 @@
+DROP FUNCTION IF EXISTS haversine_km(DOUBLE PRECISION, DOUBLE PRECISION, DOUBLE PRECISION, DOUBLE PRECISION);
+
+DROP INDEX IF EXISTS tours_latitude_longitude_idx;
+
+ALTER TABLE tours
+    DROP CONSTRAINT IF EXISTS tours_coordinates_pair,
+    DROP CONSTRAINT IF EXISTS tours_longitude_range,
+    DROP CONSTRAINT IF EXISTS tours_latitude_range,
+    DROP COLUMN IF EXISTS longitude,
+    DROP COLUMN IF EXISTS latitude;
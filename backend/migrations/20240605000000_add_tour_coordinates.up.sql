@@ -0,0 +1,27 @@
+ALTER TABLE tours
+    ADD COLUMN latitude DOUBLE PRECISION,
+    ADD COLUMN longitude DOUBLE PRECISION,
+    ADD CONSTRAINT tours_latitude_range CHECK (latitude IS NULL OR latitude BETWEEN -90 AND 90),
+    ADD CONSTRAINT tours_longitude_range CHECK (longitude IS NULL OR longitude BETWEEN -180 AND 180),
+    ADD CONSTRAINT tours_coordinates_pair CHECK ((latitude IS NULL) = (longitude IS NULL));
+
+CREATE INDEX IF NOT EXISTS tours_latitude_longitude_idx ON tours (latitude, longitude);
+
+-- Great-circle distance on a spherical earth; accurate to ~0.5% which is plenty
+-- for "tours near me" and keeps us off PostGIS.
+CREATE OR REPLACE FUNCTION haversine_km(
+    latitude1 DOUBLE PRECISION,
+    longitude1 DOUBLE PRECISION,
+    latitude2 DOUBLE PRECISION,
+    longitude2 DOUBLE PRECISION
+)
+RETURNS DOUBLE PRECISION
+LANGUAGE sql
+IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT 2 * 6371.0088 * asin(LEAST(1, sqrt(
+        power(sin(radians(latitude2 - latitude1) / 2), 2) +
+        cos(radians(latitude1)) * cos(radians(latitude2)) *
+        power(sin(radians(longitude2 - longitude1) / 2), 2)
+    )))
+$$;
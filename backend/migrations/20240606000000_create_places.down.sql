@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS tours_place_id_idx;
+
+ALTER TABLE tours DROP COLUMN IF EXISTS place_id;
+
+DROP TABLE IF EXISTS places;
@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS places (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    country_code CHAR(2),
+    region TEXT,
+    timezone TEXT,
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+
+    CONSTRAINT places_slug_unique UNIQUE (slug),
+    CONSTRAINT places_name_not_blank CHECK (btrim(name) <> ''),
+    CONSTRAINT places_slug_format CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    CONSTRAINT places_country_code_format CHECK (country_code IS NULL OR country_code ~ '^[A-Z]{2}$'),
+    CONSTRAINT places_latitude_range CHECK (latitude IS NULL OR latitude BETWEEN -90 AND 90),
+    CONSTRAINT places_longitude_range CHECK (longitude IS NULL OR longitude BETWEEN -180 AND 180),
+    CONSTRAINT places_coordinates_pair CHECK ((latitude IS NULL) = (longitude IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS places_name_trgm_idx ON places USING GIN (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS places_aliases_idx ON places USING GIN (aliases);
+CREATE INDEX IF NOT EXISTS places_country_code_idx ON places (country_code);
+
+ALTER TABLE tours
+    ADD COLUMN place_id INTEGER REFERENCES places (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS tours_place_id_idx ON tours (place_id);
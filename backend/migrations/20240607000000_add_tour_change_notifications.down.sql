@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS tours_notify_change ON tours;
+DROP FUNCTION IF EXISTS notify_tour_change();
@@ -0,0 +1,24 @@
+CREATE OR REPLACE FUNCTION notify_tour_change() RETURNS trigger AS $$
+DECLARE
+    tour_id INTEGER;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        tour_id := OLD.id;
+    ELSE
+        tour_id := NEW.id;
+    END IF;
+
+    -- Only the id travels through NOTIFY: payloads are capped at 8000 bytes,
+    -- so listeners load the current row themselves.
+    PERFORM pg_notify(
+        'tour_changes',
+        json_build_object('operation', lower(TG_OP), 'tour_id', tour_id)::text
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tours_notify_change
+    AFTER INSERT OR UPDATE OR DELETE ON tours
+    FOR EACH ROW EXECUTE FUNCTION notify_tour_change();
@@ -0,0 +1 @@
+ALTER TABLE tours DROP COLUMN IF EXISTS slug;
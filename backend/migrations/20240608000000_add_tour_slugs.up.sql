@@ -0,0 +1,20 @@
+ALTER TABLE tours ADD COLUMN slug TEXT;
+
+-- Duplicate titles get the tour id appended so that existing rows stay unique.
+WITH base AS (
+    SELECT id,
+           COALESCE(NULLIF(btrim(regexp_replace(lower(title), '[^a-z0-9]+', '-', 'g'), '-'), ''), 'tour') AS slug
+    FROM tours
+), numbered AS (
+    SELECT id, slug, row_number() OVER (PARTITION BY slug ORDER BY id) AS position
+    FROM base
+)
+UPDATE tours
+SET slug = CASE WHEN numbered.position = 1 THEN numbered.slug ELSE numbered.slug || '-' || tours.id END
+FROM numbered
+WHERE tours.id = numbered.id;
+
+ALTER TABLE tours
+    ALTER COLUMN slug SET NOT NULL,
+    ADD CONSTRAINT tours_slug_unique UNIQUE (slug),
+    ADD CONSTRAINT tours_slug_format CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$');
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS bookings_notify_change ON bookings;
+DROP FUNCTION IF EXISTS notify_booking_change();
+DROP TABLE IF EXISTS bookings;
@@ -0,0 +1,34 @@
+CREATE TABLE IF NOT EXISTS bookings (
+    id SERIAL PRIMARY KEY,
+    tour_id INTEGER NOT NULL REFERENCES tours (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    participants INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'confirmed',
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    cancelled_at TIMESTAMP,
+
+    CONSTRAINT bookings_participants_positive CHECK (participants > 0),
+    CONSTRAINT bookings_status_valid CHECK (status IN ('confirmed', 'cancelled')),
+    CONSTRAINT bookings_cancelled_at_matches_status CHECK ((status = 'cancelled') = (cancelled_at IS NOT NULL))
+);
+
+CREATE INDEX IF NOT EXISTS bookings_tour_id_confirmed_idx ON bookings (tour_id) WHERE status = 'confirmed';
+CREATE INDEX IF NOT EXISTS bookings_user_id_created_at_idx ON bookings (user_id, created_at DESC, id DESC);
+
+CREATE OR REPLACE FUNCTION notify_booking_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR OLD.status IS DISTINCT FROM NEW.status THEN
+        PERFORM pg_notify(
+            'booking_changes',
+            json_build_object('booking_id', NEW.id, 'status', NEW.status)::text
+        );
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER bookings_notify_change
+    AFTER INSERT OR UPDATE ON bookings
+    FOR EACH ROW EXECUTE FUNCTION notify_booking_change();
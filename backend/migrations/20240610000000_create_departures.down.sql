@@ -0,0 +1,11 @@
+DROP TRIGGER IF EXISTS departures_notify_change ON departures;
+DROP FUNCTION IF EXISTS notify_departure_change();
+
+ALTER TABLE bookings ADD COLUMN tour_id INTEGER REFERENCES tours (id) ON DELETE CASCADE;
+UPDATE bookings SET tour_id = departures.tour_id FROM departures WHERE departures.id = bookings.departure_id;
+ALTER TABLE bookings ALTER COLUMN tour_id SET NOT NULL;
+DROP INDEX IF EXISTS bookings_departure_id_confirmed_idx;
+ALTER TABLE bookings DROP COLUMN departure_id;
+CREATE INDEX IF NOT EXISTS bookings_tour_id_confirmed_idx ON bookings (tour_id) WHERE status = 'confirmed';
+
+DROP TABLE IF EXISTS departures;
@@ -0,0 +1,69 @@
+CREATE TABLE IF NOT EXISTS departures (
+    id SERIAL PRIMARY KEY,
+    tour_id INTEGER NOT NULL REFERENCES tours (id) ON DELETE CASCADE,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    capacity INTEGER,
+    price DOUBLE PRECISION,
+    status TEXT NOT NULL DEFAULT 'scheduled',
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+
+    CONSTRAINT departures_tour_id_starts_at_key UNIQUE (tour_id, starts_at),
+    CONSTRAINT departures_ends_after_start CHECK (ends_at > starts_at),
+    CONSTRAINT departures_capacity_non_negative CHECK (capacity IS NULL OR capacity >= 0),
+    CONSTRAINT departures_price_non_negative CHECK (price IS NULL OR price >= 0),
+    CONSTRAINT departures_status_valid CHECK (status IN ('scheduled', 'cancelled'))
+);
+
+CREATE INDEX IF NOT EXISTS departures_starts_at_idx ON departures (starts_at) WHERE status = 'scheduled';
+
+-- Every dated tour gets one departure spanning its dates. Tours without dates that
+-- already have bookings get one on the day of their first booking.
+INSERT INTO departures (tour_id, starts_at, ends_at)
+SELECT id, start_date::timestamp, (COALESCE(end_date, start_date) + 1)::timestamp
+FROM tours
+WHERE start_date IS NOT NULL AND COALESCE(end_date, start_date) >= start_date;
+
+INSERT INTO departures (tour_id, starts_at, ends_at)
+SELECT tour_id, date_trunc('day', MIN(created_at)), date_trunc('day', MIN(created_at)) + INTERVAL '1 day'
+FROM bookings
+WHERE NOT EXISTS (SELECT 1 FROM departures WHERE departures.tour_id = bookings.tour_id)
+GROUP BY tour_id;
+
+ALTER TABLE bookings ADD COLUMN departure_id INTEGER REFERENCES departures (id) ON DELETE CASCADE;
+
+UPDATE bookings SET departure_id = (
+    SELECT id FROM departures
+    WHERE departures.tour_id = bookings.tour_id
+    ORDER BY starts_at
+    LIMIT 1
+);
+
+ALTER TABLE bookings ALTER COLUMN departure_id SET NOT NULL;
+DROP INDEX IF EXISTS bookings_tour_id_confirmed_idx;
+ALTER TABLE bookings DROP COLUMN tour_id;
+CREATE INDEX IF NOT EXISTS bookings_departure_id_confirmed_idx ON bookings (departure_id) WHERE status = 'confirmed';
+
+CREATE OR REPLACE FUNCTION notify_departure_change() RETURNS trigger AS $$
+BEGIN
+    IF OLD.status = 'scheduled' AND NEW.status = 'cancelled' THEN
+        PERFORM pg_notify(
+            'departure_changes',
+            json_build_object('operation', 'cancelled', 'departure_id', NEW.id)::text
+        );
+    ELSIF NEW.status = 'scheduled'
+        AND (OLD.starts_at IS DISTINCT FROM NEW.starts_at OR OLD.ends_at IS DISTINCT FROM NEW.ends_at) THEN
+        PERFORM pg_notify(
+            'departure_changes',
+            json_build_object('operation', 'rescheduled', 'departure_id', NEW.id)::text
+        );
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER departures_notify_change
+    AFTER UPDATE ON departures
+    FOR EACH ROW EXECUTE FUNCTION notify_departure_change();
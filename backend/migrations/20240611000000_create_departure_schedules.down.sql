@@ -0,0 +1,8 @@
+DROP TABLE IF EXISTS tour_blackout_calendars;
+DROP TABLE IF EXISTS blackout_dates;
+DROP TABLE IF EXISTS blackout_calendars;
+
+DROP INDEX IF EXISTS departures_schedule_id_starts_at_idx;
+ALTER TABLE departures DROP COLUMN IF EXISTS schedule_id;
+
+DROP TABLE IF EXISTS departure_schedules;
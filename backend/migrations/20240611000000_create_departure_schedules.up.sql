@@ -0,0 +1,56 @@
+CREATE TABLE IF NOT EXISTS departure_schedules (
+    id SERIAL PRIMARY KEY,
+    tour_id INTEGER NOT NULL REFERENCES tours (id) ON DELETE CASCADE,
+    rrule TEXT NOT NULL,
+    starts_on DATE NOT NULL,
+    start_time TIME NOT NULL,
+    duration_minutes INTEGER NOT NULL,
+    exception_dates DATE[] NOT NULL DEFAULT '{}',
+    capacity INTEGER,
+    price DOUBLE PRECISION,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+
+    CONSTRAINT departure_schedules_duration_positive CHECK (duration_minutes > 0),
+    CONSTRAINT departure_schedules_capacity_non_negative CHECK (capacity IS NULL OR capacity >= 0),
+    CONSTRAINT departure_schedules_price_non_negative CHECK (price IS NULL OR price >= 0)
+);
+
+CREATE INDEX IF NOT EXISTS departure_schedules_tour_id_idx ON departure_schedules (tour_id);
+
+ALTER TABLE departures
+    ADD COLUMN schedule_id INTEGER REFERENCES departure_schedules (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS departures_schedule_id_starts_at_idx ON departures (schedule_id, starts_at);
+
+CREATE TABLE IF NOT EXISTS blackout_calendars (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+
+    CONSTRAINT blackout_calendars_name_unique UNIQUE (name),
+    CONSTRAINT blackout_calendars_name_not_blank CHECK (btrim(name) <> '')
+);
+
+CREATE TABLE IF NOT EXISTS blackout_dates (
+    id SERIAL PRIMARY KEY,
+    calendar_id INTEGER NOT NULL REFERENCES blackout_calendars (id) ON DELETE CASCADE,
+    starts_on DATE NOT NULL,
+    ends_on DATE NOT NULL,
+    reason TEXT,
+
+    CONSTRAINT blackout_dates_range_valid CHECK (ends_on >= starts_on)
+);
+
+CREATE INDEX IF NOT EXISTS blackout_dates_calendar_id_idx ON blackout_dates (calendar_id, starts_on);
+
+CREATE TABLE IF NOT EXISTS tour_blackout_calendars (
+    tour_id INTEGER NOT NULL REFERENCES tours (id) ON DELETE CASCADE,
+    calendar_id INTEGER NOT NULL REFERENCES blackout_calendars (id) ON DELETE CASCADE,
+
+    PRIMARY KEY (tour_id, calendar_id)
+);
+
+CREATE INDEX IF NOT EXISTS tour_blackout_calendars_calendar_id_idx ON tour_blackout_calendars (calendar_id);
@@ -0,0 +1,20 @@
+-- Seats reserved during checkout. Active holds count against capacity until they
+-- expire, are released or are converted into a booking.
+CREATE TABLE IF NOT EXISTS seat_holds (
+    id SERIAL PRIMARY KEY,
+    departure_id INTEGER NOT NULL REFERENCES departures (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    seats INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active',
+    booking_id INTEGER REFERENCES bookings (id) ON DELETE SET NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT seat_holds_seats_positive CHECK (seats > 0),
+    CONSTRAINT seat_holds_status_valid CHECK (status IN ('active', 'converted', 'released', 'expired')),
+    CONSTRAINT seat_holds_booking_id_matches_status CHECK (booking_id IS NULL OR status = 'converted')
+);
+
+CREATE INDEX IF NOT EXISTS seat_holds_departure_id_active_idx ON seat_holds (departure_id) WHERE status = 'active';
+CREATE INDEX IF NOT EXISTS seat_holds_expires_at_active_idx ON seat_holds (expires_at) WHERE status = 'active';
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS waitlist_entries_notify_change ON waitlist_entries;
+DROP FUNCTION IF EXISTS notify_waitlist_change();
+DROP TABLE IF EXISTS waitlist_entries;
@@ -0,0 +1,45 @@
+-- Users waiting for seats on a sold-out departure. When seats free up the oldest
+-- entries that fit are offered them: a seat hold is placed for the user and a
+-- claim token is issued, valid until the hold expires.
+CREATE TABLE IF NOT EXISTS waitlist_entries (
+    id SERIAL PRIMARY KEY,
+    departure_id INTEGER NOT NULL REFERENCES departures (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    participants INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'waiting',
+    hold_id INTEGER REFERENCES seat_holds (id) ON DELETE SET NULL,
+    claim_token TEXT UNIQUE,
+    offer_expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT waitlist_entries_participants_positive CHECK (participants > 0),
+    CONSTRAINT waitlist_entries_status_valid CHECK (status IN ('waiting', 'offered', 'claimed', 'expired', 'left', 'closed')),
+    CONSTRAINT waitlist_entries_offer_matches_status CHECK (status <> 'offered' OR (claim_token IS NOT NULL AND offer_expires_at IS NOT NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS waitlist_entries_departure_id_user_id_open_idx
+    ON waitlist_entries (departure_id, user_id) WHERE status IN ('waiting', 'offered');
+CREATE INDEX IF NOT EXISTS waitlist_entries_departure_id_waiting_idx
+    ON waitlist_entries (departure_id, created_at, id) WHERE status = 'waiting';
+CREATE INDEX IF NOT EXISTS waitlist_entries_offer_expires_at_offered_idx
+    ON waitlist_entries (offer_expires_at) WHERE status = 'offered';
+CREATE INDEX IF NOT EXISTS waitlist_entries_user_id_created_at_idx
+    ON waitlist_entries (user_id, created_at DESC, id DESC);
+
+CREATE OR REPLACE FUNCTION notify_waitlist_change() RETURNS trigger AS $$
+BEGIN
+    IF NEW.status = 'offered' AND OLD.status IS DISTINCT FROM NEW.status THEN
+        PERFORM pg_notify(
+            'waitlist_changes',
+            json_build_object('entry_id', NEW.id)::text
+        );
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER waitlist_entries_notify_change
+    AFTER UPDATE ON waitlist_entries
+    FOR EACH ROW EXECUTE FUNCTION notify_waitlist_change();
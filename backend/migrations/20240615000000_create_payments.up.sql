@@ -0,0 +1,35 @@
+-- One row per attempt to pay for a booking. Status moves
+-- pending -> authorized -> captured -> refunded, and pending or authorized
+-- payments can fail.
+CREATE TABLE IF NOT EXISTS payments (
+    id SERIAL PRIMARY KEY,
+    booking_id INTEGER NOT NULL REFERENCES bookings (id) ON DELETE CASCADE,
+    provider TEXT NOT NULL,
+    provider_payment_id TEXT,
+    amount_minor BIGINT NOT NULL,
+    currency TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    failure_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT payments_amount_minor_positive CHECK (amount_minor > 0),
+    CONSTRAINT payments_currency_valid CHECK (currency ~ '^[A-Z]{3}$'),
+    CONSTRAINT payments_status_valid CHECK (status IN ('pending', 'authorized', 'captured', 'refunded', 'failed')),
+    CONSTRAINT payments_provider_payment_id_unique UNIQUE (provider, provider_payment_id)
+);
+
+-- A booking has at most one payment that hasn't failed.
+CREATE UNIQUE INDEX IF NOT EXISTS payments_booking_id_open_idx
+    ON payments (booking_id) WHERE status <> 'failed';
+
+-- Webhook deliveries already handled, so redelivered events are only acknowledged.
+CREATE TABLE IF NOT EXISTS payment_webhook_events (
+    id SERIAL PRIMARY KEY,
+    provider TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    payment_id INTEGER REFERENCES payments (id) ON DELETE SET NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT payment_webhook_events_event_id_unique UNIQUE (provider, event_id)
+);
@@ -0,0 +1,34 @@
+DROP TABLE IF EXISTS exchange_rates;
+
+-- Prices go back to major units; currencies are dropped.
+ALTER TABLE departure_schedules ADD COLUMN price DOUBLE PRECISION;
+UPDATE departure_schedules SET price = departure_schedules.price_minor / power(10, CASE tours.currency
+        WHEN 'ISK' THEN 0 WHEN 'JPY' THEN 0 WHEN 'KRW' THEN 0
+        WHEN 'BHD' THEN 3 WHEN 'JOD' THEN 3 WHEN 'KWD' THEN 3
+        ELSE 2 END)
+    FROM tours WHERE tours.id = departure_schedules.tour_id;
+ALTER TABLE departure_schedules DROP COLUMN price_minor;
+ALTER TABLE departure_schedules
+    ADD CONSTRAINT departure_schedules_price_non_negative CHECK (price IS NULL OR price >= 0);
+
+ALTER TABLE departures ADD COLUMN price DOUBLE PRECISION;
+UPDATE departures SET price = departures.price_minor / power(10, CASE tours.currency
+        WHEN 'ISK' THEN 0 WHEN 'JPY' THEN 0 WHEN 'KRW' THEN 0
+        WHEN 'BHD' THEN 3 WHEN 'JOD' THEN 3 WHEN 'KWD' THEN 3
+        ELSE 2 END)
+    FROM tours WHERE tours.id = departures.tour_id;
+ALTER TABLE departures DROP COLUMN price_minor;
+ALTER TABLE departures
+    ADD CONSTRAINT departures_price_non_negative CHECK (price IS NULL OR price >= 0);
+
+DROP INDEX IF EXISTS tours_price_minor_id_idx;
+DROP INDEX IF EXISTS tours_currency_price_minor_id_idx;
+ALTER TABLE tours ADD COLUMN price DOUBLE PRECISION;
+UPDATE tours SET price = price_minor / power(10, CASE currency
+        WHEN 'ISK' THEN 0 WHEN 'JPY' THEN 0 WHEN 'KRW' THEN 0
+        WHEN 'BHD' THEN 3 WHEN 'JOD' THEN 3 WHEN 'KWD' THEN 3
+        ELSE 2 END);
+ALTER TABLE tours DROP COLUMN price_minor, DROP COLUMN currency;
+ALTER TABLE tours
+    ADD CONSTRAINT tours_price_non_negative CHECK (price IS NULL OR price >= 0);
+CREATE INDEX IF NOT EXISTS tours_price_id_idx ON tours (price, id);
@@ -0,0 +1,44 @@
+-- Prices are stored in the minor unit of the tour's currency (cents for USD), so
+-- they are exact. Existing prices were all in US dollars.
+ALTER TABLE tours
+    ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD',
+    ADD COLUMN price_minor BIGINT;
+
+UPDATE tours SET price_minor = ROUND(price::numeric * 100);
+
+DROP INDEX IF EXISTS tours_price_id_idx;
+ALTER TABLE tours DROP COLUMN price;
+ALTER TABLE tours
+    ADD CONSTRAINT tours_price_minor_non_negative CHECK (price_minor IS NULL OR price_minor >= 0),
+    ADD CONSTRAINT tours_currency_valid CHECK (currency ~ '^[A-Z]{3}$');
+
+CREATE INDEX IF NOT EXISTS tours_currency_price_minor_id_idx ON tours (currency, price_minor, id);
+CREATE INDEX IF NOT EXISTS tours_price_minor_id_idx ON tours (price_minor, id);
+
+-- Departure and schedule prices are in their tour's currency.
+ALTER TABLE departures ADD COLUMN price_minor BIGINT;
+UPDATE departures SET price_minor = ROUND(price::numeric * 100);
+ALTER TABLE departures DROP COLUMN price;
+ALTER TABLE departures
+    ADD CONSTRAINT departures_price_minor_non_negative CHECK (price_minor IS NULL OR price_minor >= 0);
+
+ALTER TABLE departure_schedules ADD COLUMN price_minor BIGINT;
+UPDATE departure_schedules SET price_minor = ROUND(price::numeric * 100);
+ALTER TABLE departure_schedules DROP COLUMN price;
+ALTER TABLE departure_schedules
+    ADD CONSTRAINT departure_schedules_price_minor_non_negative CHECK (price_minor IS NULL OR price_minor >= 0);
+
+-- Rates are managed by admins. A rate also converts the other way, unless that
+-- direction has its own row.
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    id SERIAL PRIMARY KEY,
+    base_currency TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    rate NUMERIC(24, 12) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT exchange_rates_rate_positive CHECK (rate > 0),
+    CONSTRAINT exchange_rates_currencies_distinct CHECK (base_currency <> quote_currency),
+    CONSTRAINT exchange_rates_currencies_unique UNIQUE (base_currency, quote_currency)
+);
@@ -0,0 +1,8 @@
+DROP TABLE IF EXISTS booking_price_lines;
+
+ALTER TABLE bookings
+    DROP COLUMN IF EXISTS price_minor,
+    DROP COLUMN IF EXISTS currency;
+
+DROP TABLE IF EXISTS pricing_rules;
+DROP TABLE IF EXISTS tour_price_categories;
@@ -0,0 +1,87 @@
+-- Participant categories a tour offers besides adults, who pay the departure's or
+-- the tour's price. A category without its own price pays the adult price.
+CREATE TABLE IF NOT EXISTS tour_price_categories (
+    tour_id INTEGER NOT NULL REFERENCES tours (id) ON DELETE CASCADE,
+    category TEXT NOT NULL,
+    price_minor BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (tour_id, category),
+    CONSTRAINT tour_price_categories_category_valid CHECK (category IN ('child', 'senior', 'student')),
+    CONSTRAINT tour_price_categories_price_minor_non_negative CHECK (price_minor IS NULL OR price_minor >= 0)
+);
+
+-- Adjustments to the per-participant price. Each rule either changes the price by
+-- `percent` or by the signed `amount_minor` in the tour's currency, and applies
+-- when every condition that is set matches the departure and the party.
+CREATE TABLE IF NOT EXISTS pricing_rules (
+    id SERIAL PRIMARY KEY,
+    tour_id INTEGER NOT NULL REFERENCES tours (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    is_stackable BOOLEAN NOT NULL DEFAULT TRUE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    category TEXT,
+    percent NUMERIC,
+    amount_minor BIGINT,
+    starts_on DATE,
+    ends_on DATE,
+    weekdays INTEGER[] NOT NULL DEFAULT '{}',
+    min_lead_days INTEGER,
+    max_lead_days INTEGER,
+    min_group_size INTEGER,
+    max_group_size INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT pricing_rules_name_not_blank CHECK (btrim(name) <> ''),
+    CONSTRAINT pricing_rules_category_valid CHECK (category IS NULL OR category IN ('adult', 'child', 'senior', 'student')),
+    CONSTRAINT pricing_rules_adjustment_valid CHECK ((percent IS NULL) <> (amount_minor IS NULL)),
+    CONSTRAINT pricing_rules_percent_valid CHECK (percent IS NULL OR percent >= -100),
+    CONSTRAINT pricing_rules_dates_valid CHECK (ends_on IS NULL OR starts_on IS NULL OR ends_on >= starts_on),
+    CONSTRAINT pricing_rules_weekdays_valid CHECK (weekdays <@ ARRAY[1, 2, 3, 4, 5, 6, 7]),
+    CONSTRAINT pricing_rules_lead_days_valid CHECK (max_lead_days IS NULL OR min_lead_days IS NULL OR max_lead_days >= min_lead_days),
+    CONSTRAINT pricing_rules_group_size_valid CHECK (max_group_size IS NULL OR min_group_size IS NULL OR max_group_size >= min_group_size)
+);
+
+CREATE INDEX IF NOT EXISTS pricing_rules_tour_id_idx ON pricing_rules (tour_id);
+
+-- A booking keeps the price it was quoted, itemized the same way as the quote.
+ALTER TABLE bookings
+    ADD COLUMN price_minor BIGINT,
+    ADD COLUMN currency TEXT;
+
+UPDATE bookings
+SET price_minor = COALESCE(departures.price_minor, tours.price_minor, 0) * bookings.participants,
+    currency = tours.currency
+FROM departures
+JOIN tours ON tours.id = departures.tour_id
+WHERE departures.id = bookings.departure_id;
+
+ALTER TABLE bookings
+    ALTER COLUMN price_minor SET NOT NULL,
+    ALTER COLUMN currency SET NOT NULL,
+    ADD CONSTRAINT bookings_price_minor_non_negative CHECK (price_minor >= 0),
+    ADD CONSTRAINT bookings_currency_valid CHECK (currency ~ '^[A-Z]{3}$');
+
+CREATE TABLE IF NOT EXISTS booking_price_lines (
+    id SERIAL PRIMARY KEY,
+    booking_id INTEGER NOT NULL REFERENCES bookings (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    category TEXT NOT NULL,
+    description TEXT NOT NULL,
+    pricing_rule_id INTEGER REFERENCES pricing_rules (id) ON DELETE SET NULL,
+    quantity INTEGER NOT NULL,
+    unit_amount_minor BIGINT NOT NULL,
+    amount_minor BIGINT NOT NULL,
+
+    CONSTRAINT booking_price_lines_position_unique UNIQUE (booking_id, position),
+    CONSTRAINT booking_price_lines_kind_valid CHECK (kind IN ('base', 'adjustment')),
+    CONSTRAINT booking_price_lines_quantity_positive CHECK (quantity > 0)
+);
+
+INSERT INTO booking_price_lines (booking_id, position, kind, category, description, quantity, unit_amount_minor, amount_minor)
+SELECT id, 0, 'base', 'adult', 'Adult', participants, price_minor / participants, price_minor
+FROM bookings;
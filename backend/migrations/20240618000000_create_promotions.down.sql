@@ -0,0 +1,10 @@
+-- Bookings keep their discounted price; only the discount lines go.
+DELETE FROM booking_price_lines WHERE kind = 'discount';
+ALTER TABLE booking_price_lines
+    DROP CONSTRAINT IF EXISTS booking_price_lines_category_valid,
+    DROP CONSTRAINT IF EXISTS booking_price_lines_kind_valid,
+    ADD CONSTRAINT booking_price_lines_kind_valid CHECK (kind IN ('base', 'adjustment')),
+    ALTER COLUMN category SET NOT NULL;
+
+DROP TABLE IF EXISTS promotion_redemptions;
+DROP TABLE IF EXISTS promotions;
@@ -0,0 +1,58 @@
+-- Promo codes. A promotion takes `percent` off a booking's price, or the fixed
+-- `amount_minor` in `currency`, while every limit that is set allows it. Codes are
+-- stored in upper case and matched case-insensitively.
+CREATE TABLE IF NOT EXISTS promotions (
+    id SERIAL PRIMARY KEY,
+    code TEXT NOT NULL,
+    name TEXT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    percent NUMERIC,
+    amount_minor BIGINT,
+    currency TEXT,
+    min_spend_minor BIGINT,
+    starts_at TIMESTAMPTZ,
+    ends_at TIMESTAMPTZ,
+    max_redemptions INTEGER,
+    max_redemptions_per_user INTEGER,
+    tour_ids INTEGER[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT promotions_code_unique UNIQUE (code),
+    CONSTRAINT promotions_code_format CHECK (code ~ '^[A-Z0-9_-]+$'),
+    CONSTRAINT promotions_name_not_blank CHECK (btrim(name) <> ''),
+    CONSTRAINT promotions_discount_valid CHECK ((percent IS NULL) <> (amount_minor IS NULL)),
+    CONSTRAINT promotions_percent_valid CHECK (percent IS NULL OR (percent > 0 AND percent <= 100)),
+    CONSTRAINT promotions_amount_minor_positive CHECK (amount_minor IS NULL OR amount_minor > 0),
+    CONSTRAINT promotions_min_spend_minor_non_negative CHECK (min_spend_minor IS NULL OR min_spend_minor >= 0),
+    CONSTRAINT promotions_currency_valid CHECK (currency IS NULL OR currency ~ '^[A-Z]{3}$'),
+    CONSTRAINT promotions_currency_required CHECK (currency IS NOT NULL OR (amount_minor IS NULL AND min_spend_minor IS NULL)),
+    CONSTRAINT promotions_dates_valid CHECK (ends_at IS NULL OR starts_at IS NULL OR ends_at > starts_at),
+    CONSTRAINT promotions_max_redemptions_positive CHECK (max_redemptions IS NULL OR max_redemptions > 0),
+    CONSTRAINT promotions_max_redemptions_per_user_positive CHECK (max_redemptions_per_user IS NULL OR max_redemptions_per_user > 0)
+);
+
+-- One row per booking made with a promotion. Redemptions of cancelled bookings are
+-- kept for reporting but no longer count towards the limits.
+CREATE TABLE IF NOT EXISTS promotion_redemptions (
+    id SERIAL PRIMARY KEY,
+    promotion_id INTEGER NOT NULL REFERENCES promotions (id) ON DELETE RESTRICT,
+    booking_id INTEGER NOT NULL REFERENCES bookings (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    discount_minor BIGINT NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT promotion_redemptions_booking_id_unique UNIQUE (booking_id),
+    CONSTRAINT promotion_redemptions_discount_minor_non_negative CHECK (discount_minor >= 0)
+);
+
+CREATE INDEX IF NOT EXISTS promotion_redemptions_promotion_id_user_id_idx
+    ON promotion_redemptions (promotion_id, user_id);
+
+-- Discount lines apply to the whole booking rather than to a category.
+ALTER TABLE booking_price_lines
+    ALTER COLUMN category DROP NOT NULL,
+    DROP CONSTRAINT booking_price_lines_kind_valid,
+    ADD CONSTRAINT booking_price_lines_kind_valid CHECK (kind IN ('base', 'adjustment', 'discount')),
+    ADD CONSTRAINT booking_price_lines_category_valid CHECK ((category IS NULL) = (kind = 'discount'));
@@ -1,94 +1,91 @@
-use serde::Deserialize;
-use sqlx::PgPool;
-use std::{env, fs::File, io::Read, sync::Arc};
-use tracing::debug;
+use std::env;
+
+use crate::service::booking::BookingService;
+use crate::service::departure::DepartureService;
+use crate::service::events::EventService;
+use crate::service::exchange_rate::ExchangeRateService;
+use crate::service::oauth::OAuthService;
+use crate::service::payment::PaymentService;
+use crate::service::place::PlaceService;
+use crate::service::pricing::PricingService;
+use crate::service::promotion::PromotionService;
+use crate::service::schedule::ScheduleService;
+use crate::service::tour::TourService;
+use crate::service::waitlist::WaitlistService;
 
 pub struct ApplicationData {
-    pub database_pool: PgPool,
+    pub tour_service: TourService,
+    pub place_service: PlaceService,
+    pub departure_service: DepartureService,
+    pub schedule_service: ScheduleService,
+    pub booking_service: BookingService,
+    pub waitlist_service: WaitlistService,
+    pub payment_service: PaymentService,
+    pub exchange_rate_service: ExchangeRateService,
+    pub pricing_service: PricingService,
+    pub promotion_service: PromotionService,
+    pub event_service: EventService,
+    pub oauth_service: OAuthService,
 }
 
 impl ApplicationData {
-    pub fn new(database_pool: PgPool) -> Self {
-        Self { database_pool }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        tour_service: TourService,
+        place_service: PlaceService,
+        departure_service: DepartureService,
+        schedule_service: ScheduleService,
+        booking_service: BookingService,
+        waitlist_service: WaitlistService,
+        payment_service: PaymentService,
+        exchange_rate_service: ExchangeRateService,
+        pricing_service: PricingService,
+        promotion_service: PromotionService,
+        event_service: EventService,
+        oauth_service: OAuthService,
+    ) -> Self {
+        Self {
+            tour_service,
+            place_service,
+            departure_service,
+            schedule_service,
+            booking_service,
+            waitlist_service,
+            payment_service,
+            exchange_rate_service,
+            pricing_service,
+            promotion_service,
+            event_service,
+            oauth_service,
+        }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub struct ApplicationConfig {
-    pub database_user: String,
-    pub database_password: String,
-    pub database_name: String,
-    pub database_host: String,
-    pub database_port: u16,
     pub database_url: String,
     pub database_max_connections_count: u32,
+}
 
-    pub ssl_key_path: String,
-    pub ssl_cert_path: String,
-
-    pub secret_key: String,
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("{0} must be set")]
+    MissingVariable(String),
+    #[error("{name} is invalid: {message}")]
+    InvalidVariable { name: String, message: String },
+    #[error("failed to build HTTP client: {0}")]
+    HttpClient(#[from] reqwest::Error),
+}
 
-    pub session_table_name: String,
+pub fn get_required_env(name: &str) -> Result<String, ConfigError> {
+    env::var(name).map_err(|_| ConfigError::MissingVariable(name.to_string()))
 }
 
 impl ApplicationConfig {
-    fn is_env_var_set(var_name: &str) -> bool {
-        match env::var(var_name) {
-            Ok(_) => true,
-            Err(env::VarError::NotPresent) => false,
-            Err(_) => false,
-        }
-    }
-
-    pub fn from_env() -> Self {
-        Self {
-            database_user: env::var("DATABASE_USER").expect("DATABASE_USER must be set"),
-            database_password: env::var("DATABASE_PASSWORD")
-                .expect("DATABASE_PASSWORD must be set"),
-            database_name: env::var("DATABASE_NAME").expect("DATABASE_NAME must be set"),
-            database_host: env::var("DATABASE_HOST").expect("DATABASE_HOST must be set"),
-            database_port: env::var("DATABASE_PORT")
-                .expect("DATABASE_PORT must be set")
-                .parse()
-                .unwrap(),
-            database_url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(Self {
+            database_url: get_required_env("DATABASE_URL")?,
             database_max_connections_count: 5,
-
-            ssl_key_path: env::var("SSL_KEY_PATH").unwrap_or("key.pem".to_string()),
-            ssl_cert_path: env::var("SSL_CERT_PATH").unwrap_or("cert.pem".to_string()),
-
-            secret_key: env::var("SECRET_KEY").expect("SECRET_KEY must be set"),
-
-            session_table_name: env::var("DATABASE_SESSION_TABLE_NAME")
-                .unwrap_or("public.\"Sessions\"".to_string()),
-        }
-    }
-
-    pub fn from_file(file_path: &str) -> Self {
-        let mut file = File::open(file_path).expect("Failed to open config file");
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .expect("Failed to read config file");
-
-        let config: ApplicationConfig =
-            serde_json::from_str(&contents).expect("Failed to parse config file");
-
-        config
-    }
-
-    pub fn get() -> &'static Self {
-        static INSTANCE: once_cell::sync::Lazy<ApplicationConfig> =
-            once_cell::sync::Lazy::new(|| {
-                let config = if ApplicationConfig::is_env_var_set("CONFIG_FROM_ENV") {
-                    ApplicationConfig::from_env()
-                } else {
-                    ApplicationConfig::from_file("config.json")
-                };
-
-                debug!("Config: {:#?}", config);
-                config
-            });
-
-        &INSTANCE
+        })
     }
 }
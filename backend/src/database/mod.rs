@@ -0,0 +1,64 @@
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use tracing::info;
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+pub async fn run_migrations(database_pool: &PgPool) -> Result<(), MigrateError> {
+    info!("Running database migrations");
+    MIGRATOR.run(database_pool).await
+}
+
+pub async fn revert_last_migration(database_pool: &PgPool) -> Result<(), MigrateError> {
+    let applied_versions = get_applied_versions(database_pool).await?;
+
+    let Some(last_version) = applied_versions.iter().max().copied() else {
+        info!("No migrations to revert");
+        return Ok(());
+    };
+
+    let target_version = applied_versions
+        .iter()
+        .filter(|version| **version < last_version)
+        .max()
+        .copied()
+        .unwrap_or(0);
+
+    info!("Reverting migration {}", last_version);
+    MIGRATOR.undo(database_pool, target_version).await
+}
+
+pub async fn print_migration_status(database_pool: &PgPool) -> Result<(), MigrateError> {
+    let applied_versions = get_applied_versions(database_pool).await?;
+
+    for migration in MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+    {
+        let status = if applied_versions.contains(&migration.version) {
+            "applied"
+        } else {
+            "pending"
+        };
+
+        println!(
+            "{:<16} {:<8} {}",
+            migration.version, status, migration.description
+        );
+    }
+
+    Ok(())
+}
+
+async fn get_applied_versions(database_pool: &PgPool) -> Result<HashSet<i64>, MigrateError> {
+    let mut connection = database_pool.acquire().await?;
+    connection.ensure_migrations_table().await?;
+
+    Ok(connection
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect())
+}
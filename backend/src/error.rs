@@ -0,0 +1,267 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpResponse, ResponseError};
+use async_graphql::{ErrorExtensions, Value};
+use serde_json::json;
+use std::fmt::Display;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::schema::validation::ValidationErrors;
+use crate::service::payment::provider::PaymentProviderError;
+use crate::service::ServiceError;
+
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
+// Errors as they are shown to clients. Internal failures are logged together with
+// a correlation id, and only that id is returned.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Not found")]
+    NotFound,
+    #[error("Validation failed")]
+    Validation(ValidationErrors),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("Authentication required")]
+    Unauthenticated,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("Payments are disabled")]
+    PaymentsDisabled,
+    #[error("Internal server error")]
+    Internal { correlation_id: Uuid },
+}
+
+impl AppError {
+    pub fn internal(operation: &str, error: impl Display) -> Self {
+        let correlation_id = Uuid::new_v4();
+        error!(%correlation_id, "Failed to {}: {}", operation, error);
+        AppError::Internal { correlation_id }
+    }
+
+    pub fn from_service_error(operation: &str, error: ServiceError) -> Self {
+        match error {
+            ServiceError::NotFound => AppError::NotFound,
+            ServiceError::Validation(errors) => AppError::Validation(errors),
+            ServiceError::Conflict(message) => AppError::Conflict(message),
+            ServiceError::Repository(error) => AppError::internal(operation, error),
+            ServiceError::PaymentProvider(PaymentProviderError::InvalidSignature) => {
+                AppError::Unauthenticated
+            }
+            ServiceError::PaymentProvider(PaymentProviderError::MalformedWebhook(message)) => {
+                AppError::validation("payload", message)
+            }
+            ServiceError::PaymentProvider(error) => AppError::internal(operation, error),
+            ServiceError::PaymentsDisabled => AppError::PaymentsDisabled,
+        }
+    }
+
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        let mut errors = ValidationErrors::new();
+        errors.add(field, message);
+        AppError::Validation(errors)
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound => "NOT_FOUND",
+            AppError::Validation(_) => "VALIDATION_FAILED",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::Unauthenticated => "UNAUTHENTICATED",
+            AppError::Forbidden => "FORBIDDEN",
+            AppError::PaymentsDisabled => "PAYMENTS_DISABLED",
+            AppError::Internal { .. } => "INTERNAL",
+        }
+    }
+
+    fn correlation_id(&self) -> Option<Uuid> {
+        match self {
+            AppError::Internal { correlation_id } => Some(*correlation_id),
+            _ => None,
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unauthenticated => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden => StatusCode::FORBIDDEN,
+            AppError::PaymentsDisabled => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // RFC 7807 problem details with `code`, `errors` and `correlationId` as
+    // extension members.
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let mut problem = json!({
+            "type": "about:blank",
+            "title": status.canonical_reason().unwrap_or_default(),
+            "status": status.as_u16(),
+            "detail": self.to_string(),
+            "code": self.code(),
+        });
+        if let AppError::Validation(errors) = self {
+            problem["errors"] = json!(errors.fields());
+        }
+
+        let mut response = HttpResponse::build(status);
+        response.insert_header((header::CONTENT_TYPE, PROBLEM_JSON_CONTENT_TYPE));
+        if let Some(correlation_id) = self.correlation_id() {
+            problem["correlationId"] = json!(correlation_id.to_string());
+            response.insert_header((CORRELATION_ID_HEADER, correlation_id.to_string()));
+        }
+
+        response.body(problem.to_string())
+    }
+}
+
+impl ErrorExtensions for AppError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, extensions| {
+            extensions.set("code", self.code());
+            if let AppError::Validation(errors) = self {
+                let fields = errors
+                    .fields()
+                    .iter()
+                    .map(|(field, messages)| {
+                        (
+                            async_graphql::Name::new(field),
+                            Value::List(messages.iter().cloned().map(Value::String).collect()),
+                        )
+                    })
+                    .collect();
+                extensions.set("fields", Value::Object(fields));
+            }
+            if let Some(correlation_id) = self.correlation_id() {
+                extensions.set("correlationId", correlation_id.to_string());
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::repository::RepositoryError;
+
+    async fn fail(error: web::Path<String>) -> Result<HttpResponse, AppError> {
+        Err(match error.as_str() {
+            "not-found" => AppError::from_service_error("load", ServiceError::NotFound),
+            "validation" => AppError::validation("title", "must not be empty"),
+            "conflict" => AppError::Conflict("Only 0 seats are available".to_string()),
+            "unauthenticated" => AppError::Unauthenticated,
+            "forbidden" => AppError::Forbidden,
+            "payments-disabled" => {
+                AppError::from_service_error("pay", ServiceError::PaymentsDisabled)
+            }
+            _ => AppError::from_service_error(
+                "load",
+                ServiceError::Repository(RepositoryError::Conflict("boom".to_string())),
+            ),
+        })
+    }
+
+    async fn problem(error: &str) -> (StatusCode, Option<String>, Option<String>, Value) {
+        let app = test::init_service(App::new().route("/fail/{error}", web::get().to(fail))).await;
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/fail/{}", error))
+                .to_request(),
+        )
+        .await;
+
+        let header_value = |name| {
+            response
+                .headers()
+                .get(name)
+                .map(|value: &header::HeaderValue| value.to_str().unwrap().to_string())
+        };
+        let content_type = header_value(header::CONTENT_TYPE.as_str());
+        let correlation_id = header_value(CORRELATION_ID_HEADER);
+        let status = response.status();
+        (
+            status,
+            content_type,
+            correlation_id,
+            test::read_body_json(response).await,
+        )
+    }
+
+    #[actix_web::test]
+    async fn errors_are_problem_details_with_stable_codes() {
+        for (error, status, code) in [
+            ("not-found", StatusCode::NOT_FOUND, "NOT_FOUND"),
+            ("validation", StatusCode::BAD_REQUEST, "VALIDATION_FAILED"),
+            ("conflict", StatusCode::CONFLICT, "CONFLICT"),
+            (
+                "unauthenticated",
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHENTICATED",
+            ),
+            ("forbidden", StatusCode::FORBIDDEN, "FORBIDDEN"),
+            (
+                "payments-disabled",
+                StatusCode::SERVICE_UNAVAILABLE,
+                "PAYMENTS_DISABLED",
+            ),
+            ("internal", StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL"),
+        ] {
+            let (actual_status, content_type, _, problem) = problem(error).await;
+
+            assert_eq!(actual_status, status, "{}", error);
+            assert_eq!(content_type.as_deref(), Some(PROBLEM_JSON_CONTENT_TYPE));
+            assert_eq!(problem["code"], code, "{}", error);
+            assert_eq!(problem["status"], status.as_u16(), "{}", error);
+            assert_eq!(problem["type"], "about:blank", "{}", error);
+        }
+    }
+
+    #[actix_web::test]
+    async fn validation_problems_list_field_errors() {
+        let (_, _, correlation_id, problem) = problem("validation").await;
+
+        assert_eq!(problem["errors"]["title"][0], "must not be empty");
+        assert_eq!(correlation_id, None);
+        assert!(problem.get("correlationId").is_none());
+    }
+
+    #[actix_web::test]
+    async fn internal_errors_echo_the_correlation_id_without_details() {
+        let (_, _, correlation_id, problem) = problem("internal").await;
+
+        let correlation_id = correlation_id.expect("a correlation id header");
+        assert!(Uuid::parse_str(&correlation_id).is_ok());
+        assert_eq!(problem["correlationId"], correlation_id.as_str());
+        assert_eq!(problem["detail"], "Internal server error");
+        assert!(!problem.to_string().contains("boom"));
+    }
+
+    #[actix_web::test]
+    async fn graphql_errors_carry_the_same_code_and_correlation_id() {
+        let error = AppError::internal("load", "boom");
+        let AppError::Internal { correlation_id } = error else {
+            unreachable!();
+        };
+
+        let extensions = error.extend().extensions.unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&async_graphql::Value::from("INTERNAL"))
+        );
+        assert_eq!(
+            extensions.get("correlationId"),
+            Some(&async_graphql::Value::from(correlation_id.to_string()))
+        );
+    }
+}
@@ -1,7 +1,14 @@
 mod config;
+mod database;
+mod error;
 mod middleware;
+mod models;
+mod repository;
 mod routes;
 mod schema;
+mod service;
+#[cfg(test)]
+mod test_support;
 
 use actix_cors::Cors;
 use actix_identity::IdentityMiddleware;
@@ -10,7 +17,7 @@ use actix_session::{
     storage::CookieSessionStore,
     SessionMiddleware,
 };
-use actix_web::{cookie, guard, http, web, App, HttpServer};
+use actix_web::{cookie, guard, web, App, HttpServer};
 
 use ::clap::{Arg, ArgAction, ArgMatches, Command};
 use async_graphql::Schema;
@@ -22,13 +29,38 @@ use std::env;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_actix_web::TracingLogger;
 use tracing_subscriber::EnvFilter;
 
 use crate::config::ApplicationConfig;
 use crate::config::ApplicationData;
-use crate::schema::graphql::{MutationRoot, QueryRoot};
+use crate::repository::booking::postgres::PostgresBookingRepository;
+use crate::repository::departure::postgres::PostgresDepartureRepository;
+use crate::repository::exchange_rate::postgres::PostgresExchangeRateRepository;
+use crate::repository::payment::postgres::PostgresPaymentRepository;
+use crate::repository::place::postgres::PostgresPlaceRepository;
+use crate::repository::pricing::postgres::PostgresPricingRepository;
+use crate::repository::promotion::postgres::PostgresPromotionRepository;
+use crate::repository::schedule::postgres::PostgresScheduleRepository;
+use crate::repository::tour::postgres::PostgresTourRepository;
+use crate::repository::waitlist::postgres::PostgresWaitlistRepository;
+use crate::schema::graphql::{MutationRoot, QueryRoot, SubscriptionRoot};
+use crate::service::booking::{BookingService, DEFAULT_HOLD_TTL_MINUTES};
+use crate::service::departure::DepartureService;
+use crate::service::events::EventService;
+use crate::service::exchange_rate::ExchangeRateService;
+use crate::service::oauth::OAuthService;
+use crate::service::payment::{self, PaymentService};
+use crate::service::place::{
+    BackfillOptions, BackfillOutcome, PlaceService, DEFAULT_MATCH_THRESHOLD,
+};
+use crate::service::pricing::PricingService;
+use crate::service::promotion::PromotionService;
+use crate::service::schedule::{ScheduleService, DEFAULT_HORIZON_DAYS};
+use crate::service::tour::TourService;
+use crate::service::waitlist::{WaitlistService, DEFAULT_CLAIM_URL, DEFAULT_OFFER_TTL_MINUTES};
+use crate::service::ServiceError;
 
 fn get_arguments() -> ArgMatches {
     Command::new("backend")
@@ -50,11 +82,96 @@ fn get_arguments() -> ArgMatches {
                 .required(false)
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("hold-ttl-minutes")
+                .long("hold-ttl-minutes")
+                .value_name("MINUTES")
+                .help("How long seat holds reserve seats before they expire")
+                .value_parser(clap::value_parser!(u64).range(1..=1440))
+                .default_value("15")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("waitlist-offer-minutes")
+                .long("waitlist-offer-minutes")
+                .value_name("MINUTES")
+                .help("How long waitlisted users have to claim offered seats")
+                .value_parser(clap::value_parser!(u64).range(1..=10080))
+                .default_value("60")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("run-migrations")
+                .long("run-migrations")
+                .help("Applies pending database migrations on startup")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-fake-payments")
+                .long("allow-fake-payments")
+                .help("Allows PAYMENT_PROVIDER=fake, which accepts payments without charging (development only)")
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("migrate")
+                .about("Manages database migrations")
+                .subcommand_required(true)
+                .subcommand(Command::new("up").about("Applies all pending migrations"))
+                .subcommand(Command::new("down").about("Reverts the last applied migration"))
+                .subcommand(Command::new("status").about("Lists migrations and their status")),
+        )
+        .subcommand(
+            Command::new("places")
+                .about("Manages places")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("backfill")
+                        .about("Links tours without a place to the closest matching place")
+                        .arg(
+                            Arg::new("threshold")
+                                .long("threshold")
+                                .value_name("SCORE")
+                                .help("Minimum trigram similarity for a match (0 to 1)")
+                                .value_parser(clap::value_parser!(f32))
+                                .default_value("0.6")
+                                .action(ArgAction::Set),
+                        )
+                        .arg(
+                            Arg::new("create-missing")
+                                .long("create-missing")
+                                .help("Creates a place for every location without a match")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Reports matches without changing any data")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("departures")
+                .about("Manages departures")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("materialize")
+                        .about("Expands departure schedules into departures")
+                        .arg(
+                            Arg::new("horizon-days")
+                                .long("horizon-days")
+                                .value_name("DAYS")
+                                .help("How many days ahead to create departures for")
+                                .value_parser(clap::value_parser!(u64).range(1..=730))
+                                .default_value("90")
+                                .action(ArgAction::Set),
+                        ),
+                ),
+        )
         .get_matches()
 }
 
-fn get_socket_address() -> SocketAddr {
-    let arguments = get_arguments();
+fn get_socket_address(arguments: &ArgMatches) -> SocketAddr {
     let default_ip_address = IpAddr::from([127, 0, 0, 1]);
     let default_port = 8000;
 
@@ -74,6 +191,83 @@ async fn create_postgres_pool(config: &ApplicationConfig) -> Result<Pool<Postgre
         .await
 }
 
+async fn run_migrate_command(
+    arguments: &ArgMatches,
+    database_pool: &Pool<Postgres>,
+) -> Result<(), sqlx::migrate::MigrateError> {
+    match arguments.subcommand() {
+        Some(("up", _)) => database::run_migrations(database_pool).await,
+        Some(("down", _)) => database::revert_last_migration(database_pool).await,
+        Some(("status", _)) => database::print_migration_status(database_pool).await,
+        _ => unreachable!("clap requires a migrate subcommand"),
+    }
+}
+
+async fn run_places_command(
+    arguments: &ArgMatches,
+    place_service: &PlaceService,
+) -> Result<(), ServiceError> {
+    match arguments.subcommand() {
+        Some(("backfill", backfill_arguments)) => {
+            let options = BackfillOptions {
+                threshold: backfill_arguments
+                    .get_one("threshold")
+                    .copied()
+                    .unwrap_or(DEFAULT_MATCH_THRESHOLD),
+                create_missing: backfill_arguments.get_flag("create-missing"),
+                dry_run: backfill_arguments.get_flag("dry-run"),
+            };
+
+            for entry in place_service.backfill_tour_places(options).await? {
+                match entry.outcome {
+                    BackfillOutcome::Matched { place, score } => println!(
+                        "matched   {:?} -> {} ({:.2}), {} tours",
+                        entry.location, place.slug, score, entry.tours_updated
+                    ),
+                    BackfillOutcome::Created { place } => println!(
+                        "created   {:?} -> {}, {} tours",
+                        entry.location, place.slug, entry.tours_updated
+                    ),
+                    BackfillOutcome::WouldCreate => {
+                        println!("would create {:?}", entry.location)
+                    }
+                    BackfillOutcome::Unmatched => println!("unmatched {:?}", entry.location),
+                }
+            }
+
+            Ok(())
+        }
+        _ => unreachable!("clap requires a places subcommand"),
+    }
+}
+
+async fn run_departures_command(
+    arguments: &ArgMatches,
+    database_pool: &Pool<Postgres>,
+) -> Result<(), ServiceError> {
+    match arguments.subcommand() {
+        Some(("materialize", materialize_arguments)) => {
+            let horizon_days = materialize_arguments
+                .get_one("horizon-days")
+                .copied()
+                .unwrap_or(DEFAULT_HORIZON_DAYS);
+            let schedule_service = ScheduleService::new(
+                Arc::new(PostgresScheduleRepository::new(database_pool.clone())),
+                horizon_days,
+            );
+
+            let summary = schedule_service.materialize(None).await?;
+            println!(
+                "created {}, updated {}, removed {} departures",
+                summary.created, summary.updated, summary.removed
+            );
+
+            Ok(())
+        }
+        _ => unreachable!("clap requires a departures subcommand"),
+    }
+}
+
 fn init_tracing() {
     let format = tracing_subscriber::fmt::format()
         .with_level(true)
@@ -92,34 +286,158 @@ async fn main() -> std::io::Result<()> {
 
     if let Err(error) = dotenv::dotenv() {
         error!("Failed to load .env file: {}", error);
-        return Err(std::io::Error::new(std::io::ErrorKind::Other, error));
+        return Err(std::io::Error::other(error));
     }
 
     if let Err(error) = dotenv::from_filename(".env_backend") {
         error!("Failed to load .env_backend file: {}", error);
-        return Err(std::io::Error::new(std::io::ErrorKind::Other, error));
+        return Err(std::io::Error::other(error));
     }
 
     init_tracing();
 
-    let secret_key = env::var("NEXTAUTH_SECRET").expect("NEXTAUTH_SECRET not set");
-
-    let socket_addres = get_socket_address();
-    let application_config = config::ApplicationConfig::from_env();
+    let arguments = get_arguments();
+    let application_config = config::ApplicationConfig::from_env().map_err(|error| {
+        error!("Failed to load config: {}", error);
+        std::io::Error::other(error)
+    })?;
     let postgres_pool = match create_postgres_pool(&application_config).await {
         Ok(pool) => pool,
         Err(error) => {
             error!("Failed to create postgres pool: {}", error);
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, error));
+            return Err(std::io::Error::other(error));
         }
     };
 
+    if let Some(("migrate", migrate_arguments)) = arguments.subcommand() {
+        return run_migrate_command(migrate_arguments, &postgres_pool)
+            .await
+            .map_err(|error| {
+                error!("Failed to run migrations: {}", error);
+                std::io::Error::other(error)
+            });
+    }
+
+    let place_service = PlaceService::new(Arc::new(PostgresPlaceRepository::new(
+        postgres_pool.clone(),
+    )));
+
+    if let Some(("places", places_arguments)) = arguments.subcommand() {
+        return run_places_command(places_arguments, &place_service)
+            .await
+            .map_err(|error| {
+                error!("Failed to run places command: {}", error);
+                std::io::Error::other(error)
+            });
+    }
+
+    if let Some(("departures", departures_arguments)) = arguments.subcommand() {
+        return run_departures_command(departures_arguments, &postgres_pool)
+            .await
+            .map_err(|error| {
+                error!("Failed to run departures command: {}", error);
+                std::io::Error::other(error)
+            });
+    }
+
+    if arguments.get_flag("run-migrations") {
+        if let Err(error) = database::run_migrations(&postgres_pool).await {
+            error!("Failed to run migrations: {}", error);
+            return Err(std::io::Error::other(error));
+        }
+    }
+
+    let session_key = config::get_required_env("NEXTAUTH_SECRET")
+        .map_err(|error| error.to_string())
+        .and_then(|secret_key| {
+            cookie::Key::try_from(secret_key.as_bytes())
+                .map_err(|error| format!("NEXTAUTH_SECRET is invalid: {}", error))
+        })
+        .map_err(|error| {
+            error!("Failed to load session key: {}", error);
+            std::io::Error::other(error)
+        })?;
+    let oauth_service = OAuthService::from_env().map_err(|error| {
+        error!("Failed to configure OAuth providers: {}", error);
+        std::io::Error::other(error)
+    })?;
+    let socket_addres = get_socket_address(&arguments);
+
     // let application_data = Arc::new(web::Data::new(postgres_pool));
-    let application_data = web::Data::new(ApplicationData::new(postgres_pool));
-    let application_schema =
-        Schema::build(QueryRoot, MutationRoot, async_graphql::EmptySubscription)
-            .data(application_data.clone())
-            .finish();
+    let tour_service =
+        TourService::new(Arc::new(PostgresTourRepository::new(postgres_pool.clone())));
+    let departure_service = DepartureService::new(Arc::new(PostgresDepartureRepository::new(
+        postgres_pool.clone(),
+    )));
+    let schedule_service = ScheduleService::new(
+        Arc::new(PostgresScheduleRepository::new(postgres_pool.clone())),
+        DEFAULT_HORIZON_DAYS,
+    );
+    schedule_service.spawn_materializer();
+    let booking_service = BookingService::new(
+        Arc::new(PostgresBookingRepository::new(postgres_pool.clone())),
+        arguments
+            .get_one("hold-ttl-minutes")
+            .copied()
+            .unwrap_or(DEFAULT_HOLD_TTL_MINUTES),
+    );
+    booking_service.spawn_hold_sweeper();
+    let waitlist_service = WaitlistService::new(
+        Arc::new(PostgresWaitlistRepository::new(postgres_pool.clone())),
+        arguments
+            .get_one("waitlist-offer-minutes")
+            .copied()
+            .unwrap_or(DEFAULT_OFFER_TTL_MINUTES),
+        env::var("WAITLIST_CLAIM_URL").unwrap_or(DEFAULT_CLAIM_URL.to_string()),
+    );
+    waitlist_service.spawn_promoter();
+    let payment_provider = payment::provider_from_env(arguments.get_flag("allow-fake-payments"))
+        .map_err(|error| {
+            error!("Failed to configure payment provider: {}", error);
+            std::io::Error::other(error)
+        })?;
+    match &payment_provider {
+        Some(provider) => info!("Taking payments with the {} provider", provider.name()),
+        None => warn!("PAYMENT_PROVIDER isn't set, payments are disabled"),
+    }
+    let payment_service = PaymentService::new(
+        Arc::new(PostgresPaymentRepository::new(postgres_pool.clone())),
+        payment_provider,
+    );
+    let exchange_rate_service = ExchangeRateService::new(Arc::new(
+        PostgresExchangeRateRepository::new(postgres_pool.clone()),
+    ));
+    let promotion_repository = Arc::new(PostgresPromotionRepository::new(postgres_pool.clone()));
+    let pricing_service = PricingService::new(
+        Arc::new(PostgresPricingRepository::new(postgres_pool.clone())),
+        promotion_repository.clone(),
+    );
+    let promotion_service = PromotionService::new(promotion_repository);
+    let event_service = EventService::new();
+    event_service.spawn_listener(
+        postgres_pool.clone(),
+        tour_service.clone(),
+        departure_service.clone(),
+        booking_service.clone(),
+        waitlist_service.clone(),
+    );
+    let application_data = web::Data::new(ApplicationData::new(
+        tour_service,
+        place_service,
+        departure_service,
+        schedule_service,
+        booking_service,
+        waitlist_service,
+        payment_service,
+        exchange_rate_service,
+        pricing_service,
+        promotion_service,
+        event_service,
+        oauth_service,
+    ));
+    let application_schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(application_data.clone())
+        .finish();
 
     info!("Socket address: {:#?}", socket_addres);
 
@@ -127,17 +445,14 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .wrap(IdentityMiddleware::builder().build())
             .wrap(
-                SessionMiddleware::builder(
-                    CookieSessionStore::default(),
-                    cookie::Key::from(secret_key.as_bytes()),
-                )
-                .cookie_name("session-id".to_string())
-                .cookie_secure(false)
-                .session_lifecycle(SessionLifecycle::PersistentSession(
-                    PersistentSession::default(),
-                ))
-                .cookie_content_security(CookieContentSecurity::Signed)
-                .build(),
+                SessionMiddleware::builder(CookieSessionStore::default(), session_key.clone())
+                    .cookie_name("session-id".to_string())
+                    .cookie_secure(false)
+                    .session_lifecycle(SessionLifecycle::PersistentSession(
+                        PersistentSession::default(),
+                    ))
+                    .cookie_content_security(CookieContentSecurity::Signed)
+                    .build(),
             )
             .wrap(Cors::permissive())
             .wrap(TracingLogger::default())
@@ -147,6 +462,13 @@ async fn main() -> std::io::Result<()> {
                     .guard(guard::Post())
                     .route(web::post().to(routes::graphql_handler)),
             )
+            .service(
+                web::resource("/graphql/ws")
+                    .app_data(web::Data::new(application_schema.clone()))
+                    .guard(guard::Get())
+                    .guard(guard::Header("upgrade", "websocket"))
+                    .route(web::get().to(routes::graphql_subscription_handler)),
+            )
             .service(
                 web::resource("/graphql/playground")
                     .route(web::get().to(routes::graphql_playground)),
@@ -155,6 +477,9 @@ async fn main() -> std::io::Result<()> {
                 web::scope("/api")
                     .app_data(application_data.clone())
                     .route("/tours", web::get().to(routes::get_tours))
+                    .route("/suggest", web::get().to(routes::suggest))
+                    .route("/events", web::get().to(routes::events))
+                    .route("/payments/webhook", web::post().to(routes::payment_webhook))
                     .service(
                         web::scope("/auth")
                             .route("/github", web::get().to(routes::github_login))
@@ -0,0 +1,79 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::schema::validation::ValidationErrors;
+
+pub const MAX_PARTICIPANTS_PER_BOOKING: i32 = 50;
+
+#[derive(Enum, Serialize, Deserialize, sqlx::Type, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum BookingStatus {
+    Confirmed,
+    Cancelled,
+}
+
+// `price_minor` is the total the booking was quoted, in its tour's currency.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct Booking {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub departure_id: i32,
+    #[graphql(skip)]
+    pub user_id: String,
+    pub participants: i32,
+    pub status: BookingStatus,
+    #[graphql(skip)]
+    pub price_minor: i64,
+    #[graphql(skip)]
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+pub fn validate_participants(errors: &mut ValidationErrors, field: &str, participants: i32) {
+    if !(1..=MAX_PARTICIPANTS_PER_BOOKING).contains(&participants) {
+        errors.add(
+            field,
+            format!("must be between 1 and {}", MAX_PARTICIPANTS_PER_BOOKING),
+        );
+    }
+}
+
+#[derive(Enum, Serialize, Deserialize, sqlx::Type, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum SeatHoldStatus {
+    Active,
+    Converted,
+    Released,
+    Expired,
+}
+
+// Seats reserved on a departure during checkout. While active and before
+// `expires_at` they count against capacity like a confirmed booking.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct SeatHold {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub departure_id: i32,
+    #[graphql(skip)]
+    pub user_id: String,
+    pub seats: i32,
+    pub status: SeatHoldStatus,
+    pub booking_id: Option<i32>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SeatHold {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.status == SeatHoldStatus::Active && self.expires_at > now
+    }
+}
@@ -0,0 +1,94 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::models::money::to_minor_units;
+use crate::schema::validation::{validate_max_participants, validate_price, ValidationErrors};
+
+pub const MAX_DEPARTURES_PAGE_SIZE: usize = 366;
+
+#[derive(Enum, Serialize, Deserialize, sqlx::Type, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum DepartureStatus {
+    Scheduled,
+    Cancelled,
+}
+
+// `capacity` and `price` override the tour's `maxParticipants` and `price` when set.
+// The price is in the tour's currency.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct Departure {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub tour_id: i32,
+    pub schedule_id: Option<i32>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub capacity: Option<i32>,
+    #[graphql(skip)]
+    pub price_minor: Option<i64>,
+    pub status: DepartureStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct CreateDepartureInput {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub capacity: Option<i32>,
+    // In the tour's currency.
+    pub price: Option<Decimal>,
+}
+
+impl CreateDepartureInput {
+    pub fn validate(&self, now: DateTime<Utc>, currency: &str) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        validate_schedule(&mut errors, self.starts_at, self.ends_at, now);
+        if let Some(capacity) = self.capacity {
+            validate_max_participants(&mut errors, "capacity", capacity);
+        }
+        if let Some(price) = self.price {
+            validate_price(&mut errors, "price", price, currency);
+        }
+
+        errors.into_result()
+    }
+
+    pub fn price_minor(&self, currency: &str) -> Option<i64> {
+        to_minor_units(self.price, currency)
+    }
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct RescheduleDepartureInput {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl RescheduleDepartureInput {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        validate_schedule(&mut errors, self.starts_at, self.ends_at, now);
+
+        errors.into_result()
+    }
+}
+
+fn validate_schedule(
+    errors: &mut ValidationErrors,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) {
+    if starts_at <= now {
+        errors.add("startsAt", "must be in the future");
+    }
+    if ends_at <= starts_at {
+        errors.add("endsAt", "must be after startsAt");
+    }
+}
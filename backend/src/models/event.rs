@@ -0,0 +1,181 @@
+use async_graphql::{Enum, SimpleObject};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::models::booking::{Booking, BookingStatus};
+use crate::models::departure::Departure;
+use crate::models::tour::Tour;
+use crate::models::user::CurrentUser;
+use crate::models::waitlist::WaitlistEntry;
+
+#[derive(Enum, Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TourChangeOperation {
+    #[serde(rename = "insert")]
+    Created,
+    #[serde(rename = "update")]
+    Updated,
+    #[serde(rename = "delete")]
+    Deleted,
+}
+
+// Payload sent by the `notify_tour_change` trigger.
+#[derive(Deserialize, Debug)]
+pub struct TourNotification {
+    pub operation: TourChangeOperation,
+    pub tour_id: i32,
+}
+
+// Payload sent by the `notify_booking_change` trigger.
+#[derive(Deserialize, Debug)]
+pub struct BookingNotification {
+    pub booking_id: i32,
+}
+
+// Payload sent by the `notify_waitlist_change` trigger when seats are offered.
+#[derive(Deserialize, Debug)]
+pub struct WaitlistNotification {
+    pub entry_id: i32,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DepartureChangeOperation {
+    Cancelled,
+    Rescheduled,
+}
+
+// Payload sent by the `notify_departure_change` trigger.
+#[derive(Deserialize, Debug)]
+pub struct DepartureNotification {
+    pub operation: DepartureChangeOperation,
+    pub departure_id: i32,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct TourChange {
+    pub operation: TourChangeOperation,
+    pub tour_id: i32,
+    pub tour: Option<Tour>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ServerEventKind {
+    TourCreated,
+    TourUpdated,
+    BookingConfirmed,
+    BookingCancelled,
+    DepartureCancelled,
+    DepartureRescheduled,
+    WaitlistOffered,
+}
+
+impl ServerEventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ServerEventKind::TourCreated => "tour.created",
+            ServerEventKind::TourUpdated => "tour.updated",
+            ServerEventKind::BookingConfirmed => "booking.confirmed",
+            ServerEventKind::BookingCancelled => "booking.cancelled",
+            ServerEventKind::DepartureCancelled => "departure.cancelled",
+            ServerEventKind::DepartureRescheduled => "departure.rescheduled",
+            ServerEventKind::WaitlistOffered => "waitlist.offered",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventAudience {
+    Public,
+    Admins,
+    // The user with this id, and admins.
+    User(String),
+}
+
+impl EventAudience {
+    pub fn includes(&self, user: Option<&CurrentUser>) -> bool {
+        match self {
+            EventAudience::Public => true,
+            EventAudience::Admins => user.is_some_and(|user| user.is_admin),
+            EventAudience::User(user_id) => {
+                user.is_some_and(|user| &user.id == user_id || user.is_admin)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ServerEvent {
+    pub id: u64,
+    pub kind: ServerEventKind,
+    pub audience: EventAudience,
+    pub data: serde_json::Value,
+    pub created_at: Instant,
+}
+
+impl ServerEvent {
+    pub fn from_tour_change(
+        change: &TourChange,
+    ) -> Option<(ServerEventKind, EventAudience, serde_json::Value)> {
+        let kind = match change.operation {
+            TourChangeOperation::Created => ServerEventKind::TourCreated,
+            TourChangeOperation::Updated => ServerEventKind::TourUpdated,
+            TourChangeOperation::Deleted => return None,
+        };
+        let tour = change.tour.as_ref()?;
+        // Only admins hear about inactive tours, like in listings (see
+        // `Tour::is_listed_for`).
+        let audience = if tour.is_active {
+            EventAudience::Public
+        } else {
+            EventAudience::Admins
+        };
+
+        Some((kind, audience, serde_json::to_value(tour).ok()?))
+    }
+
+    pub fn from_booking(
+        booking: &Booking,
+    ) -> Option<(ServerEventKind, EventAudience, serde_json::Value)> {
+        let kind = match booking.status {
+            BookingStatus::Confirmed => ServerEventKind::BookingConfirmed,
+            BookingStatus::Cancelled => ServerEventKind::BookingCancelled,
+        };
+
+        Some((
+            kind,
+            EventAudience::User(booking.user_id.clone()),
+            serde_json::to_value(booking).ok()?,
+        ))
+    }
+
+    // Sent to the owner of each booking on a departure that was changed.
+    pub fn from_departure_change(
+        operation: DepartureChangeOperation,
+        departure: &Departure,
+        booking: &Booking,
+    ) -> Option<(ServerEventKind, EventAudience, serde_json::Value)> {
+        let kind = match operation {
+            DepartureChangeOperation::Cancelled => ServerEventKind::DepartureCancelled,
+            DepartureChangeOperation::Rescheduled => ServerEventKind::DepartureRescheduled,
+        };
+
+        Some((
+            kind,
+            EventAudience::User(booking.user_id.clone()),
+            serde_json::json!({ "departure": departure, "booking": booking }),
+        ))
+    }
+
+    // Sent to the user the seats are offered to, with the link to claim them.
+    pub fn from_waitlist_offer(
+        entry: &WaitlistEntry,
+        claim_url: &str,
+    ) -> Option<(ServerEventKind, EventAudience, serde_json::Value)> {
+        Some((
+            ServerEventKind::WaitlistOffered,
+            EventAudience::User(entry.user_id.clone()),
+            serde_json::json!({ "entry": entry, "claimUrl": claim_url }),
+        ))
+    }
+}
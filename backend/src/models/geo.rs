@@ -0,0 +1,253 @@
+use async_graphql::{InputObject, SimpleObject};
+use sqlx::FromRow;
+
+use crate::models::tour::Tour;
+use crate::schema::validation::ValidationErrors;
+
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+pub const MAX_RADIUS_KM: f64 = 20_000.0;
+
+#[cfg(test)]
+pub fn haversine_km(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let delta_latitude = (latitude2 - latitude1).to_radians();
+    let delta_longitude = (longitude2 - longitude1).to_radians();
+
+    let a = (delta_latitude / 2.0).sin().powi(2)
+        + latitude1.to_radians().cos()
+            * latitude2.to_radians().cos()
+            * (delta_longitude / 2.0).sin().powi(2);
+
+    // Rounding can push `a` just past 1 for antipodal points.
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+pub fn validate_latitude(errors: &mut ValidationErrors, field: &str, latitude: f64) {
+    if !(-90.0..=90.0).contains(&latitude) {
+        errors.add(field, "must be between -90 and 90");
+    }
+}
+
+pub fn validate_longitude(errors: &mut ValidationErrors, field: &str, longitude: f64) {
+    if !(-180.0..=180.0).contains(&longitude) {
+        errors.add(field, "must be between -180 and 180");
+    }
+}
+
+pub fn validate_coordinates(
+    errors: &mut ValidationErrors,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+) {
+    match (latitude, longitude) {
+        (Some(latitude), Some(longitude)) => {
+            validate_latitude(errors, "latitude", latitude);
+            validate_longitude(errors, "longitude", longitude);
+        }
+        (Some(_), None) => errors.add("longitude", "must be set together with latitude"),
+        (None, Some(_)) => errors.add("latitude", "must be set together with longitude"),
+        (None, None) => {}
+    }
+}
+
+#[derive(InputObject, Clone, Copy, Debug)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl BoundingBox {
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        validate_latitude(&mut errors, "south", self.south);
+        validate_latitude(&mut errors, "north", self.north);
+        validate_longitude(&mut errors, "west", self.west);
+        validate_longitude(&mut errors, "east", self.east);
+        if self.north < self.south {
+            errors.add("north", "must be greater than or equal to south");
+        }
+
+        errors.into_result()
+    }
+
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.west > self.east
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        let longitude_matches = if self.crosses_antimeridian() {
+            longitude >= self.west || longitude <= self.east
+        } else {
+            (self.west..=self.east).contains(&longitude)
+        };
+
+        (self.south..=self.north).contains(&latitude) && longitude_matches
+    }
+
+    // Smallest box around a circle, used to let the coordinates index discard
+    // most rows before the exact distance is computed. The longitude half-width
+    // is taken at the latitude where the circle is widest, not at its centre.
+    pub fn around(latitude: f64, longitude: f64, radius_km: f64) -> Self {
+        let angular_radius = radius_km / EARTH_RADIUS_KM;
+        let latitude_delta = angular_radius.to_degrees();
+        let south = (latitude - latitude_delta).max(-90.0);
+        let north = (latitude + latitude_delta).min(90.0);
+
+        let longitude_delta = (angular_radius.sin() / latitude.to_radians().cos())
+            .asin()
+            .to_degrees();
+        if south <= -90.0
+            || north >= 90.0
+            || !longitude_delta.is_finite()
+            || longitude_delta >= 180.0
+        {
+            return Self {
+                south,
+                west: -180.0,
+                north,
+                east: 180.0,
+            };
+        }
+
+        let wrap = |longitude: f64| {
+            if longitude < -180.0 {
+                longitude + 360.0
+            } else if longitude > 180.0 {
+                longitude - 360.0
+            } else {
+                longitude
+            }
+        };
+
+        Self {
+            south,
+            west: wrap(longitude - longitude_delta),
+            north,
+            east: wrap(longitude + longitude_delta),
+        }
+    }
+}
+
+#[derive(SimpleObject, FromRow, Clone, Debug)]
+pub struct NearbyTour {
+    #[sqlx(flatten)]
+    pub tour: Tour,
+    pub distance_km: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {} to be within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    // Point reached by travelling `distance_km` from the origin on `bearing`.
+    fn destination(latitude: f64, longitude: f64, bearing: f64, distance_km: f64) -> (f64, f64) {
+        let angular_distance = distance_km / EARTH_RADIUS_KM;
+        let (latitude, longitude, bearing) = (
+            latitude.to_radians(),
+            longitude.to_radians(),
+            bearing.to_radians(),
+        );
+
+        let destination_latitude = (latitude.sin() * angular_distance.cos()
+            + latitude.cos() * angular_distance.sin() * bearing.cos())
+        .asin();
+        let destination_longitude = longitude
+            + (bearing.sin() * angular_distance.sin() * latitude.cos())
+                .atan2(angular_distance.cos() - latitude.sin() * destination_latitude.sin());
+
+        let destination_longitude =
+            (destination_longitude.to_degrees() + 540.0).rem_euclid(360.0) - 180.0;
+        (destination_latitude.to_degrees(), destination_longitude)
+    }
+
+    fn assert_contains_circle(latitude: f64, longitude: f64, radius_km: f64) {
+        let bounding_box = BoundingBox::around(latitude, longitude, radius_km);
+
+        for bearing in (0..360).map(f64::from) {
+            for fraction in [0.25, 0.5, 0.9, 0.999] {
+                let (point_latitude, point_longitude) =
+                    destination(latitude, longitude, bearing, radius_km * fraction);
+                assert!(
+                    bounding_box.contains(point_latitude, point_longitude),
+                    "{:?} around ({}, {}) with radius {} misses ({}, {})",
+                    bounding_box,
+                    latitude,
+                    longitude,
+                    radius_km,
+                    point_latitude,
+                    point_longitude
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn haversine_matches_known_city_distances() {
+        // London - Paris, New York - Los Angeles, Sydney - Melbourne.
+        assert_close(haversine_km(51.5074, -0.1278, 48.8566, 2.3522), 343.6, 1.0);
+        assert_close(
+            haversine_km(40.7128, -74.0060, 34.0522, -118.2437),
+            3935.8,
+            1.0,
+        );
+        assert_close(
+            haversine_km(-33.8688, 151.2093, -37.8136, 144.9631),
+            713.4,
+            1.0,
+        );
+        assert_eq!(haversine_km(48.8566, 2.3522, 48.8566, 2.3522), 0.0);
+    }
+
+    #[test]
+    fn haversine_handles_antipodal_points() {
+        let distance = haversine_km(0.0, 0.0, 0.0, 180.0);
+
+        assert!(distance.is_finite());
+        assert_close(distance, std::f64::consts::PI * EARTH_RADIUS_KM, 1e-6);
+    }
+
+    #[test]
+    fn around_contains_every_point_within_the_radius() {
+        assert_contains_circle(38.7223, -9.1393, 50.0);
+        assert_contains_circle(-33.8688, 151.2093, 1000.0);
+    }
+
+    #[test]
+    fn around_contains_every_point_within_the_radius_at_high_latitudes() {
+        assert_contains_circle(69.6492, 18.9553, 300.0);
+        assert_contains_circle(80.0, 15.0, 500.0);
+        assert_contains_circle(-77.85, 166.67, 400.0);
+    }
+
+    #[test]
+    fn around_wraps_across_the_antimeridian() {
+        let bounding_box = BoundingBox::around(-17.7134, 178.065, 300.0);
+
+        assert!(bounding_box.crosses_antimeridian());
+        assert_contains_circle(-17.7134, 178.065, 300.0);
+        assert_contains_circle(65.0, -179.5, 200.0);
+    }
+
+    #[test]
+    fn around_covers_all_longitudes_when_the_circle_reaches_a_pole() {
+        let bounding_box = BoundingBox::around(85.0, 40.0, 800.0);
+
+        assert_eq!((bounding_box.west, bounding_box.east), (-180.0, 180.0));
+        assert_eq!(bounding_box.north, 90.0);
+        assert_contains_circle(85.0, 40.0, 800.0);
+        assert_contains_circle(0.0, 0.0, MAX_RADIUS_KM);
+    }
+}
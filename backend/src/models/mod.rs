@@ -0,0 +1,17 @@
+pub mod booking;
+pub mod departure;
+pub mod event;
+pub mod geo;
+pub mod money;
+pub mod node;
+pub mod pagination;
+pub mod payment;
+pub mod place;
+pub mod pricing;
+pub mod promotion;
+pub mod recurrence;
+pub mod schedule;
+pub mod search;
+pub mod tour;
+pub mod user;
+pub mod waitlist;
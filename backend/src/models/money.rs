@@ -0,0 +1,245 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::schema::validation::ValidationErrors;
+
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+// Converted amounts are rounded to the target currency's minor unit with halves
+// going to the even neighbour, so rounding errors don't drift in one direction.
+// Amounts that are entered are never rounded; extra decimal places are rejected.
+pub const CONVERSION_ROUNDING: RoundingStrategy = RoundingStrategy::MidpointNearestEven;
+
+// ISO 4217 codes prices can be set in, with the number of decimal places in each
+// currency's minor unit.
+const CURRENCIES: &[(&str, u32)] = &[
+    ("AUD", 2),
+    ("BHD", 3),
+    ("CAD", 2),
+    ("CHF", 2),
+    ("CNY", 2),
+    ("CZK", 2),
+    ("DKK", 2),
+    ("EUR", 2),
+    ("GBP", 2),
+    ("HUF", 2),
+    ("ISK", 0),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("MXN", 2),
+    ("NOK", 2),
+    ("NZD", 2),
+    ("PLN", 2),
+    ("SEK", 2),
+    ("TRY", 2),
+    ("UAH", 2),
+    ("USD", 2),
+];
+
+pub fn minor_unit_digits(currency: &str) -> Option<u32> {
+    CURRENCIES
+        .iter()
+        .find(|(code, _)| *code == currency)
+        .map(|(_, digits)| *digits)
+}
+
+pub fn validate_currency(errors: &mut ValidationErrors, field: &str, currency: &str) {
+    if minor_unit_digits(currency).is_none() {
+        errors.add(field, "must be a supported ISO 4217 currency code");
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum MoneyError {
+    #[error("{0} is not a supported currency")]
+    UnsupportedCurrency(String),
+    #[error("must have at most {0} decimal places")]
+    TooPrecise(u32),
+    #[error("is out of range")]
+    OutOfRange,
+}
+
+// An amount in the smallest unit of its currency, e.g. cents for USD.
+#[derive(SimpleObject, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[graphql(complex)]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: &str) -> Self {
+        Self {
+            amount_minor,
+            currency: currency.to_string(),
+        }
+    }
+
+    // Exact: amounts finer than the currency's minor unit are an error.
+    pub fn from_decimal(amount: Decimal, currency: &str) -> Result<Self, MoneyError> {
+        let digits = digits_of(currency)?;
+        let normalized = amount.normalize();
+        if normalized.scale() > digits {
+            return Err(MoneyError::TooPrecise(digits));
+        }
+
+        Self::from_exact(normalized, currency, digits)
+    }
+
+    // Rounds to the currency's minor unit with `CONVERSION_ROUNDING`.
+    pub fn from_decimal_rounded(amount: Decimal, currency: &str) -> Result<Self, MoneyError> {
+        let digits = digits_of(currency)?;
+        let rounded = amount.round_dp_with_strategy(digits, CONVERSION_ROUNDING);
+
+        Self::from_exact(rounded, currency, digits)
+    }
+
+    pub fn as_decimal(&self) -> Decimal {
+        let digits = minor_unit_digits(&self.currency).unwrap_or(0);
+        Decimal::new(self.amount_minor, digits)
+    }
+
+    fn from_exact(amount: Decimal, currency: &str, digits: u32) -> Result<Self, MoneyError> {
+        let amount_minor = amount
+            .checked_mul(Decimal::from(10_i64.pow(digits)))
+            .and_then(|amount_minor| i64::try_from(amount_minor.trunc()).ok())
+            .ok_or(MoneyError::OutOfRange)?;
+
+        Ok(Self::new(amount_minor, currency))
+    }
+}
+
+fn digits_of(currency: &str) -> Result<u32, MoneyError> {
+    minor_unit_digits(currency).ok_or_else(|| MoneyError::UnsupportedCurrency(currency.to_string()))
+}
+
+// Converts an optional price entered in `currency` into minor units.
+pub fn to_minor_units(price: Option<Decimal>, currency: &str) -> Option<i64> {
+    price
+        .and_then(|price| Money::from_decimal(price, currency).ok())
+        .map(|money| money.amount_minor)
+}
+
+// How many units of `quote_currency` one unit of `base_currency` buys. The inverse
+// direction is derived from the same rate when it isn't set on its own.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+pub struct ExchangeRate {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn exact_amounts_use_the_currency_minor_unit() {
+        let cases = [
+            (dec("1500"), "JPY", 1500),
+            (dec("1500.000"), "JPY", 1500),
+            (dec("12.34"), "USD", 1234),
+            (dec("12.3"), "USD", 1230),
+            (dec("-12.30"), "USD", -1230),
+            (dec("1.234"), "BHD", 1234),
+            (dec("0.001"), "BHD", 1),
+            (dec("0"), "EUR", 0),
+        ];
+
+        for (amount, currency, amount_minor) in cases {
+            assert_eq!(
+                Money::from_decimal(amount, currency),
+                Ok(Money::new(amount_minor, currency)),
+                "{} {}",
+                amount,
+                currency
+            );
+        }
+    }
+
+    #[test]
+    fn exact_amounts_reject_extra_precision() {
+        let cases = [
+            (dec("1500.5"), "JPY", MoneyError::TooPrecise(0)),
+            (dec("12.345"), "USD", MoneyError::TooPrecise(2)),
+            (dec("0.001"), "EUR", MoneyError::TooPrecise(2)),
+            (dec("1.2345"), "BHD", MoneyError::TooPrecise(3)),
+            (
+                dec("1"),
+                "XBT",
+                MoneyError::UnsupportedCurrency("XBT".to_string()),
+            ),
+            (
+                dec("1"),
+                "usd",
+                MoneyError::UnsupportedCurrency("usd".to_string()),
+            ),
+            (dec("100000000000000000"), "USD", MoneyError::OutOfRange),
+        ];
+
+        for (amount, currency, error) in cases {
+            assert_eq!(
+                Money::from_decimal(amount, currency),
+                Err(error),
+                "{} {}",
+                amount,
+                currency
+            );
+        }
+    }
+
+    #[test]
+    fn rounded_amounts_go_to_the_even_neighbour_at_midpoints() {
+        let cases = [
+            (dec("0.125"), "USD", 12),
+            (dec("0.135"), "USD", 14),
+            (dec("0.145"), "USD", 14),
+            (dec("-0.125"), "USD", -12),
+            (dec("-0.135"), "USD", -14),
+            (dec("0.1251"), "USD", 13),
+            (dec("0.1249"), "USD", 12),
+            (dec("2.5"), "JPY", 2),
+            (dec("3.5"), "JPY", 4),
+            (dec("1867.9058"), "JPY", 1868),
+            (dec("0.0005"), "BHD", 0),
+            (dec("0.0015"), "BHD", 2),
+            (dec("1.2344"), "BHD", 1234),
+            (dec("12.34"), "USD", 1234),
+        ];
+
+        for (amount, currency, amount_minor) in cases {
+            assert_eq!(
+                Money::from_decimal_rounded(amount, currency),
+                Ok(Money::new(amount_minor, currency)),
+                "{} {}",
+                amount,
+                currency
+            );
+        }
+        assert_eq!(
+            Money::from_decimal_rounded(dec("1.5"), "XBT"),
+            Err(MoneyError::UnsupportedCurrency("XBT".to_string()))
+        );
+    }
+
+    #[test]
+    fn minor_units_convert_back_to_decimals() {
+        assert_eq!(Money::new(1234, "USD").as_decimal(), dec("12.34"));
+        assert_eq!(Money::new(1500, "JPY").as_decimal(), dec("1500"));
+        assert_eq!(Money::new(1234, "BHD").as_decimal(), dec("1.234"));
+
+        assert_eq!(to_minor_units(Some(dec("9.99")), "EUR"), Some(999));
+        assert_eq!(to_minor_units(Some(dec("9.999")), "EUR"), None);
+        assert_eq!(to_minor_units(None, "EUR"), None);
+    }
+}
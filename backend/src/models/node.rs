@@ -0,0 +1,123 @@
+use async_graphql::{Interface, ID};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::models::booking::{Booking, SeatHold};
+use crate::models::departure::Departure;
+use crate::models::payment::Payment;
+use crate::models::place::Place;
+use crate::models::pricing::PricingRule;
+use crate::models::promotion::Promotion;
+use crate::models::schedule::{BlackoutCalendar, BlackoutDate, DepartureSchedule};
+use crate::models::tour::Tour;
+use crate::models::waitlist::WaitlistEntry;
+
+// Every type with a global id, so each id handed out can be refetched.
+#[derive(Interface, Clone)]
+#[graphql(field(name = "id", method = "global_id", ty = "ID"))]
+pub enum Node {
+    Tour(Tour),
+    Place(Place),
+    Departure(Departure),
+    DepartureSchedule(DepartureSchedule),
+    BlackoutCalendar(BlackoutCalendar),
+    BlackoutDate(BlackoutDate),
+    Booking(Booking),
+    SeatHold(SeatHold),
+    WaitlistEntry(WaitlistEntry),
+    Payment(Payment),
+    PricingRule(PricingRule),
+    Promotion(Promotion),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NodeType {
+    Tour,
+    Place,
+    Departure,
+    DepartureSchedule,
+    BlackoutCalendar,
+    BlackoutDate,
+    Booking,
+    SeatHold,
+    WaitlistEntry,
+    Payment,
+    PricingRule,
+    Promotion,
+}
+
+impl NodeType {
+    fn name(self) -> &'static str {
+        match self {
+            NodeType::Tour => "Tour",
+            NodeType::Place => "Place",
+            NodeType::Departure => "Departure",
+            NodeType::DepartureSchedule => "DepartureSchedule",
+            NodeType::BlackoutCalendar => "BlackoutCalendar",
+            NodeType::BlackoutDate => "BlackoutDate",
+            NodeType::Booking => "Booking",
+            NodeType::SeatHold => "SeatHold",
+            NodeType::WaitlistEntry => "WaitlistEntry",
+            NodeType::Payment => "Payment",
+            NodeType::PricingRule => "PricingRule",
+            NodeType::Promotion => "Promotion",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Tour" => Some(NodeType::Tour),
+            "Place" => Some(NodeType::Place),
+            "Departure" => Some(NodeType::Departure),
+            "DepartureSchedule" => Some(NodeType::DepartureSchedule),
+            "BlackoutCalendar" => Some(NodeType::BlackoutCalendar),
+            "BlackoutDate" => Some(NodeType::BlackoutDate),
+            "Booking" => Some(NodeType::Booking),
+            "SeatHold" => Some(NodeType::SeatHold),
+            "WaitlistEntry" => Some(NodeType::WaitlistEntry),
+            "Payment" => Some(NodeType::Payment),
+            "PricingRule" => Some(NodeType::PricingRule),
+            "Promotion" => Some(NodeType::Promotion),
+            _ => None,
+        }
+    }
+}
+
+// Relay global ids: base64 of `<TypeName>:<database id>`, e.g. `Tour:42`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlobalId {
+    pub node_type: NodeType,
+    pub id: i32,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid global id")]
+pub struct InvalidGlobalId;
+
+impl GlobalId {
+    pub fn new(node_type: NodeType, id: i32) -> Self {
+        Self { node_type, id }
+    }
+
+    pub fn encode(self) -> ID {
+        ID(STANDARD.encode(format!("{}:{}", self.node_type.name(), self.id)))
+    }
+
+    pub fn decode(id: &str) -> Result<Self, InvalidGlobalId> {
+        let decoded = STANDARD.decode(id).map_err(|_| InvalidGlobalId)?;
+        let decoded = String::from_utf8(decoded).map_err(|_| InvalidGlobalId)?;
+        let (name, id) = decoded.split_once(':').ok_or(InvalidGlobalId)?;
+
+        Ok(Self {
+            node_type: NodeType::from_name(name).ok_or(InvalidGlobalId)?,
+            id: id.parse().map_err(|_| InvalidGlobalId)?,
+        })
+    }
+
+    pub fn decode_as(id: &str, node_type: NodeType) -> Result<i32, InvalidGlobalId> {
+        match Self::decode(id)? {
+            global_id if global_id.node_type == node_type => Ok(global_id.id),
+            _ => Err(InvalidGlobalId),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct PageRequest<C> {
+    pub after: Option<C>,
+    pub before: Option<C>,
+    pub first: Option<usize>,
+    pub last: Option<usize>,
+}
+
+impl<C> PageRequest<C> {
+    pub fn forward(after: Option<C>, first: Option<usize>) -> Self {
+        Self {
+            after,
+            before: None,
+            first,
+            last: None,
+        }
+    }
+
+    pub fn is_backward(&self) -> bool {
+        self.first.is_none() && self.last.is_some()
+    }
+
+    pub fn limit(&self) -> usize {
+        self.first
+            .or(self.last)
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .min(MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub has_previous_page: bool,
+    pub has_next_page: bool,
+    pub total_count: i64,
+}
+
+impl<T> Page<T> {
+    // `items` holds up to `limit + 1` rows in query order; the extra row only
+    // signals that another page exists in the direction of travel.
+    pub fn from_query<C>(
+        mut items: Vec<T>,
+        page_request: &PageRequest<C>,
+        total_count: i64,
+    ) -> Self {
+        let limit = page_request.limit();
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+
+        if page_request.is_backward() {
+            items.reverse();
+
+            Self {
+                items,
+                has_previous_page: has_more,
+                has_next_page: page_request.before.is_some(),
+                total_count,
+            }
+        } else {
+            Self {
+                items,
+                has_previous_page: page_request.after.is_some(),
+                has_next_page: has_more,
+                total_count,
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Enum, Serialize, Deserialize, sqlx::Type, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum PaymentStatus {
+    Pending,
+    Authorized,
+    Captured,
+    Refunded,
+    Failed,
+}
+
+impl PaymentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Authorized => "authorized",
+            PaymentStatus::Captured => "captured",
+            PaymentStatus::Refunded => "refunded",
+            PaymentStatus::Failed => "failed",
+        }
+    }
+
+    // The statuses a payment can move to this one from.
+    pub fn sources(self) -> &'static [PaymentStatus] {
+        match self {
+            PaymentStatus::Pending => &[],
+            PaymentStatus::Authorized => &[PaymentStatus::Pending],
+            PaymentStatus::Captured => &[PaymentStatus::Authorized],
+            PaymentStatus::Refunded => &[PaymentStatus::Captured],
+            PaymentStatus::Failed => &[PaymentStatus::Pending, PaymentStatus::Authorized],
+        }
+    }
+
+    pub fn can_transition_to(self, next: PaymentStatus) -> bool {
+        next.sources().contains(&self)
+    }
+}
+
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct Payment {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub booking_id: i32,
+    pub provider: String,
+    #[graphql(skip)]
+    pub provider_payment_id: Option<String>,
+    #[graphql(skip)]
+    pub amount_minor: i64,
+    #[graphql(skip)]
+    pub currency: String,
+    pub status: PaymentStatus,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A status change reported by the provider through a verified webhook.
+#[derive(Clone, Debug)]
+pub struct PaymentWebhookEvent {
+    pub id: String,
+    pub provider_payment_id: String,
+    pub status: PaymentStatus,
+    pub failure_reason: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum WebhookOutcome {
+    Applied(Payment),
+    // The payment is unknown or can't move to the reported status, e.g. when
+    // events arrive out of order.
+    Ignored,
+    // The event was handled before.
+    Duplicate,
+}
+
+impl WebhookOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookOutcome::Applied(_) => "applied",
+            WebhookOutcome::Ignored => "ignored",
+            WebhookOutcome::Duplicate => "duplicate",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PaymentStatus::*;
+
+    #[test]
+    fn payments_only_move_along_the_state_machine() {
+        let statuses = [Pending, Authorized, Captured, Refunded, Failed];
+        let allowed = [
+            (Pending, Authorized),
+            (Pending, Failed),
+            (Authorized, Captured),
+            (Authorized, Failed),
+            (Captured, Refunded),
+        ];
+
+        for from in statuses {
+            for to in statuses {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{} -> {}",
+                    from.as_str(),
+                    to.as_str()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use async_graphql::{InputObject, MaybeUndefined, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::models::geo::validate_coordinates;
+use crate::models::tour::patched_value;
+use crate::schema::validation::{validate_slug, validate_title, ValidationErrors};
+
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct Place {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub country_code: Option<String>,
+    pub region: Option<String>,
+    pub timezone: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub aliases: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(FromRow, Clone, Debug)]
+pub struct PlaceMatch {
+    #[sqlx(flatten)]
+    pub place: Place,
+    pub score: f32,
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct CreatePlaceInput {
+    pub name: String,
+    pub slug: Option<String>,
+    pub country_code: Option<String>,
+    pub region: Option<String>,
+    pub timezone: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    #[graphql(default)]
+    pub aliases: Vec<String>,
+}
+
+impl CreatePlaceInput {
+    pub fn normalize(mut self) -> Self {
+        self.slug = Some(
+            self.slug
+                .as_deref()
+                .map(str::trim)
+                .filter(|slug| !slug.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| slug::slugify(&self.name)),
+        );
+        self.country_code = self
+            .country_code
+            .map(|country_code| country_code.trim().to_uppercase());
+        self.aliases = normalize_aliases(self.aliases);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        validate_title(&mut errors, "name", &self.name);
+        if let Some(slug) = &self.slug {
+            validate_slug(&mut errors, "slug", slug);
+        }
+        if let Some(country_code) = &self.country_code {
+            validate_country_code(&mut errors, country_code);
+        }
+        if let Some(timezone) = &self.timezone {
+            validate_timezone(&mut errors, "timezone", timezone);
+        }
+        validate_coordinates(&mut errors, self.latitude, self.longitude);
+
+        errors.into_result()
+    }
+}
+
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct UpdatePlaceInput {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub country_code: MaybeUndefined<String>,
+    pub region: MaybeUndefined<String>,
+    pub timezone: MaybeUndefined<String>,
+    pub latitude: MaybeUndefined<f64>,
+    pub longitude: MaybeUndefined<f64>,
+    pub aliases: Option<Vec<String>>,
+}
+
+impl UpdatePlaceInput {
+    pub fn normalize(mut self) -> Self {
+        self.slug = self.slug.map(|slug| slug.trim().to_string());
+        self.country_code = self
+            .country_code
+            .map_value(|country_code| country_code.trim().to_uppercase());
+        self.aliases = self.aliases.map(normalize_aliases);
+        self
+    }
+
+    pub fn validate(&self, place: &Place) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let Some(name) = &self.name {
+            validate_title(&mut errors, "name", name);
+        }
+        if let Some(slug) = &self.slug {
+            validate_slug(&mut errors, "slug", slug);
+        }
+        if let Some(country_code) = self.country_code.value() {
+            validate_country_code(&mut errors, country_code);
+        }
+        if let Some(timezone) = self.timezone.value() {
+            validate_timezone(&mut errors, "timezone", timezone);
+        }
+        validate_coordinates(
+            &mut errors,
+            patched_value(&self.latitude, place.latitude),
+            patched_value(&self.longitude, place.longitude),
+        );
+
+        errors.into_result()
+    }
+}
+
+fn normalize_aliases(aliases: Vec<String>) -> Vec<String> {
+    let mut aliases: Vec<String> = aliases
+        .into_iter()
+        .map(|alias| alias.trim().to_string())
+        .filter(|alias| !alias.is_empty())
+        .collect();
+    aliases.sort();
+    aliases.dedup();
+    aliases
+}
+
+fn validate_country_code(errors: &mut ValidationErrors, country_code: &str) {
+    if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_uppercase()) {
+        errors.add("countryCode", "must be an ISO 3166-1 alpha-2 code");
+    }
+}
+
+pub fn validate_timezone(errors: &mut ValidationErrors, field: &str, timezone: &str) {
+    if timezone.parse::<chrono_tz::Tz>().is_err() {
+        errors.add(field, "must be an IANA time zone name");
+    }
+}
@@ -0,0 +1,921 @@
+use async_graphql::{Enum, InputObject, MaybeUndefined, OneofObject, SimpleObject};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::cmp::Reverse;
+
+use crate::models::booking::{validate_participants, MAX_PARTICIPANTS_PER_BOOKING};
+use crate::models::money::{Money, MoneyError};
+use crate::schema::validation::{validate_title, ValidationErrors};
+
+// Percentage adjustments are rounded to whole minor units per participant, with
+// halves away from zero, so a line's amount is always its unit amount times its
+// quantity.
+pub const ADJUSTMENT_ROUNDING: RoundingStrategy = RoundingStrategy::MidpointAwayFromZero;
+pub(crate) const MAX_PERCENT_DECIMAL_PLACES: u32 = 4;
+const MAX_PERCENT: i64 = 1000;
+
+#[derive(Enum, Serialize, Deserialize, sqlx::Type, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum PriceCategory {
+    Adult,
+    Child,
+    Senior,
+    Student,
+}
+
+impl PriceCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            PriceCategory::Adult => "Adult",
+            PriceCategory::Child => "Child",
+            PriceCategory::Senior => "Senior",
+            PriceCategory::Student => "Student",
+        }
+    }
+}
+
+#[derive(Enum, Serialize, Deserialize, sqlx::Type, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum PriceLineKind {
+    Base,
+    Adjustment,
+    Discount,
+}
+
+// A category the tour offers besides adults. Without a price of its own it pays
+// the adult price, which rules for the category can then adjust.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct TourPriceCategory {
+    #[graphql(skip)]
+    pub tour_id: i32,
+    pub category: PriceCategory,
+    #[graphql(skip)]
+    pub price_minor: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// When a rule applies. Conditions that aren't set always match; dates and
+// weekdays are those of the departure in the tour's time zone.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug, Default)]
+pub struct PricingConditions {
+    pub starts_on: Option<NaiveDate>,
+    pub ends_on: Option<NaiveDate>,
+    // ISO weekdays, 1 for Monday to 7 for Sunday; empty for every day.
+    pub weekdays: Vec<i32>,
+    // Whole days between the quote and the departure.
+    pub min_lead_days: Option<i32>,
+    pub max_lead_days: Option<i32>,
+    // Participants in the party across all categories.
+    pub min_group_size: Option<i32>,
+    pub max_group_size: Option<i32>,
+}
+
+impl PricingConditions {
+    fn matches(&self, date: NaiveDate, lead_days: i64, group_size: i32) -> bool {
+        let weekday = date.weekday().number_from_monday() as i32;
+
+        self.starts_on.is_none_or(|starts_on| date >= starts_on)
+            && self.ends_on.is_none_or(|ends_on| date <= ends_on)
+            && (self.weekdays.is_empty() || self.weekdays.contains(&weekday))
+            && self
+                .min_lead_days
+                .is_none_or(|min_lead_days| lead_days >= i64::from(min_lead_days))
+            && self
+                .max_lead_days
+                .is_none_or(|max_lead_days| lead_days <= i64::from(max_lead_days))
+            && self
+                .min_group_size
+                .is_none_or(|min_group_size| group_size >= min_group_size)
+            && self
+                .max_group_size
+                .is_none_or(|max_group_size| group_size <= max_group_size)
+    }
+}
+
+// Changes the per-participant price by `percent` or by `amount` in the tour's
+// currency; both are negative for discounts. Matching rules apply in descending
+// priority, each to the price left by the ones before it. A rule that isn't
+// stackable only applies on its own: it's skipped once another rule has applied,
+// and no rule applies after it.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct PricingRule {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub tour_id: i32,
+    pub name: String,
+    pub priority: i32,
+    pub is_stackable: bool,
+    pub is_active: bool,
+    // Null when the rule applies to every category.
+    pub category: Option<PriceCategory>,
+    pub percent: Option<Decimal>,
+    #[graphql(skip)]
+    pub amount_minor: Option<i64>,
+    #[sqlx(flatten)]
+    pub conditions: PricingConditions,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PricingRule {
+    fn applies_to(&self, category: PriceCategory) -> bool {
+        self.category
+            .is_none_or(|rule_category| rule_category == category)
+    }
+
+    // The change to a unit price, which never takes it below zero.
+    fn adjustment(&self, unit_amount_minor: i64) -> Option<i64> {
+        let adjustment = match (self.percent, self.amount_minor) {
+            (Some(percent), _) => Decimal::from(unit_amount_minor)
+                .checked_mul(percent)
+                .and_then(|amount| amount.checked_div(Decimal::ONE_HUNDRED))
+                .map(|amount| amount.round_dp_with_strategy(0, ADJUSTMENT_ROUNDING))
+                .and_then(|amount| i64::try_from(amount).ok())?,
+            (None, Some(amount_minor)) => amount_minor,
+            (None, None) => 0,
+        };
+
+        Some(adjustment.max(-unit_amount_minor))
+    }
+}
+
+#[derive(InputObject, Copy, Clone, Debug)]
+pub struct ParticipantsInput {
+    pub category: PriceCategory,
+    pub count: i32,
+}
+
+pub fn adults(count: i32) -> Vec<ParticipantsInput> {
+    vec![ParticipantsInput {
+        category: PriceCategory::Adult,
+        count,
+    }]
+}
+
+pub fn party_size(party: &[ParticipantsInput]) -> i32 {
+    party.iter().fold(0_i32, |size, participants| {
+        size.saturating_add(participants.count)
+    })
+}
+
+pub fn validate_party(errors: &mut ValidationErrors, field: &str, party: &[ParticipantsInput]) {
+    if party.iter().any(|participants| participants.count < 1) {
+        errors.add(field, "must have a count of at least 1 in each category");
+        return;
+    }
+    let has_duplicates = party.iter().enumerate().any(|(index, participants)| {
+        party[..index]
+            .iter()
+            .any(|other| other.category == participants.category)
+    });
+    if has_duplicates {
+        errors.add(field, "must list each category once");
+        return;
+    }
+    validate_participants(errors, field, party_size(party));
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum PricingError {
+    #[error("include the {} category, which the tour doesn't offer", .0.label().to_lowercase())]
+    CategoryUnavailable(PriceCategory),
+    #[error("make the price out of range")]
+    OutOfRange,
+}
+
+// Everything the price of a departure depends on.
+#[derive(Clone, Debug)]
+pub struct PriceList {
+    pub departure_id: i32,
+    pub tour_id: i32,
+    pub starts_at: DateTime<Utc>,
+    pub timezone: Tz,
+    pub currency: String,
+    // The departure's price, or the tour's; tours without a price are free.
+    pub price_minor: Option<i64>,
+    pub categories: Vec<TourPriceCategory>,
+    pub rules: Vec<PricingRule>,
+}
+
+impl PriceList {
+    pub fn quote(
+        &self,
+        party: &[ParticipantsInput],
+        now: DateTime<Utc>,
+    ) -> Result<Quote, PricingError> {
+        let participants = party_size(party);
+        let date = self.starts_at.with_timezone(&self.timezone).date_naive();
+        let lead_days = (self.starts_at - now).num_days();
+
+        let mut rules = self
+            .rules
+            .iter()
+            .filter(|rule| rule.is_active && rule.conditions.matches(date, lead_days, participants))
+            .collect::<Vec<_>>();
+        rules.sort_by_key(|rule| (Reverse(rule.priority), rule.id));
+
+        let mut lines = Vec::new();
+        for group in party {
+            let mut unit_amount_minor = self.unit_price(group.category)?;
+            lines.push(self.line(
+                PriceLineKind::Base,
+                group,
+                group.category.label(),
+                None,
+                unit_amount_minor,
+            )?);
+
+            let category_rules = rules.iter().filter(|rule| rule.applies_to(group.category));
+            for rule in stack(category_rules.copied()) {
+                let adjustment = rule
+                    .adjustment(unit_amount_minor)
+                    .ok_or(PricingError::OutOfRange)?;
+                if adjustment == 0 {
+                    continue;
+                }
+                unit_amount_minor += adjustment;
+                lines.push(self.line(
+                    PriceLineKind::Adjustment,
+                    group,
+                    &rule.name,
+                    Some(rule.id),
+                    adjustment,
+                )?);
+            }
+        }
+
+        let total_minor = lines
+            .iter()
+            .try_fold(0_i64, |total, line| total.checked_add(line.amount_minor))
+            .ok_or(PricingError::OutOfRange)?;
+
+        Ok(Quote {
+            departure_id: self.departure_id,
+            tour_id: self.tour_id,
+            participants,
+            lines,
+            total_minor,
+            currency: self.currency.clone(),
+            promotion_id: None,
+            discount_minor: 0,
+        })
+    }
+
+    fn unit_price(&self, category: PriceCategory) -> Result<i64, PricingError> {
+        if category == PriceCategory::Adult {
+            return Ok(self.price_minor.unwrap_or(0));
+        }
+
+        self.categories
+            .iter()
+            .find(|tour_category| tour_category.category == category)
+            .map(|tour_category| tour_category.price_minor.or(self.price_minor).unwrap_or(0))
+            .ok_or(PricingError::CategoryUnavailable(category))
+    }
+
+    fn line(
+        &self,
+        kind: PriceLineKind,
+        participants: &ParticipantsInput,
+        description: &str,
+        pricing_rule_id: Option<i32>,
+        unit_amount_minor: i64,
+    ) -> Result<PriceLine, PricingError> {
+        let amount_minor = unit_amount_minor
+            .checked_mul(i64::from(participants.count))
+            .ok_or(PricingError::OutOfRange)?;
+
+        Ok(PriceLine {
+            kind,
+            category: Some(participants.category),
+            description: description.to_string(),
+            pricing_rule_id,
+            quantity: participants.count,
+            unit_amount_minor,
+            amount_minor,
+            currency: self.currency.clone(),
+        })
+    }
+}
+
+fn stack<'a>(rules: impl Iterator<Item = &'a PricingRule>) -> Vec<&'a PricingRule> {
+    let mut applied = Vec::new();
+    for rule in rules {
+        if !rule.is_stackable && !applied.is_empty() {
+            continue;
+        }
+        applied.push(rule);
+        if !rule.is_stackable {
+            break;
+        }
+    }
+
+    applied
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+#[graphql(complex)]
+pub struct Quote {
+    #[graphql(skip)]
+    pub departure_id: i32,
+    #[graphql(skip)]
+    pub tour_id: i32,
+    pub participants: i32,
+    pub lines: Vec<PriceLine>,
+    #[graphql(skip)]
+    pub total_minor: i64,
+    #[graphql(skip)]
+    pub currency: String,
+    // The promotion whose code was applied, and how much it took off.
+    #[graphql(skip)]
+    pub promotion_id: Option<i32>,
+    #[graphql(skip)]
+    pub discount_minor: i64,
+}
+
+// Base lines hold a category's price; each adjustment line follows the base line
+// of the category it adjusts, with a per-participant `unitAmount`. A promo code's
+// discount comes last, on a line of its own without a category.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct PriceLine {
+    pub kind: PriceLineKind,
+    pub category: Option<PriceCategory>,
+    pub description: String,
+    #[graphql(skip)]
+    pub pricing_rule_id: Option<i32>,
+    pub quantity: i32,
+    #[graphql(skip)]
+    pub unit_amount_minor: i64,
+    #[graphql(skip)]
+    pub amount_minor: i64,
+    #[graphql(skip)]
+    pub currency: String,
+}
+
+#[derive(OneofObject, Copy, Clone, Debug)]
+pub enum PriceAdjustmentInput {
+    // E.g. -20 for 20% off.
+    Percent(Decimal),
+    // Per participant, in the tour's currency.
+    Amount(Decimal),
+}
+
+impl PriceAdjustmentInput {
+    fn validate(&self, errors: &mut ValidationErrors, currency: &str) {
+        match *self {
+            PriceAdjustmentInput::Percent(percent) => {
+                if percent < Decimal::from(-100) || percent > Decimal::from(MAX_PERCENT) {
+                    errors.add(
+                        "adjustment",
+                        format!("must be between -100 and {} percent", MAX_PERCENT),
+                    );
+                } else if percent.normalize().scale() > MAX_PERCENT_DECIMAL_PLACES {
+                    errors.add(
+                        "adjustment",
+                        format!(
+                            "must have at most {} decimal places",
+                            MAX_PERCENT_DECIMAL_PLACES
+                        ),
+                    );
+                }
+            }
+            PriceAdjustmentInput::Amount(amount) => match Money::from_decimal(amount, currency) {
+                Ok(_) | Err(MoneyError::UnsupportedCurrency(_)) => {}
+                Err(error) => errors.add("adjustment", error.to_string()),
+            },
+        }
+    }
+
+    // The rule's `percent` and `amount_minor`.
+    pub fn into_parts(self, currency: &str) -> (Option<Decimal>, Option<i64>) {
+        match self {
+            PriceAdjustmentInput::Percent(percent) => (Some(percent.normalize()), None),
+            PriceAdjustmentInput::Amount(amount) => (
+                None,
+                Money::from_decimal(amount, currency)
+                    .ok()
+                    .map(|money| money.amount_minor),
+            ),
+        }
+    }
+}
+
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct PricingConditionsInput {
+    pub starts_on: Option<NaiveDate>,
+    pub ends_on: Option<NaiveDate>,
+    pub weekdays: Option<Vec<i32>>,
+    pub min_lead_days: Option<i32>,
+    pub max_lead_days: Option<i32>,
+    pub min_group_size: Option<i32>,
+    pub max_group_size: Option<i32>,
+}
+
+impl PricingConditionsInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let (Some(starts_on), Some(ends_on)) = (self.starts_on, self.ends_on) {
+            if ends_on < starts_on {
+                errors.add("conditions.endsOn", "must not be before startsOn");
+            }
+        }
+        if self
+            .weekdays
+            .iter()
+            .flatten()
+            .any(|weekday| !(1..=7).contains(weekday))
+        {
+            errors.add(
+                "conditions.weekdays",
+                "must be between 1 (Monday) and 7 (Sunday)",
+            );
+        }
+        validate_range(
+            errors,
+            "LeadDays",
+            self.min_lead_days,
+            self.max_lead_days,
+            0..=i32::MAX,
+        );
+        validate_range(
+            errors,
+            "GroupSize",
+            self.min_group_size,
+            self.max_group_size,
+            1..=MAX_PARTICIPANTS_PER_BOOKING,
+        );
+    }
+}
+
+impl From<PricingConditionsInput> for PricingConditions {
+    fn from(input: PricingConditionsInput) -> Self {
+        let mut weekdays = input.weekdays.unwrap_or_default();
+        weekdays.sort();
+        weekdays.dedup();
+
+        Self {
+            starts_on: input.starts_on,
+            ends_on: input.ends_on,
+            weekdays,
+            min_lead_days: input.min_lead_days,
+            max_lead_days: input.max_lead_days,
+            min_group_size: input.min_group_size,
+            max_group_size: input.max_group_size,
+        }
+    }
+}
+
+// Checks `conditions.min<name>` and `conditions.max<name>`.
+fn validate_range(
+    errors: &mut ValidationErrors,
+    name: &str,
+    min: Option<i32>,
+    max: Option<i32>,
+    bounds: std::ops::RangeInclusive<i32>,
+) {
+    for (field, value) in [("min", min), ("max", max)] {
+        if value.is_some_and(|value| !bounds.contains(&value)) {
+            let message = if *bounds.end() == i32::MAX {
+                format!("must be at least {}", bounds.start())
+            } else {
+                format!("must be between {} and {}", bounds.start(), bounds.end())
+            };
+            errors.add(&format!("conditions.{}{}", field, name), message);
+        }
+    }
+    if let (Some(min), Some(max)) = (min, max) {
+        if max < min {
+            errors.add(
+                &format!("conditions.max{}", name),
+                format!("must not be less than min{}", name),
+            );
+        }
+    }
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct CreatePricingRuleInput {
+    pub name: String,
+    pub priority: Option<i32>,
+    pub is_stackable: Option<bool>,
+    pub is_active: Option<bool>,
+    pub category: Option<PriceCategory>,
+    pub adjustment: PriceAdjustmentInput,
+    pub conditions: Option<PricingConditionsInput>,
+}
+
+impl CreatePricingRuleInput {
+    pub fn normalize(mut self) -> Self {
+        self.name = self.name.trim().to_string();
+        self
+    }
+
+    pub fn validate(&self, currency: &str) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        validate_title(&mut errors, "name", &self.name);
+        self.adjustment.validate(&mut errors, currency);
+        if let Some(conditions) = &self.conditions {
+            conditions.validate(&mut errors);
+        }
+
+        errors.into_result()
+    }
+}
+
+// `conditions` replaces all of the rule's conditions when given.
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct UpdatePricingRuleInput {
+    pub name: Option<String>,
+    pub priority: Option<i32>,
+    pub is_stackable: Option<bool>,
+    pub is_active: Option<bool>,
+    pub category: MaybeUndefined<PriceCategory>,
+    pub adjustment: Option<PriceAdjustmentInput>,
+    pub conditions: Option<PricingConditionsInput>,
+}
+
+impl UpdatePricingRuleInput {
+    pub fn normalize(mut self) -> Self {
+        self.name = self.name.map(|name| name.trim().to_string());
+        self
+    }
+
+    pub fn validate(&self, currency: &str) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &self.name {
+            validate_title(&mut errors, "name", name);
+        }
+        if let Some(adjustment) = &self.adjustment {
+            adjustment.validate(&mut errors, currency);
+        }
+        if let Some(conditions) = &self.conditions {
+            conditions.validate(&mut errors);
+        }
+
+        errors.into_result()
+    }
+
+    pub fn apply(self, rule: &mut PricingRule, currency: &str) {
+        if let Some(name) = self.name {
+            rule.name = name;
+        }
+        if let Some(priority) = self.priority {
+            rule.priority = priority;
+        }
+        if let Some(is_stackable) = self.is_stackable {
+            rule.is_stackable = is_stackable;
+        }
+        if let Some(is_active) = self.is_active {
+            rule.is_active = is_active;
+        }
+        match self.category {
+            MaybeUndefined::Undefined => {}
+            MaybeUndefined::Null => rule.category = None,
+            MaybeUndefined::Value(category) => rule.category = Some(category),
+        }
+        if let Some(adjustment) = self.adjustment {
+            (rule.percent, rule.amount_minor) = adjustment.into_parts(currency);
+        }
+        if let Some(conditions) = self.conditions {
+            rule.conditions = conditions.into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn starts_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 15, 9, 0, 0).unwrap()
+    }
+
+    fn rule(
+        id: i32,
+        priority: i32,
+        percent: Option<&str>,
+        amount_minor: Option<i64>,
+    ) -> PricingRule {
+        PricingRule {
+            id,
+            tour_id: 1,
+            name: format!("Rule {}", id),
+            priority,
+            is_stackable: true,
+            is_active: true,
+            category: None,
+            percent: percent.map(|percent| percent.parse().unwrap()),
+            amount_minor,
+            conditions: PricingConditions::default(),
+            created_at: starts_at(),
+            updated_at: starts_at(),
+        }
+    }
+
+    fn percent(id: i32, priority: i32, percent: &str) -> PricingRule {
+        rule(id, priority, Some(percent), None)
+    }
+
+    fn amount(id: i32, priority: i32, amount_minor: i64) -> PricingRule {
+        rule(id, priority, None, Some(amount_minor))
+    }
+
+    fn exclusive(rule: PricingRule) -> PricingRule {
+        PricingRule {
+            is_stackable: false,
+            ..rule
+        }
+    }
+
+    fn category(category: PriceCategory, price_minor: Option<i64>) -> TourPriceCategory {
+        TourPriceCategory {
+            tour_id: 1,
+            category,
+            price_minor,
+            created_at: starts_at(),
+            updated_at: starts_at(),
+        }
+    }
+
+    fn price_list(price_minor: Option<i64>, rules: Vec<PricingRule>) -> PriceList {
+        PriceList {
+            departure_id: 1,
+            tour_id: 1,
+            starts_at: starts_at(),
+            timezone: Tz::Europe__Lisbon,
+            currency: "EUR".to_string(),
+            price_minor,
+            categories: Vec::new(),
+            rules,
+        }
+    }
+
+    fn quote(price_list: &PriceList, party: &[ParticipantsInput]) -> Quote {
+        price_list
+            .quote(party, starts_at() - chrono::Duration::days(30))
+            .unwrap()
+    }
+
+    // Each line's description, unit amount and amount.
+    fn lines(quote: &Quote) -> Vec<(String, i64, i64)> {
+        quote
+            .lines
+            .iter()
+            .map(|line| {
+                (
+                    line.description.clone(),
+                    line.unit_amount_minor,
+                    line.amount_minor,
+                )
+            })
+            .collect()
+    }
+
+    fn line(description: &str, unit_amount_minor: i64, amount_minor: i64) -> (String, i64, i64) {
+        (description.to_string(), unit_amount_minor, amount_minor)
+    }
+
+    fn ids(rules: &[PricingRule]) -> Vec<i32> {
+        let mut rules = rules.iter().collect::<Vec<_>>();
+        rules.sort_by_key(|rule| (Reverse(rule.priority), rule.id));
+        stack(rules.into_iter())
+            .iter()
+            .map(|rule| rule.id)
+            .collect()
+    }
+
+    #[test]
+    fn rules_apply_in_descending_priority_to_the_price_left_before_them() {
+        let price_list = price_list(
+            Some(10000),
+            vec![percent(1, 1, "-10"), amount(2, 5, -1000), amount(3, 1, 250)],
+        );
+
+        let quote = quote(&price_list, &adults(2));
+        assert_eq!(
+            lines(&quote),
+            vec![
+                line("Adult", 10000, 20000),
+                line("Rule 2", -1000, -2000),
+                // Equal priorities apply in id order.
+                line("Rule 1", -900, -1800),
+                line("Rule 3", 250, 500),
+            ]
+        );
+        assert_eq!(quote.total_minor, 16700);
+        assert_eq!(quote.participants, 2);
+        assert_eq!(quote.currency, "EUR");
+    }
+
+    #[test]
+    fn inactive_and_unmatched_rules_are_skipped() {
+        let mut inactive = amount(1, 1, -100);
+        inactive.is_active = false;
+        let mut weekdays_only = amount(2, 1, -200);
+        weekdays_only.conditions.weekdays = vec![1, 2, 3, 4, 5];
+        let mut groups = amount(3, 1, -300);
+        groups.conditions.min_group_size = Some(4);
+        let mut last_minute = amount(4, 1, -400);
+        last_minute.conditions.max_lead_days = Some(7);
+        let mut june = amount(5, 1, -500);
+        june.conditions.starts_on = NaiveDate::from_ymd_opt(2024, 6, 1);
+        june.conditions.ends_on = NaiveDate::from_ymd_opt(2024, 6, 30);
+
+        let price_list = price_list(
+            Some(10000),
+            vec![inactive, weekdays_only, groups, last_minute, june],
+        );
+        assert_eq!(
+            lines(&quote(&price_list, &adults(2))),
+            vec![line("Adult", 10000, 20000), line("Rule 5", -500, -1000)]
+        );
+    }
+
+    #[test]
+    fn rules_that_are_not_stackable_apply_on_their_own() {
+        let cases: &[(Vec<PricingRule>, &[i32])] = &[
+            (
+                vec![amount(1, 3, -1), amount(2, 2, -1), amount(3, 1, -1)],
+                &[1, 2, 3],
+            ),
+            // First: no other rule applies after it.
+            (vec![exclusive(amount(1, 3, -1)), amount(2, 2, -1)], &[1]),
+            // After others: skipped, and later stackable rules still apply.
+            (
+                vec![
+                    amount(1, 3, -1),
+                    exclusive(amount(2, 2, -1)),
+                    amount(3, 1, -1),
+                ],
+                &[1, 3],
+            ),
+            (
+                vec![exclusive(amount(1, 3, -1)), exclusive(amount(2, 2, -1))],
+                &[1],
+            ),
+            (vec![amount(1, 1, -1), exclusive(amount(2, 2, -1))], &[2]),
+            (vec![], &[]),
+        ];
+
+        for (rules, expected) in cases {
+            assert_eq!(ids(rules), *expected, "{:?}", rules);
+        }
+    }
+
+    #[test]
+    fn exclusive_rules_win_over_lower_priorities_in_quotes() {
+        let price_list = price_list(
+            Some(10000),
+            vec![
+                percent(1, 1, "-50"),
+                exclusive(percent(2, 9, "-20")),
+                amount(3, 5, -100),
+            ],
+        );
+
+        assert_eq!(
+            lines(&quote(&price_list, &adults(1))),
+            vec![line("Adult", 10000, 10000), line("Rule 2", -2000, -2000)]
+        );
+    }
+
+    #[test]
+    fn categories_without_a_price_pay_the_tour_price() {
+        let mut children_half_off = percent(1, 1, "-50");
+        children_half_off.category = Some(PriceCategory::Child);
+        let mut price_list = price_list(Some(3000), vec![children_half_off]);
+        price_list.categories = vec![
+            category(PriceCategory::Child, Some(2000)),
+            category(PriceCategory::Senior, None),
+        ];
+        let party = [
+            ParticipantsInput {
+                category: PriceCategory::Adult,
+                count: 2,
+            },
+            ParticipantsInput {
+                category: PriceCategory::Child,
+                count: 1,
+            },
+            ParticipantsInput {
+                category: PriceCategory::Senior,
+                count: 1,
+            },
+        ];
+
+        let quote = quote(&price_list, &party);
+        assert_eq!(
+            lines(&quote),
+            vec![
+                line("Adult", 3000, 6000),
+                line("Child", 2000, 2000),
+                line("Rule 1", -1000, -1000),
+                line("Senior", 3000, 3000),
+            ]
+        );
+        assert_eq!(quote.total_minor, 10000);
+
+        let student = [ParticipantsInput {
+            category: PriceCategory::Student,
+            count: 1,
+        }];
+        assert_eq!(
+            price_list
+                .quote(&student, starts_at())
+                .map(|quote| quote.total_minor),
+            Err(PricingError::CategoryUnavailable(PriceCategory::Student))
+        );
+    }
+
+    #[test]
+    fn tours_without_a_price_are_free() {
+        let mut price_list = price_list(None, vec![percent(1, 1, "-10")]);
+        price_list.categories = vec![category(PriceCategory::Child, None)];
+        let party = [ParticipantsInput {
+            category: PriceCategory::Child,
+            count: 2,
+        }];
+
+        let quote = quote(&price_list, &party);
+        assert_eq!(lines(&quote), vec![line("Child", 0, 0)]);
+        assert_eq!(quote.total_minor, 0);
+    }
+
+    #[test]
+    fn percentages_round_half_away_from_zero_per_participant() {
+        let cases = [
+            ("-10", 1005, -101),
+            ("10", 1005, 101),
+            ("-10", 1004, -100),
+            ("-10", 1006, -101),
+            ("-50", 15, -8),
+            ("50", 15, 8),
+            ("-12.5", 100, -13),
+            ("0.0001", 1, 0),
+            ("-100", 999, -999),
+        ];
+        for (value, unit_amount_minor, expected) in cases {
+            assert_eq!(
+                percent(1, 1, value).adjustment(unit_amount_minor),
+                Some(expected),
+                "{}% of {}",
+                value,
+                unit_amount_minor
+            );
+        }
+
+        // The rounded unit amount is multiplied, so lines add up exactly.
+        let price_list = price_list(Some(1005), vec![percent(1, 1, "-10")]);
+        assert_eq!(
+            lines(&quote(&price_list, &adults(3))),
+            vec![line("Adult", 1005, 3015), line("Rule 1", -101, -303)]
+        );
+    }
+
+    #[test]
+    fn adjustments_never_take_the_price_below_zero() {
+        assert_eq!(amount(1, 1, -5000).adjustment(3000), Some(-3000));
+        assert_eq!(percent(1, 1, "-150").adjustment(3000), Some(-3000));
+        assert_eq!(amount(1, 1, -5000).adjustment(0), Some(0));
+        assert_eq!(rule(1, 1, None, None).adjustment(3000), Some(0));
+
+        // A surcharge after the price reached zero still applies.
+        let price_list = price_list(
+            Some(3000),
+            vec![amount(1, 3, -5000), percent(2, 2, "-10"), amount(3, 1, 500)],
+        );
+        let quote = quote(&price_list, &adults(2));
+        assert_eq!(
+            lines(&quote),
+            vec![
+                line("Adult", 3000, 6000),
+                line("Rule 1", -3000, -6000),
+                line("Rule 3", 500, 1000),
+            ]
+        );
+        assert_eq!(quote.total_minor, 1000);
+    }
+
+    #[test]
+    fn prices_out_of_range_are_rejected() {
+        assert_eq!(percent(1, 1, "1000").adjustment(i64::MAX), None);
+
+        let price_list = price_list(Some(i64::MAX / 2), vec![]);
+        assert_eq!(
+            price_list
+                .quote(&adults(3), starts_at())
+                .map(|quote| quote.total_minor),
+            Err(PricingError::OutOfRange)
+        );
+    }
+}
@@ -0,0 +1,736 @@
+use async_graphql::{InputObject, MaybeUndefined, OneofObject, SimpleObject};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::models::booking::BookingStatus;
+use crate::models::money::{to_minor_units, validate_currency, Money, MoneyError};
+use crate::models::pricing::{
+    PriceLine, PriceLineKind, Quote, ADJUSTMENT_ROUNDING, MAX_PERCENT_DECIMAL_PLACES,
+};
+use crate::models::tour::{apply_patch, patched_value};
+use crate::schema::validation::{validate_price, validate_title, ValidationErrors};
+
+const MIN_CODE_LENGTH: usize = 3;
+const MAX_CODE_LENGTH: usize = 32;
+
+// Takes `percent` off a booking's price, or a fixed amount in `currency` that it
+// never exceeds. A promotion with a currency only applies to prices in it. Limits
+// that aren't set don't apply.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct Promotion {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub is_active: bool,
+    pub percent: Option<Decimal>,
+    #[graphql(skip)]
+    pub amount_minor: Option<i64>,
+    pub currency: Option<String>,
+    #[graphql(skip)]
+    pub min_spend_minor: Option<i64>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub max_redemptions: Option<i32>,
+    pub max_redemptions_per_user: Option<i32>,
+    // Empty when the promotion applies to every tour.
+    pub tour_ids: Vec<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Promotion {
+    // Checks the promotion's limits against the quote, then takes its discount off
+    // the quote's total with a discount line.
+    pub fn apply(
+        &self,
+        quote: &mut Quote,
+        counts: RedemptionCounts,
+        now: DateTime<Utc>,
+    ) -> Result<(), PromotionError> {
+        if !self.is_active {
+            return Err(PromotionError::Invalid);
+        }
+        if self.starts_at.is_some_and(|starts_at| now < starts_at) {
+            return Err(PromotionError::NotStarted);
+        }
+        if self.ends_at.is_some_and(|ends_at| now >= ends_at) {
+            return Err(PromotionError::Expired);
+        }
+        if !self.tour_ids.is_empty() && !self.tour_ids.contains(&quote.tour_id) {
+            return Err(PromotionError::TourNotEligible);
+        }
+        if let Some(currency) = &self.currency {
+            if *currency != quote.currency {
+                return Err(PromotionError::CurrencyMismatch(currency.clone()));
+            }
+            if let Some(min_spend_minor) = self.min_spend_minor {
+                if quote.total_minor < min_spend_minor {
+                    return Err(PromotionError::MinimumSpend(Money::new(
+                        min_spend_minor,
+                        currency,
+                    )));
+                }
+            }
+        }
+        if self
+            .max_redemptions
+            .is_some_and(|max_redemptions| counts.total >= i64::from(max_redemptions))
+        {
+            return Err(PromotionError::Exhausted);
+        }
+        if let (Some(max_redemptions), Some(by_user)) =
+            (self.max_redemptions_per_user, counts.by_user)
+        {
+            if by_user >= i64::from(max_redemptions) {
+                return Err(PromotionError::UserLimitReached);
+            }
+        }
+
+        let discount_minor = self.discount(quote.total_minor);
+        if discount_minor > 0 {
+            quote.lines.push(PriceLine {
+                kind: PriceLineKind::Discount,
+                category: None,
+                description: format!("Promo code {}", self.code),
+                pricing_rule_id: None,
+                quantity: 1,
+                unit_amount_minor: -discount_minor,
+                amount_minor: -discount_minor,
+                currency: quote.currency.clone(),
+            });
+            quote.total_minor -= discount_minor;
+        }
+        quote.promotion_id = Some(self.id);
+        quote.discount_minor = discount_minor;
+
+        Ok(())
+    }
+
+    // Percentages are rounded to whole minor units with `ADJUSTMENT_ROUNDING`.
+    fn discount(&self, total_minor: i64) -> i64 {
+        let discount_minor = match (self.percent, self.amount_minor) {
+            (Some(percent), _) => {
+                let discount = (Decimal::from(total_minor) * percent / Decimal::ONE_HUNDRED)
+                    .round_dp_with_strategy(0, ADJUSTMENT_ROUNDING);
+                i64::try_from(discount).unwrap_or(total_minor)
+            }
+            (None, Some(amount_minor)) => amount_minor,
+            (None, None) => 0,
+        };
+
+        discount_minor.clamp(0, total_minor.max(0))
+    }
+}
+
+// Redemptions of a promotion by bookings that aren't cancelled. `by_user` is
+// `None` when nobody is signed in, so per-user limits aren't checked.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RedemptionCounts {
+    pub total: i64,
+    pub by_user: Option<i64>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum PromotionError {
+    #[error("is not a valid code")]
+    Invalid,
+    #[error("is not valid yet")]
+    NotStarted,
+    #[error("has expired")]
+    Expired,
+    #[error("doesn't apply to this tour")]
+    TourNotEligible,
+    #[error("only applies to prices in {0}")]
+    CurrencyMismatch(String),
+    #[error("requires spending at least {} {}", .0.as_decimal(), .0.currency)]
+    MinimumSpend(Money),
+    #[error("has been fully redeemed")]
+    Exhausted,
+    #[error("has already been used the maximum number of times")]
+    UserLimitReached,
+}
+
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct PromotionRedemption {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    #[graphql(skip)]
+    pub promotion_id: i32,
+    #[graphql(skip)]
+    pub booking_id: i32,
+    pub user_id: String,
+    #[graphql(skip)]
+    pub discount_minor: i64,
+    #[graphql(skip)]
+    pub currency: String,
+    // Redemptions of cancelled bookings no longer count towards the limits.
+    pub booking_status: BookingStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+// Codes are matched in upper case, so users can type them in any case.
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+fn validate_code(errors: &mut ValidationErrors, code: &str) {
+    let is_valid = (MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&code.len())
+        && code
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_'));
+    if !is_valid {
+        errors.add(
+            "code",
+            format!(
+                "must be {} to {} letters, digits, hyphens or underscores",
+                MIN_CODE_LENGTH, MAX_CODE_LENGTH
+            ),
+        );
+    }
+}
+
+fn validate_window(
+    errors: &mut ValidationErrors,
+    starts_at: Option<DateTime<Utc>>,
+    ends_at: Option<DateTime<Utc>>,
+) {
+    if let (Some(starts_at), Some(ends_at)) = (starts_at, ends_at) {
+        if ends_at <= starts_at {
+            errors.add("endsAt", "must be after startsAt");
+        }
+    }
+}
+
+fn validate_limit(errors: &mut ValidationErrors, field: &str, limit: Option<i32>) {
+    if limit.is_some_and(|limit| limit < 1) {
+        errors.add(field, "must be at least 1");
+    }
+}
+
+// Fixed discounts and minimum spends are amounts, which need a currency.
+fn validate_currency_required(
+    errors: &mut ValidationErrors,
+    currency: Option<&str>,
+    has_amount: bool,
+    has_min_spend: bool,
+) {
+    if currency.is_none() && (has_amount || has_min_spend) {
+        errors.add(
+            "currency",
+            "is required with a fixed discount or a minimum spend",
+        );
+    }
+}
+
+#[derive(OneofObject, Copy, Clone, Debug)]
+pub enum PromotionDiscountInput {
+    // E.g. 10 for 10% off.
+    Percent(Decimal),
+    // Off the whole booking, in the promotion's currency.
+    Amount(Decimal),
+}
+
+impl PromotionDiscountInput {
+    fn validate(&self, errors: &mut ValidationErrors, currency: Option<&str>) {
+        match *self {
+            PromotionDiscountInput::Percent(percent) => {
+                if percent <= Decimal::ZERO || percent > Decimal::ONE_HUNDRED {
+                    errors.add("discount", "must be more than 0 and at most 100 percent");
+                } else if percent.normalize().scale() > MAX_PERCENT_DECIMAL_PLACES {
+                    errors.add(
+                        "discount",
+                        format!(
+                            "must have at most {} decimal places",
+                            MAX_PERCENT_DECIMAL_PLACES
+                        ),
+                    );
+                }
+            }
+            PromotionDiscountInput::Amount(amount) => {
+                if amount <= Decimal::ZERO {
+                    errors.add("discount", "must be greater than 0");
+                    return;
+                }
+                let Some(currency) = currency else {
+                    return;
+                };
+                match Money::from_decimal(amount, currency) {
+                    Ok(_) | Err(MoneyError::UnsupportedCurrency(_)) => {}
+                    Err(error) => errors.add("discount", error.to_string()),
+                }
+            }
+        }
+    }
+
+    fn is_amount(&self) -> bool {
+        matches!(self, PromotionDiscountInput::Amount(_))
+    }
+
+    // The promotion's `percent` and `amount_minor`.
+    pub fn into_parts(self, currency: Option<&str>) -> (Option<Decimal>, Option<i64>) {
+        match self {
+            PromotionDiscountInput::Percent(percent) => (Some(percent.normalize()), None),
+            PromotionDiscountInput::Amount(amount) => (
+                None,
+                currency.and_then(|currency| to_minor_units(Some(amount), currency)),
+            ),
+        }
+    }
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct CreatePromotionInput {
+    pub code: String,
+    pub name: String,
+    pub is_active: Option<bool>,
+    pub discount: PromotionDiscountInput,
+    pub currency: Option<String>,
+    // In `currency`, compared with the price before the discount.
+    pub min_spend: Option<Decimal>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub max_redemptions: Option<i32>,
+    pub max_redemptions_per_user: Option<i32>,
+    pub tour_ids: Option<Vec<i32>>,
+}
+
+impl CreatePromotionInput {
+    pub fn normalize(mut self) -> Self {
+        self.code = normalize_code(&self.code);
+        self.name = self.name.trim().to_string();
+        if let Some(tour_ids) = &mut self.tour_ids {
+            tour_ids.sort();
+            tour_ids.dedup();
+        }
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        validate_code(&mut errors, &self.code);
+        validate_title(&mut errors, "name", &self.name);
+        if let Some(currency) = &self.currency {
+            validate_currency(&mut errors, "currency", currency);
+        }
+        self.discount
+            .validate(&mut errors, self.currency.as_deref());
+        if let (Some(min_spend), Some(currency)) = (self.min_spend, &self.currency) {
+            validate_price(&mut errors, "minSpend", min_spend, currency);
+        }
+        validate_currency_required(
+            &mut errors,
+            self.currency.as_deref(),
+            self.discount.is_amount(),
+            self.min_spend.is_some(),
+        );
+        validate_window(&mut errors, self.starts_at, self.ends_at);
+        validate_limit(&mut errors, "maxRedemptions", self.max_redemptions);
+        validate_limit(
+            &mut errors,
+            "maxRedemptionsPerUser",
+            self.max_redemptions_per_user,
+        );
+
+        errors.into_result()
+    }
+
+    // Only valid after `validate`.
+    pub fn min_spend_minor(&self) -> Option<i64> {
+        self.currency
+            .as_deref()
+            .and_then(|currency| to_minor_units(self.min_spend, currency))
+    }
+}
+
+// `tourIds` replaces all of the promotion's tours when given. Amounts are in the
+// promotion's currency, and must be set again when it changes.
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct UpdatePromotionInput {
+    pub code: Option<String>,
+    pub name: Option<String>,
+    pub is_active: Option<bool>,
+    pub discount: Option<PromotionDiscountInput>,
+    pub currency: MaybeUndefined<String>,
+    pub min_spend: MaybeUndefined<Decimal>,
+    pub starts_at: MaybeUndefined<DateTime<Utc>>,
+    pub ends_at: MaybeUndefined<DateTime<Utc>>,
+    pub max_redemptions: MaybeUndefined<i32>,
+    pub max_redemptions_per_user: MaybeUndefined<i32>,
+    pub tour_ids: Option<Vec<i32>>,
+}
+
+impl UpdatePromotionInput {
+    pub fn normalize(mut self) -> Self {
+        self.code = self.code.map(|code| normalize_code(&code));
+        self.name = self.name.map(|name| name.trim().to_string());
+        if let Some(tour_ids) = &mut self.tour_ids {
+            tour_ids.sort();
+            tour_ids.dedup();
+        }
+        self
+    }
+
+    pub fn validate(&self, promotion: &Promotion) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(code) = &self.code {
+            validate_code(&mut errors, code);
+        }
+        if let Some(name) = &self.name {
+            validate_title(&mut errors, "name", name);
+        }
+
+        let currency = match &self.currency {
+            MaybeUndefined::Undefined => promotion.currency.as_deref(),
+            MaybeUndefined::Null => None,
+            MaybeUndefined::Value(currency) => {
+                validate_currency(&mut errors, "currency", currency);
+                Some(currency.as_str())
+            }
+        };
+        let changes_currency = currency != promotion.currency.as_deref();
+
+        match &self.discount {
+            Some(discount) => discount.validate(&mut errors, currency),
+            None if changes_currency && promotion.amount_minor.is_some() => {
+                errors.add("discount", "must be set again when the currency changes");
+            }
+            None => {}
+        }
+        match (&self.min_spend, currency) {
+            (MaybeUndefined::Value(min_spend), Some(currency)) => {
+                validate_price(&mut errors, "minSpend", *min_spend, currency);
+            }
+            (MaybeUndefined::Undefined, _)
+                if changes_currency && promotion.min_spend_minor.is_some() =>
+            {
+                errors.add("minSpend", "must be set again when the currency changes");
+            }
+            _ => {}
+        }
+        validate_currency_required(
+            &mut errors,
+            currency,
+            self.discount
+                .map_or(promotion.amount_minor.is_some(), |discount| {
+                    discount.is_amount()
+                }),
+            match self.min_spend {
+                MaybeUndefined::Undefined => promotion.min_spend_minor.is_some(),
+                MaybeUndefined::Null => false,
+                MaybeUndefined::Value(_) => true,
+            },
+        );
+
+        validate_window(
+            &mut errors,
+            patched_value(&self.starts_at, promotion.starts_at),
+            patched_value(&self.ends_at, promotion.ends_at),
+        );
+        validate_limit(
+            &mut errors,
+            "maxRedemptions",
+            self.max_redemptions.value().copied(),
+        );
+        validate_limit(
+            &mut errors,
+            "maxRedemptionsPerUser",
+            self.max_redemptions_per_user.value().copied(),
+        );
+
+        errors.into_result()
+    }
+
+    pub fn apply(self, promotion: &mut Promotion) {
+        if let Some(code) = self.code {
+            promotion.code = code;
+        }
+        if let Some(name) = self.name {
+            promotion.name = name;
+        }
+        if let Some(is_active) = self.is_active {
+            promotion.is_active = is_active;
+        }
+        apply_patch(&mut promotion.currency, self.currency);
+        let currency = promotion.currency.as_deref();
+        if let Some(discount) = self.discount {
+            (promotion.percent, promotion.amount_minor) = discount.into_parts(currency);
+        }
+        match self.min_spend {
+            MaybeUndefined::Undefined => {}
+            MaybeUndefined::Null => promotion.min_spend_minor = None,
+            MaybeUndefined::Value(min_spend) => {
+                promotion.min_spend_minor =
+                    currency.and_then(|currency| to_minor_units(Some(min_spend), currency));
+            }
+        }
+        apply_patch(&mut promotion.starts_at, self.starts_at);
+        apply_patch(&mut promotion.ends_at, self.ends_at);
+        apply_patch(&mut promotion.max_redemptions, self.max_redemptions);
+        apply_patch(
+            &mut promotion.max_redemptions_per_user,
+            self.max_redemptions_per_user,
+        );
+        if let Some(tour_ids) = self.tour_ids {
+            promotion.tour_ids = tour_ids;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap()
+    }
+
+    fn promotion() -> Promotion {
+        Promotion {
+            id: 7,
+            code: "SUMMER".to_string(),
+            name: "Summer".to_string(),
+            is_active: true,
+            percent: Some(Decimal::TEN),
+            amount_minor: None,
+            currency: None,
+            min_spend_minor: None,
+            starts_at: None,
+            ends_at: None,
+            max_redemptions: None,
+            max_redemptions_per_user: None,
+            tour_ids: Vec::new(),
+            created_at: now(),
+            updated_at: now(),
+        }
+    }
+
+    fn fixed(amount_minor: i64, currency: &str) -> Promotion {
+        Promotion {
+            percent: None,
+            amount_minor: Some(amount_minor),
+            currency: Some(currency.to_string()),
+            ..promotion()
+        }
+    }
+
+    fn quote(total_minor: i64) -> Quote {
+        Quote {
+            departure_id: 1,
+            tour_id: 1,
+            participants: 1,
+            lines: Vec::new(),
+            total_minor,
+            currency: "USD".to_string(),
+            promotion_id: None,
+            discount_minor: 0,
+        }
+    }
+
+    fn counts(total: i64, by_user: Option<i64>) -> RedemptionCounts {
+        RedemptionCounts { total, by_user }
+    }
+
+    // The discount taken off `quote`, or why the promotion doesn't apply.
+    fn apply(
+        promotion: &Promotion,
+        quote: Quote,
+        counts: RedemptionCounts,
+    ) -> Result<i64, PromotionError> {
+        let mut quote = quote;
+        let total_minor = quote.total_minor;
+        let result = promotion.apply(&mut quote, counts, now());
+        if result.is_err() {
+            assert_eq!(quote.total_minor, total_minor);
+            assert!(quote.lines.is_empty());
+            assert_eq!(quote.promotion_id, None);
+        }
+
+        result.map(|()| quote.discount_minor)
+    }
+
+    #[test]
+    fn promotions_apply_within_their_window() {
+        let hour = chrono::Duration::hours(1);
+        let cases = [
+            (None, None, Ok(1000)),
+            (Some(now() + hour), None, Err(PromotionError::NotStarted)),
+            (Some(now()), None, Ok(1000)),
+            (None, Some(now() + hour), Ok(1000)),
+            (None, Some(now()), Err(PromotionError::Expired)),
+            (
+                Some(now() - hour),
+                Some(now() - hour / 2),
+                Err(PromotionError::Expired),
+            ),
+        ];
+
+        for (starts_at, ends_at, expected) in cases {
+            let promotion = Promotion {
+                starts_at,
+                ends_at,
+                ..promotion()
+            };
+            assert_eq!(
+                apply(&promotion, quote(10000), counts(0, None)),
+                expected,
+                "{:?} to {:?}",
+                starts_at,
+                ends_at
+            );
+        }
+
+        let inactive = Promotion {
+            is_active: false,
+            ..promotion()
+        };
+        assert_eq!(
+            apply(&inactive, quote(10000), counts(0, None)),
+            Err(PromotionError::Invalid)
+        );
+    }
+
+    #[test]
+    fn promotions_apply_only_to_their_tours() {
+        let promotion = Promotion {
+            tour_ids: vec![2, 3],
+            ..promotion()
+        };
+
+        assert_eq!(
+            apply(&promotion, quote(10000), counts(0, None)),
+            Err(PromotionError::TourNotEligible)
+        );
+        let eligible = Quote {
+            tour_id: 3,
+            ..quote(10000)
+        };
+        assert_eq!(apply(&promotion, eligible, counts(0, None)), Ok(1000));
+    }
+
+    #[test]
+    fn promotions_with_a_currency_need_prices_in_it() {
+        let eur = Quote {
+            currency: "EUR".to_string(),
+            ..quote(10000)
+        };
+
+        let error = apply(&fixed(500, "USD"), eur.clone(), counts(0, None)).unwrap_err();
+        assert_eq!(error, PromotionError::CurrencyMismatch("USD".to_string()));
+        assert_eq!(error.to_string(), "only applies to prices in USD");
+
+        let percent_in_usd = Promotion {
+            currency: Some("USD".to_string()),
+            ..promotion()
+        };
+        assert_eq!(
+            apply(&percent_in_usd, eur.clone(), counts(0, None)),
+            Err(PromotionError::CurrencyMismatch("USD".to_string()))
+        );
+        // Percentages without a currency apply to any price.
+        assert_eq!(apply(&promotion(), eur, counts(0, None)), Ok(1000));
+    }
+
+    #[test]
+    fn promotions_need_the_minimum_spend() {
+        let promotion = Promotion {
+            min_spend_minor: Some(5000),
+            ..fixed(500, "USD")
+        };
+
+        let error = apply(&promotion, quote(4999), counts(0, None)).unwrap_err();
+        assert_eq!(error, PromotionError::MinimumSpend(Money::new(5000, "USD")));
+        assert_eq!(error.to_string(), "requires spending at least 50.00 USD");
+        assert_eq!(apply(&promotion, quote(5000), counts(0, None)), Ok(500));
+    }
+
+    #[test]
+    fn discounts_never_exceed_the_subtotal() {
+        let percent = |percent: &str| Promotion {
+            percent: Some(percent.parse().unwrap()),
+            ..promotion()
+        };
+        let cases = [
+            (fixed(500, "USD"), 1500, 500),
+            (fixed(2000, "USD"), 1500, 1500),
+            (fixed(2000, "USD"), 0, 0),
+            (percent("10"), 1005, 101),
+            (percent("10"), 1004, 100),
+            (percent("12.5"), 100, 13),
+            (percent("100"), 1999, 1999),
+            (percent("0.0001"), 1999, 0),
+        ];
+
+        for (promotion, total_minor, expected) in cases {
+            assert_eq!(
+                apply(&promotion, quote(total_minor), counts(0, None)),
+                Ok(expected),
+                "{:?} off {}",
+                (promotion.percent, promotion.amount_minor),
+                total_minor
+            );
+        }
+    }
+
+    #[test]
+    fn discounts_get_a_line_of_their_own() {
+        let mut discounted = quote(1500);
+        fixed(2000, "USD")
+            .apply(&mut discounted, counts(0, None), now())
+            .unwrap();
+
+        assert_eq!(discounted.total_minor, 0);
+        assert_eq!(discounted.discount_minor, 1500);
+        assert_eq!(discounted.promotion_id, Some(7));
+        assert_eq!(discounted.lines.len(), 1);
+        let line = &discounted.lines[0];
+        assert_eq!(line.kind, PriceLineKind::Discount);
+        assert_eq!(line.category, None);
+        assert_eq!(line.description, "Promo code SUMMER");
+        assert_eq!(
+            (line.quantity, line.unit_amount_minor, line.amount_minor),
+            (1, -1500, -1500)
+        );
+
+        // Nothing to take off still records the promotion, without a line.
+        let mut free = quote(0);
+        promotion()
+            .apply(&mut free, counts(0, None), now())
+            .unwrap();
+        assert_eq!(free.promotion_id, Some(7));
+        assert!(free.lines.is_empty());
+    }
+
+    #[test]
+    fn promotions_stop_at_their_redemption_limits() {
+        let promotion = Promotion {
+            max_redemptions: Some(5),
+            max_redemptions_per_user: Some(2),
+            ..promotion()
+        };
+        let cases = [
+            (counts(0, Some(0)), Ok(1000)),
+            (counts(4, Some(1)), Ok(1000)),
+            (counts(5, Some(0)), Err(PromotionError::Exhausted)),
+            (counts(6, None), Err(PromotionError::Exhausted)),
+            (counts(3, Some(2)), Err(PromotionError::UserLimitReached)),
+            // Quotes for nobody in particular skip the per-user limit.
+            (counts(3, None), Ok(1000)),
+        ];
+
+        for (counts, expected) in cases {
+            assert_eq!(
+                apply(&promotion, quote(10000), counts),
+                expected,
+                "{:?}",
+                counts
+            );
+        }
+    }
+}
@@ -0,0 +1,750 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+// Occurrences further than this past the start of the requested window are never
+// generated, which bounds the work done for a single expansion.
+const MAX_EXPANSION_DAYS: u64 = 366 * 10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// A weekday in BYDAY, optionally with an ordinal within the month (`2MO`, `-1FR`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+// The date-level subset of an iCalendar RRULE (RFC 5545): FREQ, INTERVAL, COUNT,
+// UNTIL, BYDAY, BYMONTHDAY and BYMONTH. Times of day come from the schedule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+    pub by_day: Vec<ByDay>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RecurrenceError {
+    #[error("FREQ is required")]
+    MissingFrequency,
+    #[error("{0} is not supported")]
+    Unsupported(String),
+    #[error("{0} has an invalid value")]
+    InvalidValue(String),
+    #[error("COUNT and UNTIL can't both be set")]
+    CountAndUntil,
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = RecurrenceError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut frequency = None;
+        let mut recurrence = RecurrenceRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| RecurrenceError::InvalidValue(part.to_string()))?;
+            let name = name.to_ascii_uppercase();
+            let invalid = || RecurrenceError::InvalidValue(name.clone());
+
+            match name.as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => {
+                            return Err(RecurrenceError::Unsupported(format!("FREQ={}", other)))
+                        }
+                    })
+                }
+                "INTERVAL" => {
+                    recurrence.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(invalid)?
+                }
+                "COUNT" => {
+                    recurrence.count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                "UNTIL" => recurrence.until = Some(parse_until(value).ok_or_else(invalid)?),
+                "BYDAY" => {
+                    recurrence.by_day = value
+                        .split(',')
+                        .map(parse_by_day)
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "BYMONTHDAY" => {
+                    recurrence.by_month_day = value
+                        .split(',')
+                        .map(|day| {
+                            day.parse::<i32>()
+                                .ok()
+                                .filter(|day| *day != 0 && (-31..=31).contains(day))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "BYMONTH" => {
+                    recurrence.by_month = value
+                        .split(',')
+                        .map(|month| {
+                            month
+                                .parse::<u32>()
+                                .ok()
+                                .filter(|month| (1..=12).contains(month))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                // Weeks always start on Monday; WKST=MO is accepted for compatibility.
+                "WKST" if value.eq_ignore_ascii_case("MO") => {}
+                _ => return Err(RecurrenceError::Unsupported(name)),
+            }
+        }
+
+        recurrence.frequency = frequency.ok_or(RecurrenceError::MissingFrequency)?;
+        if recurrence.count.is_some() && recurrence.until.is_some() {
+            return Err(RecurrenceError::CountAndUntil);
+        }
+        let has_ordinals = recurrence.by_day.iter().any(|day| day.ordinal.is_some());
+        let ordinals_allowed = match recurrence.frequency {
+            Frequency::Monthly => true,
+            Frequency::Yearly => !recurrence.by_month.is_empty(),
+            Frequency::Daily | Frequency::Weekly => false,
+        };
+        if has_ordinals && !ordinals_allowed {
+            return Err(RecurrenceError::InvalidValue("BYDAY".to_string()));
+        }
+
+        Ok(recurrence)
+    }
+}
+
+impl RecurrenceRule {
+    // Dates in `from..=to` on which the rule recurs. COUNT is counted from `start`,
+    // so only rules with a COUNT are walked from their start; the rest begin at the
+    // window.
+    pub fn occurrences(&self, start: NaiveDate, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let window_start = start.max(from);
+        let last = [
+            self.until,
+            Some(to),
+            window_start.checked_add_days(Days::new(MAX_EXPANSION_DAYS)),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(to);
+        let first = if self.count.is_some() {
+            start
+        } else {
+            window_start
+        };
+
+        let mut occurrences = Vec::new();
+        let mut matched = 0;
+        for date in first.iter_days().take_while(|date| *date <= last) {
+            if !self.matches(start, date) {
+                continue;
+            }
+
+            matched += 1;
+            if self.count.is_some_and(|count| matched > count) {
+                break;
+            }
+            if date >= from {
+                occurrences.push(date);
+            }
+        }
+
+        occurrences
+    }
+
+    fn matches(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if !self.by_month.is_empty() && !self.by_month.contains(&date.month()) {
+            return false;
+        }
+        if !self.by_month_day.is_empty()
+            && !self.by_month_day.iter().any(|day| is_month_day(date, *day))
+        {
+            return false;
+        }
+
+        let interval = i64::from(self.interval);
+        match self.frequency {
+            Frequency::Daily => {
+                (date - start).num_days() % interval == 0 && self.matches_weekday(date)
+            }
+            Frequency::Weekly => {
+                let weeks = (week_start(date) - week_start(start)).num_days() / 7;
+                let weekday_matches = if self.by_day.is_empty() {
+                    !self.by_month_day.is_empty() || date.weekday() == start.weekday()
+                } else {
+                    self.matches_weekday(date)
+                };
+                weeks % interval == 0 && weekday_matches
+            }
+            Frequency::Monthly => {
+                let months = months_between(start, date);
+                months % interval == 0 && self.matches_day_of_month(start, date)
+            }
+            Frequency::Yearly => {
+                let years = i64::from(date.year() - start.year());
+                let month_matches = !self.by_month.is_empty() || date.month() == start.month();
+                years % interval == 0 && month_matches && self.matches_day_of_month(start, date)
+            }
+        }
+    }
+
+    fn matches_weekday(&self, date: NaiveDate) -> bool {
+        self.by_day.is_empty() || self.by_day.iter().any(|day| day.weekday == date.weekday())
+    }
+
+    fn matches_day_of_month(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if !self.by_day.is_empty() {
+            return self
+                .by_day
+                .iter()
+                .any(|day| is_weekday_of_month(date, *day));
+        }
+
+        !self.by_month_day.is_empty() || date.day() == start.day()
+    }
+}
+
+// Converts a local wall-clock time to UTC. Times repeated when clocks go back
+// resolve to the earlier instant; times skipped when clocks go forward move past
+// the gap.
+pub fn local_to_utc(date: NaiveDate, time: NaiveTime, timezone: Tz) -> DateTime<Utc> {
+    let local = date.and_time(time);
+
+    (0..=4)
+        .find_map(|step| {
+            let shifted = local + TimeDelta::minutes(30 * step);
+            timezone.from_local_datetime(&shifted).earliest()
+        })
+        .map(|instant| instant.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+fn parse_until(value: &str) -> Option<NaiveDate> {
+    let date = value.get(..8)?;
+    NaiveDate::parse_from_str(date, "%Y%m%d").ok()
+}
+
+fn parse_by_day(value: &str) -> Option<ByDay> {
+    let value = value.trim();
+    let split_at = value.len().checked_sub(2)?;
+    let (ordinal, weekday) = value.split_at(split_at);
+    let weekday = match weekday.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(
+            ordinal
+                .parse::<i32>()
+                .ok()
+                .filter(|ordinal| *ordinal != 0 && (-5..=5).contains(ordinal))?,
+        ),
+    };
+
+    Some(ByDay { ordinal, weekday })
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(u64::from(date.weekday().num_days_from_monday()))
+}
+
+fn months_between(start: NaiveDate, date: NaiveDate) -> i64 {
+    i64::from(date.year() - start.year()) * 12 + i64::from(date.month()) - i64::from(start.month())
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap_or(date);
+    let next = first
+        .checked_add_months(chrono::Months::new(1))
+        .unwrap_or(first);
+    (next - first).num_days() as u32
+}
+
+fn is_month_day(date: NaiveDate, day: i32) -> bool {
+    let day_of_month = date.day() as i32;
+    if day > 0 {
+        day_of_month == day
+    } else {
+        day_of_month == days_in_month(date) as i32 + day + 1
+    }
+}
+
+fn is_weekday_of_month(date: NaiveDate, by_day: ByDay) -> bool {
+    if date.weekday() != by_day.weekday {
+        return false;
+    }
+
+    match by_day.ordinal {
+        None => true,
+        Some(ordinal) if ordinal > 0 => (date.day() as i32 - 1) / 7 + 1 == ordinal,
+        Some(ordinal) => (days_in_month(date) as i32 - date.day() as i32) / 7 + 1 == -ordinal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn expand(rule: &str, start: &str, to: &str) -> Vec<NaiveDate> {
+        let rule: RecurrenceRule = rule.parse().unwrap();
+        rule.occurrences(date(start), date(start), date(to))
+    }
+
+    // Expansions from the examples in RFC 5545 section 3.8.5.3.
+    #[test]
+    fn rules_expand_like_the_rfc_examples() {
+        let cases: &[(&str, &str, &str, &[&str])] = &[
+            (
+                "RRULE:FREQ=DAILY;COUNT=10",
+                "1997-09-02",
+                "1998-12-31",
+                &[
+                    "1997-09-02",
+                    "1997-09-03",
+                    "1997-09-04",
+                    "1997-09-05",
+                    "1997-09-06",
+                    "1997-09-07",
+                    "1997-09-08",
+                    "1997-09-09",
+                    "1997-09-10",
+                    "1997-09-11",
+                ],
+            ),
+            (
+                "FREQ=DAILY;INTERVAL=10;COUNT=5",
+                "1997-09-02",
+                "1998-12-31",
+                &[
+                    "1997-09-02",
+                    "1997-09-12",
+                    "1997-09-22",
+                    "1997-10-02",
+                    "1997-10-12",
+                ],
+            ),
+            (
+                "FREQ=DAILY;INTERVAL=2",
+                "1997-09-02",
+                "1997-09-10",
+                &[
+                    "1997-09-02",
+                    "1997-09-04",
+                    "1997-09-06",
+                    "1997-09-08",
+                    "1997-09-10",
+                ],
+            ),
+            (
+                "FREQ=WEEKLY;COUNT=10",
+                "1997-09-02",
+                "1998-12-31",
+                &[
+                    "1997-09-02",
+                    "1997-09-09",
+                    "1997-09-16",
+                    "1997-09-23",
+                    "1997-09-30",
+                    "1997-10-07",
+                    "1997-10-14",
+                    "1997-10-21",
+                    "1997-10-28",
+                    "1997-11-04",
+                ],
+            ),
+            (
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=8;BYDAY=TU,TH",
+                "1997-09-02",
+                "1998-12-31",
+                &[
+                    "1997-09-02",
+                    "1997-09-04",
+                    "1997-09-16",
+                    "1997-09-18",
+                    "1997-09-30",
+                    "1997-10-02",
+                    "1997-10-14",
+                    "1997-10-16",
+                ],
+            ),
+            (
+                "FREQ=WEEKLY;UNTIL=19971007;WKST=MO;BYDAY=TU,TH",
+                "1997-09-02",
+                "1998-12-31",
+                &[
+                    "1997-09-02",
+                    "1997-09-04",
+                    "1997-09-09",
+                    "1997-09-11",
+                    "1997-09-16",
+                    "1997-09-18",
+                    "1997-09-23",
+                    "1997-09-25",
+                    "1997-09-30",
+                    "1997-10-02",
+                    "1997-10-07",
+                ],
+            ),
+            (
+                "FREQ=MONTHLY;COUNT=10;BYDAY=1FR",
+                "1997-09-05",
+                "1998-12-31",
+                &[
+                    "1997-09-05",
+                    "1997-10-03",
+                    "1997-11-07",
+                    "1997-12-05",
+                    "1998-01-02",
+                    "1998-02-06",
+                    "1998-03-06",
+                    "1998-04-03",
+                    "1998-05-01",
+                    "1998-06-05",
+                ],
+            ),
+            (
+                "FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=1SU,-1SU",
+                "1997-09-07",
+                "1998-12-31",
+                &[
+                    "1997-09-07",
+                    "1997-09-28",
+                    "1997-11-02",
+                    "1997-11-30",
+                    "1998-01-04",
+                    "1998-01-25",
+                    "1998-03-01",
+                    "1998-03-29",
+                    "1998-05-03",
+                    "1998-05-31",
+                ],
+            ),
+            (
+                "FREQ=MONTHLY;COUNT=6;BYDAY=-2MO",
+                "1997-09-22",
+                "1998-12-31",
+                &[
+                    "1997-09-22",
+                    "1997-10-20",
+                    "1997-11-17",
+                    "1997-12-22",
+                    "1998-01-19",
+                    "1998-02-16",
+                ],
+            ),
+            (
+                "FREQ=MONTHLY;BYMONTHDAY=-3",
+                "1997-09-28",
+                "1998-02-28",
+                &[
+                    "1997-09-28",
+                    "1997-10-29",
+                    "1997-11-28",
+                    "1997-12-29",
+                    "1998-01-29",
+                    "1998-02-26",
+                ],
+            ),
+            (
+                "FREQ=MONTHLY;COUNT=10;BYMONTHDAY=1,-1",
+                "1997-09-30",
+                "1998-12-31",
+                &[
+                    "1997-09-30",
+                    "1997-10-01",
+                    "1997-10-31",
+                    "1997-11-01",
+                    "1997-11-30",
+                    "1997-12-01",
+                    "1997-12-31",
+                    "1998-01-01",
+                    "1998-01-31",
+                    "1998-02-01",
+                ],
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13",
+                "1997-09-02",
+                "2000-12-31",
+                &[
+                    "1998-02-13",
+                    "1998-03-13",
+                    "1998-11-13",
+                    "1999-08-13",
+                    "2000-10-13",
+                ],
+            ),
+            (
+                "FREQ=YEARLY;COUNT=10;BYMONTH=6,7",
+                "1997-06-10",
+                "2005-12-31",
+                &[
+                    "1997-06-10",
+                    "1997-07-10",
+                    "1998-06-10",
+                    "1998-07-10",
+                    "1999-06-10",
+                    "1999-07-10",
+                    "2000-06-10",
+                    "2000-07-10",
+                    "2001-06-10",
+                    "2001-07-10",
+                ],
+            ),
+            (
+                "FREQ=YEARLY;INTERVAL=4;BYMONTH=11;BYDAY=TU;BYMONTHDAY=2,3,4,5,6,7,8",
+                "1996-11-05",
+                "2004-12-31",
+                &["1996-11-05", "2000-11-07", "2004-11-02"],
+            ),
+            (
+                "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH",
+                "1997-11-27",
+                "1999-12-31",
+                &["1997-11-27", "1998-11-26", "1999-11-25"],
+            ),
+        ];
+
+        for (rule, start, to, expected) in cases {
+            let expected: Vec<_> = expected.iter().map(|value| date(value)).collect();
+            assert_eq!(expand(rule, start, to), expected, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn until_is_inclusive_and_by_month_filters_daily_rules() {
+        let dates = expand(
+            "FREQ=DAILY;UNTIL=20000131T140000Z;BYMONTH=1",
+            "1998-01-01",
+            "2001-12-31",
+        );
+        assert_eq!(dates.len(), 93);
+        assert_eq!(dates.first(), Some(&date("1998-01-01")));
+        assert_eq!(dates.last(), Some(&date("2000-01-31")));
+        assert!(dates.iter().all(|date| date.month() == 1));
+    }
+
+    #[test]
+    fn count_is_counted_from_the_start_not_the_window() {
+        let rule: RecurrenceRule = "FREQ=WEEKLY;COUNT=3".parse().unwrap();
+        let dates = rule.occurrences(date("2024-01-01"), date("2024-01-10"), date("2024-12-31"));
+        assert_eq!(dates, vec![date("2024-01-15")]);
+    }
+
+    #[test]
+    fn open_ended_rules_expand_windows_long_after_the_start() {
+        let rule: RecurrenceRule = "FREQ=DAILY".parse().unwrap();
+        let dates = rule.occurrences(date("2000-01-01"), date("2030-06-01"), date("2030-06-03"));
+        assert_eq!(
+            dates,
+            vec![date("2030-06-01"), date("2030-06-02"), date("2030-06-03")]
+        );
+
+        // Intervals stay anchored to the start, not to the window.
+        let rule: RecurrenceRule = "FREQ=WEEKLY;INTERVAL=2".parse().unwrap();
+        let dates = rule.occurrences(date("2024-01-01"), date("2040-01-01"), date("2040-01-31"));
+        assert_eq!(dates, vec![date("2040-01-09"), date("2040-01-23")]);
+    }
+
+    #[test]
+    fn count_reaching_past_the_expansion_limit_is_counted_from_the_start() {
+        let rule: RecurrenceRule = "FREQ=DAILY;COUNT=5000".parse().unwrap();
+        let dates = rule.occurrences(date("2000-01-01"), date("2013-09-07"), date("2013-09-20"));
+        assert_eq!(dates, vec![date("2013-09-07"), date("2013-09-08")]);
+    }
+
+    #[test]
+    fn expansion_stops_after_the_limit_past_the_window_start() {
+        let rule: RecurrenceRule = "FREQ=YEARLY".parse().unwrap();
+        let dates = rule.occurrences(date("2000-03-01"), date("2030-01-01"), date("2100-12-31"));
+        assert_eq!(dates.first(), Some(&date("2030-03-01")));
+        assert_eq!(dates.last(), Some(&date("2039-03-01")));
+    }
+
+    #[test]
+    fn local_times_resolve_across_dst_transitions() {
+        use chrono_tz::{America::New_York, Europe::Lisbon};
+
+        let cases = [
+            // Ordinary times on either side of the transitions.
+            (New_York, "2024-01-15", "12:00", "2024-01-15T17:00:00Z"),
+            (New_York, "2024-07-15", "12:00", "2024-07-15T16:00:00Z"),
+            // 02:00-03:00 doesn't exist on 10 March; times in the gap move to 03:00 EDT.
+            (New_York, "2024-03-10", "01:59", "2024-03-10T06:59:00Z"),
+            (New_York, "2024-03-10", "02:00", "2024-03-10T07:00:00Z"),
+            (New_York, "2024-03-10", "02:30", "2024-03-10T07:00:00Z"),
+            (New_York, "2024-03-10", "03:00", "2024-03-10T07:00:00Z"),
+            // 01:00-02:00 happens twice on 3 November; the earlier, EDT instant wins.
+            (New_York, "2024-11-03", "01:00", "2024-11-03T05:00:00Z"),
+            (New_York, "2024-11-03", "01:30", "2024-11-03T05:30:00Z"),
+            (New_York, "2024-11-03", "02:00", "2024-11-03T07:00:00Z"),
+            // 01:00-02:00 doesn't exist on 31 March; times in the gap move to 02:00 WEST.
+            (Lisbon, "2024-03-31", "00:59", "2024-03-31T00:59:00Z"),
+            (Lisbon, "2024-03-31", "01:30", "2024-03-31T01:00:00Z"),
+            (Lisbon, "2024-03-31", "02:00", "2024-03-31T01:00:00Z"),
+            // 01:00-02:00 happens twice on 27 October; the earlier, WEST instant wins.
+            (Lisbon, "2024-10-27", "01:00", "2024-10-27T00:00:00Z"),
+            (Lisbon, "2024-10-27", "01:30", "2024-10-27T00:30:00Z"),
+            (Lisbon, "2024-10-27", "02:00", "2024-10-27T02:00:00Z"),
+        ];
+
+        for (timezone, day, time, expected) in cases {
+            let time = NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+            assert_eq!(
+                local_to_utc(date(day), time, timezone)
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                expected,
+                "{} {} in {}",
+                day,
+                time,
+                timezone
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let cases: &[(&str, RecurrenceError)] = &[
+            (
+                "FREQ=HOURLY",
+                RecurrenceError::Unsupported("FREQ=HOURLY".to_string()),
+            ),
+            (
+                "FREQ=FORTNIGHTLY",
+                RecurrenceError::Unsupported("FREQ=FORTNIGHTLY".to_string()),
+            ),
+            ("INTERVAL=2;COUNT=3", RecurrenceError::MissingFrequency),
+            (
+                "FREQ=DAILY;COUNT=5;UNTIL=19971224",
+                RecurrenceError::CountAndUntil,
+            ),
+            (
+                "FREQ=DAILY;UNTIL=19971224;COUNT=5",
+                RecurrenceError::CountAndUntil,
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=54MO",
+                RecurrenceError::InvalidValue("BYDAY".to_string()),
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=-54MO",
+                RecurrenceError::InvalidValue("BYDAY".to_string()),
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=6MO",
+                RecurrenceError::InvalidValue("BYDAY".to_string()),
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=0MO",
+                RecurrenceError::InvalidValue("BYDAY".to_string()),
+            ),
+            (
+                "FREQ=YEARLY;BYDAY=20MO",
+                RecurrenceError::InvalidValue("BYDAY".to_string()),
+            ),
+            (
+                "FREQ=WEEKLY;BYDAY=1MO",
+                RecurrenceError::InvalidValue("BYDAY".to_string()),
+            ),
+            (
+                "FREQ=WEEKLY;BYDAY=XX",
+                RecurrenceError::InvalidValue("BYDAY".to_string()),
+            ),
+            (
+                "FREQ=DAILY;INTERVAL=0",
+                RecurrenceError::InvalidValue("INTERVAL".to_string()),
+            ),
+            (
+                "FREQ=DAILY;COUNT=0",
+                RecurrenceError::InvalidValue("COUNT".to_string()),
+            ),
+            (
+                "FREQ=DAILY;UNTIL=1997",
+                RecurrenceError::InvalidValue("UNTIL".to_string()),
+            ),
+            (
+                "FREQ=MONTHLY;BYMONTHDAY=0",
+                RecurrenceError::InvalidValue("BYMONTHDAY".to_string()),
+            ),
+            (
+                "FREQ=MONTHLY;BYMONTHDAY=-32",
+                RecurrenceError::InvalidValue("BYMONTHDAY".to_string()),
+            ),
+            (
+                "FREQ=YEARLY;BYMONTH=13",
+                RecurrenceError::InvalidValue("BYMONTH".to_string()),
+            ),
+            (
+                "FREQ=MONTHLY;BYSETPOS=-1",
+                RecurrenceError::Unsupported("BYSETPOS".to_string()),
+            ),
+            (
+                "FREQ=WEEKLY;WKST=SU",
+                RecurrenceError::Unsupported("WKST".to_string()),
+            ),
+            ("FREQ", RecurrenceError::InvalidValue("FREQ".to_string())),
+        ];
+
+        for (rule, expected) in cases {
+            assert_eq!(
+                rule.parse::<RecurrenceRule>().as_ref(),
+                Err(expected),
+                "{}",
+                rule
+            );
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use async_graphql::{InputObject, MaybeUndefined, SimpleObject};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::models::money::to_minor_units;
+use crate::models::recurrence::RecurrenceRule;
+use crate::models::tour::patched_value;
+use crate::schema::validation::{validate_max_participants, validate_price, ValidationErrors};
+
+pub const MAX_DURATION_MINUTES: i32 = 60 * 24 * 30;
+
+// Expands into departures starting at `start_time` in the tour's local time on
+// every date matched by `rrule`, except `exception_dates` and blackout dates. The
+// price is in the tour's currency.
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct DepartureSchedule {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub tour_id: i32,
+    pub rrule: String,
+    pub starts_on: NaiveDate,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i32,
+    pub exception_dates: Vec<NaiveDate>,
+    pub capacity: Option<i32>,
+    #[graphql(skip)]
+    pub price_minor: Option<i64>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DepartureSchedule {
+    pub fn recurrence(&self) -> Option<RecurrenceRule> {
+        self.rrule.parse().ok()
+    }
+}
+
+// A schedule together with its tour's time zone, which its local times are in.
+#[derive(FromRow, Clone, Debug)]
+pub struct MaterializableSchedule {
+    #[sqlx(flatten)]
+    pub schedule: DepartureSchedule,
+    pub timezone: String,
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct CreateDepartureScheduleInput {
+    pub rrule: String,
+    pub starts_on: NaiveDate,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i32,
+    pub exception_dates: Option<Vec<NaiveDate>>,
+    pub capacity: Option<i32>,
+    // In the tour's currency.
+    pub price: Option<Decimal>,
+    pub is_active: Option<bool>,
+}
+
+impl CreateDepartureScheduleInput {
+    pub fn normalize(mut self) -> Self {
+        self.rrule = normalize_rrule(&self.rrule);
+        self.exception_dates = self.exception_dates.map(normalize_dates);
+        self
+    }
+
+    pub fn validate(&self, currency: &str) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        validate_rule(
+            &mut errors,
+            &self.rrule,
+            self.duration_minutes,
+            self.capacity,
+        );
+        if let Some(price) = self.price {
+            validate_price(&mut errors, "price", price, currency);
+        }
+
+        errors.into_result()
+    }
+
+    pub fn price_minor(&self, currency: &str) -> Option<i64> {
+        to_minor_units(self.price, currency)
+    }
+}
+
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct UpdateDepartureScheduleInput {
+    pub rrule: Option<String>,
+    pub starts_on: Option<NaiveDate>,
+    pub start_time: Option<NaiveTime>,
+    pub duration_minutes: Option<i32>,
+    pub exception_dates: Option<Vec<NaiveDate>>,
+    pub capacity: MaybeUndefined<i32>,
+    // In the tour's currency.
+    pub price: MaybeUndefined<Decimal>,
+    pub is_active: Option<bool>,
+}
+
+impl UpdateDepartureScheduleInput {
+    pub fn normalize(mut self) -> Self {
+        self.rrule = self.rrule.as_deref().map(normalize_rrule);
+        self.exception_dates = self.exception_dates.map(normalize_dates);
+        self
+    }
+
+    pub fn validate(
+        &self,
+        schedule: &DepartureSchedule,
+        currency: &str,
+    ) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        validate_rule(
+            &mut errors,
+            self.rrule.as_deref().unwrap_or(&schedule.rrule),
+            self.duration_minutes.unwrap_or(schedule.duration_minutes),
+            patched_value(&self.capacity, schedule.capacity),
+        );
+        if let MaybeUndefined::Value(price) = self.price {
+            validate_price(&mut errors, "price", price, currency);
+        }
+
+        errors.into_result()
+    }
+
+    pub fn apply(self, schedule: &mut DepartureSchedule, currency: &str) {
+        if let Some(rrule) = self.rrule {
+            schedule.rrule = rrule;
+        }
+        if let Some(starts_on) = self.starts_on {
+            schedule.starts_on = starts_on;
+        }
+        if let Some(start_time) = self.start_time {
+            schedule.start_time = start_time;
+        }
+        if let Some(duration_minutes) = self.duration_minutes {
+            schedule.duration_minutes = duration_minutes;
+        }
+        if let Some(exception_dates) = self.exception_dates {
+            schedule.exception_dates = exception_dates;
+        }
+        schedule.capacity = patched_value(&self.capacity, schedule.capacity);
+        match self.price {
+            MaybeUndefined::Undefined => {}
+            MaybeUndefined::Null => schedule.price_minor = None,
+            MaybeUndefined::Value(price) => {
+                schedule.price_minor = to_minor_units(Some(price), currency)
+            }
+        }
+        if let Some(is_active) = self.is_active {
+            schedule.is_active = is_active;
+        }
+    }
+}
+
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct BlackoutCalendar {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct BlackoutDate {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub calendar_id: i32,
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+    pub reason: Option<String>,
+}
+
+impl BlackoutDate {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        (self.starts_on..=self.ends_on).contains(&date)
+    }
+}
+
+#[derive(SimpleObject, Copy, Clone, Debug, Default)]
+pub struct MaterializationSummary {
+    pub created: u64,
+    pub updated: u64,
+    pub removed: u64,
+}
+
+impl std::ops::AddAssign for MaterializationSummary {
+    fn add_assign(&mut self, other: Self) {
+        self.created += other.created;
+        self.updated += other.updated;
+        self.removed += other.removed;
+    }
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct BlackoutDateInput {
+    pub starts_on: NaiveDate,
+    pub ends_on: Option<NaiveDate>,
+    pub reason: Option<String>,
+}
+
+pub fn validate_blackout_dates(errors: &mut ValidationErrors, dates: &[BlackoutDateInput]) {
+    if dates
+        .iter()
+        .any(|date| date.ends_on.is_some_and(|ends_on| ends_on < date.starts_on))
+    {
+        errors.add("dates", "must not end before they start");
+    }
+}
+
+fn validate_rule(
+    errors: &mut ValidationErrors,
+    rrule: &str,
+    duration_minutes: i32,
+    capacity: Option<i32>,
+) {
+    if let Err(error) = rrule.parse::<RecurrenceRule>() {
+        errors.add("rrule", error.to_string());
+    }
+    if !(1..=MAX_DURATION_MINUTES).contains(&duration_minutes) {
+        errors.add(
+            "durationMinutes",
+            format!("must be between 1 and {}", MAX_DURATION_MINUTES),
+        );
+    }
+    if let Some(capacity) = capacity {
+        validate_max_participants(errors, "capacity", capacity);
+    }
+}
+
+fn normalize_rrule(rrule: &str) -> String {
+    let rrule = rrule.trim();
+    rrule
+        .strip_prefix("RRULE:")
+        .unwrap_or(rrule)
+        .to_ascii_uppercase()
+}
+
+fn normalize_dates(mut dates: Vec<NaiveDate>) -> Vec<NaiveDate> {
+    dates.sort();
+    dates.dedup();
+    dates
+}
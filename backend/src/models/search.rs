@@ -0,0 +1,65 @@
+use async_graphql::{Enum, SimpleObject};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::models::tour::Tour;
+use crate::schema::validation::ValidationErrors;
+
+pub const DEFAULT_LANGUAGE: &str = "english";
+
+// Text search configurations shipped with stock Postgres.
+pub const SEARCH_LANGUAGES: &[&str] = &[
+    "simple",
+    "arabic",
+    "danish",
+    "dutch",
+    "english",
+    "finnish",
+    "french",
+    "german",
+    "greek",
+    "hungarian",
+    "indonesian",
+    "irish",
+    "italian",
+    "lithuanian",
+    "nepali",
+    "norwegian",
+    "portuguese",
+    "romanian",
+    "russian",
+    "spanish",
+    "swedish",
+    "tamil",
+    "turkish",
+];
+
+pub fn validate_language(errors: &mut ValidationErrors, field: &str, language: &str) {
+    if !SEARCH_LANGUAGES.contains(&language) {
+        errors.add(field, "is not a supported search language");
+    }
+}
+
+#[derive(SimpleObject, FromRow, Clone, Debug)]
+pub struct TourSearchResult {
+    #[sqlx(flatten)]
+    pub tour: Tour,
+    pub rank: f32,
+    pub headline: String,
+}
+
+#[derive(Enum, Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Place,
+    Tour,
+}
+
+#[derive(SimpleObject, Serialize, Clone, Debug)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub text: String,
+    pub place_id: Option<i32>,
+    pub tour_id: Option<i32>,
+    pub score: f32,
+}
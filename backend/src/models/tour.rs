@@ -0,0 +1,558 @@
+use async_graphql::connection::CursorType;
+use async_graphql::{Enum, InputObject, MaybeUndefined, SimpleObject};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+#[cfg(test)]
+use std::cmp::Ordering;
+
+use crate::models::geo::validate_coordinates;
+use crate::models::money::{to_minor_units, validate_currency, DEFAULT_CURRENCY};
+use crate::models::place::validate_timezone;
+use crate::models::search::validate_language;
+use crate::models::user::CurrentUser;
+use crate::schema::validation::{
+    validate_image_url, validate_max_participants, validate_price, validate_rating, validate_slug,
+    validate_title, ValidationErrors,
+};
+
+#[derive(SimpleObject, Serialize, Deserialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct Tour {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub description: Option<String>,
+    #[graphql(deprecation = "Use `departures` instead")]
+    pub start_date: Option<NaiveDate>,
+    #[graphql(deprecation = "Use `departures` instead")]
+    pub end_date: Option<NaiveDate>,
+    #[graphql(skip)]
+    pub price_minor: Option<i64>,
+    pub currency: String,
+    pub rating: Option<f64>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub location: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub image_url: Option<String>,
+    pub is_active: bool,
+    pub max_participants: Option<i32>,
+    pub language: String,
+    pub place_id: Option<i32>,
+    pub timezone: String,
+}
+
+impl Tour {
+    // Falls back to UTC for names the time zone database doesn't know.
+    pub fn time_zone(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    // Inactive tours are left out of listings, search results and change feeds
+    // for everyone but admins. They can still be fetched by id or slug.
+    pub fn is_listed_for(&self, user: Option<&CurrentUser>) -> bool {
+        self.is_active || user.is_some_and(|user| user.is_admin)
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct TourConnectionFields {
+    pub total_count: i64,
+}
+
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct TourFilter {
+    pub location: Option<String>,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    // Price bounds are in `currency`, and only match tours priced in it.
+    pub currency: Option<String>,
+    pub price_min: Option<Decimal>,
+    pub price_max: Option<Decimal>,
+    pub min_rating: Option<f64>,
+    pub is_active: Option<bool>,
+    pub available_seats: Option<i32>,
+    pub place_id: Option<i32>,
+}
+
+impl TourFilter {
+    // Narrows the filter to the tours `user` may list, see `Tour::is_listed_for`.
+    pub fn restrict_to_listed(
+        &mut self,
+        user: Option<&CurrentUser>,
+    ) -> Result<(), ValidationErrors> {
+        if user.is_some_and(|user| user.is_admin) {
+            return Ok(());
+        }
+        if self.is_active == Some(false) {
+            let mut errors = ValidationErrors::new();
+            errors.add("isActive", "inactive tours are only listed for admins");
+            return Err(errors);
+        }
+
+        self.is_active = Some(true);
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let (Some(date_from), Some(date_to)) = (self.date_from, self.date_to) {
+            if date_to < date_from {
+                errors.add("dateTo", "must be on or after dateFrom");
+            }
+        }
+        if let Some(currency) = &self.currency {
+            validate_currency(&mut errors, "currency", currency);
+        }
+        if let Some(price_min) = self.price_min {
+            validate_price(&mut errors, "priceMin", price_min, self.price_currency());
+        }
+        if let Some(price_max) = self.price_max {
+            validate_price(&mut errors, "priceMax", price_max, self.price_currency());
+        }
+        if let (Some(price_min), Some(price_max)) = (self.price_min, self.price_max) {
+            if price_max < price_min {
+                errors.add("priceMax", "must be greater than or equal to priceMin");
+            }
+        }
+        if let Some(min_rating) = self.min_rating {
+            validate_rating(&mut errors, "minRating", min_rating);
+        }
+        if let Some(available_seats) = self.available_seats {
+            if available_seats < 0 {
+                errors.add("availableSeats", "must be greater than or equal to 0");
+            }
+        }
+
+        errors.into_result()
+    }
+
+    // The currency tours must be priced in, if any.
+    pub fn required_currency(&self) -> Option<&str> {
+        if self.currency.is_some() || self.price_min.is_some() || self.price_max.is_some() {
+            Some(self.price_currency())
+        } else {
+            None
+        }
+    }
+
+    pub fn price_min_minor(&self) -> Option<i64> {
+        to_minor_units(self.price_min, self.price_currency())
+    }
+
+    pub fn price_max_minor(&self) -> Option<i64> {
+        to_minor_units(self.price_max, self.price_currency())
+    }
+
+    fn price_currency(&self) -> &str {
+        self.currency.as_deref().unwrap_or(DEFAULT_CURRENCY)
+    }
+
+    pub fn matches(&self, tour: &Tour) -> bool {
+        let location_matches = self.location.as_ref().is_none_or(|location| {
+            tour.location.as_ref().is_some_and(|tour_location| {
+                tour_location
+                    .to_lowercase()
+                    .contains(&location.to_lowercase())
+            })
+        });
+        let date_from_matches = self.date_from.is_none_or(|date_from| {
+            tour.end_date
+                .or(tour.start_date)
+                .is_some_and(|end_date| end_date >= date_from)
+        });
+        let date_to_matches = self.date_to.is_none_or(|date_to| {
+            tour.start_date
+                .is_some_and(|start_date| start_date <= date_to)
+        });
+        let currency_matches = self
+            .required_currency()
+            .is_none_or(|currency| tour.currency == currency);
+        let price_matches = self.price_min_minor().is_none_or(|price_min| {
+            tour.price_minor
+                .is_some_and(|price_minor| price_minor >= price_min)
+        }) && self.price_max_minor().is_none_or(|price_max| {
+            tour.price_minor
+                .is_some_and(|price_minor| price_minor <= price_max)
+        });
+        let rating_matches = self
+            .min_rating
+            .is_none_or(|min_rating| tour.rating.is_some_and(|rating| rating >= min_rating));
+        let is_active_matches = self
+            .is_active
+            .is_none_or(|is_active| tour.is_active == is_active);
+        let seats_matches = self.available_seats.is_none_or(|available_seats| {
+            tour.max_participants
+                .is_none_or(|max_participants| max_participants >= available_seats)
+        });
+        let place_matches = self
+            .place_id
+            .is_none_or(|place_id| tour.place_id == Some(place_id));
+
+        location_matches
+            && place_matches
+            && date_from_matches
+            && date_to_matches
+            && currency_matches
+            && price_matches
+            && rating_matches
+            && is_active_matches
+            && seats_matches
+    }
+}
+
+#[derive(Enum, Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TourSortField {
+    // Compares amounts in minor units, so it's only meaningful within one currency.
+    Price,
+    Rating,
+    StartDate,
+    #[default]
+    CreatedAt,
+}
+
+#[derive(Enum, Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(InputObject, Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TourSort {
+    #[graphql(default)]
+    pub field: TourSortField,
+    #[graphql(default)]
+    pub direction: SortDirection,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum SortValue {
+    Integer(Option<i64>),
+    Float(Option<f64>),
+    Date(Option<NaiveDate>),
+    DateTime(Option<DateTime<Utc>>),
+}
+
+impl SortValue {
+    pub fn is_null(&self) -> bool {
+        match self {
+            SortValue::Integer(value) => value.is_none(),
+            SortValue::Float(value) => value.is_none(),
+            SortValue::Date(value) => value.is_none(),
+            SortValue::DateTime(value) => value.is_none(),
+        }
+    }
+
+    #[cfg(test)]
+    fn cmp_non_null(&self, other: &SortValue) -> Ordering {
+        match (self, other) {
+            (SortValue::Integer(Some(left)), SortValue::Integer(Some(right))) => left.cmp(right),
+            (SortValue::Float(Some(left)), SortValue::Float(Some(right))) => left.total_cmp(right),
+            (SortValue::Date(Some(left)), SortValue::Date(Some(right))) => left.cmp(right),
+            (SortValue::DateTime(Some(left)), SortValue::DateTime(Some(right))) => left.cmp(right),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TourCursor {
+    pub sort: TourSort,
+    pub value: SortValue,
+    pub id: i32,
+}
+
+impl TourCursor {
+    pub fn new(tour: &Tour, sort: TourSort) -> Self {
+        let value = match sort.field {
+            TourSortField::Price => SortValue::Integer(tour.price_minor),
+            TourSortField::Rating => SortValue::Float(tour.rating),
+            TourSortField::StartDate => SortValue::Date(tour.start_date),
+            TourSortField::CreatedAt => SortValue::DateTime(tour.created_at),
+        };
+
+        Self {
+            sort,
+            value,
+            id: tour.id,
+        }
+    }
+
+    #[cfg(test)]
+    // Mirrors `ORDER BY <field> <direction> NULLS LAST, id <direction>`.
+    pub fn cmp_position(&self, other: &TourCursor) -> Ordering {
+        let apply_direction = |ordering: Ordering| match self.sort.direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        };
+
+        let value_ordering = match (self.value.is_null(), other.value.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => apply_direction(self.value.cmp_non_null(&other.value)),
+        };
+
+        value_ordering.then_with(|| apply_direction(self.id.cmp(&other.id)))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid cursor")]
+pub struct InvalidCursor;
+
+impl CursorType for TourCursor {
+    type Error = InvalidCursor;
+
+    fn decode_cursor(cursor: &str) -> Result<Self, Self::Error> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| InvalidCursor)?;
+        serde_json::from_slice(&bytes).map_err(|_| InvalidCursor)
+    }
+
+    fn encode_cursor(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+}
+
+#[derive(InputObject, Clone, Debug)]
+pub struct CreateTourInput {
+    pub title: String,
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    // In `currency`, which defaults to USD.
+    pub price: Option<Decimal>,
+    pub currency: Option<String>,
+    pub rating: Option<f64>,
+    pub location: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub image_url: Option<String>,
+    pub is_active: bool,
+    pub max_participants: Option<i32>,
+    pub language: Option<String>,
+    pub place_id: Option<i32>,
+    pub timezone: Option<String>,
+}
+
+impl CreateTourInput {
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        validate_title(&mut errors, "title", &self.title);
+        if let Some(slug) = &self.slug {
+            validate_slug(&mut errors, "slug", slug);
+        }
+        if let Some(language) = &self.language {
+            validate_language(&mut errors, "language", language);
+        }
+        if let Some(timezone) = &self.timezone {
+            validate_timezone(&mut errors, "timezone", timezone);
+        }
+        if let Some(currency) = &self.currency {
+            validate_currency(&mut errors, "currency", currency);
+        }
+        validate_date_range(&mut errors, self.start_date, self.end_date);
+        validate_coordinates(&mut errors, self.latitude, self.longitude);
+        validate_optional_fields(
+            &mut errors,
+            self.price,
+            self.currency(),
+            self.rating,
+            self.max_participants,
+            self.image_url.as_deref(),
+        );
+
+        errors.into_result()
+    }
+
+    pub fn currency(&self) -> &str {
+        self.currency.as_deref().unwrap_or(DEFAULT_CURRENCY)
+    }
+
+    pub fn price_minor(&self) -> Option<i64> {
+        to_minor_units(self.price, self.currency())
+    }
+}
+
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct UpdateTourInput {
+    pub title: Option<String>,
+    pub slug: Option<String>,
+    pub description: MaybeUndefined<String>,
+    pub start_date: MaybeUndefined<NaiveDate>,
+    pub end_date: MaybeUndefined<NaiveDate>,
+    // In `currency`, or the tour's current currency when that isn't changed.
+    pub price: MaybeUndefined<Decimal>,
+    pub currency: Option<String>,
+    pub rating: MaybeUndefined<f64>,
+    pub location: MaybeUndefined<String>,
+    pub latitude: MaybeUndefined<f64>,
+    pub longitude: MaybeUndefined<f64>,
+    pub image_url: MaybeUndefined<String>,
+    pub is_active: Option<bool>,
+    pub max_participants: MaybeUndefined<i32>,
+    pub language: Option<String>,
+    pub place_id: MaybeUndefined<i32>,
+    pub timezone: Option<String>,
+}
+
+impl UpdateTourInput {
+    pub fn validate(&self, tour: &Tour) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let Some(title) = &self.title {
+            validate_title(&mut errors, "title", title);
+        }
+        if let Some(slug) = &self.slug {
+            validate_slug(&mut errors, "slug", slug);
+        }
+        if let Some(language) = &self.language {
+            validate_language(&mut errors, "language", language);
+        }
+        if let Some(timezone) = &self.timezone {
+            validate_timezone(&mut errors, "timezone", timezone);
+        }
+        if let Some(currency) = &self.currency {
+            validate_currency(&mut errors, "currency", currency);
+            if *currency != tour.currency && tour.price_minor.is_some() && self.price.is_undefined()
+            {
+                errors.add("price", "must be set again when the currency changes");
+            }
+        }
+        validate_date_range(
+            &mut errors,
+            patched_value(&self.start_date, tour.start_date),
+            patched_value(&self.end_date, tour.end_date),
+        );
+        validate_coordinates(
+            &mut errors,
+            patched_value(&self.latitude, tour.latitude),
+            patched_value(&self.longitude, tour.longitude),
+        );
+        validate_optional_fields(
+            &mut errors,
+            self.price.value().copied(),
+            self.currency.as_deref().unwrap_or(&tour.currency),
+            self.rating.value().copied(),
+            self.max_participants.value().copied(),
+            self.image_url.value().map(String::as_str),
+        );
+
+        errors.into_result()
+    }
+
+    // Pins the currency a new price is in, so the price can be stored without the tour.
+    pub fn normalize(mut self, tour: &Tour) -> Self {
+        if self.currency.is_none() && !self.price.is_undefined() {
+            self.currency = Some(tour.currency.clone());
+        }
+        self
+    }
+
+    // Only valid after `normalize`.
+    pub fn price_minor(&self) -> MaybeUndefined<i64> {
+        let currency = self.currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+        match self.price {
+            MaybeUndefined::Undefined => MaybeUndefined::Undefined,
+            MaybeUndefined::Null => MaybeUndefined::Null,
+            MaybeUndefined::Value(price) => to_minor_units(Some(price), currency)
+                .map_or(MaybeUndefined::Null, MaybeUndefined::Value),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn apply(self, tour: &mut Tour) {
+        apply_patch(&mut tour.price_minor, self.price_minor());
+        if let Some(title) = self.title {
+            tour.title = title;
+        }
+        if let Some(slug) = self.slug {
+            tour.slug = slug;
+        }
+        apply_patch(&mut tour.description, self.description);
+        apply_patch(&mut tour.start_date, self.start_date);
+        apply_patch(&mut tour.end_date, self.end_date);
+        if let Some(currency) = self.currency {
+            tour.currency = currency;
+        }
+        apply_patch(&mut tour.rating, self.rating);
+        apply_patch(&mut tour.location, self.location);
+        apply_patch(&mut tour.latitude, self.latitude);
+        apply_patch(&mut tour.longitude, self.longitude);
+        apply_patch(&mut tour.image_url, self.image_url);
+        if let Some(is_active) = self.is_active {
+            tour.is_active = is_active;
+        }
+        apply_patch(&mut tour.max_participants, self.max_participants);
+        if let Some(language) = self.language {
+            tour.language = language;
+        }
+        apply_patch(&mut tour.place_id, self.place_id);
+        if let Some(timezone) = self.timezone {
+            tour.timezone = timezone;
+        }
+    }
+}
+
+pub(crate) fn patched_value<T: Copy>(patch: &MaybeUndefined<T>, current: Option<T>) -> Option<T> {
+    match patch {
+        MaybeUndefined::Undefined => current,
+        MaybeUndefined::Null => None,
+        MaybeUndefined::Value(value) => Some(*value),
+    }
+}
+
+pub(crate) fn apply_patch<T>(field: &mut Option<T>, patch: MaybeUndefined<T>) {
+    match patch {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => *field = None,
+        MaybeUndefined::Value(value) => *field = Some(value),
+    }
+}
+
+fn validate_date_range(
+    errors: &mut ValidationErrors,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) {
+    if let (Some(start_date), Some(end_date)) = (start_date, end_date) {
+        if end_date < start_date {
+            errors.add("endDate", "must be on or after startDate");
+        }
+    }
+}
+
+fn validate_optional_fields(
+    errors: &mut ValidationErrors,
+    price: Option<Decimal>,
+    currency: &str,
+    rating: Option<f64>,
+    max_participants: Option<i32>,
+    image_url: Option<&str>,
+) {
+    if let Some(price) = price {
+        validate_price(errors, "price", price, currency);
+    }
+    if let Some(rating) = rating {
+        validate_rating(errors, "rating", rating);
+    }
+    if let Some(max_participants) = max_participants {
+        validate_max_participants(errors, "maxParticipants", max_participants);
+    }
+    if let Some(image_url) = image_url {
+        validate_image_url(errors, "imageUrl", image_url);
+    }
+}
@@ -0,0 +1,84 @@
+use josekit::jwt::JwtPayload;
+
+#[derive(Clone, Debug)]
+pub struct CurrentUser {
+    pub id: String,
+    pub is_admin: bool,
+}
+
+impl CurrentUser {
+    // Admins are listed by id in `admin_user_ids`, a comma-separated list like
+    // ADMIN_USER_IDS. Names and other claims can be chosen by the user at sign-in,
+    // so they never grant admin access.
+    pub fn from_payload(payload: &JwtPayload, admin_user_ids: &str) -> Option<Self> {
+        let id = payload
+            .subject()
+            .or_else(|| payload.claim("email").and_then(|email| email.as_str()))
+            .map(str::to_string)?;
+        let is_admin = admin_user_ids
+            .split(',')
+            .map(str::trim)
+            .any(|admin_user_id| !admin_user_id.is_empty() && admin_user_id == id);
+
+        Some(Self { id, is_admin })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn payload(subject: Option<&str>, name: &str, email: &str) -> JwtPayload {
+        let mut payload = JwtPayload::new();
+        if let Some(subject) = subject {
+            payload.set_subject(subject);
+        }
+        payload.set_claim("name", Some(json!(name))).unwrap();
+        payload.set_claim("email", Some(json!(email))).unwrap();
+        payload
+    }
+
+    #[test]
+    fn admins_come_from_the_allowlist() {
+        let user = CurrentUser::from_payload(
+            &payload(Some("user-2"), "Jo", "jo@example.com"),
+            "user-1, user-2",
+        )
+        .unwrap();
+        assert_eq!(user.id, "user-2");
+        assert!(user.is_admin);
+
+        let user = CurrentUser::from_payload(
+            &payload(Some("user-3"), "Jo", "jo@example.com"),
+            "user-1, user-2",
+        )
+        .unwrap();
+        assert!(!user.is_admin);
+    }
+
+    #[test]
+    fn the_admin_name_does_not_grant_admin() {
+        let user =
+            CurrentUser::from_payload(&payload(Some("user-1"), "admin", "admin@example.com"), "")
+                .unwrap();
+        assert!(!user.is_admin);
+    }
+
+    #[test]
+    fn empty_allowlist_entries_match_nobody() {
+        let user =
+            CurrentUser::from_payload(&payload(None, "Jo", "jo@example.com"), " , ,").unwrap();
+        assert_eq!(user.id, "jo@example.com");
+        assert!(!user.is_admin);
+    }
+
+    #[test]
+    fn the_email_is_the_id_without_a_subject() {
+        let user =
+            CurrentUser::from_payload(&payload(None, "Jo", "jo@example.com"), "jo@example.com")
+                .unwrap();
+        assert!(user.is_admin);
+    }
+}
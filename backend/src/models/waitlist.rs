@@ -0,0 +1,39 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Enum, Serialize, Deserialize, sqlx::Type, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum WaitlistStatus {
+    Waiting,
+    // Seats are held for the user until `offer_expires_at`.
+    Offered,
+    Claimed,
+    // The offer ran out before it was claimed.
+    Expired,
+    Left,
+    // The departure was cancelled.
+    Closed,
+}
+
+#[derive(SimpleObject, Serialize, FromRow, Clone, Debug)]
+#[graphql(complex)]
+pub struct WaitlistEntry {
+    #[graphql(name = "databaseId")]
+    pub id: i32,
+    pub departure_id: i32,
+    #[graphql(skip)]
+    pub user_id: String,
+    pub participants: i32,
+    pub status: WaitlistStatus,
+    #[graphql(skip)]
+    pub hold_id: Option<i32>,
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub claim_token: Option<String>,
+    pub offer_expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,104 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::models::booking::{Booking, SeatHold};
+use crate::models::pricing::{ParticipantsInput, PriceLine, PricingError};
+use crate::models::promotion::PromotionError;
+use crate::repository::RepositoryError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BookingError {
+    #[error("departure not found")]
+    DepartureNotFound,
+    #[error("departure is not open for booking")]
+    DepartureUnavailable,
+    #[error("only {available} seats are available")]
+    InsufficientSeats { available: i32 },
+    #[error("seat hold not found")]
+    HoldNotFound,
+    #[error("seat hold is no longer active")]
+    HoldInactive,
+    #[error("participants don't add up to the {seats} held seats")]
+    HeldSeatsMismatch { seats: i32 },
+    #[error(transparent)]
+    Pricing(#[from] PricingError),
+    #[error(transparent)]
+    Promotion(#[from] PromotionError),
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+}
+
+impl From<sqlx::Error> for BookingError {
+    fn from(error: sqlx::Error) -> Self {
+        BookingError::Repository(error.into())
+    }
+}
+
+#[async_trait]
+pub trait BookingRepository: Send + Sync {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Booking>, RepositoryError>;
+
+    async fn find_by_user(
+        &self,
+        user_id: &str,
+        limit: usize,
+    ) -> Result<Vec<Booking>, RepositoryError>;
+
+    // Bookings still confirmed on the departure, plus those cancelled at `changed_at`
+    // as part of the same change.
+    async fn find_affected_by_departure(
+        &self,
+        departure_id: i32,
+        changed_at: DateTime<Utc>,
+    ) -> Result<Vec<Booking>, RepositoryError>;
+
+    async fn find_price_lines(&self, booking_id: i32) -> Result<Vec<PriceLine>, RepositoryError>;
+
+    // Checks capacity, prices the party, applies `promo_code` and inserts the
+    // booking in one transaction that locks the departure row, so concurrent
+    // bookings for the same departure are serialized.
+    async fn create(
+        &self,
+        departure_id: i32,
+        user_id: &str,
+        party: &[ParticipantsInput],
+        promo_code: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Booking, BookingError>;
+
+    async fn cancel(&self, id: i32, now: DateTime<Utc>)
+        -> Result<Option<Booking>, RepositoryError>;
+
+    async fn find_hold_by_id(&self, id: i32) -> Result<Option<SeatHold>, RepositoryError>;
+
+    // Checks capacity the same way as `create`, counting other active holds.
+    async fn create_hold(
+        &self,
+        departure_id: i32,
+        user_id: &str,
+        seats: i32,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<SeatHold, BookingError>;
+
+    // Books the held seats and marks the hold converted in one transaction. Without
+    // a party, every held seat is priced as an adult.
+    async fn convert_hold(
+        &self,
+        id: i32,
+        party: Option<&[ParticipantsInput]>,
+        promo_code: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Booking, BookingError>;
+
+    async fn release_hold(
+        &self,
+        id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<SeatHold>, RepositoryError>;
+
+    // Marks active holds past their expiry as expired and returns how many were.
+    async fn expire_holds(&self, now: DateTime<Utc>) -> Result<u64, RepositoryError>;
+}
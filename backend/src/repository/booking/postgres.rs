@@ -0,0 +1,458 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::models::booking::{Booking, SeatHold};
+use crate::models::pricing::{adults, party_size, ParticipantsInput, PriceLine, Quote};
+use crate::models::promotion::PromotionError;
+use crate::repository::booking::{BookingError, BookingRepository};
+use crate::repository::pricing::postgres::find_price_list;
+use crate::repository::promotion::postgres::{count_redemptions, find_by_code};
+use crate::repository::RepositoryError;
+
+pub struct PostgresBookingRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresBookingRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl BookingRepository for PostgresBookingRepository {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Booking>, RepositoryError> {
+        let booking = sqlx::query_as::<_, Booking>("SELECT * FROM bookings WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(booking)
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: &str,
+        limit: usize,
+    ) -> Result<Vec<Booking>, RepositoryError> {
+        let bookings = sqlx::query_as::<_, Booking>(
+            r#"
+            SELECT * FROM bookings
+            WHERE user_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit as i64)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(bookings)
+    }
+
+    async fn find_affected_by_departure(
+        &self,
+        departure_id: i32,
+        changed_at: DateTime<Utc>,
+    ) -> Result<Vec<Booking>, RepositoryError> {
+        let bookings = sqlx::query_as::<_, Booking>(
+            r#"
+            SELECT * FROM bookings
+            WHERE departure_id = $1 AND (status = 'confirmed' OR cancelled_at = $2)
+            ORDER BY id
+            "#,
+        )
+        .bind(departure_id)
+        .bind(changed_at)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(bookings)
+    }
+
+    async fn find_price_lines(&self, booking_id: i32) -> Result<Vec<PriceLine>, RepositoryError> {
+        let lines = sqlx::query_as::<_, PriceLine>(
+            r#"
+            SELECT booking_price_lines.*, bookings.currency
+            FROM booking_price_lines
+            JOIN bookings ON bookings.id = booking_price_lines.booking_id
+            WHERE booking_price_lines.booking_id = $1
+            ORDER BY booking_price_lines.position
+            "#,
+        )
+        .bind(booking_id)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(lines)
+    }
+
+    async fn create(
+        &self,
+        departure_id: i32,
+        user_id: &str,
+        party: &[ParticipantsInput],
+        promo_code: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Booking, BookingError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let available = lock_available_seats(&mut transaction, departure_id, None, now).await?;
+        ensure_available(available, party_size(party))?;
+        let quote = quote_booking(
+            &mut transaction,
+            departure_id,
+            user_id,
+            party,
+            promo_code,
+            now,
+        )
+        .await?;
+        let booking = insert_booking(&mut transaction, user_id, &quote, now).await?;
+
+        transaction.commit().await?;
+
+        Ok(booking)
+    }
+
+    async fn cancel(
+        &self,
+        id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Booking>, RepositoryError> {
+        let booking = sqlx::query_as::<_, Booking>(
+            r#"
+            UPDATE bookings SET status = 'cancelled', cancelled_at = $2, updated_at = $2
+            WHERE id = $1 AND status = 'confirmed'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(booking)
+    }
+
+    async fn find_hold_by_id(&self, id: i32) -> Result<Option<SeatHold>, RepositoryError> {
+        let hold = sqlx::query_as::<_, SeatHold>("SELECT * FROM seat_holds WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(hold)
+    }
+
+    async fn create_hold(
+        &self,
+        departure_id: i32,
+        user_id: &str,
+        seats: i32,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<SeatHold, BookingError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let available = lock_available_seats(&mut transaction, departure_id, None, now).await?;
+        ensure_available(available, seats)?;
+        let hold = insert_hold(
+            &mut transaction,
+            departure_id,
+            user_id,
+            seats,
+            expires_at,
+            now,
+        )
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(hold)
+    }
+
+    async fn convert_hold(
+        &self,
+        id: i32,
+        party: Option<&[ParticipantsInput]>,
+        promo_code: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Booking, BookingError> {
+        let mut transaction = self.database_pool.begin().await?;
+        let booking = convert_hold(&mut transaction, id, party, promo_code, now).await?;
+        transaction.commit().await?;
+
+        Ok(booking)
+    }
+
+    async fn release_hold(
+        &self,
+        id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<SeatHold>, RepositoryError> {
+        let hold = sqlx::query_as::<_, SeatHold>(
+            r#"
+            UPDATE seat_holds SET status = 'released', updated_at = $2
+            WHERE id = $1 AND status = 'active' AND expires_at > $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(hold)
+    }
+
+    async fn expire_holds(&self, now: DateTime<Utc>) -> Result<u64, RepositoryError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE seat_holds SET status = 'expired', updated_at = $1
+            WHERE status = 'active' AND expires_at <= $1
+            "#,
+        )
+        .bind(now)
+        .execute(&self.database_pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// Locks the departure row, so concurrent bookings and holds for it are serialized,
+// and returns its free seats, or `None` when neither the departure nor its tour
+// limits participants. Seats of `converting_hold` count as free.
+pub(crate) async fn lock_available_seats(
+    transaction: &mut Transaction<'_, Postgres>,
+    departure_id: i32,
+    converting_hold: Option<i32>,
+    now: DateTime<Utc>,
+) -> Result<Option<i64>, BookingError> {
+    let (is_open, capacity) = sqlx::query_as::<_, (bool, Option<i32>)>(
+        r#"
+        SELECT
+            departures.status = 'scheduled' AND departures.starts_at > $2 AND tours.is_active,
+            COALESCE(departures.capacity, tours.max_participants)
+        FROM departures
+        JOIN tours ON tours.id = departures.tour_id
+        WHERE departures.id = $1
+        FOR UPDATE OF departures
+        "#,
+    )
+    .bind(departure_id)
+    .bind(now)
+    .fetch_optional(&mut **transaction)
+    .await?
+    .ok_or(BookingError::DepartureNotFound)?;
+
+    if !is_open {
+        return Err(BookingError::DepartureUnavailable);
+    }
+    let Some(capacity) = capacity else {
+        return Ok(None);
+    };
+
+    let taken_seats = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT (
+            SELECT COALESCE(SUM(participants), 0) FROM bookings
+            WHERE departure_id = $1 AND status = 'confirmed'
+        ) + (
+            SELECT COALESCE(SUM(seats), 0) FROM seat_holds
+            WHERE departure_id = $1 AND status = 'active' AND expires_at > $2
+              AND id IS DISTINCT FROM $3
+        )::bigint
+        "#,
+    )
+    .bind(departure_id)
+    .bind(now)
+    .bind(converting_hold)
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(Some((i64::from(capacity) - taken_seats).max(0)))
+}
+
+pub(crate) fn ensure_available(available: Option<i64>, seats: i32) -> Result<(), BookingError> {
+    match available {
+        Some(available) if i64::from(seats) > available => Err(BookingError::InsufficientSeats {
+            available: available as i32,
+        }),
+        _ => Ok(()),
+    }
+}
+
+// Prices the party with the departure's price list as it is inside the
+// transaction, the same way `quote` does, and checks `promo_code` against the
+// promotion's redemptions with the promotion locked.
+async fn quote_booking(
+    transaction: &mut Transaction<'_, Postgres>,
+    departure_id: i32,
+    user_id: &str,
+    party: &[ParticipantsInput],
+    promo_code: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Quote, BookingError> {
+    let price_list = find_price_list(transaction, departure_id)
+        .await?
+        .ok_or(BookingError::DepartureNotFound)?;
+    let mut quote = price_list.quote(party, now)?;
+
+    if let Some(promo_code) = promo_code {
+        let promotion = find_by_code(transaction, promo_code, true)
+            .await?
+            .ok_or(PromotionError::Invalid)?;
+        let counts = count_redemptions(transaction, promotion.id, Some(user_id)).await?;
+        promotion.apply(&mut quote, counts, now)?;
+    }
+
+    Ok(quote)
+}
+
+async fn insert_booking(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &str,
+    quote: &Quote,
+    now: DateTime<Utc>,
+) -> Result<Booking, BookingError> {
+    let booking = sqlx::query_as::<_, Booking>(
+        r#"
+        INSERT INTO bookings (departure_id, user_id, participants, status, price_minor, currency, created_at, updated_at)
+        VALUES ($1, $2, $3, 'confirmed', $4, $5, $6, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(quote.departure_id)
+    .bind(user_id)
+    .bind(quote.participants)
+    .bind(quote.total_minor)
+    .bind(&quote.currency)
+    .bind(now)
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    for (position, line) in quote.lines.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO booking_price_lines (booking_id, position, kind, category, description, pricing_rule_id, quantity, unit_amount_minor, amount_minor)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(booking.id)
+        .bind(position as i32)
+        .bind(line.kind)
+        .bind(line.category)
+        .bind(&line.description)
+        .bind(line.pricing_rule_id)
+        .bind(line.quantity)
+        .bind(line.unit_amount_minor)
+        .bind(line.amount_minor)
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    if let Some(promotion_id) = quote.promotion_id {
+        sqlx::query(
+            r#"
+            INSERT INTO promotion_redemptions (promotion_id, booking_id, user_id, discount_minor, currency, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(promotion_id)
+        .bind(booking.id)
+        .bind(user_id)
+        .bind(quote.discount_minor)
+        .bind(&quote.currency)
+        .bind(now)
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    Ok(booking)
+}
+
+pub(crate) async fn insert_hold(
+    transaction: &mut Transaction<'_, Postgres>,
+    departure_id: i32,
+    user_id: &str,
+    seats: i32,
+    expires_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<SeatHold, BookingError> {
+    let hold = sqlx::query_as::<_, SeatHold>(
+        r#"
+        INSERT INTO seat_holds (departure_id, user_id, seats, status, expires_at, created_at, updated_at)
+        VALUES ($1, $2, $3, 'active', $4, $5, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(departure_id)
+    .bind(user_id)
+    .bind(seats)
+    .bind(expires_at)
+    .bind(now)
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(hold)
+}
+
+// Books the seats of an active hold and marks it converted. Without a party,
+// every held seat is priced as an adult.
+pub(crate) async fn convert_hold(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: i32,
+    party: Option<&[ParticipantsInput]>,
+    promo_code: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Booking, BookingError> {
+    let departure_id =
+        sqlx::query_scalar::<_, i32>("SELECT departure_id FROM seat_holds WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut **transaction)
+            .await?
+            .ok_or(BookingError::HoldNotFound)?;
+
+    // The departure is locked before the hold, in the same order as departure
+    // cancellation, so the two can't deadlock.
+    let available = lock_available_seats(transaction, departure_id, Some(id), now).await?;
+    let hold = sqlx::query_as::<_, SeatHold>("SELECT * FROM seat_holds WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_one(&mut **transaction)
+        .await?;
+    if !hold.is_active(now) {
+        return Err(BookingError::HoldInactive);
+    }
+    ensure_available(available, hold.seats)?;
+
+    let party = party.map_or_else(|| adults(hold.seats), <[_]>::to_vec);
+    if party_size(&party) != hold.seats {
+        return Err(BookingError::HeldSeatsMismatch { seats: hold.seats });
+    }
+    let quote = quote_booking(
+        transaction,
+        departure_id,
+        &hold.user_id,
+        &party,
+        promo_code,
+        now,
+    )
+    .await?;
+    let booking = insert_booking(transaction, &hold.user_id, &quote, now).await?;
+    sqlx::query(
+        r#"
+        UPDATE seat_holds SET status = 'converted', booking_id = $2, updated_at = $3
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(booking.id)
+    .bind(now)
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(booking)
+}
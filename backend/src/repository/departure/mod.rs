@@ -0,0 +1,50 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::models::departure::{CreateDepartureInput, Departure, RescheduleDepartureInput};
+use crate::repository::RepositoryError;
+
+#[async_trait]
+pub trait DepartureRepository: Send + Sync {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Departure>, RepositoryError>;
+
+    async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<Departure>, RepositoryError>;
+
+    async fn find_by_tour(
+        &self,
+        tour_id: i32,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Departure>, RepositoryError>;
+
+    // `None` when neither the departure nor its tour limits participants.
+    async fn count_available_seats(&self, id: i32) -> Result<Option<i64>, RepositoryError>;
+
+    async fn create(
+        &self,
+        tour_id: i32,
+        input: CreateDepartureInput,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Departure, RepositoryError>;
+
+    // Rescheduled departures are detached from their schedule so that
+    // materializing it doesn't move them back.
+    async fn reschedule(
+        &self,
+        id: i32,
+        input: RescheduleDepartureInput,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Departure>, RepositoryError>;
+
+    // Cancels the departure together with its confirmed bookings, which are marked
+    // cancelled at the same `now`.
+    async fn cancel(
+        &self,
+        id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Departure>, RepositoryError>;
+}
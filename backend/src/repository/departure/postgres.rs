@@ -0,0 +1,195 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::models::departure::{CreateDepartureInput, Departure, RescheduleDepartureInput};
+use crate::repository::departure::DepartureRepository;
+use crate::repository::RepositoryError;
+
+pub struct PostgresDepartureRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresDepartureRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl DepartureRepository for PostgresDepartureRepository {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Departure>, RepositoryError> {
+        let departure = sqlx::query_as::<_, Departure>("SELECT * FROM departures WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(departure)
+    }
+
+    async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<Departure>, RepositoryError> {
+        let departures =
+            sqlx::query_as::<_, Departure>("SELECT * FROM departures WHERE id = ANY($1)")
+                .bind(ids)
+                .fetch_all(&self.database_pool)
+                .await?;
+
+        Ok(departures)
+    }
+
+    async fn find_by_tour(
+        &self,
+        tour_id: i32,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Departure>, RepositoryError> {
+        let mut query_builder =
+            QueryBuilder::<Postgres>::new("SELECT * FROM departures WHERE tour_id = ");
+        query_builder.push_bind(tour_id);
+        if let Some(from) = from {
+            query_builder.push(" AND starts_at >= ").push_bind(from);
+        }
+        if let Some(to) = to {
+            query_builder.push(" AND starts_at < ").push_bind(to);
+        }
+        query_builder
+            .push(" ORDER BY starts_at, id LIMIT ")
+            .push_bind(limit as i64);
+
+        let departures = query_builder
+            .build_query_as::<Departure>()
+            .fetch_all(&self.database_pool)
+            .await?;
+
+        Ok(departures)
+    }
+
+    async fn count_available_seats(&self, id: i32) -> Result<Option<i64>, RepositoryError> {
+        let available_seats = sqlx::query_scalar::<_, Option<i64>>(
+            r#"
+            SELECT GREATEST(COALESCE(departures.capacity, tours.max_participants) - (
+                SELECT COALESCE(SUM(participants), 0) FROM bookings
+                WHERE departure_id = departures.id AND status = 'confirmed'
+            ) - (
+                SELECT COALESCE(SUM(seats), 0) FROM seat_holds
+                WHERE departure_id = departures.id AND status = 'active' AND expires_at > now()
+            ), 0)::bigint
+            FROM departures
+            JOIN tours ON tours.id = departures.tour_id
+            WHERE departures.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(available_seats.flatten())
+    }
+
+    async fn create(
+        &self,
+        tour_id: i32,
+        input: CreateDepartureInput,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Departure, RepositoryError> {
+        let departure = sqlx::query_as::<_, Departure>(
+            r#"
+            INSERT INTO departures (tour_id, starts_at, ends_at, capacity, price_minor, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(tour_id)
+        .bind(input.starts_at)
+        .bind(input.ends_at)
+        .bind(input.capacity)
+        .bind(input.price_minor(currency))
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(departure)
+    }
+
+    async fn reschedule(
+        &self,
+        id: i32,
+        input: RescheduleDepartureInput,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Departure>, RepositoryError> {
+        let departure = sqlx::query_as::<_, Departure>(
+            r#"
+            UPDATE departures SET starts_at = $2, ends_at = $3, schedule_id = NULL, updated_at = $4
+            WHERE id = $1 AND status = 'scheduled'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(input.starts_at)
+        .bind(input.ends_at)
+        .bind(now)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(departure)
+    }
+
+    async fn cancel(
+        &self,
+        id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Departure>, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let departure = sqlx::query_as::<_, Departure>(
+            r#"
+            UPDATE departures SET status = 'cancelled', updated_at = $2
+            WHERE id = $1 AND status = 'scheduled'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        if departure.is_some() {
+            sqlx::query(
+                r#"
+                UPDATE bookings SET status = 'cancelled', cancelled_at = $2, updated_at = $2
+                WHERE departure_id = $1 AND status = 'confirmed'
+                "#,
+            )
+            .bind(id)
+            .bind(now)
+            .execute(&mut *transaction)
+            .await?;
+            sqlx::query(
+                r#"
+                UPDATE seat_holds SET status = 'released', updated_at = $2
+                WHERE departure_id = $1 AND status = 'active'
+                "#,
+            )
+            .bind(id)
+            .bind(now)
+            .execute(&mut *transaction)
+            .await?;
+            sqlx::query(
+                r#"
+                UPDATE waitlist_entries SET status = 'closed', updated_at = $2
+                WHERE departure_id = $1 AND status IN ('waiting', 'offered')
+                "#,
+            )
+            .bind(id)
+            .bind(now)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(departure)
+    }
+}
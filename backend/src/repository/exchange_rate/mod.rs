@@ -0,0 +1,35 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::models::money::ExchangeRate;
+use crate::repository::RepositoryError;
+
+#[async_trait]
+pub trait ExchangeRateRepository: Send + Sync {
+    async fn find_all(&self) -> Result<Vec<ExchangeRate>, RepositoryError>;
+
+    // The rate set for `base_currency` to `quote_currency`, falling back to the
+    // reverse pair. Returns the rate and whether it's the reverse one.
+    async fn find_rate(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+    ) -> Result<Option<(Decimal, bool)>, RepositoryError>;
+
+    async fn upsert(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+        rate: Decimal,
+        now: DateTime<Utc>,
+    ) -> Result<ExchangeRate, RepositoryError>;
+
+    async fn delete(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+    ) -> Result<bool, RepositoryError>;
+}
@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use crate::models::money::ExchangeRate;
+use crate::repository::exchange_rate::ExchangeRateRepository;
+use crate::repository::RepositoryError;
+
+pub struct PostgresExchangeRateRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresExchangeRateRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl ExchangeRateRepository for PostgresExchangeRateRepository {
+    async fn find_all(&self) -> Result<Vec<ExchangeRate>, RepositoryError> {
+        let rates = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            SELECT base_currency, quote_currency, trim_scale(rate) AS rate, updated_at FROM exchange_rates
+            ORDER BY base_currency, quote_currency
+            "#,
+        )
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(rates)
+    }
+
+    async fn find_rate(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+    ) -> Result<Option<(Decimal, bool)>, RepositoryError> {
+        let rate = sqlx::query_as::<_, (Decimal, bool)>(
+            r#"
+            SELECT rate, base_currency <> $1 AS is_reverse FROM exchange_rates
+            WHERE (base_currency = $1 AND quote_currency = $2)
+                OR (base_currency = $2 AND quote_currency = $1)
+            ORDER BY is_reverse
+            LIMIT 1
+            "#,
+        )
+        .bind(base_currency)
+        .bind(quote_currency)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(rate)
+    }
+
+    async fn upsert(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+        rate: Decimal,
+        now: DateTime<Utc>,
+    ) -> Result<ExchangeRate, RepositoryError> {
+        let rate = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            INSERT INTO exchange_rates (base_currency, quote_currency, rate, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $4)
+            ON CONFLICT (base_currency, quote_currency)
+            DO UPDATE SET rate = EXCLUDED.rate, updated_at = EXCLUDED.updated_at
+            RETURNING base_currency, quote_currency, trim_scale(rate) AS rate, updated_at
+            "#,
+        )
+        .bind(base_currency)
+        .bind(quote_currency)
+        .bind(rate)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(rate)
+    }
+
+    async fn delete(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+    ) -> Result<bool, RepositoryError> {
+        let result = sqlx::query(
+            "DELETE FROM exchange_rates WHERE base_currency = $1 AND quote_currency = $2",
+        )
+        .bind(base_currency)
+        .bind(quote_currency)
+        .execute(&self.database_pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,68 @@
+pub mod booking;
+pub mod departure;
+pub mod exchange_rate;
+pub mod payment;
+pub mod place;
+pub mod pricing;
+pub mod promotion;
+pub mod schedule;
+pub mod tour;
+pub mod waitlist;
+
+use async_graphql::MaybeUndefined;
+use sqlx::{Encode, Postgres, QueryBuilder, Type};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoryError {
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    ForeignKey(String),
+    #[error("database error: {0}")]
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(error: sqlx::Error) -> Self {
+        match &error {
+            sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+                RepositoryError::Conflict(
+                    database_error.constraint().unwrap_or_default().to_string(),
+                )
+            }
+            sqlx::Error::Database(database_error) if database_error.is_foreign_key_violation() => {
+                RepositoryError::ForeignKey(
+                    database_error.constraint().unwrap_or_default().to_string(),
+                )
+            }
+            _ => RepositoryError::Database(error),
+        }
+    }
+}
+
+pub(crate) fn escape_like_pattern(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+pub(crate) fn push_patch<T>(
+    query_builder: &mut QueryBuilder<'static, Postgres>,
+    column: &str,
+    value: MaybeUndefined<T>,
+) where
+    T: 'static + Send + for<'q> Encode<'q, Postgres> + Type<Postgres>,
+{
+    match value {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => {
+            query_builder.push(format!(", {} = NULL", column));
+        }
+        MaybeUndefined::Value(value) => {
+            query_builder
+                .push(format!(", {} = ", column))
+                .push_bind(value);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::models::booking::BookingStatus;
+use crate::models::payment::{Payment, PaymentStatus, PaymentWebhookEvent, WebhookOutcome};
+use crate::repository::RepositoryError;
+
+#[derive(FromRow, Clone, Debug)]
+pub struct PayableBooking {
+    pub user_id: String,
+    pub status: BookingStatus,
+    pub amount_minor: i64,
+    pub currency: String,
+}
+
+#[async_trait]
+pub trait PaymentRepository: Send + Sync {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Payment>, RepositoryError>;
+
+    async fn find_by_booking(&self, booking_id: i32) -> Result<Vec<Payment>, RepositoryError>;
+
+    async fn find_payable_booking(
+        &self,
+        booking_id: i32,
+    ) -> Result<Option<PayableBooking>, RepositoryError>;
+
+    async fn create(
+        &self,
+        booking_id: i32,
+        provider: &str,
+        amount_minor: i64,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Payment, RepositoryError>;
+
+    async fn set_provider_payment_id(
+        &self,
+        id: i32,
+        provider_payment_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Payment>, RepositoryError>;
+
+    // Moves the payment to `status` only from one of `status.sources()`, and returns
+    // `None` when it's in any other status.
+    async fn transition(
+        &self,
+        id: i32,
+        status: PaymentStatus,
+        failure_reason: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Payment>, RepositoryError>;
+
+    // Records the event and applies it in one transaction, so an event delivered
+    // more than once changes the payment at most once.
+    async fn apply_webhook_event(
+        &self,
+        provider: &str,
+        event: &PaymentWebhookEvent,
+        now: DateTime<Utc>,
+    ) -> Result<WebhookOutcome, RepositoryError>;
+}
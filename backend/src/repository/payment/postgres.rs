@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::models::payment::{Payment, PaymentStatus, PaymentWebhookEvent, WebhookOutcome};
+use crate::repository::payment::{PayableBooking, PaymentRepository};
+use crate::repository::RepositoryError;
+
+pub struct PostgresPaymentRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresPaymentRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl PaymentRepository for PostgresPaymentRepository {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Payment>, RepositoryError> {
+        let payment = sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(payment)
+    }
+
+    async fn find_by_booking(&self, booking_id: i32) -> Result<Vec<Payment>, RepositoryError> {
+        let payments = sqlx::query_as::<_, Payment>(
+            "SELECT * FROM payments WHERE booking_id = $1 ORDER BY created_at, id",
+        )
+        .bind(booking_id)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(payments)
+    }
+
+    async fn find_payable_booking(
+        &self,
+        booking_id: i32,
+    ) -> Result<Option<PayableBooking>, RepositoryError> {
+        let booking = sqlx::query_as::<_, PayableBooking>(
+            r#"
+            SELECT user_id, status, price_minor AS amount_minor, currency
+            FROM bookings
+            WHERE id = $1
+            "#,
+        )
+        .bind(booking_id)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(booking)
+    }
+
+    async fn create(
+        &self,
+        booking_id: i32,
+        provider: &str,
+        amount_minor: i64,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Payment, RepositoryError> {
+        let payment = sqlx::query_as::<_, Payment>(
+            r#"
+            INSERT INTO payments (booking_id, provider, amount_minor, currency, status, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, 'pending', $5, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(booking_id)
+        .bind(provider)
+        .bind(amount_minor)
+        .bind(currency)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(payment)
+    }
+
+    async fn set_provider_payment_id(
+        &self,
+        id: i32,
+        provider_payment_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Payment>, RepositoryError> {
+        let payment = sqlx::query_as::<_, Payment>(
+            r#"
+            UPDATE payments SET provider_payment_id = $2, updated_at = $3
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(provider_payment_id)
+        .bind(now)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(payment)
+    }
+
+    async fn transition(
+        &self,
+        id: i32,
+        status: PaymentStatus,
+        failure_reason: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Payment>, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+        let payment = transition(&mut transaction, id, status, failure_reason, now).await?;
+        transaction.commit().await?;
+
+        Ok(payment)
+    }
+
+    async fn apply_webhook_event(
+        &self,
+        provider: &str,
+        event: &PaymentWebhookEvent,
+        now: DateTime<Utc>,
+    ) -> Result<WebhookOutcome, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let payment = sqlx::query_as::<_, Payment>(
+            r#"
+            SELECT * FROM payments
+            WHERE provider = $1 AND provider_payment_id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(provider)
+        .bind(&event.provider_payment_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        // A concurrent delivery of the same event waits here until the first one
+        // commits, and then finds it recorded.
+        let recorded = sqlx::query_scalar::<_, i32>(
+            r#"
+            INSERT INTO payment_webhook_events (provider, event_id, payment_id, received_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (provider, event_id) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(provider)
+        .bind(&event.id)
+        .bind(payment.as_ref().map(|payment| payment.id))
+        .bind(now)
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if recorded.is_none() {
+            return Ok(WebhookOutcome::Duplicate);
+        }
+
+        let outcome = match payment {
+            Some(payment) if payment.status.can_transition_to(event.status) => transition(
+                &mut transaction,
+                payment.id,
+                event.status,
+                event.failure_reason.as_deref(),
+                now,
+            )
+            .await?
+            .map_or(WebhookOutcome::Ignored, WebhookOutcome::Applied),
+            _ => WebhookOutcome::Ignored,
+        };
+
+        transaction.commit().await?;
+
+        Ok(outcome)
+    }
+}
+
+async fn transition(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: i32,
+    status: PaymentStatus,
+    failure_reason: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Option<Payment>, RepositoryError> {
+    let sources: Vec<&str> = status
+        .sources()
+        .iter()
+        .map(|source| source.as_str())
+        .collect();
+    let payment = sqlx::query_as::<_, Payment>(
+        r#"
+        UPDATE payments SET status = $2, failure_reason = $3, updated_at = $4
+        WHERE id = $1 AND status = ANY($5)
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(status)
+    .bind(failure_reason)
+    .bind(now)
+    .bind(sources)
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(payment)
+}
@@ -0,0 +1,48 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::models::place::{CreatePlaceInput, Place, PlaceMatch, UpdatePlaceInput};
+use crate::repository::RepositoryError;
+
+#[async_trait]
+pub trait PlaceRepository: Send + Sync {
+    async fn find(&self, query: Option<&str>, limit: usize) -> Result<Vec<Place>, RepositoryError>;
+
+    async fn find_by_id(&self, id: i32) -> Result<Option<Place>, RepositoryError>;
+
+    async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<Place>, RepositoryError>;
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Place>, RepositoryError>;
+
+    async fn create(
+        &self,
+        input: CreatePlaceInput,
+        now: DateTime<Utc>,
+    ) -> Result<Place, RepositoryError>;
+
+    async fn update(
+        &self,
+        id: i32,
+        input: UpdatePlaceInput,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Place>, RepositoryError>;
+
+    async fn delete(&self, id: i32) -> Result<bool, RepositoryError>;
+
+    async fn find_unassigned_locations(&self) -> Result<Vec<String>, RepositoryError>;
+
+    async fn find_best_match(
+        &self,
+        location: &str,
+        threshold: f32,
+    ) -> Result<Option<PlaceMatch>, RepositoryError>;
+
+    async fn assign_location(
+        &self,
+        location: &str,
+        place_id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<u64, RepositoryError>;
+}
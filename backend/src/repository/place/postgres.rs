@@ -0,0 +1,226 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::models::place::{CreatePlaceInput, Place, PlaceMatch, UpdatePlaceInput};
+use crate::repository::place::PlaceRepository;
+use crate::repository::{escape_like_pattern, push_patch, RepositoryError};
+
+pub struct PostgresPlaceRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresPlaceRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl PlaceRepository for PostgresPlaceRepository {
+    async fn find(&self, query: Option<&str>, limit: usize) -> Result<Vec<Place>, RepositoryError> {
+        let places = sqlx::query_as::<_, Place>(
+            r#"
+            SELECT * FROM places
+            WHERE $1::text IS NULL
+               OR $1 <% name
+               OR name ILIKE $2
+               OR EXISTS (SELECT 1 FROM unnest(aliases) AS alias WHERE alias ILIKE $2)
+            ORDER BY COALESCE(word_similarity($1, name), 0) DESC, name, id
+            LIMIT $3
+            "#,
+        )
+        .bind(query)
+        .bind(query.map(|query| format!("{}%", escape_like_pattern(query))))
+        .bind(limit as i64)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(places)
+    }
+
+    async fn find_by_id(&self, id: i32) -> Result<Option<Place>, RepositoryError> {
+        let place = sqlx::query_as::<_, Place>("SELECT * FROM places WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(place)
+    }
+
+    async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<Place>, RepositoryError> {
+        let places = sqlx::query_as::<_, Place>("SELECT * FROM places WHERE id = ANY($1)")
+            .bind(ids)
+            .fetch_all(&self.database_pool)
+            .await?;
+
+        Ok(places)
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Place>, RepositoryError> {
+        let place = sqlx::query_as::<_, Place>("SELECT * FROM places WHERE slug = $1")
+            .bind(slug)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(place)
+    }
+
+    async fn create(
+        &self,
+        input: CreatePlaceInput,
+        now: DateTime<Utc>,
+    ) -> Result<Place, RepositoryError> {
+        let place = sqlx::query_as::<_, Place>(
+            r#"
+            INSERT INTO places (name, slug, country_code, region, timezone, latitude, longitude, aliases, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#,
+        )
+        .bind(input.name.trim())
+        .bind(input.slug)
+        .bind(input.country_code)
+        .bind(input.region)
+        .bind(input.timezone)
+        .bind(input.latitude)
+        .bind(input.longitude)
+        .bind(input.aliases)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(place)
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        input: UpdatePlaceInput,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Place>, RepositoryError> {
+        let mut query_builder = QueryBuilder::<Postgres>::new("UPDATE places SET updated_at = ");
+        query_builder.push_bind(now);
+
+        if let Some(name) = input.name {
+            query_builder
+                .push(", name = ")
+                .push_bind(name.trim().to_string());
+        }
+        if let Some(slug) = input.slug {
+            query_builder.push(", slug = ").push_bind(slug);
+        }
+        push_patch(&mut query_builder, "country_code", input.country_code);
+        push_patch(&mut query_builder, "region", input.region);
+        push_patch(&mut query_builder, "timezone", input.timezone);
+        push_patch(&mut query_builder, "latitude", input.latitude);
+        push_patch(&mut query_builder, "longitude", input.longitude);
+        if let Some(aliases) = input.aliases {
+            query_builder.push(", aliases = ").push_bind(aliases);
+        }
+
+        query_builder
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(" RETURNING *");
+
+        let place = query_builder
+            .build_query_as::<Place>()
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(place)
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM places WHERE id = $1")
+            .bind(id)
+            .execute(&self.database_pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_unassigned_locations(&self) -> Result<Vec<String>, RepositoryError> {
+        let locations = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT btrim(location) FROM tours
+            WHERE place_id IS NULL AND location IS NOT NULL AND btrim(location) <> ''
+            ORDER BY 1
+            "#,
+        )
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(locations)
+    }
+
+    async fn find_best_match(
+        &self,
+        location: &str,
+        threshold: f32,
+    ) -> Result<Option<PlaceMatch>, RepositoryError> {
+        // `strict_word_similarity` lets "Paris" match "Paris, France" without also
+        // matching "Parisville"; plain `similarity` covers misspellings.
+        let place_match = sqlx::query_as::<_, PlaceMatch>(
+            r#"
+            SELECT places.*, scores.score::real AS score
+            FROM places
+            CROSS JOIN LATERAL (
+                SELECT MAX(GREATEST(similarity(candidate, $1), strict_word_similarity(candidate, $1))) AS score
+                FROM unnest(array_append(places.aliases, places.name)) AS candidate
+            ) scores
+            WHERE scores.score >= $2
+            ORDER BY scores.score DESC, places.id
+            LIMIT 1
+            "#,
+        )
+        .bind(location)
+        .bind(threshold)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(place_match)
+    }
+
+    async fn assign_location(
+        &self,
+        location: &str,
+        place_id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<u64, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE tours SET place_id = $2, updated_at = $3
+            WHERE btrim(location) = $1 AND place_id IS NULL
+            "#,
+        )
+        .bind(location)
+        .bind(place_id)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+
+        // Keep the raw spelling as an alias so later lookups match it exactly.
+        sqlx::query(
+            r#"
+            UPDATE places SET aliases = array_append(aliases, $1), updated_at = $3
+            WHERE id = $2
+              AND lower(name) <> lower($1)
+              AND NOT EXISTS (SELECT 1 FROM unnest(aliases) AS alias WHERE lower(alias) = lower($1))
+            "#,
+        )
+        .bind(location)
+        .bind(place_id)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,56 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::models::pricing::{
+    CreatePricingRuleInput, PriceCategory, PriceList, PricingRule, TourPriceCategory,
+};
+use crate::repository::RepositoryError;
+
+#[async_trait]
+pub trait PricingRepository: Send + Sync {
+    async fn find_categories(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<TourPriceCategory>, RepositoryError>;
+
+    async fn upsert_category(
+        &self,
+        tour_id: i32,
+        category: PriceCategory,
+        price_minor: Option<i64>,
+        now: DateTime<Utc>,
+    ) -> Result<TourPriceCategory, RepositoryError>;
+
+    async fn delete_category(
+        &self,
+        tour_id: i32,
+        category: PriceCategory,
+    ) -> Result<bool, RepositoryError>;
+
+    async fn find_rule(&self, id: i32) -> Result<Option<PricingRule>, RepositoryError>;
+
+    async fn find_rules(&self, tour_id: i32) -> Result<Vec<PricingRule>, RepositoryError>;
+
+    async fn create_rule(
+        &self,
+        tour_id: i32,
+        input: CreatePricingRuleInput,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<PricingRule, RepositoryError>;
+
+    async fn update_rule(
+        &self,
+        rule: &PricingRule,
+        now: DateTime<Utc>,
+    ) -> Result<Option<PricingRule>, RepositoryError>;
+
+    async fn delete_rule(&self, id: i32) -> Result<bool, RepositoryError>;
+
+    async fn find_price_list(
+        &self,
+        departure_id: i32,
+    ) -> Result<Option<PriceList>, RepositoryError>;
+}
@@ -0,0 +1,259 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::pricing::{
+    CreatePricingRuleInput, PriceCategory, PriceList, PricingConditions, PricingRule,
+    TourPriceCategory,
+};
+use crate::repository::pricing::PricingRepository;
+use crate::repository::RepositoryError;
+
+pub struct PostgresPricingRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresPricingRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl PricingRepository for PostgresPricingRepository {
+    async fn find_categories(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<TourPriceCategory>, RepositoryError> {
+        let mut connection = self.database_pool.acquire().await?;
+
+        Ok(find_categories(&mut connection, tour_id).await?)
+    }
+
+    async fn upsert_category(
+        &self,
+        tour_id: i32,
+        category: PriceCategory,
+        price_minor: Option<i64>,
+        now: DateTime<Utc>,
+    ) -> Result<TourPriceCategory, RepositoryError> {
+        let category = sqlx::query_as::<_, TourPriceCategory>(
+            r#"
+            INSERT INTO tour_price_categories (tour_id, category, price_minor, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $4)
+            ON CONFLICT (tour_id, category)
+            DO UPDATE SET price_minor = EXCLUDED.price_minor, updated_at = EXCLUDED.updated_at
+            RETURNING *
+            "#,
+        )
+        .bind(tour_id)
+        .bind(category)
+        .bind(price_minor)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(category)
+    }
+
+    async fn delete_category(
+        &self,
+        tour_id: i32,
+        category: PriceCategory,
+    ) -> Result<bool, RepositoryError> {
+        let result =
+            sqlx::query("DELETE FROM tour_price_categories WHERE tour_id = $1 AND category = $2")
+                .bind(tour_id)
+                .bind(category)
+                .execute(&self.database_pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_rule(&self, id: i32) -> Result<Option<PricingRule>, RepositoryError> {
+        let rule = sqlx::query_as::<_, PricingRule>("SELECT * FROM pricing_rules WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(rule)
+    }
+
+    async fn find_rules(&self, tour_id: i32) -> Result<Vec<PricingRule>, RepositoryError> {
+        let mut connection = self.database_pool.acquire().await?;
+
+        Ok(find_rules(&mut connection, tour_id, false).await?)
+    }
+
+    async fn create_rule(
+        &self,
+        tour_id: i32,
+        input: CreatePricingRuleInput,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<PricingRule, RepositoryError> {
+        let (percent, amount_minor) = input.adjustment.into_parts(currency);
+        let conditions = PricingConditions::from(input.conditions.unwrap_or_default());
+        let rule = sqlx::query_as::<_, PricingRule>(
+            r#"
+            INSERT INTO pricing_rules (
+                tour_id, name, priority, is_stackable, is_active, category, percent, amount_minor,
+                starts_on, ends_on, weekdays, min_lead_days, max_lead_days, min_group_size, max_group_size,
+                created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $16)
+            RETURNING *
+            "#,
+        )
+        .bind(tour_id)
+        .bind(input.name)
+        .bind(input.priority.unwrap_or(0))
+        .bind(input.is_stackable.unwrap_or(true))
+        .bind(input.is_active.unwrap_or(true))
+        .bind(input.category)
+        .bind(percent)
+        .bind(amount_minor)
+        .bind(conditions.starts_on)
+        .bind(conditions.ends_on)
+        .bind(conditions.weekdays)
+        .bind(conditions.min_lead_days)
+        .bind(conditions.max_lead_days)
+        .bind(conditions.min_group_size)
+        .bind(conditions.max_group_size)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(rule)
+    }
+
+    async fn update_rule(
+        &self,
+        rule: &PricingRule,
+        now: DateTime<Utc>,
+    ) -> Result<Option<PricingRule>, RepositoryError> {
+        let conditions = &rule.conditions;
+        let rule = sqlx::query_as::<_, PricingRule>(
+            r#"
+            UPDATE pricing_rules
+            SET name = $2, priority = $3, is_stackable = $4, is_active = $5, category = $6,
+                percent = $7, amount_minor = $8, starts_on = $9, ends_on = $10, weekdays = $11,
+                min_lead_days = $12, max_lead_days = $13, min_group_size = $14, max_group_size = $15,
+                updated_at = $16
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(rule.id)
+        .bind(&rule.name)
+        .bind(rule.priority)
+        .bind(rule.is_stackable)
+        .bind(rule.is_active)
+        .bind(rule.category)
+        .bind(rule.percent)
+        .bind(rule.amount_minor)
+        .bind(conditions.starts_on)
+        .bind(conditions.ends_on)
+        .bind(&conditions.weekdays)
+        .bind(conditions.min_lead_days)
+        .bind(conditions.max_lead_days)
+        .bind(conditions.min_group_size)
+        .bind(conditions.max_group_size)
+        .bind(now)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(rule)
+    }
+
+    async fn delete_rule(&self, id: i32) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM pricing_rules WHERE id = $1")
+            .bind(id)
+            .execute(&self.database_pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_price_list(
+        &self,
+        departure_id: i32,
+    ) -> Result<Option<PriceList>, RepositoryError> {
+        let mut connection = self.database_pool.acquire().await?;
+
+        Ok(find_price_list(&mut connection, departure_id).await?)
+    }
+}
+
+// Takes a connection so bookings can be priced inside their own transaction.
+pub(crate) async fn find_price_list(
+    connection: &mut PgConnection,
+    departure_id: i32,
+) -> Result<Option<PriceList>, sqlx::Error> {
+    let Some((tour_id, starts_at, timezone, currency, price_minor)) =
+        sqlx::query_as::<_, (i32, DateTime<Utc>, String, String, Option<i64>)>(
+            r#"
+            SELECT
+                tours.id,
+                departures.starts_at,
+                tours.timezone,
+                tours.currency,
+                COALESCE(departures.price_minor, tours.price_minor)
+            FROM departures
+            JOIN tours ON tours.id = departures.tour_id
+            WHERE departures.id = $1
+            "#,
+        )
+        .bind(departure_id)
+        .fetch_optional(&mut *connection)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let categories = find_categories(connection, tour_id).await?;
+    let rules = find_rules(connection, tour_id, true).await?;
+
+    Ok(Some(PriceList {
+        departure_id,
+        tour_id,
+        starts_at,
+        timezone: timezone.parse().unwrap_or(Tz::UTC),
+        currency,
+        price_minor,
+        categories,
+        rules,
+    }))
+}
+
+async fn find_categories(
+    connection: &mut PgConnection,
+    tour_id: i32,
+) -> Result<Vec<TourPriceCategory>, sqlx::Error> {
+    sqlx::query_as::<_, TourPriceCategory>(
+        "SELECT * FROM tour_price_categories WHERE tour_id = $1 ORDER BY category",
+    )
+    .bind(tour_id)
+    .fetch_all(connection)
+    .await
+}
+
+async fn find_rules(
+    connection: &mut PgConnection,
+    tour_id: i32,
+    active_only: bool,
+) -> Result<Vec<PricingRule>, sqlx::Error> {
+    sqlx::query_as::<_, PricingRule>(
+        r#"
+        SELECT * FROM pricing_rules
+        WHERE tour_id = $1 AND (is_active OR NOT $2)
+        ORDER BY priority DESC, id
+        "#,
+    )
+    .bind(tour_id)
+    .bind(active_only)
+    .fetch_all(connection)
+    .await
+}
@@ -0,0 +1,46 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::models::promotion::{
+    CreatePromotionInput, Promotion, PromotionRedemption, RedemptionCounts,
+};
+use crate::repository::RepositoryError;
+
+#[async_trait]
+pub trait PromotionRepository: Send + Sync {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Promotion>, RepositoryError>;
+
+    async fn find_by_code(&self, code: &str) -> Result<Option<Promotion>, RepositoryError>;
+
+    async fn find_all(&self) -> Result<Vec<Promotion>, RepositoryError>;
+
+    // Fails with `RepositoryError::ForeignKey` when a tour in `tour_ids` doesn't exist.
+    async fn create(
+        &self,
+        input: CreatePromotionInput,
+        now: DateTime<Utc>,
+    ) -> Result<Promotion, RepositoryError>;
+
+    async fn update(
+        &self,
+        promotion: &Promotion,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Promotion>, RepositoryError>;
+
+    // Fails with `RepositoryError::ForeignKey` once the promotion has redemptions.
+    async fn delete(&self, id: i32) -> Result<bool, RepositoryError>;
+
+    async fn count_redemptions(
+        &self,
+        promotion_id: i32,
+        user_id: Option<&str>,
+    ) -> Result<RedemptionCounts, RepositoryError>;
+
+    async fn find_redemptions(
+        &self,
+        promotion_id: i32,
+        limit: usize,
+    ) -> Result<Vec<PromotionRedemption>, RepositoryError>;
+}
@@ -0,0 +1,224 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::promotion::{
+    CreatePromotionInput, Promotion, PromotionRedemption, RedemptionCounts,
+};
+use crate::repository::promotion::PromotionRepository;
+use crate::repository::RepositoryError;
+
+pub struct PostgresPromotionRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresPromotionRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+
+    async fn ensure_tours_exist(&self, tour_ids: &[i32]) -> Result<(), RepositoryError> {
+        let existing =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM tours WHERE id = ANY($1)")
+                .bind(tour_ids)
+                .fetch_one(&self.database_pool)
+                .await?;
+
+        if existing == tour_ids.len() as i64 {
+            Ok(())
+        } else {
+            Err(RepositoryError::ForeignKey(
+                "promotions_tour_ids_fkey".to_string(),
+            ))
+        }
+    }
+}
+
+#[async_trait]
+impl PromotionRepository for PostgresPromotionRepository {
+    async fn find_by_id(&self, id: i32) -> Result<Option<Promotion>, RepositoryError> {
+        let promotion = sqlx::query_as::<_, Promotion>("SELECT * FROM promotions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(promotion)
+    }
+
+    async fn find_by_code(&self, code: &str) -> Result<Option<Promotion>, RepositoryError> {
+        let mut connection = self.database_pool.acquire().await?;
+
+        Ok(find_by_code(&mut connection, code, false).await?)
+    }
+
+    async fn find_all(&self) -> Result<Vec<Promotion>, RepositoryError> {
+        let promotions = sqlx::query_as::<_, Promotion>("SELECT * FROM promotions ORDER BY code")
+            .fetch_all(&self.database_pool)
+            .await?;
+
+        Ok(promotions)
+    }
+
+    async fn create(
+        &self,
+        input: CreatePromotionInput,
+        now: DateTime<Utc>,
+    ) -> Result<Promotion, RepositoryError> {
+        let tour_ids = input.tour_ids.clone().unwrap_or_default();
+        self.ensure_tours_exist(&tour_ids).await?;
+
+        let min_spend_minor = input.min_spend_minor();
+        let (percent, amount_minor) = input.discount.into_parts(input.currency.as_deref());
+        let promotion = sqlx::query_as::<_, Promotion>(
+            r#"
+            INSERT INTO promotions (
+                code, name, is_active, percent, amount_minor, currency, min_spend_minor,
+                starts_at, ends_at, max_redemptions, max_redemptions_per_user, tour_ids,
+                created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $13)
+            RETURNING *
+            "#,
+        )
+        .bind(input.code)
+        .bind(input.name)
+        .bind(input.is_active.unwrap_or(true))
+        .bind(percent)
+        .bind(amount_minor)
+        .bind(input.currency)
+        .bind(min_spend_minor)
+        .bind(input.starts_at)
+        .bind(input.ends_at)
+        .bind(input.max_redemptions)
+        .bind(input.max_redemptions_per_user)
+        .bind(tour_ids)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(promotion)
+    }
+
+    async fn update(
+        &self,
+        promotion: &Promotion,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Promotion>, RepositoryError> {
+        self.ensure_tours_exist(&promotion.tour_ids).await?;
+
+        let promotion = sqlx::query_as::<_, Promotion>(
+            r#"
+            UPDATE promotions
+            SET code = $2, name = $3, is_active = $4, percent = $5, amount_minor = $6,
+                currency = $7, min_spend_minor = $8, starts_at = $9, ends_at = $10,
+                max_redemptions = $11, max_redemptions_per_user = $12, tour_ids = $13,
+                updated_at = $14
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(promotion.id)
+        .bind(&promotion.code)
+        .bind(&promotion.name)
+        .bind(promotion.is_active)
+        .bind(promotion.percent)
+        .bind(promotion.amount_minor)
+        .bind(&promotion.currency)
+        .bind(promotion.min_spend_minor)
+        .bind(promotion.starts_at)
+        .bind(promotion.ends_at)
+        .bind(promotion.max_redemptions)
+        .bind(promotion.max_redemptions_per_user)
+        .bind(&promotion.tour_ids)
+        .bind(now)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(promotion)
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM promotions WHERE id = $1")
+            .bind(id)
+            .execute(&self.database_pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn count_redemptions(
+        &self,
+        promotion_id: i32,
+        user_id: Option<&str>,
+    ) -> Result<RedemptionCounts, RepositoryError> {
+        let mut connection = self.database_pool.acquire().await?;
+
+        Ok(count_redemptions(&mut connection, promotion_id, user_id).await?)
+    }
+
+    async fn find_redemptions(
+        &self,
+        promotion_id: i32,
+        limit: usize,
+    ) -> Result<Vec<PromotionRedemption>, RepositoryError> {
+        let redemptions = sqlx::query_as::<_, PromotionRedemption>(
+            r#"
+            SELECT promotion_redemptions.*, bookings.status AS booking_status
+            FROM promotion_redemptions
+            JOIN bookings ON bookings.id = promotion_redemptions.booking_id
+            WHERE promotion_redemptions.promotion_id = $1
+            ORDER BY promotion_redemptions.created_at DESC, promotion_redemptions.id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(promotion_id)
+        .bind(limit as i64)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(redemptions)
+    }
+}
+
+// Bookings lock the promotion, so concurrent redemptions of one code are
+// serialized and can't overshoot its limits.
+pub(crate) async fn find_by_code(
+    connection: &mut PgConnection,
+    code: &str,
+    for_update: bool,
+) -> Result<Option<Promotion>, sqlx::Error> {
+    let query = if for_update {
+        "SELECT * FROM promotions WHERE code = $1 FOR UPDATE"
+    } else {
+        "SELECT * FROM promotions WHERE code = $1"
+    };
+
+    sqlx::query_as::<_, Promotion>(query)
+        .bind(code)
+        .fetch_optional(connection)
+        .await
+}
+
+pub(crate) async fn count_redemptions(
+    connection: &mut PgConnection,
+    promotion_id: i32,
+    user_id: Option<&str>,
+) -> Result<RedemptionCounts, sqlx::Error> {
+    let (total, by_user) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT COUNT(*), COUNT(*) FILTER (WHERE promotion_redemptions.user_id = $2)
+        FROM promotion_redemptions
+        JOIN bookings ON bookings.id = promotion_redemptions.booking_id
+        WHERE promotion_redemptions.promotion_id = $1 AND bookings.status <> 'cancelled'
+        "#,
+    )
+    .bind(promotion_id)
+    .bind(user_id)
+    .fetch_one(connection)
+    .await?;
+
+    Ok(RedemptionCounts {
+        total,
+        by_user: user_id.map(|_| by_user),
+    })
+}
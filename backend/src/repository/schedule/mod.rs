@@ -0,0 +1,110 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::models::schedule::{
+    BlackoutCalendar, BlackoutDate, BlackoutDateInput, CreateDepartureScheduleInput,
+    DepartureSchedule, MaterializableSchedule, MaterializationSummary,
+};
+use crate::repository::RepositoryError;
+
+// The departures a schedule should have between `from` and `until`, as UTC
+// start and end times.
+pub struct ScheduledDepartures<'a> {
+    pub schedule: &'a DepartureSchedule,
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub departures: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+#[async_trait]
+pub trait ScheduleRepository: Send + Sync {
+    async fn find_by_id(&self, id: i32) -> Result<Option<DepartureSchedule>, RepositoryError>;
+
+    async fn find_by_tour(&self, tour_id: i32) -> Result<Vec<DepartureSchedule>, RepositoryError>;
+
+    // All schedules, or those of `tour_ids`, with the time zone of their tour.
+    async fn find_materializable(
+        &self,
+        tour_ids: Option<&[i32]>,
+    ) -> Result<Vec<MaterializableSchedule>, RepositoryError>;
+
+    async fn create(
+        &self,
+        tour_id: i32,
+        input: CreateDepartureScheduleInput,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<DepartureSchedule, RepositoryError>;
+
+    async fn update(
+        &self,
+        schedule: &DepartureSchedule,
+        now: DateTime<Utc>,
+    ) -> Result<Option<DepartureSchedule>, RepositoryError>;
+
+    // Removes the schedule's future departures that have no bookings before
+    // deleting it; booked ones are kept and detached.
+    async fn delete(&self, id: i32, now: DateTime<Utc>) -> Result<bool, RepositoryError>;
+
+    // Makes the schedule's departures between `from` and `until` match the
+    // expected ones. Departures with bookings, cancelled departures and
+    // departures created by hand are left untouched.
+    async fn reconcile(
+        &self,
+        scheduled: ScheduledDepartures<'_>,
+        now: DateTime<Utc>,
+    ) -> Result<MaterializationSummary, RepositoryError>;
+
+    async fn find_calendars(&self) -> Result<Vec<BlackoutCalendar>, RepositoryError>;
+
+    async fn find_calendar(&self, id: i32) -> Result<Option<BlackoutCalendar>, RepositoryError>;
+
+    async fn find_calendars_by_tour(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<BlackoutCalendar>, RepositoryError>;
+
+    async fn create_calendar(
+        &self,
+        name: &str,
+        now: DateTime<Utc>,
+    ) -> Result<BlackoutCalendar, RepositoryError>;
+
+    async fn delete_calendar(&self, id: i32) -> Result<bool, RepositoryError>;
+
+    async fn find_blackout_dates(
+        &self,
+        calendar_id: i32,
+    ) -> Result<Vec<BlackoutDate>, RepositoryError>;
+
+    async fn find_blackout_date(&self, id: i32) -> Result<Option<BlackoutDate>, RepositoryError>;
+
+    // Blackout dates from every calendar of the tour that overlap `from..=to`.
+    async fn find_tour_blackout_dates(
+        &self,
+        tour_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<BlackoutDate>, RepositoryError>;
+
+    async fn add_blackout_dates(
+        &self,
+        calendar_id: i32,
+        dates: &[BlackoutDateInput],
+    ) -> Result<Vec<BlackoutDate>, RepositoryError>;
+
+    async fn remove_blackout_date(&self, id: i32) -> Result<Option<BlackoutDate>, RepositoryError>;
+
+    async fn set_tour_calendars(
+        &self,
+        tour_id: i32,
+        calendar_ids: &[i32],
+    ) -> Result<(), RepositoryError>;
+
+    async fn find_tour_ids_by_calendar(
+        &self,
+        calendar_id: i32,
+    ) -> Result<Vec<i32>, RepositoryError>;
+}
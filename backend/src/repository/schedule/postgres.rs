@@ -0,0 +1,435 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::models::schedule::{
+    BlackoutCalendar, BlackoutDate, BlackoutDateInput, CreateDepartureScheduleInput,
+    DepartureSchedule, MaterializableSchedule, MaterializationSummary,
+};
+use crate::repository::schedule::{ScheduleRepository, ScheduledDepartures};
+use crate::repository::RepositoryError;
+
+pub struct PostgresScheduleRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresScheduleRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl ScheduleRepository for PostgresScheduleRepository {
+    async fn find_by_id(&self, id: i32) -> Result<Option<DepartureSchedule>, RepositoryError> {
+        let schedule = sqlx::query_as::<_, DepartureSchedule>(
+            "SELECT * FROM departure_schedules WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    async fn find_by_tour(&self, tour_id: i32) -> Result<Vec<DepartureSchedule>, RepositoryError> {
+        let schedules = sqlx::query_as::<_, DepartureSchedule>(
+            "SELECT * FROM departure_schedules WHERE tour_id = $1 ORDER BY id",
+        )
+        .bind(tour_id)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    async fn find_materializable(
+        &self,
+        tour_ids: Option<&[i32]>,
+    ) -> Result<Vec<MaterializableSchedule>, RepositoryError> {
+        let schedules = sqlx::query_as::<_, MaterializableSchedule>(
+            r#"
+            SELECT departure_schedules.*, tours.timezone
+            FROM departure_schedules
+            JOIN tours ON tours.id = departure_schedules.tour_id
+            WHERE $1::integer[] IS NULL OR departure_schedules.tour_id = ANY($1)
+            ORDER BY departure_schedules.id
+            "#,
+        )
+        .bind(tour_ids)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    async fn create(
+        &self,
+        tour_id: i32,
+        input: CreateDepartureScheduleInput,
+        currency: &str,
+        now: DateTime<Utc>,
+    ) -> Result<DepartureSchedule, RepositoryError> {
+        let price_minor = input.price_minor(currency);
+        let schedule = sqlx::query_as::<_, DepartureSchedule>(
+            r#"
+            INSERT INTO departure_schedules (tour_id, rrule, starts_on, start_time, duration_minutes, exception_dates, capacity, price_minor, is_active, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10)
+            RETURNING *
+            "#,
+        )
+        .bind(tour_id)
+        .bind(input.rrule)
+        .bind(input.starts_on)
+        .bind(input.start_time)
+        .bind(input.duration_minutes)
+        .bind(input.exception_dates.unwrap_or_default())
+        .bind(input.capacity)
+        .bind(price_minor)
+        .bind(input.is_active.unwrap_or(true))
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    async fn update(
+        &self,
+        schedule: &DepartureSchedule,
+        now: DateTime<Utc>,
+    ) -> Result<Option<DepartureSchedule>, RepositoryError> {
+        let schedule = sqlx::query_as::<_, DepartureSchedule>(
+            r#"
+            UPDATE departure_schedules
+            SET rrule = $2, starts_on = $3, start_time = $4, duration_minutes = $5, exception_dates = $6,
+                capacity = $7, price_minor = $8, is_active = $9, updated_at = $10
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(&schedule.rrule)
+        .bind(schedule.starts_on)
+        .bind(schedule.start_time)
+        .bind(schedule.duration_minutes)
+        .bind(&schedule.exception_dates)
+        .bind(schedule.capacity)
+        .bind(schedule.price_minor)
+        .bind(schedule.is_active)
+        .bind(now)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    async fn delete(&self, id: i32, now: DateTime<Utc>) -> Result<bool, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM departures
+            WHERE schedule_id = $1 AND starts_at > $2
+                AND NOT EXISTS (SELECT 1 FROM bookings WHERE bookings.departure_id = departures.id)
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+
+        let result = sqlx::query("DELETE FROM departure_schedules WHERE id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn reconcile(
+        &self,
+        scheduled: ScheduledDepartures<'_>,
+        now: DateTime<Utc>,
+    ) -> Result<MaterializationSummary, RepositoryError> {
+        let schedule = scheduled.schedule;
+        let (starts, ends): (Vec<DateTime<Utc>>, Vec<DateTime<Utc>>) =
+            scheduled.departures.into_iter().unzip();
+        let mut transaction = self.database_pool.begin().await?;
+
+        // Serializes materializers running on several instances.
+        let exists = sqlx::query_scalar::<_, i32>(
+            "SELECT id FROM departure_schedules WHERE id = $1 FOR UPDATE",
+        )
+        .bind(schedule.id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if exists.is_none() {
+            return Ok(MaterializationSummary::default());
+        }
+
+        let removed = sqlx::query(
+            r#"
+            DELETE FROM departures
+            WHERE schedule_id = $1 AND status = 'scheduled'
+                AND starts_at > $2 AND starts_at <= $3
+                AND NOT (starts_at = ANY($4))
+                AND NOT EXISTS (SELECT 1 FROM bookings WHERE bookings.departure_id = departures.id)
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(scheduled.from)
+        .bind(scheduled.until)
+        .bind(&starts)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE departures
+            SET ends_at = expected.ends_at, capacity = $4, price_minor = $5, updated_at = $6
+            FROM UNNEST($2::timestamptz[], $3::timestamptz[]) AS expected (starts_at, ends_at)
+            WHERE departures.schedule_id = $1 AND departures.status = 'scheduled'
+                AND departures.starts_at = expected.starts_at
+                AND (departures.ends_at, departures.capacity, departures.price_minor)
+                    IS DISTINCT FROM (expected.ends_at, $4::integer, $5::bigint)
+                AND NOT EXISTS (SELECT 1 FROM bookings WHERE bookings.departure_id = departures.id)
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(&starts)
+        .bind(&ends)
+        .bind(schedule.capacity)
+        .bind(schedule.price_minor)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+
+        let created = sqlx::query(
+            r#"
+            INSERT INTO departures (tour_id, schedule_id, starts_at, ends_at, capacity, price_minor, created_at, updated_at)
+            SELECT $1, $2, expected.starts_at, expected.ends_at, $5, $6, $7, $7
+            FROM UNNEST($3::timestamptz[], $4::timestamptz[]) AS expected (starts_at, ends_at)
+            ON CONFLICT (tour_id, starts_at) DO NOTHING
+            "#,
+        )
+        .bind(schedule.tour_id)
+        .bind(schedule.id)
+        .bind(&starts)
+        .bind(&ends)
+        .bind(schedule.capacity)
+        .bind(schedule.price_minor)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+
+        transaction.commit().await?;
+
+        Ok(MaterializationSummary {
+            created,
+            updated,
+            removed,
+        })
+    }
+
+    async fn find_calendars(&self) -> Result<Vec<BlackoutCalendar>, RepositoryError> {
+        let calendars =
+            sqlx::query_as::<_, BlackoutCalendar>("SELECT * FROM blackout_calendars ORDER BY name")
+                .fetch_all(&self.database_pool)
+                .await?;
+
+        Ok(calendars)
+    }
+
+    async fn find_calendar(&self, id: i32) -> Result<Option<BlackoutCalendar>, RepositoryError> {
+        let calendar =
+            sqlx::query_as::<_, BlackoutCalendar>("SELECT * FROM blackout_calendars WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.database_pool)
+                .await?;
+
+        Ok(calendar)
+    }
+
+    async fn find_calendars_by_tour(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<BlackoutCalendar>, RepositoryError> {
+        let calendars = sqlx::query_as::<_, BlackoutCalendar>(
+            r#"
+            SELECT blackout_calendars.* FROM blackout_calendars
+            JOIN tour_blackout_calendars ON tour_blackout_calendars.calendar_id = blackout_calendars.id
+            WHERE tour_blackout_calendars.tour_id = $1
+            ORDER BY blackout_calendars.name
+            "#,
+        )
+        .bind(tour_id)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(calendars)
+    }
+
+    async fn create_calendar(
+        &self,
+        name: &str,
+        now: DateTime<Utc>,
+    ) -> Result<BlackoutCalendar, RepositoryError> {
+        let calendar = sqlx::query_as::<_, BlackoutCalendar>(
+            r#"
+            INSERT INTO blackout_calendars (name, created_at, updated_at)
+            VALUES ($1, $2, $2)
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(calendar)
+    }
+
+    async fn delete_calendar(&self, id: i32) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM blackout_calendars WHERE id = $1")
+            .bind(id)
+            .execute(&self.database_pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_blackout_dates(
+        &self,
+        calendar_id: i32,
+    ) -> Result<Vec<BlackoutDate>, RepositoryError> {
+        let dates = sqlx::query_as::<_, BlackoutDate>(
+            "SELECT * FROM blackout_dates WHERE calendar_id = $1 ORDER BY starts_on, id",
+        )
+        .bind(calendar_id)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(dates)
+    }
+
+    async fn find_blackout_date(&self, id: i32) -> Result<Option<BlackoutDate>, RepositoryError> {
+        let date = sqlx::query_as::<_, BlackoutDate>("SELECT * FROM blackout_dates WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(date)
+    }
+
+    async fn find_tour_blackout_dates(
+        &self,
+        tour_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<BlackoutDate>, RepositoryError> {
+        let dates = sqlx::query_as::<_, BlackoutDate>(
+            r#"
+            SELECT blackout_dates.* FROM blackout_dates
+            JOIN tour_blackout_calendars ON tour_blackout_calendars.calendar_id = blackout_dates.calendar_id
+            WHERE tour_blackout_calendars.tour_id = $1
+                AND blackout_dates.ends_on >= $2 AND blackout_dates.starts_on <= $3
+            "#,
+        )
+        .bind(tour_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(dates)
+    }
+
+    async fn add_blackout_dates(
+        &self,
+        calendar_id: i32,
+        dates: &[BlackoutDateInput],
+    ) -> Result<Vec<BlackoutDate>, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let mut added = Vec::with_capacity(dates.len());
+        for date in dates {
+            let blackout_date = sqlx::query_as::<_, BlackoutDate>(
+                r#"
+                INSERT INTO blackout_dates (calendar_id, starts_on, ends_on, reason)
+                VALUES ($1, $2, $3, $4)
+                RETURNING *
+                "#,
+            )
+            .bind(calendar_id)
+            .bind(date.starts_on)
+            .bind(date.ends_on.unwrap_or(date.starts_on))
+            .bind(&date.reason)
+            .fetch_one(&mut *transaction)
+            .await?;
+            added.push(blackout_date);
+        }
+
+        transaction.commit().await?;
+
+        Ok(added)
+    }
+
+    async fn remove_blackout_date(&self, id: i32) -> Result<Option<BlackoutDate>, RepositoryError> {
+        let date = sqlx::query_as::<_, BlackoutDate>(
+            "DELETE FROM blackout_dates WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(date)
+    }
+
+    async fn set_tour_calendars(
+        &self,
+        tour_id: i32,
+        calendar_ids: &[i32],
+    ) -> Result<(), RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        sqlx::query("DELETE FROM tour_blackout_calendars WHERE tour_id = $1")
+            .bind(tour_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO tour_blackout_calendars (tour_id, calendar_id)
+            SELECT $1, calendar_id FROM UNNEST($2::integer[]) AS calendar_id
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(tour_id)
+        .bind(calendar_ids)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn find_tour_ids_by_calendar(
+        &self,
+        calendar_id: i32,
+    ) -> Result<Vec<i32>, RepositoryError> {
+        let tour_ids = sqlx::query_scalar::<_, i32>(
+            "SELECT tour_id FROM tour_blackout_calendars WHERE calendar_id = $1",
+        )
+        .bind(calendar_id)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(tour_ids)
+    }
+}
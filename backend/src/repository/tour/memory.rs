@@ -0,0 +1,381 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::models::geo::{haversine_km, BoundingBox, NearbyTour};
+use crate::models::pagination::{Page, PageRequest};
+use crate::models::search::{Suggestion, SuggestionKind, TourSearchResult, DEFAULT_LANGUAGE};
+use crate::models::tour::{
+    CreateTourInput, Tour, TourCursor, TourFilter, TourSort, UpdateTourInput,
+};
+use crate::repository::tour::TourRepository;
+use crate::repository::RepositoryError;
+
+#[derive(Default)]
+pub struct InMemoryTourRepository {
+    state: Mutex<InMemoryState>,
+}
+
+#[derive(Default)]
+struct InMemoryState {
+    tours: BTreeMap<i32, Tour>,
+    last_id: i32,
+}
+
+impl InMemoryTourRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tours(tours: Vec<Tour>) -> Self {
+        let last_id = tours.iter().map(|tour| tour.id).max().unwrap_or(0);
+        let tours = tours.into_iter().map(|tour| (tour.id, tour)).collect();
+
+        Self {
+            state: Mutex::new(InMemoryState { tours, last_id }),
+        }
+    }
+}
+
+#[async_trait]
+impl TourRepository for InMemoryTourRepository {
+    async fn find_all(&self) -> Result<Vec<Tour>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+        Ok(state.tours.values().cloned().collect())
+    }
+
+    async fn find_page(
+        &self,
+        filter: &TourFilter,
+        sort: TourSort,
+        page_request: &PageRequest<TourCursor>,
+    ) -> Result<Page<Tour>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+
+        let matching_tours: Vec<&Tour> = state
+            .tours
+            .values()
+            .filter(|tour| filter.matches(tour))
+            .collect();
+        let total_count = matching_tours.len() as i64;
+
+        let mut tours: Vec<(TourCursor, Tour)> = matching_tours
+            .into_iter()
+            .map(|tour| (TourCursor::new(tour, sort), tour.clone()))
+            .filter(|(cursor, _)| {
+                page_request
+                    .after
+                    .as_ref()
+                    .is_none_or(|after| cursor.cmp_position(after) == Ordering::Greater)
+            })
+            .filter(|(cursor, _)| {
+                page_request
+                    .before
+                    .as_ref()
+                    .is_none_or(|before| cursor.cmp_position(before) == Ordering::Less)
+            })
+            .collect();
+
+        tours.sort_by(|(left, _), (right, _)| left.cmp_position(right));
+        if page_request.is_backward() {
+            tours.reverse();
+        }
+        tours.truncate(page_request.limit() + 1);
+
+        Ok(Page::from_query(
+            tours.into_iter().map(|(_, tour)| tour).collect(),
+            page_request,
+            total_count,
+        ))
+    }
+
+    // Approximates websearch semantics with case-insensitive substring matching:
+    // quoted phrases and bare words must all match, `-word` excludes.
+    async fn search(
+        &self,
+        query: &str,
+        language: Option<&str>,
+        include_inactive: bool,
+        limit: usize,
+    ) -> Result<Vec<TourSearchResult>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+        let (included, excluded) = parse_search_terms(query);
+        if included.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut results: Vec<TourSearchResult> = state
+            .tours
+            .values()
+            .filter(|tour| language.is_none_or(|language| tour.language == language))
+            .filter(|tour| include_inactive || tour.is_active)
+            .filter_map(|tour| {
+                let fields = [
+                    (tour.title.to_lowercase(), 1.0),
+                    (
+                        tour.description.clone().unwrap_or_default().to_lowercase(),
+                        0.4,
+                    ),
+                    (
+                        tour.location.clone().unwrap_or_default().to_lowercase(),
+                        0.2,
+                    ),
+                ];
+                let contains = |term: &String| fields.iter().any(|(text, _)| text.contains(term));
+
+                if !included.iter().all(contains) || excluded.iter().any(contains) {
+                    return None;
+                }
+
+                let rank = included
+                    .iter()
+                    .flat_map(|term| {
+                        fields
+                            .iter()
+                            .filter(move |(text, _)| text.contains(term))
+                            .map(|(_, weight)| *weight)
+                    })
+                    .sum();
+
+                Some(TourSearchResult {
+                    tour: tour.clone(),
+                    rank,
+                    headline: tour
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| tour.title.clone()),
+                })
+            })
+            .collect();
+
+        results.sort_by(|left, right| {
+            right
+                .rank
+                .total_cmp(&left.rank)
+                .then(left.tour.id.cmp(&right.tour.id))
+        });
+        results.truncate(limit);
+
+        Ok(results)
+    }
+
+    async fn suggest(
+        &self,
+        prefix: &str,
+        kind: Option<SuggestionKind>,
+        limit: usize,
+    ) -> Result<Vec<Suggestion>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+        let prefix = prefix.to_lowercase();
+        let score = |text: &str| {
+            let text = text.to_lowercase();
+            if text.starts_with(&prefix) {
+                Some(2.0)
+            } else if text.contains(&prefix) {
+                Some(1.0)
+            } else {
+                None
+            }
+        };
+
+        let mut suggestions: Vec<Suggestion> = Vec::new();
+        for tour in state.tours.values().filter(|tour| tour.is_active) {
+            if kind.is_none_or(|kind| kind == SuggestionKind::Place) {
+                if let Some(location) = &tour.location {
+                    let is_duplicate = suggestions.iter().any(|suggestion| {
+                        suggestion.kind == SuggestionKind::Place && &suggestion.text == location
+                    });
+                    if let (false, Some(score)) = (is_duplicate, score(location)) {
+                        suggestions.push(Suggestion {
+                            kind: SuggestionKind::Place,
+                            text: location.clone(),
+                            place_id: tour.place_id,
+                            tour_id: None,
+                            score,
+                        });
+                    }
+                }
+            }
+            if kind.is_none_or(|kind| kind == SuggestionKind::Tour) {
+                if let Some(score) = score(&tour.title) {
+                    suggestions.push(Suggestion {
+                        kind: SuggestionKind::Tour,
+                        text: tour.title.clone(),
+                        place_id: None,
+                        tour_id: Some(tour.id),
+                        score,
+                    });
+                }
+            }
+        }
+
+        suggestions.sort_by(|left, right| {
+            right
+                .score
+                .total_cmp(&left.score)
+                .then_with(|| left.text.cmp(&right.text))
+        });
+        suggestions.truncate(limit);
+
+        Ok(suggestions)
+    }
+
+    async fn find_nearby(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        filter: &TourFilter,
+        limit: usize,
+    ) -> Result<Vec<NearbyTour>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+
+        let mut tours: Vec<NearbyTour> = state
+            .tours
+            .values()
+            .filter(|tour| filter.matches(tour))
+            .filter_map(|tour| {
+                let distance_km =
+                    haversine_km(latitude, longitude, tour.latitude?, tour.longitude?);
+                (distance_km <= radius_km).then(|| NearbyTour {
+                    tour: tour.clone(),
+                    distance_km,
+                })
+            })
+            .collect();
+
+        tours.sort_by(|left, right| {
+            left.distance_km
+                .total_cmp(&right.distance_km)
+                .then(left.tour.id.cmp(&right.tour.id))
+        });
+        tours.truncate(limit);
+
+        Ok(tours)
+    }
+
+    async fn find_in_bounding_box(
+        &self,
+        bounding_box: &BoundingBox,
+        filter: &TourFilter,
+        limit: usize,
+    ) -> Result<Vec<Tour>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+
+        Ok(state
+            .tours
+            .values()
+            .filter(|tour| filter.matches(tour))
+            .filter(|tour| match (tour.latitude, tour.longitude) {
+                (Some(latitude), Some(longitude)) => bounding_box.contains(latitude, longitude),
+                _ => false,
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_by_id(&self, id: i32) -> Result<Option<Tour>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+        Ok(state.tours.get(&id).cloned())
+    }
+
+    async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<Tour>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+        Ok(ids
+            .iter()
+            .filter_map(|id| state.tours.get(id).cloned())
+            .collect())
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tour>, RepositoryError> {
+        let state = self.state.lock().unwrap();
+        Ok(state.tours.values().find(|tour| tour.slug == slug).cloned())
+    }
+
+    async fn create(
+        &self,
+        input: CreateTourInput,
+        now: DateTime<Utc>,
+    ) -> Result<Tour, RepositoryError> {
+        let mut state = self.state.lock().unwrap();
+        state.last_id += 1;
+        let price_minor = input.price_minor();
+        let currency = input.currency().to_string();
+
+        let tour = Tour {
+            id: state.last_id,
+            slug: input.slug.unwrap_or_else(|| slug::slugify(&input.title)),
+            title: input.title,
+            description: input.description,
+            start_date: input.start_date,
+            end_date: input.end_date,
+            price_minor,
+            currency,
+            rating: input.rating,
+            created_at: Some(now),
+            updated_at: Some(now),
+            location: input.location,
+            latitude: input.latitude,
+            longitude: input.longitude,
+            image_url: input.image_url,
+            is_active: input.is_active,
+            max_participants: input.max_participants,
+            language: input
+                .language
+                .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string()),
+            place_id: input.place_id,
+            timezone: input.timezone.unwrap_or_else(|| "UTC".to_string()),
+        };
+        state.tours.insert(tour.id, tour.clone());
+
+        Ok(tour)
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        input: UpdateTourInput,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Tour>, RepositoryError> {
+        let mut state = self.state.lock().unwrap();
+
+        Ok(state.tours.get_mut(&id).map(|tour| {
+            input.apply(tour);
+            tour.updated_at = Some(now);
+            tour.clone()
+        }))
+    }
+
+    // Departures aren't kept in memory.
+    async fn has_price_overrides(&self, _id: i32) -> Result<bool, RepositoryError> {
+        Ok(false)
+    }
+}
+
+fn parse_search_terms(query: &str) -> (Vec<String>, Vec<String>) {
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
+
+    for (index, part) in query.to_lowercase().split('"').enumerate() {
+        if index % 2 == 1 {
+            if !part.trim().is_empty() {
+                included.push(part.trim().to_string());
+            }
+            continue;
+        }
+
+        for word in part.split_whitespace() {
+            match word.strip_prefix('-') {
+                Some(word) if !word.is_empty() => excluded.push(word.to_string()),
+                Some(_) => {}
+                None if word != "or" => included.push(word.to_string()),
+                None => {}
+            }
+        }
+    }
+
+    (included, excluded)
+}
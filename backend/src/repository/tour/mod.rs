@@ -0,0 +1,80 @@
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::models::geo::{BoundingBox, NearbyTour};
+use crate::models::pagination::{Page, PageRequest};
+use crate::models::search::{Suggestion, SuggestionKind, TourSearchResult};
+use crate::models::tour::{
+    CreateTourInput, Tour, TourCursor, TourFilter, TourSort, UpdateTourInput,
+};
+use crate::repository::RepositoryError;
+
+#[async_trait]
+pub trait TourRepository: Send + Sync {
+    async fn find_all(&self) -> Result<Vec<Tour>, RepositoryError>;
+
+    async fn find_page(
+        &self,
+        filter: &TourFilter,
+        sort: TourSort,
+        page_request: &PageRequest<TourCursor>,
+    ) -> Result<Page<Tour>, RepositoryError>;
+
+    async fn search(
+        &self,
+        query: &str,
+        language: Option<&str>,
+        include_inactive: bool,
+        limit: usize,
+    ) -> Result<Vec<TourSearchResult>, RepositoryError>;
+
+    async fn suggest(
+        &self,
+        prefix: &str,
+        kind: Option<SuggestionKind>,
+        limit: usize,
+    ) -> Result<Vec<Suggestion>, RepositoryError>;
+
+    async fn find_nearby(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        filter: &TourFilter,
+        limit: usize,
+    ) -> Result<Vec<NearbyTour>, RepositoryError>;
+
+    async fn find_in_bounding_box(
+        &self,
+        bounding_box: &BoundingBox,
+        filter: &TourFilter,
+        limit: usize,
+    ) -> Result<Vec<Tour>, RepositoryError>;
+
+    async fn find_by_id(&self, id: i32) -> Result<Option<Tour>, RepositoryError>;
+
+    async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<Tour>, RepositoryError>;
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tour>, RepositoryError>;
+
+    async fn create(
+        &self,
+        input: CreateTourInput,
+        now: DateTime<Utc>,
+    ) -> Result<Tour, RepositoryError>;
+
+    async fn update(
+        &self,
+        id: i32,
+        input: UpdateTourInput,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Tour>, RepositoryError>;
+
+    // Whether any departure, schedule, price category or pricing rule of the tour has
+    // an amount in its currency.
+    async fn has_price_overrides(&self, id: i32) -> Result<bool, RepositoryError>;
+}
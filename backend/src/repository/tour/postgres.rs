@@ -0,0 +1,646 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::models::geo::{BoundingBox, NearbyTour};
+use crate::models::pagination::{Page, PageRequest};
+use crate::models::search::{Suggestion, SuggestionKind, TourSearchResult, DEFAULT_LANGUAGE};
+use crate::models::tour::{
+    CreateTourInput, SortDirection, SortValue, Tour, TourCursor, TourFilter, TourSort,
+    TourSortField, UpdateTourInput,
+};
+use crate::repository::tour::TourRepository;
+use crate::repository::{escape_like_pattern, push_patch, RepositoryError};
+
+pub struct PostgresTourRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresTourRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl TourRepository for PostgresTourRepository {
+    async fn find_all(&self) -> Result<Vec<Tour>, RepositoryError> {
+        let tours = sqlx::query_as::<_, Tour>(
+            r#"
+            SELECT * FROM tours
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(tours)
+    }
+
+    async fn find_page(
+        &self,
+        filter: &TourFilter,
+        sort: TourSort,
+        page_request: &PageRequest<TourCursor>,
+    ) -> Result<Page<Tour>, RepositoryError> {
+        let mut query_builder = QueryBuilder::<Postgres>::new("SELECT * FROM tours WHERE TRUE");
+        push_filter(&mut query_builder, filter);
+
+        if let Some(after) = &page_request.after {
+            push_cursor_condition(&mut query_builder, after, CursorBound::After);
+        }
+        if let Some(before) = &page_request.before {
+            push_cursor_condition(&mut query_builder, before, CursorBound::Before);
+        }
+
+        let column = get_sort_column(sort.field);
+        let (direction, nulls) = match (sort.direction, page_request.is_backward()) {
+            (SortDirection::Asc, false) => ("ASC", "NULLS LAST"),
+            (SortDirection::Desc, false) => ("DESC", "NULLS LAST"),
+            (SortDirection::Asc, true) => ("DESC", "NULLS FIRST"),
+            (SortDirection::Desc, true) => ("ASC", "NULLS FIRST"),
+        };
+        query_builder.push(format!(
+            " ORDER BY {} {} {}, id {}",
+            column, direction, nulls, direction
+        ));
+        query_builder
+            .push(" LIMIT ")
+            .push_bind(page_request.limit() as i64 + 1);
+
+        let tours = query_builder
+            .build_query_as::<Tour>()
+            .fetch_all(&self.database_pool)
+            .await?;
+
+        let mut count_query_builder =
+            QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM tours WHERE TRUE");
+        push_filter(&mut count_query_builder, filter);
+        let total_count = count_query_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.database_pool)
+            .await?;
+
+        Ok(Page::from_query(tours, page_request, total_count))
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        language: Option<&str>,
+        include_inactive: bool,
+        limit: usize,
+    ) -> Result<Vec<TourSearchResult>, RepositoryError> {
+        // Without an explicit language every tour parses the query with its own
+        // configuration, which is more accurate but cannot use the GIN index.
+        let config = match language {
+            Some(_) => "$2::regconfig",
+            None => "tours_text_search_config(tours.language)",
+        };
+
+        let results = sqlx::query_as::<_, TourSearchResult>(&format!(
+            r#"
+            SELECT tours.*,
+                   ts_rank(search_vector, search_query) AS rank,
+                   ts_headline(
+                       {config},
+                       coalesce(description, title),
+                       search_query,
+                       'StartSel=<b>, StopSel=</b>, MaxWords=35, MinWords=15, MaxFragments=2'
+                   ) AS headline
+            FROM tours,
+                 LATERAL websearch_to_tsquery({config}, $1) AS search_query
+            WHERE search_vector @@ search_query
+              AND ($2::text IS NULL OR language = $2)
+              AND ($4 OR is_active)
+            ORDER BY rank DESC, id
+            LIMIT $3
+            "#,
+            config = config
+        ))
+        .bind(query)
+        .bind(language)
+        .bind(limit as i64)
+        .bind(include_inactive)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(results)
+    }
+
+    async fn suggest(
+        &self,
+        prefix: &str,
+        kind: Option<SuggestionKind>,
+        limit: usize,
+    ) -> Result<Vec<Suggestion>, RepositoryError> {
+        // `<%` (word similarity) tolerates typos; exact prefix matches are boosted
+        // above fuzzy ones. The tour title and location branches are served by the
+        // trigram GIN indexes on `tours`. Places match on their name and aliases,
+        // which are unnested per row and therefore scanned; the gazetteer is small
+        // enough for that, and `places_aliases_idx` only serves containment lookups.
+        // Tours that have not been linked to a place yet still contribute their
+        // free-text location.
+        let place_query = r#"
+            SELECT name AS text, id AS place_id, NULL::integer AS tour_id, score
+            FROM (
+                SELECT places.id, places.name,
+                       MAX(word_similarity($1, candidate)
+                           + CASE WHEN candidate ILIKE $2 THEN 1 ELSE 0 END) AS score
+                FROM places
+                CROSS JOIN LATERAL unnest(array_append(places.aliases, places.name)) AS candidate
+                WHERE $1 <% candidate OR candidate ILIKE $2
+                GROUP BY places.id
+            ) place_matches
+            UNION ALL
+            SELECT location AS text,
+                   NULL::integer AS place_id,
+                   NULL::integer AS tour_id,
+                   MAX(word_similarity($1, location))
+                       + CASE WHEN location ILIKE $2 THEN 1 ELSE 0 END AS score
+            FROM tours
+            WHERE is_active AND place_id IS NULL AND ($1 <% location OR location ILIKE $2)
+            GROUP BY location
+        "#;
+        let tour_query = r#"
+            SELECT title AS text,
+                   NULL::integer AS place_id,
+                   id AS tour_id,
+                   word_similarity($1, title)
+                       + CASE WHEN title ILIKE $2 THEN 1 ELSE 0 END AS score
+            FROM tours
+            WHERE is_active AND ($1 <% title OR title ILIKE $2)
+        "#;
+
+        let mut subqueries = Vec::new();
+        if kind.is_none_or(|kind| kind == SuggestionKind::Place) {
+            subqueries.push(format!(
+                "SELECT 'place' AS kind, * FROM ({}) places",
+                place_query
+            ));
+        }
+        if kind.is_none_or(|kind| kind == SuggestionKind::Tour) {
+            subqueries.push(format!(
+                "SELECT 'tour' AS kind, * FROM ({}) titles",
+                tour_query
+            ));
+        }
+
+        let rows = sqlx::query_as::<_, (String, String, Option<i32>, Option<i32>, f32)>(&format!(
+            "SELECT kind, text, place_id, tour_id, score::real FROM ({}) suggestions ORDER BY score DESC, text LIMIT $3",
+            subqueries.join(" UNION ALL ")
+        ))
+        .bind(prefix)
+        .bind(format!("{}%", escape_like_pattern(prefix)))
+        .bind(limit as i64)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(kind, text, place_id, tour_id, score)| Suggestion {
+                kind: if kind == "place" {
+                    SuggestionKind::Place
+                } else {
+                    SuggestionKind::Tour
+                },
+                text,
+                place_id,
+                tour_id,
+                score,
+            })
+            .collect())
+    }
+
+    async fn find_nearby(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        filter: &TourFilter,
+        limit: usize,
+    ) -> Result<Vec<NearbyTour>, RepositoryError> {
+        let mut query_builder =
+            QueryBuilder::<Postgres>::new("SELECT * FROM (SELECT tours.*, haversine_km(");
+        query_builder
+            .push_bind(latitude)
+            .push(", ")
+            .push_bind(longitude)
+            .push(", latitude, longitude) AS distance_km FROM tours WHERE latitude IS NOT NULL");
+        push_bounding_box(
+            &mut query_builder,
+            &BoundingBox::around(latitude, longitude, radius_km),
+        );
+        push_filter(&mut query_builder, filter);
+        query_builder
+            .push(") nearby_tours WHERE distance_km <= ")
+            .push_bind(radius_km)
+            .push(" ORDER BY distance_km, id LIMIT ")
+            .push_bind(limit as i64);
+
+        let tours = query_builder
+            .build_query_as::<NearbyTour>()
+            .fetch_all(&self.database_pool)
+            .await?;
+
+        Ok(tours)
+    }
+
+    async fn find_in_bounding_box(
+        &self,
+        bounding_box: &BoundingBox,
+        filter: &TourFilter,
+        limit: usize,
+    ) -> Result<Vec<Tour>, RepositoryError> {
+        let mut query_builder =
+            QueryBuilder::<Postgres>::new("SELECT * FROM tours WHERE latitude IS NOT NULL");
+        push_bounding_box(&mut query_builder, bounding_box);
+        push_filter(&mut query_builder, filter);
+        query_builder
+            .push(" ORDER BY id LIMIT ")
+            .push_bind(limit as i64);
+
+        let tours = query_builder
+            .build_query_as::<Tour>()
+            .fetch_all(&self.database_pool)
+            .await?;
+
+        Ok(tours)
+    }
+
+    async fn find_by_id(&self, id: i32) -> Result<Option<Tour>, RepositoryError> {
+        let tour = sqlx::query_as::<_, Tour>(
+            r#"
+            SELECT * FROM tours
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(tour)
+    }
+
+    async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<Tour>, RepositoryError> {
+        let tours = sqlx::query_as::<_, Tour>(
+            r#"
+            SELECT * FROM tours
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(tours)
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tour>, RepositoryError> {
+        let tour = sqlx::query_as::<_, Tour>(
+            r#"
+            SELECT * FROM tours
+            WHERE slug = $1
+            "#,
+        )
+        .bind(slug)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(tour)
+    }
+
+    async fn create(
+        &self,
+        input: CreateTourInput,
+        now: DateTime<Utc>,
+    ) -> Result<Tour, RepositoryError> {
+        let price_minor = input.price_minor();
+        let currency = input.currency().to_string();
+        let tour = sqlx::query_as::<_, Tour>(
+            r#"
+            INSERT INTO tours (title, slug, description, start_date, end_date, price_minor, currency, rating, location, latitude, longitude, image_url, is_active, max_participants, language, place_id, timezone, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, COALESCE($17, (SELECT timezone FROM places WHERE id = $16), 'UTC'), $18, $19)
+            RETURNING *
+            "#
+        )
+        .bind(input.title)
+        .bind(input.slug)
+        .bind(input.description)
+        .bind(input.start_date)
+        .bind(input.end_date)
+        .bind(price_minor)
+        .bind(currency)
+        .bind(input.rating)
+        .bind(input.location)
+        .bind(input.latitude)
+        .bind(input.longitude)
+        .bind(input.image_url)
+        .bind(input.is_active)
+        .bind(input.max_participants)
+        .bind(
+            input
+                .language
+                .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string()),
+        )
+        .bind(input.place_id)
+        .bind(input.timezone)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(tour)
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        input: UpdateTourInput,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Tour>, RepositoryError> {
+        let tour = build_update_query(id, input, now)
+            .build_query_as::<Tour>()
+            .fetch_optional(&self.database_pool)
+            .await?;
+
+        Ok(tour)
+    }
+
+    async fn has_price_overrides(&self, id: i32) -> Result<bool, RepositoryError> {
+        let has_price_overrides = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM departures WHERE tour_id = $1 AND price_minor IS NOT NULL)
+                OR EXISTS (SELECT 1 FROM departure_schedules WHERE tour_id = $1 AND price_minor IS NOT NULL)
+                OR EXISTS (SELECT 1 FROM tour_price_categories WHERE tour_id = $1 AND price_minor IS NOT NULL)
+                OR EXISTS (SELECT 1 FROM pricing_rules WHERE tour_id = $1 AND amount_minor IS NOT NULL)
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(has_price_overrides)
+    }
+}
+
+fn get_sort_column(field: TourSortField) -> &'static str {
+    match field {
+        TourSortField::Price => "price_minor",
+        TourSortField::Rating => "rating",
+        TourSortField::StartDate => "start_date",
+        TourSortField::CreatedAt => "created_at",
+    }
+}
+
+fn push_filter(query_builder: &mut QueryBuilder<'_, Postgres>, filter: &TourFilter) {
+    if let Some(location) = &filter.location {
+        query_builder
+            .push(" AND location ILIKE ")
+            .push_bind(format!("%{}%", escape_like_pattern(location)));
+    }
+    if let Some(place_id) = filter.place_id {
+        query_builder.push(" AND place_id = ").push_bind(place_id);
+    }
+    if let Some(date_from) = filter.date_from {
+        query_builder
+            .push(" AND COALESCE(end_date, start_date) >= ")
+            .push_bind(date_from);
+    }
+    if let Some(date_to) = filter.date_to {
+        query_builder.push(" AND start_date <= ").push_bind(date_to);
+    }
+    if let Some(currency) = filter.required_currency() {
+        query_builder
+            .push(" AND currency = ")
+            .push_bind(currency.to_string());
+    }
+    if let Some(price_min) = filter.price_min_minor() {
+        query_builder
+            .push(" AND price_minor >= ")
+            .push_bind(price_min);
+    }
+    if let Some(price_max) = filter.price_max_minor() {
+        query_builder
+            .push(" AND price_minor <= ")
+            .push_bind(price_max);
+    }
+    if let Some(min_rating) = filter.min_rating {
+        query_builder.push(" AND rating >= ").push_bind(min_rating);
+    }
+    if let Some(is_active) = filter.is_active {
+        query_builder.push(" AND is_active = ").push_bind(is_active);
+    }
+    // Matches tours with an upcoming departure that still has enough seats, counting
+    // active holds as taken.
+    if let Some(available_seats) = filter.available_seats {
+        query_builder
+            .push(
+                " AND EXISTS (SELECT 1 FROM departures \
+                WHERE departures.tour_id = tours.id AND departures.status = 'scheduled' \
+                AND departures.starts_at > now() \
+                AND (COALESCE(departures.capacity, tours.max_participants) IS NULL \
+                OR COALESCE(departures.capacity, tours.max_participants) - (\
+                SELECT COALESCE(SUM(bookings.participants), 0) FROM bookings \
+                WHERE bookings.departure_id = departures.id AND bookings.status = 'confirmed') - (\
+                SELECT COALESCE(SUM(seat_holds.seats), 0) FROM seat_holds \
+                WHERE seat_holds.departure_id = departures.id AND seat_holds.status = 'active' \
+                AND seat_holds.expires_at > now()) >= ",
+            )
+            .push_bind(available_seats)
+            .push("))");
+    }
+}
+
+fn push_bounding_box(query_builder: &mut QueryBuilder<'_, Postgres>, bounding_box: &BoundingBox) {
+    query_builder
+        .push(" AND latitude BETWEEN ")
+        .push_bind(bounding_box.south)
+        .push(" AND ")
+        .push_bind(bounding_box.north);
+
+    if bounding_box.crosses_antimeridian() {
+        query_builder
+            .push(" AND (longitude >= ")
+            .push_bind(bounding_box.west)
+            .push(" OR longitude <= ")
+            .push_bind(bounding_box.east)
+            .push(")");
+    } else {
+        query_builder
+            .push(" AND longitude BETWEEN ")
+            .push_bind(bounding_box.west)
+            .push(" AND ")
+            .push_bind(bounding_box.east);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CursorBound {
+    After,
+    Before,
+}
+
+// Keyset condition for `ORDER BY <column> <direction> NULLS LAST, id <direction>`.
+fn push_cursor_condition(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    cursor: &TourCursor,
+    bound: CursorBound,
+) {
+    let column = get_sort_column(cursor.sort.field);
+    let is_ascending = cursor.sort.direction == SortDirection::Asc;
+    let comparison = if is_ascending == (bound == CursorBound::After) {
+        ">"
+    } else {
+        "<"
+    };
+
+    query_builder.push(" AND (");
+    if cursor.value.is_null() {
+        query_builder.push(format!("({} IS NULL AND id {} ", column, comparison));
+        query_builder.push_bind(cursor.id).push(")");
+        if bound == CursorBound::Before {
+            query_builder.push(format!(" OR {} IS NOT NULL", column));
+        }
+    } else {
+        query_builder.push(format!("{} {} ", column, comparison));
+        push_sort_value(query_builder, &cursor.value);
+        query_builder.push(format!(" OR ({} = ", column));
+        push_sort_value(query_builder, &cursor.value);
+        query_builder
+            .push(format!(" AND id {} ", comparison))
+            .push_bind(cursor.id)
+            .push(")");
+        if bound == CursorBound::After {
+            query_builder.push(format!(" OR {} IS NULL", column));
+        }
+    }
+    query_builder.push(")");
+}
+
+fn push_sort_value(query_builder: &mut QueryBuilder<'_, Postgres>, value: &SortValue) {
+    match value {
+        SortValue::Integer(value) => query_builder.push_bind(*value),
+        SortValue::Float(value) => query_builder.push_bind(*value),
+        SortValue::Date(value) => query_builder.push_bind(*value),
+        SortValue::DateTime(value) => query_builder.push_bind(*value),
+    };
+}
+
+fn build_update_query(
+    id: i32,
+    input: UpdateTourInput,
+    now: DateTime<Utc>,
+) -> QueryBuilder<'static, Postgres> {
+    let price_minor = input.price_minor();
+    let mut query_builder = QueryBuilder::new("UPDATE tours SET updated_at = ");
+    query_builder.push_bind(now);
+
+    if let Some(title) = input.title {
+        query_builder.push(", title = ").push_bind(title);
+    }
+    if let Some(slug) = input.slug {
+        query_builder.push(", slug = ").push_bind(slug);
+    }
+    push_patch(&mut query_builder, "description", input.description);
+    push_patch(&mut query_builder, "start_date", input.start_date);
+    push_patch(&mut query_builder, "end_date", input.end_date);
+    push_patch(&mut query_builder, "price_minor", price_minor);
+    if let Some(currency) = input.currency {
+        query_builder.push(", currency = ").push_bind(currency);
+    }
+    push_patch(&mut query_builder, "rating", input.rating);
+    push_patch(&mut query_builder, "location", input.location);
+    push_patch(&mut query_builder, "latitude", input.latitude);
+    push_patch(&mut query_builder, "longitude", input.longitude);
+    push_patch(&mut query_builder, "image_url", input.image_url);
+    if let Some(is_active) = input.is_active {
+        query_builder.push(", is_active = ").push_bind(is_active);
+    }
+    push_patch(
+        &mut query_builder,
+        "max_participants",
+        input.max_participants,
+    );
+    if let Some(language) = input.language {
+        query_builder.push(", language = ").push_bind(language);
+    }
+    push_patch(&mut query_builder, "place_id", input.place_id);
+    if let Some(timezone) = input.timezone {
+        query_builder.push(", timezone = ").push_bind(timezone);
+    }
+
+    query_builder
+        .push(" WHERE id = ")
+        .push_bind(id)
+        .push(" RETURNING *");
+
+    query_builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::geo::MAX_RADIUS_KM;
+
+    async fn insert_tour(pool: &PgPool, slug: &str, latitude: f64, longitude: f64) {
+        sqlx::query(
+            "INSERT INTO tours (title, slug, price_minor, latitude, longitude) VALUES ($1, $1, 1000, $2, $3)",
+        )
+        .bind(slug)
+        .bind(latitude)
+        .bind(longitude)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn nearby_slugs(
+        repository: &PostgresTourRepository,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+    ) -> Vec<String> {
+        repository
+            .find_nearby(latitude, longitude, radius_km, &TourFilter::default(), 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|nearby| nearby.tour.slug)
+            .collect()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn find_nearby_keeps_tours_at_the_edge_of_a_high_latitude_circle(pool: PgPool) {
+        // 494 km away, but east of a box sized with the centre's latitude.
+        insert_tour(&pool, "edge", 81.0, 41.5).await;
+        insert_tour(&pool, "centre", 80.0, 15.0).await;
+        insert_tour(&pool, "too-far", 80.0, 60.0).await;
+        let repository = PostgresTourRepository::new(pool);
+
+        assert_eq!(
+            nearby_slugs(&repository, 80.0, 15.0, 500.0).await,
+            ["centre", "edge"]
+        );
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn find_nearby_crosses_the_antimeridian(pool: PgPool) {
+        insert_tour(&pool, "across", -17.5, -179.8).await;
+        insert_tour(&pool, "too-far", -17.7, -175.0).await;
+        // Exactly antipodal, where rounding used to push asin out of its domain.
+        insert_tour(&pool, "antipode", 17.7134, -1.935).await;
+        let repository = PostgresTourRepository::new(pool);
+
+        assert_eq!(
+            nearby_slugs(&repository, -17.7134, 178.065, 300.0).await,
+            ["across"]
+        );
+        assert_eq!(
+            nearby_slugs(&repository, -17.7134, 178.065, MAX_RADIUS_KM).await,
+            ["across", "too-far"]
+        );
+    }
+}
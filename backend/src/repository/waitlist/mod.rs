@@ -0,0 +1,97 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::models::booking::Booking;
+use crate::models::pricing::ParticipantsInput;
+use crate::models::waitlist::WaitlistEntry;
+use crate::repository::booking::BookingError;
+use crate::repository::RepositoryError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WaitlistError {
+    #[error("seats are available")]
+    SeatsAvailable,
+    #[error("offer is no longer valid")]
+    OfferInactive,
+    #[error(transparent)]
+    Booking(#[from] BookingError),
+}
+
+impl From<sqlx::Error> for WaitlistError {
+    fn from(error: sqlx::Error) -> Self {
+        WaitlistError::Booking(error.into())
+    }
+}
+
+impl From<RepositoryError> for WaitlistError {
+    fn from(error: RepositoryError) -> Self {
+        WaitlistError::Booking(error.into())
+    }
+}
+
+#[async_trait]
+pub trait WaitlistRepository: Send + Sync {
+    async fn find_by_id(&self, id: i32) -> Result<Option<WaitlistEntry>, RepositoryError>;
+
+    async fn find_by_claim_token(
+        &self,
+        claim_token: &str,
+    ) -> Result<Option<WaitlistEntry>, RepositoryError>;
+
+    async fn find_by_user(
+        &self,
+        user_id: &str,
+        limit: usize,
+    ) -> Result<Vec<WaitlistEntry>, RepositoryError>;
+
+    // 1-based position among the entries still waiting on the same departure.
+    async fn find_position(&self, id: i32) -> Result<Option<i64>, RepositoryError>;
+
+    // Fails with `SeatsAvailable` when the party could book right away.
+    async fn join(
+        &self,
+        departure_id: i32,
+        user_id: &str,
+        participants: i32,
+        now: DateTime<Utc>,
+    ) -> Result<WaitlistEntry, WaitlistError>;
+
+    // Releases the seats of an outstanding offer.
+    async fn leave(
+        &self,
+        id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<WaitlistEntry>, RepositoryError>;
+
+    // Offers the free seats of the departure to waiting entries, oldest first,
+    // skipping parties that don't fit. Each offer holds the seats until
+    // `offer_expires_at`.
+    async fn promote(
+        &self,
+        departure_id: i32,
+        offer_expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<WaitlistEntry>, WaitlistError>;
+
+    // Books the offered seats and marks the entry claimed. Without a party, every
+    // seat is priced as an adult.
+    async fn claim(
+        &self,
+        id: i32,
+        party: Option<&[ParticipantsInput]>,
+        promo_code: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Booking, WaitlistError>;
+
+    // Marks unclaimed offers past their expiry as expired, closes entries of
+    // departures that have started, and returns the departures whose offers expired.
+    async fn expire_offers(&self, now: DateTime<Utc>) -> Result<Vec<i32>, RepositoryError>;
+
+    // Upcoming scheduled departures with entries still waiting.
+    async fn find_departures_with_waiting(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<i32>, RepositoryError>;
+}
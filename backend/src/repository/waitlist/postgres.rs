@@ -0,0 +1,327 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::booking::Booking;
+use crate::models::pricing::ParticipantsInput;
+use crate::models::waitlist::{WaitlistEntry, WaitlistStatus};
+use crate::repository::booking::postgres::{convert_hold, insert_hold, lock_available_seats};
+use crate::repository::booking::BookingError;
+use crate::repository::waitlist::{WaitlistError, WaitlistRepository};
+use crate::repository::RepositoryError;
+
+pub struct PostgresWaitlistRepository {
+    database_pool: PgPool,
+}
+
+impl PostgresWaitlistRepository {
+    pub fn new(database_pool: PgPool) -> Self {
+        Self { database_pool }
+    }
+}
+
+#[async_trait]
+impl WaitlistRepository for PostgresWaitlistRepository {
+    async fn find_by_id(&self, id: i32) -> Result<Option<WaitlistEntry>, RepositoryError> {
+        let entry =
+            sqlx::query_as::<_, WaitlistEntry>("SELECT * FROM waitlist_entries WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.database_pool)
+                .await?;
+
+        Ok(entry)
+    }
+
+    async fn find_by_claim_token(
+        &self,
+        claim_token: &str,
+    ) -> Result<Option<WaitlistEntry>, RepositoryError> {
+        let entry = sqlx::query_as::<_, WaitlistEntry>(
+            "SELECT * FROM waitlist_entries WHERE claim_token = $1",
+        )
+        .bind(claim_token)
+        .fetch_optional(&self.database_pool)
+        .await?;
+
+        Ok(entry)
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: &str,
+        limit: usize,
+    ) -> Result<Vec<WaitlistEntry>, RepositoryError> {
+        let entries = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+            SELECT * FROM waitlist_entries
+            WHERE user_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit as i64)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn find_position(&self, id: i32) -> Result<Option<i64>, RepositoryError> {
+        let position = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM waitlist_entries ahead
+            JOIN waitlist_entries entry ON entry.departure_id = ahead.departure_id
+            WHERE entry.id = $1 AND entry.status = 'waiting' AND ahead.status = 'waiting'
+              AND (ahead.created_at, ahead.id) <= (entry.created_at, entry.id)
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&self.database_pool)
+        .await?;
+
+        Ok(Some(position).filter(|position| *position > 0))
+    }
+
+    async fn join(
+        &self,
+        departure_id: i32,
+        user_id: &str,
+        participants: i32,
+        now: DateTime<Utc>,
+    ) -> Result<WaitlistEntry, WaitlistError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let available = lock_available_seats(&mut transaction, departure_id, None, now).await?;
+        if available.is_none_or(|available| i64::from(participants) <= available) {
+            return Err(WaitlistError::SeatsAvailable);
+        }
+
+        let entry = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+            INSERT INTO waitlist_entries (departure_id, user_id, participants, status, created_at, updated_at)
+            VALUES ($1, $2, $3, 'waiting', $4, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(departure_id)
+        .bind(user_id)
+        .bind(participants)
+        .bind(now)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(entry)
+    }
+
+    async fn leave(
+        &self,
+        id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<WaitlistEntry>, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        // The hold is released before the entry is updated, matching the lock order
+        // of `claim`.
+        sqlx::query(
+            r#"
+            UPDATE seat_holds SET status = 'released', updated_at = $2
+            WHERE status = 'active' AND id = (
+                SELECT hold_id FROM waitlist_entries WHERE id = $1 AND status = 'offered'
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+        let entry = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+            UPDATE waitlist_entries SET status = 'left', updated_at = $2
+            WHERE id = $1 AND status IN ('waiting', 'offered')
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(entry)
+    }
+
+    async fn promote(
+        &self,
+        departure_id: i32,
+        offer_expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<WaitlistEntry>, WaitlistError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let mut remaining =
+            match lock_available_seats(&mut transaction, departure_id, None, now).await {
+                Ok(available) => available,
+                Err(BookingError::DepartureNotFound | BookingError::DepartureUnavailable) => {
+                    return Ok(Vec::new())
+                }
+                Err(error) => return Err(error.into()),
+            };
+        let waiting = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+            SELECT * FROM waitlist_entries
+            WHERE departure_id = $1 AND status = 'waiting'
+            ORDER BY created_at, id
+            FOR UPDATE
+            "#,
+        )
+        .bind(departure_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut offered = Vec::new();
+        for entry in waiting {
+            if remaining == Some(0) {
+                break;
+            }
+            if remaining.is_some_and(|remaining| i64::from(entry.participants) > remaining) {
+                continue;
+            }
+
+            let hold = insert_hold(
+                &mut transaction,
+                departure_id,
+                &entry.user_id,
+                entry.participants,
+                offer_expires_at,
+                now,
+            )
+            .await?;
+            let entry = sqlx::query_as::<_, WaitlistEntry>(
+                r#"
+                UPDATE waitlist_entries
+                SET status = 'offered', hold_id = $2, claim_token = $3, offer_expires_at = $4, updated_at = $5
+                WHERE id = $1
+                RETURNING *
+                "#,
+            )
+            .bind(entry.id)
+            .bind(hold.id)
+            .bind(Uuid::new_v4().simple().to_string())
+            .bind(offer_expires_at)
+            .bind(now)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+            remaining = remaining.map(|remaining| remaining - i64::from(entry.participants));
+            offered.push(entry);
+        }
+
+        transaction.commit().await?;
+
+        Ok(offered)
+    }
+
+    async fn claim(
+        &self,
+        id: i32,
+        party: Option<&[ParticipantsInput]>,
+        promo_code: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Booking, WaitlistError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let hold_id =
+            sqlx::query_as::<_, WaitlistEntry>("SELECT * FROM waitlist_entries WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&mut *transaction)
+                .await?
+                .filter(|entry| entry.status == WaitlistStatus::Offered)
+                .and_then(|entry| entry.hold_id)
+                .ok_or(WaitlistError::OfferInactive)?;
+
+        let booking = convert_hold(&mut transaction, hold_id, party, promo_code, now)
+            .await
+            .map_err(|error| match error {
+                BookingError::HoldNotFound | BookingError::HoldInactive => {
+                    WaitlistError::OfferInactive
+                }
+                error => error.into(),
+            })?;
+        let claimed = sqlx::query(
+            r#"
+            UPDATE waitlist_entries SET status = 'claimed', updated_at = $2
+            WHERE id = $1 AND status = 'offered'
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if claimed == 0 {
+            return Err(WaitlistError::OfferInactive);
+        }
+
+        transaction.commit().await?;
+
+        Ok(booking)
+    }
+
+    async fn expire_offers(&self, now: DateTime<Utc>) -> Result<Vec<i32>, RepositoryError> {
+        let mut transaction = self.database_pool.begin().await?;
+
+        let mut departure_ids = sqlx::query_scalar::<_, i32>(
+            r#"
+            UPDATE waitlist_entries SET status = 'expired', updated_at = $1
+            WHERE status = 'offered' AND offer_expires_at <= $1
+            RETURNING departure_id
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&mut *transaction)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE waitlist_entries SET status = 'closed', updated_at = $1
+            FROM departures
+            WHERE departures.id = waitlist_entries.departure_id
+              AND waitlist_entries.status = 'waiting' AND departures.starts_at <= $1
+            "#,
+        )
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        departure_ids.sort_unstable();
+        departure_ids.dedup();
+
+        Ok(departure_ids)
+    }
+
+    async fn find_departures_with_waiting(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<i32>, RepositoryError> {
+        let departure_ids = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT DISTINCT waitlist_entries.departure_id FROM waitlist_entries
+            JOIN departures ON departures.id = waitlist_entries.departure_id
+            WHERE waitlist_entries.status = 'waiting'
+              AND departures.status = 'scheduled' AND departures.starts_at > $1
+            ORDER BY waitlist_entries.departure_id
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        Ok(departure_ids)
+    }
+}
@@ -1,54 +1,266 @@
 use actix_session::Session;
 use actix_web::{http, web, HttpRequest, HttpResponse, Responder};
 
+use async_graphql::connection::CursorType;
 use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
-use async_graphql::Schema;
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
+use chrono::NaiveDate;
 
-use oauth2::{
-    basic::BasicClient, AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, RedirectUrl,
-    TokenResponse, TokenUrl,
-};
-
-use std::env;
-use tracing::{error, info};
+use futures_util::{stream, StreamExt};
+use rust_decimal::Decimal;
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tracing::{info, warn};
 
 use crate::config::ApplicationData;
-use crate::schema::graphql::{ApplicationSchema, Tour};
+use crate::error::AppError;
+use crate::middleware::nextauth::get_current_user;
+use crate::models::pagination::{Page, PageRequest, DEFAULT_PAGE_SIZE};
+use crate::models::search::SuggestionKind;
+use crate::models::tour::{SortDirection, Tour, TourCursor, TourFilter, TourSort, TourSortField};
+use crate::schema::graphql::ApplicationSchema;
+use crate::service::oauth::{OAuthError, OAuthProviderKind, OAuthService};
+
+const EVENT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+const PAYMENT_SIGNATURE_HEADER: &str = "X-Payment-Signature";
 
 pub async fn graphql_handler(
     application_schema: web::Data<ApplicationSchema>,
+    http_request: HttpRequest,
     request: GraphQLRequest,
 ) -> GraphQLResponse {
-    application_schema
-        .execute(request.into_inner())
-        .await
-        .into()
+    let mut request = request.into_inner();
+    if let Some(current_user) = get_current_user(&http_request) {
+        request = request.data(current_user);
+    }
+
+    application_schema.execute(request).await.into()
+}
+
+pub async fn graphql_subscription_handler(
+    application_schema: web::Data<ApplicationSchema>,
+    http_request: HttpRequest,
+    payload: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    let mut data = async_graphql::Data::default();
+    if let Some(current_user) = get_current_user(&http_request) {
+        data.insert(current_user);
+    }
+
+    GraphQLSubscription::new(ApplicationSchema::clone(&application_schema))
+        .with_data(data)
+        .start(&http_request, payload)
 }
 
 pub async fn graphql_playground() -> HttpResponse {
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
-        .body(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+        .body(playground_source(
+            GraphQLPlaygroundConfig::new("/graphql").subscription_endpoint("/graphql/ws"),
+        ))
 }
 
-pub async fn index(session: Session) -> impl Responder {
+pub async fn index(_session: Session) -> impl Responder {
     info!("Index route");
 
     HttpResponse::Ok().body("Hello World!")
 }
 
-pub async fn get_tours(application_data: web::Data<ApplicationData>) -> impl Responder {
-    match sqlx::query_as::<_, Tour>("SELECT id, title FROM public.\"Tours\"")
-        .fetch_all(&application_data.database_pool)
+#[derive(Debug, serde::Deserialize)]
+pub struct TourListQuery {
+    limit: Option<usize>,
+    cursor: Option<String>,
+    location: Option<String>,
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+    currency: Option<String>,
+    price_min: Option<Decimal>,
+    price_max: Option<Decimal>,
+    min_rating: Option<f64>,
+    is_active: Option<bool>,
+    available_seats: Option<i32>,
+    place_id: Option<i32>,
+    sort: Option<TourSortField>,
+    order: Option<SortDirection>,
+}
+
+impl TourListQuery {
+    fn filter(&self) -> TourFilter {
+        TourFilter {
+            location: self.location.clone(),
+            date_from: self.date_from,
+            date_to: self.date_to,
+            currency: self.currency.clone(),
+            price_min: self.price_min,
+            price_max: self.price_max,
+            min_rating: self.min_rating,
+            is_active: self.is_active,
+            available_seats: self.available_seats,
+            place_id: self.place_id,
+        }
+    }
+
+    fn sort(&self) -> TourSort {
+        TourSort {
+            field: self.sort.unwrap_or_default(),
+            direction: self.order.unwrap_or_default(),
+        }
+    }
+}
+
+pub async fn get_tours(
+    request: HttpRequest,
+    application_data: web::Data<ApplicationData>,
+    query: web::Query<TourListQuery>,
+) -> Result<HttpResponse, AppError> {
+    let after = query
+        .cursor
+        .as_deref()
+        .map(TourCursor::decode_cursor)
+        .transpose()
+        .map_err(|error| AppError::validation("cursor", error.to_string()))?;
+    let sort = query.sort();
+    let page_request = PageRequest::forward(after, Some(query.limit.unwrap_or(DEFAULT_PAGE_SIZE)));
+    let limit = page_request.limit();
+
+    let page = application_data
+        .tour_service
+        .get_tours_page(
+            get_current_user(&request).as_ref(),
+            query.filter(),
+            sort,
+            page_request,
+        )
         .await
-    {
-        Ok(tours) => HttpResponse::Ok().json(tours),
-        Err(error) => {
-            error!("Failed to get tours: {}", error);
-            HttpResponse::InternalServerError().body(format!("Failed to get tours: {}", error))
+        .map_err(|error| AppError::from_service_error("get tours", error))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("X-Total-Count", page.total_count.to_string()))
+        .insert_header((
+            http::header::LINK,
+            build_tours_link_header(&request, limit, sort, &page),
+        ))
+        .json(page.items))
+}
+
+fn build_tours_link_header(
+    request: &HttpRequest,
+    limit: usize,
+    sort: TourSort,
+    page: &Page<Tour>,
+) -> String {
+    let build_link = |cursor: Option<String>, relation: &str| {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        for (key, value) in url::form_urlencoded::parse(request.query_string().as_bytes()) {
+            if key != "limit" && key != "cursor" {
+                query.append_pair(&key, &value);
+            }
+        }
+        query.append_pair("limit", &limit.to_string());
+        if let Some(cursor) = cursor {
+            query.append_pair("cursor", &cursor);
+        }
+
+        format!(
+            "<{}?{}>; rel=\"{}\"",
+            request.path(),
+            query.finish(),
+            relation
+        )
+    };
+
+    let mut links = vec![build_link(None, "first")];
+    if page.has_next_page {
+        if let Some(tour) = page.items.last() {
+            links.push(build_link(
+                Some(TourCursor::new(tour, sort).encode_cursor()),
+                "next",
+            ));
         }
     }
+
+    links.join(", ")
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SuggestQuery {
+    prefix: String,
+    kind: Option<SuggestionKind>,
+    limit: Option<usize>,
+}
+
+pub async fn suggest(
+    application_data: web::Data<ApplicationData>,
+    query: web::Query<SuggestQuery>,
+) -> Result<HttpResponse, AppError> {
+    let suggestions = application_data
+        .tour_service
+        .suggest(&query.prefix, query.kind, query.limit)
+        .await
+        .map_err(|error| AppError::from_service_error("get suggestions", error))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header((http::header::CACHE_CONTROL, "public, max-age=30"))
+        .json(suggestions))
+}
+
+pub async fn events(
+    application_data: web::Data<ApplicationData>,
+    http_request: HttpRequest,
+) -> impl Responder {
+    let last_event_id = http_request
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    let events = application_data
+        .event_service
+        .subscribe_server_events(get_current_user(&http_request), last_event_id)
+        .map(|event| {
+            format!(
+                "id: {}\nevent: {}\ndata: {}\n\n",
+                event.id,
+                event.kind.name(),
+                event.data
+            )
+        });
+    // Comment lines keep idle connections from being closed by proxies.
+    let keep_alive = IntervalStream::new(time::interval_at(
+        time::Instant::now() + EVENT_KEEP_ALIVE_INTERVAL,
+        EVENT_KEEP_ALIVE_INTERVAL,
+    ))
+    .map(|_| ": keep-alive\n\n".to_string());
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((http::header::CACHE_CONTROL, "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(
+            stream::select(events, keep_alive)
+                .map(|chunk| Ok::<_, actix_web::Error>(web::Bytes::from(chunk))),
+        )
+}
+
+pub async fn payment_webhook(
+    application_data: web::Data<ApplicationData>,
+    http_request: HttpRequest,
+    payload: web::Bytes,
+) -> Result<HttpResponse, AppError> {
+    let signature = http_request
+        .headers()
+        .get(PAYMENT_SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthenticated)?;
+
+    let outcome = application_data
+        .payment_service
+        .handle_webhook(&payload, signature)
+        .await
+        .map_err(|error| AppError::from_service_error("handle payment webhook", error))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "status": outcome.name() })))
 }
 
 pub async fn protected() -> impl Responder {
@@ -59,177 +271,517 @@ pub async fn protected() -> impl Responder {
 
 #[derive(Debug, serde::Deserialize)]
 pub struct OAuth2Callback {
-    code: String,
-    state: String,
+    code: Option<String>,
+    error: Option<String>,
+    state: Option<String>,
 }
 
-pub async fn github_login() -> impl Responder {
-    let oauth_client = get_github_client();
-    let (mut authorize_url, _csrf_token) = oauth_client
-        .authorize_url(CsrfToken::new_random)
-        .add_scope(oauth2::Scope::new("user:email".to_string()))
-        .add_scope(oauth2::Scope::new("read:user".to_string()))
-        .url();
-
-    authorize_url
-        .query_pairs_mut()
-        .append_pair("prompt", "consent");
-
-    info!("Redirecting to {}", authorize_url);
-
-    HttpResponse::Found()
-        .append_header((http::header::LOCATION, authorize_url.to_string()))
-        .finish()
+pub async fn github_login(
+    session: Session,
+    application_data: web::Data<ApplicationData>,
+) -> impl Responder {
+    start_sign_in(
+        &session,
+        &application_data.oauth_service,
+        OAuthProviderKind::Github,
+    )
 }
 
 pub async fn github_callback(
     session: Session,
+    application_data: web::Data<ApplicationData>,
     callback_data: web::Query<OAuth2Callback>,
 ) -> impl Responder {
-    info!("GitHub Callback Data: {:#?}", callback_data);
+    complete_sign_in(
+        &session,
+        &application_data.oauth_service,
+        OAuthProviderKind::Github,
+        callback_data.into_inner(),
+    )
+    .await
+}
 
-    let oauth_client = get_github_client();
-    let token_result = oauth_client
-        .exchange_code(AuthorizationCode::new(callback_data.code.clone()))
-        .request_async(oauth2::reqwest::async_http_client)
-        .await;
+pub async fn google_login(
+    session: Session,
+    application_data: web::Data<ApplicationData>,
+) -> impl Responder {
+    start_sign_in(
+        &session,
+        &application_data.oauth_service,
+        OAuthProviderKind::Google,
+    )
+}
 
-    match token_result {
-        Ok(token) => {
-            let request_client = reqwest::Client::new();
-            let user_info = request_client
-                .get("https://api.github.com/user")
-                .header(
-                    "Authorization",
-                    format!("token {}", token.access_token().secret()),
-                )
-                .header("User-Agent", "actix-web-app")
-                .send()
-                .await
-                .unwrap()
-                .json::<serde_json::Value>()
-                .await
-                .unwrap();
-
-            info!("Token: {:#?}", token);
-            info!("User info: {:#?}", user_info);
-
-            session
-                .insert(
-                    "github_user",
-                    user_info["login"].as_str().unwrap().to_string(),
-                )
-                .unwrap();
+pub async fn google_callback(
+    session: Session,
+    application_data: web::Data<ApplicationData>,
+    callback_data: web::Query<OAuth2Callback>,
+) -> impl Responder {
+    complete_sign_in(
+        &session,
+        &application_data.oauth_service,
+        OAuthProviderKind::Google,
+        callback_data.into_inner(),
+    )
+    .await
+}
 
-            HttpResponse::Found()
-                .append_header((http::header::LOCATION, "/"))
-                .finish()
+// The state is kept in the signed session cookie, so a callback only completes
+// the sign-in started in the same browser.
+fn start_sign_in(
+    session: &Session,
+    oauth_service: &OAuthService,
+    kind: OAuthProviderKind,
+) -> HttpResponse {
+    let authorize_url = oauth_service.authorize_url(kind).and_then(|(url, state)| {
+        session
+            .insert(kind.state_session_key(), state.secret())
+            .map_err(|error| OAuthError::Session(error.to_string()))?;
+        Ok(url)
+    });
+
+    match authorize_url {
+        Ok(authorize_url) => {
+            info!("Redirecting to {}", authorize_url);
+            redirect(authorize_url.as_str())
         }
+        Err(error) => {
+            warn!("Failed to start {:?} sign-in: {}", kind, error);
+            redirect(&oauth_service.sign_in_error_url(&error))
+        }
+    }
+}
 
+async fn complete_sign_in(
+    session: &Session,
+    oauth_service: &OAuthService,
+    kind: OAuthProviderKind,
+    callback_data: OAuth2Callback,
+) -> HttpResponse {
+    match sign_in(session, oauth_service, kind, callback_data).await {
+        Ok(()) => redirect("/"),
         Err(error) => {
-            error!("Error: {:#?}", error);
-            HttpResponse::Unauthorized().finish()
+            warn!("{:?} sign-in failed: {}", kind, error);
+            redirect(&oauth_service.sign_in_error_url(&error))
         }
     }
 }
 
-pub async fn google_login() -> impl Responder {
-    let oauth_client = get_google_client();
-    let (mut authorize_url, _csrf_token) = oauth_client
-        .authorize_url(CsrfToken::new_random)
-        .add_scope(oauth2::Scope::new("openid".to_string()))
-        .add_scope(oauth2::Scope::new("email".to_string()))
-        .add_scope(oauth2::Scope::new("profile".to_string()))
-        .url();
+async fn sign_in(
+    session: &Session,
+    oauth_service: &OAuthService,
+    kind: OAuthProviderKind,
+    callback_data: OAuth2Callback,
+) -> Result<(), OAuthError> {
+    let issued_state = session
+        .remove_as::<String>(kind.state_session_key())
+        .and_then(Result::ok);
+    if issued_state.is_none() || issued_state != callback_data.state {
+        return Err(OAuthError::InvalidState);
+    }
+    if let Some(error) = callback_data.error {
+        return Err(OAuthError::AuthorizationDenied(error));
+    }
+    let code = callback_data
+        .code
+        .ok_or_else(|| OAuthError::AuthorizationDenied("missing authorization code".to_string()))?;
 
-    authorize_url
-        .query_pairs_mut()
-        .append_pair("prompt", "consent");
+    let user = oauth_service.fetch_user(kind, &code).await?;
+    info!("Signed in {:?} user {}", kind, user);
 
-    info!("Redirecting to {}", authorize_url);
+    session
+        .insert(kind.session_key(), user)
+        .map_err(|error| OAuthError::Session(error.to_string()))
+}
 
+fn redirect(location: &str) -> HttpResponse {
     HttpResponse::Found()
-        .append_header((http::header::LOCATION, authorize_url.to_string()))
+        .append_header((http::header::LOCATION, location))
         .finish()
 }
 
-pub async fn google_callback(
-    session: Session,
-    callback_data: web::Query<OAuth2Callback>,
-) -> impl Responder {
-    info!("Google Callback Data: {:#?}", callback_data);
+#[cfg(test)]
+mod tests {
+    use actix_session::storage::CookieSessionStore;
+    use actix_session::SessionMiddleware;
+    use actix_web::cookie::{Cookie, Key};
+    use actix_web::{test, App, HttpServer};
+    use serde_json::Value;
+    use std::sync::Arc;
+    use url::Url;
+
+    use super::*;
+    use crate::repository::tour::memory::InMemoryTourRepository;
+    use crate::test_support::{application_data, application_data_with_oauth, tour};
+
+    fn tours_app_data() -> web::Data<ApplicationData> {
+        application_data(Arc::new(InMemoryTourRepository::with_tours(vec![
+            tour(1, "Old Lisbon", 1500),
+            tour(2, "Porto Wine", 3000),
+            tour(3, "Sintra Palaces", 4500),
+        ])))
+    }
+
+    macro_rules! tours_app {
+        () => {
+            test::init_service(
+                App::new()
+                    .app_data(tours_app_data())
+                    .route("/api/tours", web::get().to(get_tours))
+                    .route("/api/suggest", web::get().to(suggest)),
+            )
+            .await
+        };
+    }
+
+    fn next_link(response: &actix_web::dev::ServiceResponse) -> Option<String> {
+        let link = response.headers().get(http::header::LINK)?.to_str().ok()?;
+        link.split(", ")
+            .find(|link| link.ends_with("rel=\"next\""))
+            .map(|link| link[1..link.find('>').unwrap()].to_string())
+    }
 
-    let oauth_client = get_google_client();
-    let token_result = oauth_client
-        .exchange_code(AuthorizationCode::new(callback_data.code.clone()))
-        .request_async(oauth2::reqwest::async_http_client)
+    #[actix_web::test]
+    async fn get_tours_paginates_with_link_header() {
+        let app = tours_app!();
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/tours?limit=2&sort=price&order=asc")
+                .to_request(),
+        )
         .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers().get("X-Total-Count").unwrap(), "3");
+        let next = next_link(&response).expect("first page links to the next one");
+        let first_page: Vec<Value> = test::read_body_json(response).await;
+        let titles: Vec<&str> = first_page
+            .iter()
+            .map(|tour| tour["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles, ["Old Lisbon", "Porto Wine"]);
+
+        let response =
+            test::call_service(&app, test::TestRequest::get().uri(&next).to_request()).await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(next_link(&response), None);
+        let second_page: Vec<Value> = test::read_body_json(response).await;
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0]["title"], "Sintra Palaces");
+    }
 
-    match token_result {
-        Ok(token) => {
-            let request_client = reqwest::Client::new();
-            let user_info = request_client
-                .get("https://www.googleapis.com/oauth2/v2/userinfo")
-                .header(
-                    "Authorization",
-                    format!("Bearer {}", token.access_token().secret()),
-                )
-                .header("User-Agent", "actix-web-app")
-                .send()
-                .await
-                .unwrap()
-                .json::<serde_json::Value>()
-                .await
-                .unwrap();
-
-            info!("Token: {:#?}", token);
-            info!("User info: {:#?}", user_info);
-
-            session
-                .insert(
-                    "google_user",
-                    user_info["email"].as_str().unwrap().to_string(),
-                )
-                .unwrap();
+    #[actix_web::test]
+    async fn get_tours_without_tours_is_empty() {
+        let app = test::init_service(
+            App::new()
+                .app_data(application_data(Arc::new(InMemoryTourRepository::new())))
+                .route("/api/tours", web::get().to(get_tours)),
+        )
+        .await;
 
-            HttpResponse::Found()
-                .append_header((http::header::LOCATION, "/"))
-                .finish()
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/api/tours").to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers().get("X-Total-Count").unwrap(), "0");
+        assert_eq!(next_link(&response), None);
+        let tours: Vec<Value> = test::read_body_json(response).await;
+        assert!(tours.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn get_tours_filters_before_counting() {
+        let app = tours_app!();
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/tours?price_min=20&currency=USD")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers().get("X-Total-Count").unwrap(), "2");
+    }
+
+    #[actix_web::test]
+    async fn get_tours_hides_inactive_tours_from_anonymous_users() {
+        let mut inactive = tour(2, "Porto Wine", 3000);
+        inactive.is_active = false;
+        let app = test::init_service(
+            App::new()
+                .app_data(application_data(Arc::new(
+                    InMemoryTourRepository::with_tours(vec![tour(1, "Old Lisbon", 1500), inactive]),
+                )))
+                .route("/api/tours", web::get().to(get_tours)),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/api/tours").to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers().get("X-Total-Count").unwrap(), "1");
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/tours?is_active=false")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn get_tours_rejects_invalid_cursors() {
+        let app = tours_app!();
+
+        for uri in [
+            "/api/tours?cursor=not-a-cursor",
+            "/api/tours?price_min=50&price_max=10",
+        ] {
+            let response =
+                test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(response.status(), http::StatusCode::BAD_REQUEST, "{}", uri);
+            let problem: Value = test::read_body_json(response).await;
+            assert_eq!(problem["code"], "VALIDATION_FAILED", "{}", uri);
         }
+    }
 
-        Err(error) => {
-            error!("Error: {:#?}", error);
-            HttpResponse::Unauthorized().finish()
+    #[actix_web::test]
+    async fn get_tours_rejects_cursors_from_another_sort() {
+        let app = tours_app!();
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/tours?limit=1&sort=price")
+                .to_request(),
+        )
+        .await;
+        let next = next_link(&response)
+            .unwrap()
+            .replace("sort=price", "sort=rating");
+
+        let response =
+            test::call_service(&app, test::TestRequest::get().uri(&next).to_request()).await;
+        assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn suggest_matches_titles_and_places() {
+        let app = tours_app!();
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/suggest?prefix=lis")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        let suggestions: Vec<Value> = test::read_body_json(response).await;
+        let texts: Vec<&str> = suggestions
+            .iter()
+            .map(|suggestion| suggestion["text"].as_str().unwrap())
+            .collect();
+        assert!(texts.contains(&"Lisbon"), "{:?}", texts);
+        assert!(texts.contains(&"Old Lisbon"), "{:?}", texts);
+    }
+
+    const TOKEN_RESPONSE: (u16, &str) = (200, r#"{"access_token":"token","token_type":"bearer"}"#);
+    const USER_RESPONSE: (u16, &str) = (200, r#"{"login":"octocat"}"#);
+
+    // A provider answering every token and user info request with the given status
+    // and body.
+    fn start_provider_stub(token: (u16, &'static str), user: (u16, &'static str)) -> Url {
+        let respond = |(status, body): (u16, &'static str)| {
+            HttpResponse::build(http::StatusCode::from_u16(status).unwrap())
+                .content_type("application/json")
+                .body(body)
+        };
+        let server = HttpServer::new(move || {
+            App::new()
+                .route(
+                    "/token",
+                    web::post().to(move || async move { respond(token) }),
+                )
+                .route("/user", web::get().to(move || async move { respond(user) }))
+        })
+        .workers(1)
+        .disable_signals()
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        Url::parse(&format!("http://{}/", address)).unwrap()
+    }
+
+    macro_rules! oauth_app {
+        ($provider_url:expr) => {
+            test::init_service(
+                App::new()
+                    .wrap(
+                        SessionMiddleware::builder(CookieSessionStore::default(), Key::generate())
+                            .cookie_secure(false)
+                            .build(),
+                    )
+                    .app_data(application_data_with_oauth(
+                        Arc::new(InMemoryTourRepository::new()),
+                        OAuthService::stub(&$provider_url),
+                    ))
+                    .route("/api/auth/github", web::get().to(github_login))
+                    .route("/api/auth/github/callback", web::get().to(github_callback)),
+            )
+            .await
+        };
+    }
+
+    fn location(response: &actix_web::dev::ServiceResponse) -> String {
+        assert_eq!(response.status(), http::StatusCode::FOUND);
+        response
+            .headers()
+            .get(http::header::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn cookies(response: &actix_web::dev::ServiceResponse) -> Vec<Cookie<'static>> {
+        response
+            .response()
+            .cookies()
+            .map(|cookie| cookie.into_owned())
+            .collect()
+    }
+
+    fn sign_in_error(code: &str) -> String {
+        format!("http://localhost:3000/sign-in?error={}", code)
+    }
+
+    // Starts a sign-in and returns the issued state with the session cookies.
+    async fn start_github_sign_in(
+        app: &impl actix_web::dev::Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+        >,
+    ) -> (String, Vec<Cookie<'static>>) {
+        let response = test::call_service(
+            app,
+            test::TestRequest::get()
+                .uri("/api/auth/github")
+                .to_request(),
+        )
+        .await;
+        let authorize_url = Url::parse(&location(&response)).unwrap();
+        let state = authorize_url
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .map(|(_, value)| value.into_owned())
+            .expect("authorize URL has a state");
+
+        (state, cookies(&response))
+    }
+
+    async fn github_callback_location(
+        app: &impl actix_web::dev::Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+        >,
+        query: &str,
+        cookies: &[Cookie<'static>],
+    ) -> (String, Vec<Cookie<'static>>) {
+        let mut request =
+            test::TestRequest::get().uri(&format!("/api/auth/github/callback?{}", query));
+        for cookie in cookies {
+            request = request.cookie(cookie.clone());
         }
+        let response = test::call_service(app, request.to_request()).await;
+
+        (location(&response), self::cookies(&response))
     }
-}
 
-pub fn get_github_client() -> BasicClient {
-    let github_client_id = env::var("GITHUB_CLIENT_ID").expect("GITHUB_CLIENT_ID not set");
-    let github_client_secret =
-        env::var("GITHUB_CLIENT_SECRET").expect("GITHUB_CLIENT_SECRET not set");
-    // let github_redirect_url = env::var("GITHUB_REDIRECT_URL").expect("GITHUB_REDIRECT_URL not set");
+    #[actix_web::test]
+    async fn github_sign_in_completes_once_with_the_issued_state() {
+        let app = oauth_app!(start_provider_stub(TOKEN_RESPONSE, USER_RESPONSE));
+        let (state, cookies) = start_github_sign_in(&app).await;
 
-    BasicClient::new(
-        ClientId::new(github_client_id),
-        Some(ClientSecret::new(github_client_secret)),
-        AuthUrl::new("https://github.com/login/oauth/authorize".to_string()).unwrap(),
-        Some(TokenUrl::new("https://github.com/login/oauth/access_token".to_string()).unwrap()),
-    )
-    // .set_redirect_uri(RedirectUrl::new(github_redirect_url).unwrap());
-}
+        let query = format!("code=code&state={}", state);
+        let (location, cookies) = github_callback_location(&app, &query, &cookies).await;
+        assert_eq!(location, "/");
 
-pub fn get_google_client() -> BasicClient {
-    let google_client_id = env::var("GOOGLE_CLIENT_ID").expect("GOOGLE_CLIENT_ID not set");
-    let google_client_secret =
-        env::var("GOOGLE_CLIENT_SECRET").expect("GOOGLE_CLIENT_SECRET not set");
-    let google_redirect_url = env::var("GOOGLE_REDIRECT_URL").expect("GOOGLE_REDIRECT_URL not set");
+        // The state is used up by the first callback.
+        let (location, _) = github_callback_location(&app, &query, &cookies).await;
+        assert_eq!(location, sign_in_error("invalid_state"));
+    }
 
-    BasicClient::new(
-        ClientId::new(google_client_id),
-        Some(ClientSecret::new(google_client_secret)),
-        AuthUrl::new("https://accounts.google.com/o/oauth2/auth".to_string()).unwrap(),
-        Some(TokenUrl::new("https://oauth2.googleapis.com/token".to_string()).unwrap()),
-    )
-    .set_redirect_uri(RedirectUrl::new(google_redirect_url).unwrap())
+    #[actix_web::test]
+    async fn github_sign_in_rejects_foreign_states() {
+        let app = oauth_app!(start_provider_stub(TOKEN_RESPONSE, USER_RESPONSE));
+        let (state, cookies) = start_github_sign_in(&app).await;
+
+        let cases = [
+            ("code=code&state=forged".to_string(), cookies.clone()),
+            ("code=code".to_string(), cookies.clone()),
+            // A callback in a browser that never started the sign-in.
+            (format!("code=code&state={}", state), Vec::new()),
+        ];
+        for (query, cookies) in cases {
+            let (location, _) = github_callback_location(&app, &query, &cookies).await;
+            assert_eq!(location, sign_in_error("invalid_state"), "{}", query);
+        }
+    }
+
+    #[actix_web::test]
+    async fn github_sign_in_failures_redirect_to_the_sign_in_page() {
+        let cases = [
+            (
+                (502, r#"{"error":"bad gateway"}"#),
+                USER_RESPONSE,
+                "token_exchange_failed",
+            ),
+            ((200, "not json"), USER_RESPONSE, "token_exchange_failed"),
+            (TOKEN_RESPONSE, (503, "{}"), "user_info_unavailable"),
+            (
+                TOKEN_RESPONSE,
+                (200, "{\"login\":"),
+                "user_info_unavailable",
+            ),
+            (TOKEN_RESPONSE, (200, r#"{"id":1}"#), "missing_user_info"),
+            (
+                TOKEN_RESPONSE,
+                (200, r#"{"login":""}"#),
+                "missing_user_info",
+            ),
+        ];
+
+        for (token, user, code) in cases {
+            let app = oauth_app!(start_provider_stub(token, user));
+            let (state, cookies) = start_github_sign_in(&app).await;
+
+            let query = format!("code=code&state={}", state);
+            let (location, _) = github_callback_location(&app, &query, &cookies).await;
+            assert_eq!(location, sign_in_error(code), "{:?} {:?}", token, user);
+        }
+    }
+
+    #[actix_web::test]
+    async fn github_sign_in_reports_denied_authorization() {
+        let app = oauth_app!(start_provider_stub(TOKEN_RESPONSE, USER_RESPONSE));
+        let (state, cookies) = start_github_sign_in(&app).await;
+
+        let query = format!("error=access_denied&state={}", state);
+        let (location, _) = github_callback_location(&app, &query, &cookies).await;
+        assert_eq!(location, sign_in_error("access_denied"));
+    }
 }
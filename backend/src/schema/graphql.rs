@@ -1,58 +1,821 @@
 use actix_web::web::Data;
-use async_graphql::{Context, FieldResult, Object, OutputType, Schema, SimpleObject};
-use chrono::{NaiveDate, NaiveDateTime, Utc};
-use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
-use tracing::{error, info};
+use async_graphql::connection::{self, Connection, Edge};
+use async_graphql::{
+    ComplexObject, Context, ErrorExtensions, FieldResult, Object, Schema, Subscription, ID,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use futures_util::{future, Stream, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
 
 use crate::config::ApplicationData;
+use crate::error::AppError;
+use crate::models::booking::{Booking, SeatHold};
+use crate::models::departure::{CreateDepartureInput, Departure, RescheduleDepartureInput};
+use crate::models::event::TourChange;
+use crate::models::geo::{BoundingBox, NearbyTour};
+use crate::models::money::{ExchangeRate, Money, DEFAULT_CURRENCY};
+use crate::models::node::{GlobalId, Node, NodeType};
+use crate::models::pagination::PageRequest;
+use crate::models::payment::Payment;
+use crate::models::place::{CreatePlaceInput, Place, UpdatePlaceInput};
+use crate::models::pricing::{
+    CreatePricingRuleInput, ParticipantsInput, PriceCategory, PriceLine, PricingRule, Quote,
+    TourPriceCategory, UpdatePricingRuleInput,
+};
+use crate::models::promotion::{
+    CreatePromotionInput, Promotion, PromotionRedemption, UpdatePromotionInput,
+};
+use crate::models::schedule::{
+    BlackoutCalendar, BlackoutDate, BlackoutDateInput, CreateDepartureScheduleInput,
+    DepartureSchedule, MaterializationSummary, UpdateDepartureScheduleInput,
+};
+use crate::models::search::{Suggestion, SuggestionKind, TourSearchResult};
+use crate::models::tour::{
+    CreateTourInput, Tour, TourConnectionFields, TourCursor, TourFilter, TourSort, UpdateTourInput,
+};
+use crate::models::user::CurrentUser;
+use crate::models::waitlist::WaitlistEntry;
+use crate::schema::guards::{get_current_user, AdminGuard};
+use crate::service::booking::BookingService;
+use crate::service::departure::DepartureService;
+use crate::service::events::EventService;
+use crate::service::exchange_rate::ExchangeRateService;
+use crate::service::payment::PaymentService;
+use crate::service::place::PlaceService;
+use crate::service::pricing::PricingService;
+use crate::service::promotion::PromotionService;
+use crate::service::schedule::ScheduleService;
+use crate::service::tour::TourService;
+use crate::service::waitlist::WaitlistService;
+use crate::service::ServiceError;
 
-#[derive(SimpleObject, Serialize, Deserialize, FromRow, Clone)]
-pub struct Tour {
-    id: i32,
-    title: String,
-    description: Option<String>,
-    start_date: Option<NaiveDate>,
-    end_date: Option<NaiveDate>,
-    price: Option<f64>,
-    rating: Option<f64>,
-    created_at: Option<NaiveDateTime>,
-    updated_at: Option<NaiveDateTime>,
-    location: Option<String>,
-    image_url: Option<String>,
-    is_active: bool,
-    max_participants: Option<i32>,
+pub struct QueryRoot;
+
+#[ComplexObject]
+impl Place {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::Place, self.id).encode()
+    }
 }
 
-pub struct QueryRoot;
+#[ComplexObject]
+impl Tour {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::Tour, self.id).encode()
+    }
+
+    async fn place(&self, context: &Context<'_>) -> FieldResult<Option<Place>> {
+        let Some(place_id) = self.place_id else {
+            return Ok(None);
+        };
+
+        get_place_service(context)?
+            .get_place(place_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get tour place", error).extend())
+    }
+
+    async fn price(&self) -> Option<Money> {
+        self.price_minor
+            .map(|price_minor| Money::new(price_minor, &self.currency))
+    }
+
+    // The price converted with the exchange rates admins have set; null when there's
+    // no rate to `currency`.
+    async fn price_in(
+        &self,
+        context: &Context<'_>,
+        currency: String,
+    ) -> FieldResult<Option<Money>> {
+        let Some(price_minor) = self.price_minor else {
+            return Ok(None);
+        };
+
+        get_exchange_rate_service(context)?
+            .convert(&Money::new(price_minor, &self.currency), &currency)
+            .await
+            .map_err(|error| AppError::from_service_error("convert tour price", error).extend())
+    }
+
+    async fn departures(
+        &self,
+        context: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<Departure>> {
+        get_departure_service(context)?
+            .get_tour_departures(self.id, from, to, first.map(|first| first as usize))
+            .await
+            .map_err(|error| AppError::from_service_error("get tour departures", error).extend())
+    }
+
+    // Categories offered besides adults.
+    async fn price_categories(&self, context: &Context<'_>) -> FieldResult<Vec<TourPriceCategory>> {
+        get_pricing_service(context)?
+            .get_tour_price_categories(self.id)
+            .await
+            .map_err(|error| {
+                AppError::from_service_error("get tour price categories", error).extend()
+            })
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn pricing_rules(&self, context: &Context<'_>) -> FieldResult<Vec<PricingRule>> {
+        get_pricing_service(context)?
+            .get_tour_pricing_rules(self.id)
+            .await
+            .map_err(|error| AppError::from_service_error("get tour pricing rules", error).extend())
+    }
+
+    async fn schedules(&self, context: &Context<'_>) -> FieldResult<Vec<DepartureSchedule>> {
+        get_schedule_service(context)?
+            .get_tour_schedules(self.id)
+            .await
+            .map_err(|error| AppError::from_service_error("get tour schedules", error).extend())
+    }
+
+    async fn blackout_calendars(
+        &self,
+        context: &Context<'_>,
+    ) -> FieldResult<Vec<BlackoutCalendar>> {
+        get_schedule_service(context)?
+            .get_tour_calendars(self.id)
+            .await
+            .map_err(|error| AppError::from_service_error("get tour calendars", error).extend())
+    }
+}
+
+#[ComplexObject]
+impl DepartureSchedule {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::DepartureSchedule, self.id).encode()
+    }
+
+    async fn price(&self, context: &Context<'_>) -> FieldResult<Option<Money>> {
+        let Some(price_minor) = self.price_minor else {
+            return Ok(None);
+        };
+
+        let currency = get_tour_currency(context, self.tour_id).await?;
+        Ok(Some(Money::new(price_minor, &currency)))
+    }
+}
+
+#[ComplexObject]
+impl BlackoutCalendar {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::BlackoutCalendar, self.id).encode()
+    }
+
+    async fn dates(&self, context: &Context<'_>) -> FieldResult<Vec<BlackoutDate>> {
+        get_schedule_service(context)?
+            .get_blackout_dates(self.id)
+            .await
+            .map_err(|error| AppError::from_service_error("get blackout dates", error).extend())
+    }
+}
+
+#[ComplexObject]
+impl BlackoutDate {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::BlackoutDate, self.id).encode()
+    }
+}
+
+#[ComplexObject]
+impl Departure {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::Departure, self.id).encode()
+    }
+
+    async fn tour(&self, context: &Context<'_>) -> FieldResult<Option<Tour>> {
+        get_tour_service(context)?
+            .get_tour(self.tour_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get departure tour", error).extend())
+    }
+
+    // Null when the tour's price applies.
+    async fn price(&self, context: &Context<'_>) -> FieldResult<Option<Money>> {
+        let Some(price_minor) = self.price_minor else {
+            return Ok(None);
+        };
+
+        let currency = get_tour_currency(context, self.tour_id).await?;
+        Ok(Some(Money::new(price_minor, &currency)))
+    }
+
+    // Null when neither the departure nor its tour limits participants.
+    async fn available_seats(&self, context: &Context<'_>) -> FieldResult<Option<i32>> {
+        get_departure_service(context)?
+            .get_available_seats(self.id)
+            .await
+            .map(|available_seats| available_seats.map(|available_seats| available_seats as i32))
+            .map_err(|error| AppError::from_service_error("get available seats", error).extend())
+    }
+
+    // The tour's IANA time zone, which `localStartsAt` and `localEndsAt` are in.
+    async fn timezone(&self, context: &Context<'_>) -> FieldResult<String> {
+        Ok(get_tour_time_zone(context, self.tour_id)
+            .await?
+            .name()
+            .to_string())
+    }
+
+    async fn local_starts_at(&self, context: &Context<'_>) -> FieldResult<NaiveDateTime> {
+        let timezone = get_tour_time_zone(context, self.tour_id).await?;
+        Ok(self.starts_at.with_timezone(&timezone).naive_local())
+    }
+
+    async fn local_ends_at(&self, context: &Context<'_>) -> FieldResult<NaiveDateTime> {
+        let timezone = get_tour_time_zone(context, self.tour_id).await?;
+        Ok(self.ends_at.with_timezone(&timezone).naive_local())
+    }
+}
+
+#[ComplexObject]
+impl Booking {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::Booking, self.id).encode()
+    }
+
+    async fn departure(&self, context: &Context<'_>) -> FieldResult<Option<Departure>> {
+        get_departure_service(context)?
+            .get_departure(self.departure_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get booking departure", error).extend())
+    }
+
+    async fn price(&self) -> Money {
+        Money::new(self.price_minor, &self.currency)
+    }
+
+    // The lines of the quote the booking was made with.
+    async fn price_lines(&self, context: &Context<'_>) -> FieldResult<Vec<PriceLine>> {
+        get_booking_service(context)?
+            .get_price_lines(self.id)
+            .await
+            .map_err(|error| {
+                AppError::from_service_error("get booking price lines", error).extend()
+            })
+    }
+
+    async fn payments(&self, context: &Context<'_>) -> FieldResult<Vec<Payment>> {
+        get_payment_service(context)?
+            .get_booking_payments(self.id)
+            .await
+            .map_err(|error| AppError::from_service_error("get booking payments", error).extend())
+    }
+}
+
+#[ComplexObject]
+impl Payment {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::Payment, self.id).encode()
+    }
+
+    async fn amount(&self) -> Money {
+        Money::new(self.amount_minor, &self.currency)
+    }
+
+    async fn booking(&self, context: &Context<'_>) -> FieldResult<Option<Booking>> {
+        get_booking_service(context)?
+            .get_booking(self.booking_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get payment booking", error).extend())
+    }
+}
+
+#[ComplexObject]
+impl WaitlistEntry {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::WaitlistEntry, self.id).encode()
+    }
+
+    async fn departure(&self, context: &Context<'_>) -> FieldResult<Option<Departure>> {
+        get_departure_service(context)?
+            .get_departure(self.departure_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get waitlist departure", error).extend())
+    }
+
+    // Null unless the entry is still waiting.
+    async fn position(&self, context: &Context<'_>) -> FieldResult<Option<i32>> {
+        get_waitlist_service(context)?
+            .get_position(self)
+            .await
+            .map(|position| position.map(|position| position as i32))
+            .map_err(|error| AppError::from_service_error("get waitlist position", error).extend())
+    }
+
+    // Null unless seats are currently offered.
+    async fn claim_url(&self, context: &Context<'_>) -> FieldResult<Option<String>> {
+        Ok(get_waitlist_service(context)?.claim_url(self))
+    }
+}
+
+#[ComplexObject]
+impl TourPriceCategory {
+    // Null when the category pays the adult price.
+    async fn price(&self, context: &Context<'_>) -> FieldResult<Option<Money>> {
+        let Some(price_minor) = self.price_minor else {
+            return Ok(None);
+        };
+
+        let currency = get_tour_currency(context, self.tour_id).await?;
+        Ok(Some(Money::new(price_minor, &currency)))
+    }
+}
+
+#[ComplexObject]
+impl PricingRule {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::PricingRule, self.id).encode()
+    }
+
+    // Per participant; null when the rule adjusts by `percent`.
+    async fn amount(&self, context: &Context<'_>) -> FieldResult<Option<Money>> {
+        let Some(amount_minor) = self.amount_minor else {
+            return Ok(None);
+        };
+
+        let currency = get_tour_currency(context, self.tour_id).await?;
+        Ok(Some(Money::new(amount_minor, &currency)))
+    }
+}
+
+#[ComplexObject]
+impl Quote {
+    async fn departure(&self, context: &Context<'_>) -> FieldResult<Option<Departure>> {
+        get_departure_service(context)?
+            .get_departure(self.departure_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get quote departure", error).extend())
+    }
+
+    async fn total(&self) -> Money {
+        Money::new(self.total_minor, &self.currency)
+    }
+}
+
+#[ComplexObject]
+impl PriceLine {
+    async fn unit_amount(&self) -> Money {
+        Money::new(self.unit_amount_minor, &self.currency)
+    }
+
+    async fn amount(&self) -> Money {
+        Money::new(self.amount_minor, &self.currency)
+    }
+
+    // Null for base and discount lines, and for rules that have since been deleted.
+    async fn pricing_rule_id(&self) -> Option<ID> {
+        self.pricing_rule_id
+            .map(|id| GlobalId::new(NodeType::PricingRule, id).encode())
+    }
+}
+
+#[ComplexObject]
+impl Promotion {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::Promotion, self.id).encode()
+    }
+
+    // Null when the promotion takes `percent` off.
+    async fn amount(&self) -> Option<Money> {
+        self.amount_minor
+            .zip(self.currency.as_deref())
+            .map(|(amount_minor, currency)| Money::new(amount_minor, currency))
+    }
+
+    async fn min_spend(&self) -> Option<Money> {
+        self.min_spend_minor
+            .zip(self.currency.as_deref())
+            .map(|(min_spend_minor, currency)| Money::new(min_spend_minor, currency))
+    }
+
+    // Redemptions that count towards the limits.
+    async fn redemption_count(&self, context: &Context<'_>) -> FieldResult<i64> {
+        get_promotion_service(context)?
+            .get_redemption_count(self.id)
+            .await
+            .map_err(|error| {
+                AppError::from_service_error("get promotion redemption count", error).extend()
+            })
+    }
+
+    // Newest first, including those of cancelled bookings.
+    async fn redemptions(
+        &self,
+        context: &Context<'_>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<PromotionRedemption>> {
+        get_promotion_service(context)?
+            .get_redemptions(self.id, first.map(|first| first as usize))
+            .await
+            .map_err(|error| {
+                AppError::from_service_error("get promotion redemptions", error).extend()
+            })
+    }
+}
+
+#[ComplexObject]
+impl PromotionRedemption {
+    async fn discount(&self) -> Money {
+        Money::new(self.discount_minor, &self.currency)
+    }
+
+    async fn booking(&self, context: &Context<'_>) -> FieldResult<Option<Booking>> {
+        get_booking_service(context)?
+            .get_booking(self.booking_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get redemption booking", error).extend())
+    }
+}
+
+#[ComplexObject]
+impl Money {
+    // The amount in major units, e.g. "12.50" for 1250 cents.
+    async fn amount(&self) -> Decimal {
+        self.as_decimal()
+    }
+}
+
+async fn get_tour_currency(context: &Context<'_>, tour_id: i32) -> FieldResult<String> {
+    let tour = get_tour_service(context)?
+        .get_tour(tour_id)
+        .await
+        .map_err(|error| AppError::from_service_error("get tour currency", error).extend())?;
+
+    Ok(tour.map_or_else(|| DEFAULT_CURRENCY.to_string(), |tour| tour.currency))
+}
+
+async fn get_tour_time_zone(context: &Context<'_>, tour_id: i32) -> FieldResult<Tz> {
+    let tour = get_tour_service(context)?
+        .get_tour(tour_id)
+        .await
+        .map_err(|error| AppError::from_service_error("get tour time zone", error).extend())?;
+
+    Ok(tour.map_or(Tz::UTC, |tour| tour.time_zone()))
+}
+
+#[ComplexObject]
+impl SeatHold {
+    #[graphql(name = "id")]
+    pub async fn global_id(&self) -> ID {
+        GlobalId::new(NodeType::SeatHold, self.id).encode()
+    }
 
-fn get_database_pool<'a>(context: &Context<'a>) -> &'a sqlx::PgPool {
-    &context
+    async fn departure(&self, context: &Context<'_>) -> FieldResult<Option<Departure>> {
+        get_departure_service(context)?
+            .get_departure(self.departure_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get hold departure", error).extend())
+    }
+
+    async fn booking(&self, context: &Context<'_>) -> FieldResult<Option<Booking>> {
+        let Some(booking_id) = self.booking_id else {
+            return Ok(None);
+        };
+
+        get_booking_service(context)?
+            .get_booking(booking_id)
+            .await
+            .map_err(|error| AppError::from_service_error("get hold booking", error).extend())
+    }
+}
+
+fn get_application_data<'a>(context: &Context<'a>) -> FieldResult<&'a ApplicationData> {
+    context
         .data::<Data<ApplicationData>>()
-        .unwrap()
-        .database_pool
+        .map(|application_data| application_data.get_ref())
+        .map_err(|error| AppError::internal("get application data", error.message).extend())
+}
+
+fn get_tour_service<'a>(context: &Context<'a>) -> FieldResult<&'a TourService> {
+    Ok(&get_application_data(context)?.tour_service)
+}
+
+fn get_place_service<'a>(context: &Context<'a>) -> FieldResult<&'a PlaceService> {
+    Ok(&get_application_data(context)?.place_service)
+}
+
+fn get_departure_service<'a>(context: &Context<'a>) -> FieldResult<&'a DepartureService> {
+    Ok(&get_application_data(context)?.departure_service)
+}
+
+fn get_schedule_service<'a>(context: &Context<'a>) -> FieldResult<&'a ScheduleService> {
+    Ok(&get_application_data(context)?.schedule_service)
+}
+
+fn get_booking_service<'a>(context: &Context<'a>) -> FieldResult<&'a BookingService> {
+    Ok(&get_application_data(context)?.booking_service)
+}
+
+fn get_waitlist_service<'a>(context: &Context<'a>) -> FieldResult<&'a WaitlistService> {
+    Ok(&get_application_data(context)?.waitlist_service)
+}
+
+fn get_payment_service<'a>(context: &Context<'a>) -> FieldResult<&'a PaymentService> {
+    Ok(&get_application_data(context)?.payment_service)
+}
+
+fn get_exchange_rate_service<'a>(context: &Context<'a>) -> FieldResult<&'a ExchangeRateService> {
+    Ok(&get_application_data(context)?.exchange_rate_service)
+}
+
+fn get_pricing_service<'a>(context: &Context<'a>) -> FieldResult<&'a PricingService> {
+    Ok(&get_application_data(context)?.pricing_service)
+}
+
+fn get_promotion_service<'a>(context: &Context<'a>) -> FieldResult<&'a PromotionService> {
+    Ok(&get_application_data(context)?.promotion_service)
+}
+
+fn get_event_service<'a>(context: &Context<'a>) -> FieldResult<&'a EventService> {
+    Ok(&get_application_data(context)?.event_service)
 }
 
 #[Object]
 impl QueryRoot {
+    #[graphql(deprecation = "Use `toursConnection` instead")]
     async fn get_tours(&self, context: &Context<'_>) -> FieldResult<Vec<Tour>> {
-        match sqlx::query_as::<_, Tour>(
-            r#"
-            SELECT * FROM public."Tours"
-            "#,
+        get_tour_service(context)?
+            .get_tours(context.data_opt::<CurrentUser>())
+            .await
+            .map_err(|error| AppError::from_service_error("get tours", error).extend())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn tours_connection(
+        &self,
+        context: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+        filter: Option<TourFilter>,
+        sort: Option<TourSort>,
+    ) -> FieldResult<Connection<TourCursor, Tour, TourConnectionFields>> {
+        let sort = sort.unwrap_or_default();
+
+        connection::query(
+            after,
+            before,
+            first,
+            last,
+            |after, before, first, last| async move {
+                let page = get_tour_service(context)?
+                    .get_tours_page(
+                        context.data_opt::<CurrentUser>(),
+                        filter.unwrap_or_default(),
+                        sort,
+                        PageRequest {
+                            after,
+                            before,
+                            first,
+                            last,
+                        },
+                    )
+                    .await
+                    .map_err(|error| {
+                        AppError::from_service_error("get tours page", error).extend()
+                    })?;
+
+                let mut connection = Connection::with_additional_fields(
+                    page.has_previous_page,
+                    page.has_next_page,
+                    TourConnectionFields {
+                        total_count: page.total_count,
+                    },
+                );
+                connection.edges.extend(
+                    page.items
+                        .into_iter()
+                        .map(|tour| Edge::new(TourCursor::new(&tour, sort), tour)),
+                );
+
+                Ok::<_, async_graphql::Error>(connection)
+            },
         )
-        .fetch_all(get_database_pool(context))
         .await
-        {
-            Ok(tours) => Ok(tours),
-            Err(error) => {
-                error!("Failed to get tours: {}", error);
-                Err(async_graphql::Error::new(format!(
-                    "Failed to get tours: {}",
-                    error
-                )))
-            }
-        }
+    }
+
+    async fn search_tours(
+        &self,
+        context: &Context<'_>,
+        query: String,
+        language: Option<String>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<TourSearchResult>> {
+        get_tour_service(context)?
+            .search_tours(
+                context.data_opt::<CurrentUser>(),
+                &query,
+                language.as_deref(),
+                first.map(|first| first as usize),
+            )
+            .await
+            .map_err(|error| AppError::from_service_error("search tours", error).extend())
+    }
+
+    async fn nearby_tours(
+        &self,
+        context: &Context<'_>,
+        lat: f64,
+        lng: f64,
+        radius_km: f64,
+        filter: Option<TourFilter>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<NearbyTour>> {
+        get_tour_service(context)?
+            .get_nearby_tours(
+                context.data_opt::<CurrentUser>(),
+                lat,
+                lng,
+                radius_km,
+                filter.unwrap_or_default(),
+                first.map(|first| first as usize),
+            )
+            .await
+            .map_err(|error| AppError::from_service_error("get nearby tours", error).extend())
+    }
+
+    async fn tours_in_bounding_box(
+        &self,
+        context: &Context<'_>,
+        bounding_box: BoundingBox,
+        filter: Option<TourFilter>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<Tour>> {
+        get_tour_service(context)?
+            .get_tours_in_bounding_box(
+                context.data_opt::<CurrentUser>(),
+                bounding_box,
+                filter.unwrap_or_default(),
+                first.map(|first| first as usize),
+            )
+            .await
+            .map_err(|error| {
+                AppError::from_service_error("get tours in bounding box", error).extend()
+            })
+    }
+
+    async fn suggest(
+        &self,
+        context: &Context<'_>,
+        prefix: String,
+        kind: Option<SuggestionKind>,
+        #[graphql(validator(minimum = 0))] limit: Option<i32>,
+    ) -> FieldResult<Vec<Suggestion>> {
+        get_tour_service(context)?
+            .suggest(&prefix, kind, limit.map(|limit| limit as usize))
+            .await
+            .map_err(|error| AppError::from_service_error("suggest", error).extend())
+    }
+
+    async fn places(
+        &self,
+        context: &Context<'_>,
+        query: Option<String>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<Place>> {
+        get_place_service(context)?
+            .get_places(query, first.map(|first| first as usize))
+            .await
+            .map_err(|error| AppError::from_service_error("get places", error).extend())
+    }
+
+    async fn place(&self, context: &Context<'_>, id: ID) -> FieldResult<Option<Place>> {
+        get_place_service(context)?
+            .get_place(decode_id(&id, NodeType::Place)?)
+            .await
+            .map_err(|error| AppError::from_service_error("get place", error).extend())
+    }
+
+    async fn place_by_slug(
+        &self,
+        context: &Context<'_>,
+        slug: String,
+    ) -> FieldResult<Option<Place>> {
+        get_place_service(context)?
+            .get_place_by_slug(&slug)
+            .await
+            .map_err(|error| AppError::from_service_error("get place by slug", error).extend())
+    }
+
+    async fn tour(&self, context: &Context<'_>, id: ID) -> FieldResult<Option<Tour>> {
+        get_tour_service(context)?
+            .get_tour(decode_id(&id, NodeType::Tour)?)
+            .await
+            .map_err(|error| AppError::from_service_error("get tour", error).extend())
+    }
+
+    async fn tour_by_slug(&self, context: &Context<'_>, slug: String) -> FieldResult<Option<Tour>> {
+        get_tour_service(context)?
+            .get_tour_by_slug(&slug)
+            .await
+            .map_err(|error| AppError::from_service_error("get tour by slug", error).extend())
+    }
+
+    async fn node(&self, context: &Context<'_>, id: ID) -> FieldResult<Option<Node>> {
+        Ok(load_nodes(context, "id", &[id]).await?.pop().flatten())
+    }
+
+    async fn nodes(
+        &self,
+        context: &Context<'_>,
+        #[graphql(validator(max_items = 100))] ids: Vec<ID>,
+    ) -> FieldResult<Vec<Option<Node>>> {
+        load_nodes(context, "ids", &ids).await
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn blackout_calendars(
+        &self,
+        context: &Context<'_>,
+    ) -> FieldResult<Vec<BlackoutCalendar>> {
+        get_schedule_service(context)?
+            .get_calendars()
+            .await
+            .map_err(|error| AppError::from_service_error("get blackout calendars", error).extend())
+    }
+
+    async fn exchange_rates(&self, context: &Context<'_>) -> FieldResult<Vec<ExchangeRate>> {
+        get_exchange_rate_service(context)?
+            .get_exchange_rates()
+            .await
+            .map_err(|error| AppError::from_service_error("get exchange rates", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn promotions(&self, context: &Context<'_>) -> FieldResult<Vec<Promotion>> {
+        get_promotion_service(context)?
+            .get_promotions()
+            .await
+            .map_err(|error| AppError::from_service_error("get promotions", error).extend())
+    }
+
+    // What booking the departure for `participants` would cost now, with
+    // `promoCode` applied. Its per-user limit is only checked when signed in.
+    async fn quote(
+        &self,
+        context: &Context<'_>,
+        departure_id: ID,
+        participants: Vec<ParticipantsInput>,
+        promo_code: Option<String>,
+    ) -> FieldResult<Quote> {
+        let current_user = context.data_opt::<CurrentUser>();
+
+        get_pricing_service(context)?
+            .quote(
+                decode_id(&departure_id, NodeType::Departure)?,
+                &participants,
+                promo_code.as_deref(),
+                current_user.map(|user| user.id.as_str()),
+            )
+            .await
+            .map_err(|error| AppError::from_service_error("quote", error).extend())
+    }
+
+    async fn my_bookings(
+        &self,
+        context: &Context<'_>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<Booking>> {
+        let current_user = get_current_user(context)?;
+
+        get_booking_service(context)?
+            .get_user_bookings(current_user, first.map(|first| first as usize))
+            .await
+            .map_err(|error| AppError::from_service_error("get bookings", error).extend())
+    }
+
+    async fn my_waitlist_entries(
+        &self,
+        context: &Context<'_>,
+        #[graphql(validator(minimum = 0))] first: Option<i32>,
+    ) -> FieldResult<Vec<WaitlistEntry>> {
+        let current_user = get_current_user(context)?;
+
+        get_waitlist_service(context)?
+            .get_user_waitlist_entries(current_user, first.map(|first| first as usize))
+            .await
+            .map_err(|error| AppError::from_service_error("get waitlist entries", error).extend())
     }
 }
 
@@ -60,114 +823,1114 @@ pub struct MutationRoot;
 
 #[Object]
 impl MutationRoot {
+    #[graphql(guard = "AdminGuard")]
     async fn create_tour(
         &self,
         context: &Context<'_>,
-        title: String,
-        description: Option<String>,
-        start_date: Option<NaiveDate>,
-        end_date: Option<NaiveDate>,
-        price: Option<f64>,
-        rating: Option<f64>,
-        location: Option<String>,
-        image_url: Option<String>,
-        is_active: bool,
-        max_participants: Option<i32>,
+        input: CreateTourInput,
     ) -> FieldResult<Tour> {
-        let now = Utc::now().naive_utc();
-
-        match sqlx::query_as::<_, Tour>(
-            r#"
-            INSERT INTO tours (title, description, start_date, end_date, price, rating, location, image_url, is_active, max_participants, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
-            RETURNING *
-            "#
-        )
-        .bind(title)
-        .bind(description)
-        .bind(start_date)
-        .bind(end_date)
-        .bind(price)
-        .bind(rating)
-        .bind(location)
-        .bind(image_url)
-        .bind(is_active)
-        .bind(max_participants)
-        .bind(now)
-        .bind(now)
-        .fetch_one(get_database_pool(context))
-        .await {
-            Ok(tour) => Ok(tour),
-            Err(error) => {
-                error!("Failed to create tour: {}", error);
-                Err(async_graphql::Error::new(format!(
-                    "Failed to create tour: {}",
-                    error
-                )))
-            }
-        }
+        get_tour_service(context)?
+            .create_tour(input)
+            .await
+            .map_err(|error| AppError::from_service_error("create tour", error).extend())
     }
 
+    #[graphql(guard = "AdminGuard")]
     async fn update_tour(
         &self,
         context: &Context<'_>,
-        id: i32,
-        title: Option<String>,
-        description: Option<String>,
-        start_date: Option<NaiveDate>,
-        end_date: Option<NaiveDate>,
-        price: Option<f64>,
-        rating: Option<f64>,
-        location: Option<String>,
-        image_url: Option<String>,
-        is_active: Option<bool>,
-        max_participants: Option<i32>,
+        id: ID,
+        input: UpdateTourInput,
     ) -> FieldResult<Tour> {
-        let now = Utc::now().naive_utc();
-
-        match sqlx::query_as::<_, Tour>(
-            r#"
-            UPDATE tours
-            SET title = COALESCE($2, title),
-                description = COALESCE($3, description),
-                start_date = COALESCE($4, start_date),
-                end_date = COALESCE($5, end_date),
-                price = COALESCE($6, price),
-                rating = COALESCE($7, rating),
-                location = COALESCE($8, location),
-                image_url = COALESCE($9, image_url),
-                is_active = COALESCE($10, is_active),
-                max_participants = COALESCE($11, max_participants),
-                updated_at = $12
-            WHERE id = $1
-            RETURNING *
-            "#,
-        )
-        .bind(id)
-        .bind(title)
-        .bind(description)
-        .bind(start_date)
-        .bind(end_date)
-        .bind(price)
-        .bind(rating)
-        .bind(location)
-        .bind(image_url)
-        .bind(is_active)
-        .bind(max_participants)
-        .bind(now)
-        .fetch_one(get_database_pool(context))
-        .await
-        {
-            Ok(tour) => Ok(tour),
-            Err(error) => {
-                error!("Failed to update tour: {}", error);
-                Err(async_graphql::Error::new(format!(
-                    "Failed to update tour: {}",
-                    error
-                )))
+        let changes_time_zone = input.timezone.is_some();
+        let tour = get_tour_service(context)?
+            .update_tour(decode_id(&id, NodeType::Tour)?, input)
+            .await
+            .map_err(|error| AppError::from_service_error("update tour", error).extend())?;
+
+        // Scheduled departures keep their local times in the new time zone.
+        if changes_time_zone {
+            get_schedule_service(context)?
+                .materialize(Some(&[tour.id]))
+                .await
+                .map_err(|error| {
+                    AppError::from_service_error("materialize departures", error).extend()
+                })?;
+        }
+
+        Ok(tour)
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn create_place(
+        &self,
+        context: &Context<'_>,
+        input: CreatePlaceInput,
+    ) -> FieldResult<Place> {
+        get_place_service(context)?
+            .create_place(input)
+            .await
+            .map_err(|error| AppError::from_service_error("create place", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn update_place(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+        input: UpdatePlaceInput,
+    ) -> FieldResult<Place> {
+        get_place_service(context)?
+            .update_place(decode_id(&id, NodeType::Place)?, input)
+            .await
+            .map_err(|error| AppError::from_service_error("update place", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn delete_place(&self, context: &Context<'_>, id: ID) -> FieldResult<bool> {
+        get_place_service(context)?
+            .delete_place(decode_id(&id, NodeType::Place)?)
+            .await
+            .map(|_| true)
+            .map_err(|error| AppError::from_service_error("delete place", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn add_departure(
+        &self,
+        context: &Context<'_>,
+        tour_id: ID,
+        input: CreateDepartureInput,
+    ) -> FieldResult<Departure> {
+        let tour_id = decode_id(&tour_id, NodeType::Tour)?;
+        let currency = get_tour_currency(context, tour_id).await?;
+
+        get_departure_service(context)?
+            .add_departure(tour_id, &currency, input)
+            .await
+            .map_err(|error| AppError::from_service_error("add departure", error).extend())
+    }
+
+    // Owners of affected bookings are notified through the event stream.
+    #[graphql(guard = "AdminGuard")]
+    async fn reschedule_departure(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+        input: RescheduleDepartureInput,
+    ) -> FieldResult<Departure> {
+        get_departure_service(context)?
+            .reschedule_departure(decode_id(&id, NodeType::Departure)?, input)
+            .await
+            .map_err(|error| AppError::from_service_error("reschedule departure", error).extend())
+    }
+
+    // Also cancels the departure's bookings and notifies their owners.
+    #[graphql(guard = "AdminGuard")]
+    async fn cancel_departure(&self, context: &Context<'_>, id: ID) -> FieldResult<Departure> {
+        get_departure_service(context)?
+            .cancel_departure(decode_id(&id, NodeType::Departure)?)
+            .await
+            .map_err(|error| AppError::from_service_error("cancel departure", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn create_departure_schedule(
+        &self,
+        context: &Context<'_>,
+        tour_id: ID,
+        input: CreateDepartureScheduleInput,
+    ) -> FieldResult<DepartureSchedule> {
+        let tour_id = decode_id(&tour_id, NodeType::Tour)?;
+        let currency = get_tour_currency(context, tour_id).await?;
+
+        get_schedule_service(context)?
+            .create_schedule(tour_id, &currency, input)
+            .await
+            .map_err(|error| AppError::from_service_error("create schedule", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn update_departure_schedule(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+        input: UpdateDepartureScheduleInput,
+    ) -> FieldResult<DepartureSchedule> {
+        let schedule_service = get_schedule_service(context)?;
+        let schedule = schedule_service
+            .get_schedule(decode_id(&id, NodeType::DepartureSchedule)?)
+            .await
+            .map_err(|error| AppError::from_service_error("get schedule", error).extend())?
+            .ok_or_else(|| AppError::NotFound.extend())?;
+        let currency = get_tour_currency(context, schedule.tour_id).await?;
+
+        schedule_service
+            .update_schedule(schedule.id, &currency, input)
+            .await
+            .map_err(|error| AppError::from_service_error("update schedule", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn delete_departure_schedule(&self, context: &Context<'_>, id: ID) -> FieldResult<bool> {
+        get_schedule_service(context)?
+            .delete_schedule(decode_id(&id, NodeType::DepartureSchedule)?)
+            .await
+            .map(|_| true)
+            .map_err(|error| AppError::from_service_error("delete schedule", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn materialize_departures(
+        &self,
+        context: &Context<'_>,
+        tour_id: Option<ID>,
+    ) -> FieldResult<MaterializationSummary> {
+        let tour_id = tour_id
+            .map(|tour_id| decode_id(&tour_id, NodeType::Tour))
+            .transpose()?;
+
+        get_schedule_service(context)?
+            .materialize(tour_id.as_ref().map(std::slice::from_ref))
+            .await
+            .map_err(|error| AppError::from_service_error("materialize departures", error).extend())
+    }
+
+    // `rate` is how many units of `quoteCurrency` one unit of `baseCurrency` buys.
+    #[graphql(guard = "AdminGuard")]
+    async fn set_exchange_rate(
+        &self,
+        context: &Context<'_>,
+        base_currency: String,
+        quote_currency: String,
+        rate: Decimal,
+    ) -> FieldResult<ExchangeRate> {
+        get_exchange_rate_service(context)?
+            .set_exchange_rate(&base_currency, &quote_currency, rate)
+            .await
+            .map_err(|error| AppError::from_service_error("set exchange rate", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn delete_exchange_rate(
+        &self,
+        context: &Context<'_>,
+        base_currency: String,
+        quote_currency: String,
+    ) -> FieldResult<bool> {
+        get_exchange_rate_service(context)?
+            .delete_exchange_rate(&base_currency, &quote_currency)
+            .await
+            .map(|_| true)
+            .map_err(|error| AppError::from_service_error("delete exchange rate", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn create_blackout_calendar(
+        &self,
+        context: &Context<'_>,
+        name: String,
+    ) -> FieldResult<BlackoutCalendar> {
+        get_schedule_service(context)?
+            .create_calendar(&name)
+            .await
+            .map_err(|error| {
+                AppError::from_service_error("create blackout calendar", error).extend()
+            })
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn delete_blackout_calendar(&self, context: &Context<'_>, id: ID) -> FieldResult<bool> {
+        get_schedule_service(context)?
+            .delete_calendar(decode_id(&id, NodeType::BlackoutCalendar)?)
+            .await
+            .map(|_| true)
+            .map_err(|error| {
+                AppError::from_service_error("delete blackout calendar", error).extend()
+            })
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn add_blackout_dates(
+        &self,
+        context: &Context<'_>,
+        calendar_id: ID,
+        #[graphql(validator(min_items = 1, max_items = 366))] dates: Vec<BlackoutDateInput>,
+    ) -> FieldResult<Vec<BlackoutDate>> {
+        get_schedule_service(context)?
+            .add_blackout_dates(decode_id(&calendar_id, NodeType::BlackoutCalendar)?, dates)
+            .await
+            .map_err(|error| AppError::from_service_error("add blackout dates", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn remove_blackout_date(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+    ) -> FieldResult<BlackoutDate> {
+        get_schedule_service(context)?
+            .remove_blackout_date(decode_id(&id, NodeType::BlackoutDate)?)
+            .await
+            .map_err(|error| AppError::from_service_error("remove blackout date", error).extend())
+    }
+
+    // Replaces the calendars whose blackout dates apply to the tour.
+    #[graphql(guard = "AdminGuard")]
+    async fn set_tour_blackout_calendars(
+        &self,
+        context: &Context<'_>,
+        tour_id: ID,
+        calendar_ids: Vec<ID>,
+    ) -> FieldResult<Vec<BlackoutCalendar>> {
+        let calendar_ids = calendar_ids
+            .iter()
+            .map(|id| decode_id(id, NodeType::BlackoutCalendar))
+            .collect::<FieldResult<Vec<_>>>()?;
+
+        get_schedule_service(context)?
+            .set_tour_calendars(decode_id(&tour_id, NodeType::Tour)?, &calendar_ids)
+            .await
+            .map_err(|error| AppError::from_service_error("set tour calendars", error).extend())
+    }
+
+    // `price` null means the category pays the adult price.
+    #[graphql(guard = "AdminGuard")]
+    async fn set_tour_price_category(
+        &self,
+        context: &Context<'_>,
+        tour_id: ID,
+        category: PriceCategory,
+        price: Option<Decimal>,
+    ) -> FieldResult<TourPriceCategory> {
+        let tour_id = decode_id(&tour_id, NodeType::Tour)?;
+        let currency = get_tour_currency(context, tour_id).await?;
+
+        get_pricing_service(context)?
+            .set_tour_price_category(tour_id, &currency, category, price)
+            .await
+            .map_err(|error| {
+                AppError::from_service_error("set tour price category", error).extend()
+            })
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn remove_tour_price_category(
+        &self,
+        context: &Context<'_>,
+        tour_id: ID,
+        category: PriceCategory,
+    ) -> FieldResult<bool> {
+        get_pricing_service(context)?
+            .remove_tour_price_category(decode_id(&tour_id, NodeType::Tour)?, category)
+            .await
+            .map(|_| true)
+            .map_err(|error| {
+                AppError::from_service_error("remove tour price category", error).extend()
+            })
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn create_pricing_rule(
+        &self,
+        context: &Context<'_>,
+        tour_id: ID,
+        input: CreatePricingRuleInput,
+    ) -> FieldResult<PricingRule> {
+        let tour_id = decode_id(&tour_id, NodeType::Tour)?;
+        let currency = get_tour_currency(context, tour_id).await?;
+
+        get_pricing_service(context)?
+            .create_pricing_rule(tour_id, &currency, input)
+            .await
+            .map_err(|error| AppError::from_service_error("create pricing rule", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn update_pricing_rule(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+        input: UpdatePricingRuleInput,
+    ) -> FieldResult<PricingRule> {
+        let pricing_service = get_pricing_service(context)?;
+        let rule = pricing_service
+            .get_pricing_rule(decode_id(&id, NodeType::PricingRule)?)
+            .await
+            .map_err(|error| AppError::from_service_error("get pricing rule", error).extend())?
+            .ok_or_else(|| AppError::NotFound.extend())?;
+        let currency = get_tour_currency(context, rule.tour_id).await?;
+
+        pricing_service
+            .update_pricing_rule(rule.id, &currency, input)
+            .await
+            .map_err(|error| AppError::from_service_error("update pricing rule", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn delete_pricing_rule(&self, context: &Context<'_>, id: ID) -> FieldResult<bool> {
+        get_pricing_service(context)?
+            .delete_pricing_rule(decode_id(&id, NodeType::PricingRule)?)
+            .await
+            .map(|_| true)
+            .map_err(|error| AppError::from_service_error("delete pricing rule", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn create_promotion(
+        &self,
+        context: &Context<'_>,
+        input: CreatePromotionInput,
+    ) -> FieldResult<Promotion> {
+        get_promotion_service(context)?
+            .create_promotion(input)
+            .await
+            .map_err(|error| AppError::from_service_error("create promotion", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn update_promotion(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+        input: UpdatePromotionInput,
+    ) -> FieldResult<Promotion> {
+        get_promotion_service(context)?
+            .update_promotion(decode_id(&id, NodeType::Promotion)?, input)
+            .await
+            .map_err(|error| AppError::from_service_error("update promotion", error).extend())
+    }
+
+    // Only promotions that were never redeemed can be deleted.
+    #[graphql(guard = "AdminGuard")]
+    async fn delete_promotion(&self, context: &Context<'_>, id: ID) -> FieldResult<bool> {
+        get_promotion_service(context)?
+            .delete_promotion(decode_id(&id, NodeType::Promotion)?)
+            .await
+            .map(|_| true)
+            .map_err(|error| AppError::from_service_error("delete promotion", error).extend())
+    }
+
+    // Books at the price `quote` returns for the same participants and promo code.
+    async fn book_tour(
+        &self,
+        context: &Context<'_>,
+        departure_id: ID,
+        participants: Vec<ParticipantsInput>,
+        promo_code: Option<String>,
+    ) -> FieldResult<Booking> {
+        let current_user = get_current_user(context)?;
+
+        get_booking_service(context)?
+            .book_tour(
+                current_user,
+                decode_id(&departure_id, NodeType::Departure)?,
+                participants,
+                promo_code,
+            )
+            .await
+            .map_err(|error| AppError::from_service_error("book tour", error).extend())
+    }
+
+    async fn cancel_booking(&self, context: &Context<'_>, id: ID) -> FieldResult<Booking> {
+        let current_user = get_current_user(context)?;
+
+        get_booking_service(context)?
+            .cancel_booking(current_user, decode_id(&id, NodeType::Booking)?)
+            .await
+            .map_err(|error| AppError::from_service_error("cancel booking", error).extend())
+    }
+
+    // Reserves seats for the configured hold time; `convertSeatHold` books them.
+    async fn hold_seats(
+        &self,
+        context: &Context<'_>,
+        departure_id: ID,
+        count: i32,
+    ) -> FieldResult<SeatHold> {
+        let current_user = get_current_user(context)?;
+
+        get_booking_service(context)?
+            .hold_seats(
+                current_user,
+                decode_id(&departure_id, NodeType::Departure)?,
+                count,
+            )
+            .await
+            .map_err(|error| AppError::from_service_error("hold seats", error).extend())
+    }
+
+    // `participants` must add up to the held seats; without it every seat is priced
+    // as an adult.
+    async fn convert_seat_hold(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+        participants: Option<Vec<ParticipantsInput>>,
+        promo_code: Option<String>,
+    ) -> FieldResult<Booking> {
+        let current_user = get_current_user(context)?;
+
+        get_booking_service(context)?
+            .convert_seat_hold(
+                current_user,
+                decode_id(&id, NodeType::SeatHold)?,
+                participants,
+                promo_code,
+            )
+            .await
+            .map_err(|error| AppError::from_service_error("convert seat hold", error).extend())
+    }
+
+    async fn release_seat_hold(&self, context: &Context<'_>, id: ID) -> FieldResult<SeatHold> {
+        let current_user = get_current_user(context)?;
+
+        get_booking_service(context)?
+            .release_seat_hold(current_user, decode_id(&id, NodeType::SeatHold)?)
+            .await
+            .map_err(|error| AppError::from_service_error("release seat hold", error).extend())
+    }
+
+    // Only for departures that can't seat the party right now.
+    async fn join_waitlist(
+        &self,
+        context: &Context<'_>,
+        departure_id: ID,
+        participants: i32,
+    ) -> FieldResult<WaitlistEntry> {
+        let current_user = get_current_user(context)?;
+
+        get_waitlist_service(context)?
+            .join_waitlist(
+                current_user,
+                decode_id(&departure_id, NodeType::Departure)?,
+                participants,
+            )
+            .await
+            .map_err(|error| AppError::from_service_error("join waitlist", error).extend())
+    }
+
+    async fn leave_waitlist(&self, context: &Context<'_>, id: ID) -> FieldResult<WaitlistEntry> {
+        let current_user = get_current_user(context)?;
+
+        get_waitlist_service(context)?
+            .leave_waitlist(current_user, decode_id(&id, NodeType::WaitlistEntry)?)
+            .await
+            .map_err(|error| AppError::from_service_error("leave waitlist", error).extend())
+    }
+
+    // `token` comes from the claim link sent with the offer. `participants` and
+    // `promoCode` work as in `convertSeatHold`.
+    async fn claim_waitlist_offer(
+        &self,
+        context: &Context<'_>,
+        token: String,
+        participants: Option<Vec<ParticipantsInput>>,
+        promo_code: Option<String>,
+    ) -> FieldResult<Booking> {
+        let current_user = get_current_user(context)?;
+
+        get_waitlist_service(context)?
+            .claim_offer(current_user, &token, participants, promo_code)
+            .await
+            .map_err(|error| AppError::from_service_error("claim waitlist offer", error).extend())
+    }
+
+    // Authorizes the booking's price with the payment provider.
+    async fn pay_booking(&self, context: &Context<'_>, booking_id: ID) -> FieldResult<Payment> {
+        let current_user = get_current_user(context)?;
+
+        get_payment_service(context)?
+            .pay_booking(current_user, decode_id(&booking_id, NodeType::Booking)?)
+            .await
+            .map_err(|error| AppError::from_service_error("pay booking", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn capture_payment(&self, context: &Context<'_>, id: ID) -> FieldResult<Payment> {
+        get_payment_service(context)?
+            .capture_payment(decode_id(&id, NodeType::Payment)?)
+            .await
+            .map_err(|error| AppError::from_service_error("capture payment", error).extend())
+    }
+
+    #[graphql(guard = "AdminGuard")]
+    async fn refund_payment(&self, context: &Context<'_>, id: ID) -> FieldResult<Payment> {
+        get_payment_service(context)?
+            .refund_payment(decode_id(&id, NodeType::Payment)?)
+            .await
+            .map_err(|error| AppError::from_service_error("refund payment", error).extend())
+    }
+}
+
+fn decode_id(id: &ID, node_type: NodeType) -> FieldResult<i32> {
+    GlobalId::decode_as(id, node_type)
+        .map_err(|error| AppError::validation("id", error.to_string()).extend())
+}
+
+// Loads nodes with one query per type and returns them in the order requested.
+async fn load_nodes(
+    context: &Context<'_>,
+    field: &str,
+    ids: &[ID],
+) -> FieldResult<Vec<Option<Node>>> {
+    let global_ids = ids
+        .iter()
+        .map(|id| GlobalId::decode(id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| AppError::validation(field, error.to_string()).extend())?;
+    let ids_of = |node_type| {
+        global_ids
+            .iter()
+            .filter(|global_id| global_id.node_type == node_type)
+            .map(|global_id| global_id.id)
+            .collect::<Vec<_>>()
+    };
+
+    let mut nodes = HashMap::new();
+    let tour_ids = ids_of(NodeType::Tour);
+    if !tour_ids.is_empty() {
+        let tours = get_tour_service(context)?
+            .get_tours_by_ids(&tour_ids)
+            .await
+            .map_err(|error| AppError::from_service_error("get tours", error).extend())?;
+        for tour in tours {
+            nodes.insert(GlobalId::new(NodeType::Tour, tour.id), Node::Tour(tour));
+        }
+    }
+    let place_ids = ids_of(NodeType::Place);
+    if !place_ids.is_empty() {
+        let places = get_place_service(context)?
+            .get_places_by_ids(&place_ids)
+            .await
+            .map_err(|error| AppError::from_service_error("get places", error).extend())?;
+        for place in places {
+            nodes.insert(GlobalId::new(NodeType::Place, place.id), Node::Place(place));
+        }
+    }
+
+    let departure_ids = ids_of(NodeType::Departure);
+    if !departure_ids.is_empty() {
+        let departures = get_departure_service(context)?
+            .get_departures_by_ids(&departure_ids)
+            .await
+            .map_err(|error| AppError::from_service_error("get departures", error).extend())?;
+        for departure in departures {
+            nodes.insert(
+                GlobalId::new(NodeType::Departure, departure.id),
+                Node::Departure(departure),
+            );
+        }
+    }
+
+    // The rest are loaded one by one, with the checks of their own queries.
+    // Nodes the requester may not see are null, like missing ones.
+    let current_user = context.data_opt::<CurrentUser>();
+    let is_admin = current_user.is_some_and(|user| user.is_admin);
+    let can_access =
+        |owner_id: &str| current_user.is_some_and(|user| user.id == owner_id || user.is_admin);
+    for global_id in &global_ids {
+        if nodes.contains_key(global_id) {
+            continue;
+        }
+
+        let id = global_id.id;
+        let node = match global_id.node_type {
+            NodeType::Tour | NodeType::Place | NodeType::Departure => None,
+            NodeType::DepartureSchedule => get_schedule_service(context)?
+                .get_schedule(id)
+                .await
+                .map_err(node_error("get schedule"))?
+                .map(Node::DepartureSchedule),
+            NodeType::BlackoutCalendar if is_admin => get_schedule_service(context)?
+                .get_calendar(id)
+                .await
+                .map_err(node_error("get blackout calendar"))?
+                .map(Node::BlackoutCalendar),
+            NodeType::BlackoutDate if is_admin => get_schedule_service(context)?
+                .get_blackout_date(id)
+                .await
+                .map_err(node_error("get blackout date"))?
+                .map(Node::BlackoutDate),
+            NodeType::Booking if current_user.is_some() => get_booking_service(context)?
+                .get_booking(id)
+                .await
+                .map_err(node_error("get booking"))?
+                .filter(|booking| can_access(&booking.user_id))
+                .map(Node::Booking),
+            NodeType::SeatHold => match current_user {
+                Some(user) => get_booking_service(context)?
+                    .get_seat_hold(user, id)
+                    .await
+                    .map_err(node_error("get seat hold"))?
+                    .map(Node::SeatHold),
+                None => None,
+            },
+            NodeType::WaitlistEntry if current_user.is_some() => get_waitlist_service(context)?
+                .get_waitlist_entry(id)
+                .await
+                .map_err(node_error("get waitlist entry"))?
+                .filter(|entry| can_access(&entry.user_id))
+                .map(Node::WaitlistEntry),
+            NodeType::Payment if current_user.is_some() => {
+                let payment = get_payment_service(context)?
+                    .get_payment(id)
+                    .await
+                    .map_err(node_error("get payment"))?;
+                match payment {
+                    Some(payment) => get_booking_service(context)?
+                        .get_booking(payment.booking_id)
+                        .await
+                        .map_err(node_error("get payment booking"))?
+                        .filter(|booking| can_access(&booking.user_id))
+                        .map(|_| Node::Payment(payment)),
+                    None => None,
+                }
             }
+            NodeType::PricingRule if is_admin => get_pricing_service(context)?
+                .get_pricing_rule(id)
+                .await
+                .map_err(node_error("get pricing rule"))?
+                .map(Node::PricingRule),
+            NodeType::Promotion if is_admin => get_promotion_service(context)?
+                .get_promotion(id)
+                .await
+                .map_err(node_error("get promotion"))?
+                .map(Node::Promotion),
+            NodeType::BlackoutCalendar
+            | NodeType::BlackoutDate
+            | NodeType::Booking
+            | NodeType::WaitlistEntry
+            | NodeType::Payment
+            | NodeType::PricingRule
+            | NodeType::Promotion => None,
+        };
+        if let Some(node) = node {
+            nodes.insert(*global_id, node);
         }
     }
+
+    Ok(global_ids
+        .iter()
+        .map(|global_id| nodes.get(global_id).cloned())
+        .collect())
 }
 
-pub type ApplicationSchema = Schema<QueryRoot, MutationRoot, async_graphql::EmptySubscription>;
+fn node_error(operation: &'static str) -> impl FnOnce(ServiceError) -> async_graphql::Error {
+    move |error| AppError::from_service_error(operation, error).extend()
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    async fn tour_changed(
+        &self,
+        context: &Context<'_>,
+        id: ID,
+    ) -> FieldResult<impl Stream<Item = TourChange>> {
+        let id = decode_id(&id, NodeType::Tour)?;
+        let current_user = context.data_opt::<CurrentUser>().cloned();
+
+        Ok(get_event_service(context)?
+            .subscribe_tour_changes()
+            .filter(move |change| {
+                future::ready(
+                    change.tour_id == id
+                        && change
+                            .tour
+                            .as_ref()
+                            .is_none_or(|tour| tour.is_listed_for(current_user.as_ref())),
+                )
+            }))
+    }
+
+    // Deletions are always delivered because the removed tour can no longer be
+    // matched against the filter.
+    async fn tours_updated(
+        &self,
+        context: &Context<'_>,
+        filter: Option<TourFilter>,
+    ) -> FieldResult<impl Stream<Item = TourChange>> {
+        let mut filter = filter.unwrap_or_default();
+        filter
+            .validate()
+            .and_then(|()| filter.restrict_to_listed(context.data_opt::<CurrentUser>()))
+            .map_err(|errors| AppError::Validation(errors).extend())?;
+
+        Ok(get_event_service(context)?
+            .subscribe_tour_changes()
+            .filter(move |change| {
+                future::ready(change.tour.as_ref().is_none_or(|tour| filter.matches(tour)))
+            }))
+    }
+}
+
+pub type ApplicationSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{Request, Response, Variables};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::repository::tour::memory::InMemoryTourRepository;
+    use crate::test_support::{admin, application_data, schema, tour, user};
+
+    fn tours_schema() -> ApplicationSchema {
+        schema(application_data(Arc::new(
+            InMemoryTourRepository::with_tours(vec![
+                tour(1, "Old Lisbon", 1500),
+                tour(2, "Porto Wine", 3000),
+                tour(3, "Sintra Palaces", 4500),
+            ]),
+        )))
+    }
+
+    async fn execute(schema: &ApplicationSchema, query: &str, variables: Value) -> Response {
+        schema
+            .execute(
+                Request::new(query)
+                    .variables(Variables::from_json(variables))
+                    .data(admin()),
+            )
+            .await
+    }
+
+    fn data(response: Response) -> Value {
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    fn error_code(response: &Response) -> Value {
+        let error = response.errors.first().expect("an error");
+        error
+            .extensions
+            .as_ref()
+            .unwrap()
+            .get("code")
+            .unwrap()
+            .clone()
+            .into_json()
+            .unwrap()
+    }
+
+    const TOURS_PAGE: &str = r#"
+        query ($after: String) {
+            toursConnection(first: 2, after: $after, sort: { field: PRICE, direction: ASC }) {
+                totalCount
+                pageInfo { hasNextPage endCursor }
+                edges { node { title } }
+            }
+        }
+    "#;
+
+    #[actix_web::test]
+    async fn tours_connection_paginates() {
+        let schema = tours_schema();
+
+        let first = data(execute(&schema, TOURS_PAGE, json!({})).await);
+        let connection = &first["toursConnection"];
+        assert_eq!(connection["totalCount"], 3);
+        assert_eq!(connection["pageInfo"]["hasNextPage"], true);
+        assert_eq!(
+            connection["edges"],
+            json!([{ "node": { "title": "Old Lisbon" } }, { "node": { "title": "Porto Wine" } }])
+        );
+
+        let after = connection["pageInfo"]["endCursor"].clone();
+        let second = data(execute(&schema, TOURS_PAGE, json!({ "after": after })).await);
+        let connection = &second["toursConnection"];
+        assert_eq!(connection["pageInfo"]["hasNextPage"], false);
+        assert_eq!(
+            connection["edges"],
+            json!([{ "node": { "title": "Sintra Palaces" } }])
+        );
+    }
+
+    #[actix_web::test]
+    async fn create_tour_validates_and_persists() {
+        let schema = tours_schema();
+        let mutation = r#"
+            mutation ($input: CreateTourInput!) {
+                createTour(input: $input) { id title slug isActive }
+            }
+        "#;
+
+        let response = execute(
+            &schema,
+            mutation,
+            json!({ "input": { "title": "  ", "rating": 7.0, "isActive": true } }),
+        )
+        .await;
+        assert_eq!(error_code(&response), json!("VALIDATION_FAILED"));
+        let fields = response.errors[0]
+            .extensions
+            .as_ref()
+            .unwrap()
+            .get("fields")
+            .unwrap();
+        let fields = fields.clone().into_json().unwrap();
+        assert!(fields.get("title").is_some(), "{}", fields);
+        assert!(fields.get("rating").is_some(), "{}", fields);
+
+        let created = data(
+            execute(
+                &schema,
+                mutation,
+                json!({ "input": { "title": "Old Lisbon", "isActive": true } }),
+            )
+            .await,
+        );
+        let created = &created["createTour"];
+        assert_eq!(created["title"], "Old Lisbon");
+        // The slug of tour 1 is taken, so a suffix is added.
+        assert_ne!(created["slug"], "old-lisbon");
+
+        let fetched = data(
+            execute(
+                &schema,
+                "query ($id: ID!) { tour(id: $id) { title } }",
+                json!({ "id": created["id"] }),
+            )
+            .await,
+        );
+        assert_eq!(fetched["tour"]["title"], "Old Lisbon");
+    }
+
+    #[actix_web::test]
+    async fn update_tour_validates_and_applies_changes() {
+        let schema = tours_schema();
+        let id = GlobalId::new(NodeType::Tour, 2).encode();
+        let mutation = r#"
+            mutation ($id: ID!, $input: UpdateTourInput!) {
+                updateTour(id: $id, input: $input) { title rating description }
+            }
+        "#;
+
+        let response = execute(
+            &schema,
+            mutation,
+            json!({ "id": id, "input": { "maxParticipants": 0 } }),
+        )
+        .await;
+        assert_eq!(error_code(&response), json!("VALIDATION_FAILED"));
+
+        let updated = data(
+            execute(
+                &schema,
+                mutation,
+                json!({ "id": id, "input": { "title": "Porto Cellars", "rating": 4.5 } }),
+            )
+            .await,
+        );
+        assert_eq!(
+            updated["updateTour"],
+            json!({ "title": "Porto Cellars", "rating": 4.5, "description": null })
+        );
+    }
+
+    #[actix_web::test]
+    async fn update_tour_clears_explicit_nulls_and_keeps_omitted_fields() {
+        let schema = tours_schema();
+        let id = GlobalId::new(NodeType::Tour, 2).encode();
+        let mutation = r#"
+            mutation ($id: ID!, $input: UpdateTourInput!) {
+                updateTour(id: $id, input: $input) {
+                    title description endDate imageUrl price { amountMinor }
+                }
+            }
+        "#;
+        let update = |input: Value| {
+            let schema = schema.clone();
+            let id = id.clone();
+            async move {
+                data(execute(&schema, mutation, json!({ "id": id, "input": input })).await)
+                    ["updateTour"]
+                    .clone()
+            }
+        };
+
+        let filled = json!({
+            "title": "Porto Wine",
+            "description": "Cellars by the river",
+            "endDate": "2024-12-31",
+            "imageUrl": "https://example.com/porto.jpg",
+            "price": { "amountMinor": 3500 },
+        });
+        assert_eq!(
+            update(json!({
+                "description": "Cellars by the river",
+                "endDate": "2024-12-31",
+                "imageUrl": "https://example.com/porto.jpg",
+                "price": "35",
+            }))
+            .await,
+            filled
+        );
+
+        // Omitted fields keep their values.
+        let mut renamed = filled.clone();
+        renamed["title"] = json!("Porto Cellars");
+        assert_eq!(update(json!({ "title": "Porto Cellars" })).await, renamed);
+
+        // Explicit nulls clear them.
+        assert_eq!(
+            update(json!({
+                "description": null,
+                "endDate": null,
+                "imageUrl": null,
+                "price": null,
+            }))
+            .await,
+            json!({
+                "title": "Porto Cellars",
+                "description": null,
+                "endDate": null,
+                "imageUrl": null,
+                "price": null,
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn tour_mutations_require_an_admin() {
+        let schema = tours_schema();
+        let id = GlobalId::new(NodeType::Tour, 1).encode();
+        let create =
+            r#"mutation { createTour(input: { title: "New", isActive: true }) { title } }"#;
+        let update =
+            r#"mutation ($id: ID!) { updateTour(id: $id, input: { title: "New" }) { title } }"#;
+        let user = CurrentUser {
+            id: "user-1".to_string(),
+            is_admin: false,
+        };
+
+        for query in [create, update] {
+            let request = Request::new(query).variables(Variables::from_json(json!({ "id": id })));
+            let response = schema.execute(request).await;
+            assert_eq!(error_code(&response), json!("UNAUTHENTICATED"));
+
+            let request = Request::new(query)
+                .variables(Variables::from_json(json!({ "id": id })))
+                .data(user.clone());
+            let response = schema.execute(request).await;
+            assert_eq!(error_code(&response), json!("FORBIDDEN"));
+        }
+    }
+
+    #[actix_web::test]
+    async fn inactive_tours_are_only_listed_for_admins() {
+        let mut inactive = tour(2, "Porto Wine", 3000);
+        inactive.is_active = false;
+        let schema = schema(application_data(Arc::new(
+            InMemoryTourRepository::with_tours(vec![tour(1, "Old Lisbon", 1500), inactive]),
+        )));
+        let query = r#"
+            {
+                getTours { title }
+                toursConnection { totalCount }
+                searchTours(query: "lisbon") { tour { title } }
+            }
+        "#;
+
+        for (user, expected_titles) in [
+            (None, vec!["Old Lisbon"]),
+            (Some(user("user-1")), vec!["Old Lisbon"]),
+            (Some(admin()), vec!["Old Lisbon", "Porto Wine"]),
+        ] {
+            let mut request = Request::new(query);
+            if let Some(user) = &user {
+                request = request.data(user.clone());
+            }
+            let result = data(schema.execute(request).await);
+
+            let titles: Vec<&str> = result["getTours"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|tour| tour["title"].as_str().unwrap())
+                .collect();
+            assert_eq!(titles, expected_titles, "{:?}", user);
+            assert_eq!(
+                result["toursConnection"]["totalCount"],
+                expected_titles.len(),
+                "{:?}",
+                user
+            );
+            assert_eq!(
+                result["searchTours"].as_array().unwrap().len(),
+                expected_titles.len(),
+                "{:?}",
+                user
+            );
+        }
+
+        let response = schema
+            .execute(Request::new(
+                "{ toursConnection(filter: { isActive: false }) { totalCount } }",
+            ))
+            .await;
+        assert_eq!(error_code(&response), json!("VALIDATION_FAILED"));
+        let response = execute(
+            &schema,
+            "{ toursConnection(filter: { isActive: false }) { totalCount } }",
+            json!({}),
+        )
+        .await;
+        assert_eq!(data(response)["toursConnection"]["totalCount"], 1);
+    }
+
+    #[actix_web::test]
+    async fn nodes_hide_owned_and_admin_only_types_from_anonymous_users() {
+        let schema = tours_schema();
+        let ids: Vec<ID> = [
+            (NodeType::Tour, 1),
+            (NodeType::Booking, 1),
+            (NodeType::SeatHold, 1),
+            (NodeType::WaitlistEntry, 1),
+            (NodeType::Payment, 1),
+            (NodeType::PricingRule, 1),
+            (NodeType::Promotion, 1),
+            (NodeType::BlackoutCalendar, 1),
+            (NodeType::BlackoutDate, 1),
+        ]
+        .into_iter()
+        .map(|(node_type, id)| GlobalId::new(node_type, id).encode())
+        .collect();
+
+        // The schema's other repositories have no database, so this also shows
+        // none of them is queried.
+        let request = Request::new("query ($ids: [ID!]!) { nodes(ids: $ids) { __typename } }")
+            .variables(Variables::from_json(json!({ "ids": ids })));
+        let nodes = data(schema.execute(request).await);
+        let mut expected = vec![Value::Null; ids.len()];
+        expected[0] = json!({ "__typename": "Tour" });
+        assert_eq!(nodes["nodes"], Value::Array(expected));
+    }
+
+    #[actix_web::test]
+    async fn missing_tours_are_not_found() {
+        let schema = tours_schema();
+        let id = GlobalId::new(NodeType::Tour, 99).encode();
+
+        let fetched = data(
+            execute(
+                &schema,
+                "query ($id: ID!) { tour(id: $id) { title } }",
+                json!({ "id": id }),
+            )
+            .await,
+        );
+        assert_eq!(fetched["tour"], Value::Null);
+
+        let response = execute(
+            &schema,
+            r#"mutation ($id: ID!) { updateTour(id: $id, input: { title: "Gone" }) { title } }"#,
+            json!({ "id": id }),
+        )
+        .await;
+        assert_eq!(error_code(&response), json!("NOT_FOUND"));
+    }
+}
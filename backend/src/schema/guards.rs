@@ -0,0 +1,22 @@
+use async_graphql::{Context, ErrorExtensions, Guard, Result};
+
+use crate::error::AppError;
+use crate::models::user::CurrentUser;
+
+pub struct AdminGuard;
+
+impl Guard for AdminGuard {
+    async fn check(&self, context: &Context<'_>) -> Result<()> {
+        if get_current_user(context)?.is_admin {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden.extend())
+        }
+    }
+}
+
+pub fn get_current_user<'a>(context: &Context<'a>) -> Result<&'a CurrentUser> {
+    context
+        .data_opt::<CurrentUser>()
+        .ok_or_else(|| AppError::Unauthenticated.extend())
+}
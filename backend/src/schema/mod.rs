@@ -1 +1,3 @@
 pub mod graphql;
+pub mod guards;
+pub mod validation;
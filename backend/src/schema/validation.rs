@@ -0,0 +1,87 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::models::money::{Money, MoneyError};
+
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationErrors {
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.fields
+            .entry(field.to_string())
+            .or_default()
+            .push(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.fields
+    }
+}
+
+pub fn validate_title(errors: &mut ValidationErrors, field: &str, title: &str) {
+    if title.trim().is_empty() {
+        errors.add(field, "must not be empty");
+    }
+}
+
+pub fn validate_slug(errors: &mut ValidationErrors, field: &str, slug: &str) {
+    if slug.is_empty() || slug::slugify(slug) != slug {
+        errors.add(
+            field,
+            "must contain only lowercase letters, digits and single hyphens",
+        );
+    }
+}
+
+// `price` is in `currency`; an unsupported currency is reported on its own field.
+pub fn validate_price(errors: &mut ValidationErrors, field: &str, price: Decimal, currency: &str) {
+    if price.is_sign_negative() && !price.is_zero() {
+        errors.add(field, "must be greater than or equal to 0");
+        return;
+    }
+    match Money::from_decimal(price, currency) {
+        Ok(_) | Err(MoneyError::UnsupportedCurrency(_)) => {}
+        Err(error) => errors.add(field, error.to_string()),
+    }
+}
+
+pub fn validate_rating(errors: &mut ValidationErrors, field: &str, rating: f64) {
+    if !rating.is_finite() || !(0.0..=5.0).contains(&rating) {
+        errors.add(field, "must be between 0 and 5");
+    }
+}
+
+pub fn validate_max_participants(errors: &mut ValidationErrors, field: &str, value: i32) {
+    if value <= 0 {
+        errors.add(field, "must be greater than 0");
+    }
+}
+
+pub fn validate_image_url(errors: &mut ValidationErrors, field: &str, image_url: &str) {
+    match Url::parse(image_url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {}
+        Ok(_) => errors.add(field, "must be an http or https URL"),
+        Err(_) => errors.add(field, "must be a well-formed URL"),
+    }
+}
@@ -0,0 +1,484 @@
+use chrono::{TimeDelta, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::models::booking::{validate_participants, Booking, BookingStatus, SeatHold};
+use crate::models::departure::Departure;
+use crate::models::pagination::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::models::pricing::{validate_party, ParticipantsInput, PriceLine};
+use crate::models::promotion::{normalize_code, PromotionError};
+use crate::models::user::CurrentUser;
+use crate::repository::booking::{BookingError, BookingRepository};
+use crate::schema::validation::ValidationErrors;
+use crate::service::ServiceError;
+
+pub const DEFAULT_HOLD_TTL_MINUTES: u64 = 15;
+const HOLD_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct BookingService {
+    repository: Arc<dyn BookingRepository>,
+    hold_ttl_minutes: u64,
+}
+
+impl BookingService {
+    pub fn new(repository: Arc<dyn BookingRepository>, hold_ttl_minutes: u64) -> Self {
+        Self {
+            repository,
+            hold_ttl_minutes,
+        }
+    }
+
+    pub async fn get_booking(&self, id: i32) -> Result<Option<Booking>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn get_price_lines(&self, booking_id: i32) -> Result<Vec<PriceLine>, ServiceError> {
+        Ok(self.repository.find_price_lines(booking_id).await?)
+    }
+
+    pub async fn get_user_bookings(
+        &self,
+        user: &CurrentUser,
+        first: Option<usize>,
+    ) -> Result<Vec<Booking>, ServiceError> {
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+        Ok(self.repository.find_by_user(&user.id, limit).await?)
+    }
+
+    pub async fn get_bookings_affected_by_departure(
+        &self,
+        departure: &Departure,
+    ) -> Result<Vec<Booking>, ServiceError> {
+        Ok(self
+            .repository
+            .find_affected_by_departure(departure.id, departure.updated_at)
+            .await?)
+    }
+
+    pub async fn book_tour(
+        &self,
+        user: &CurrentUser,
+        departure_id: i32,
+        party: Vec<ParticipantsInput>,
+        promo_code: Option<String>,
+    ) -> Result<Booking, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        validate_party(&mut errors, "participants", &party);
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        let promo_code = promo_code.map(|code| normalize_code(&code));
+        let now = Utc::now();
+        self.repository
+            .create(departure_id, &user.id, &party, promo_code.as_deref(), now)
+            .await
+            .map_err(map_booking_error)
+    }
+
+    // Bookings of other users are reported as missing so their ids can't be probed.
+    pub async fn cancel_booking(
+        &self,
+        user: &CurrentUser,
+        id: i32,
+    ) -> Result<Booking, ServiceError> {
+        let booking = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .filter(|booking| booking.user_id == user.id || user.is_admin)
+            .ok_or(ServiceError::NotFound)?;
+        if booking.status == BookingStatus::Cancelled {
+            return Err(ServiceError::Conflict(
+                "Booking is already cancelled".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        self.repository
+            .cancel(id, now)
+            .await?
+            .ok_or_else(|| ServiceError::Conflict("Booking is already cancelled".to_string()))
+    }
+
+    // Holds of other users are reported as missing, like bookings.
+    pub async fn get_seat_hold(
+        &self,
+        user: &CurrentUser,
+        id: i32,
+    ) -> Result<Option<SeatHold>, ServiceError> {
+        Ok(self
+            .repository
+            .find_hold_by_id(id)
+            .await?
+            .filter(|hold| hold.user_id == user.id || user.is_admin))
+    }
+
+    pub async fn hold_seats(
+        &self,
+        user: &CurrentUser,
+        departure_id: i32,
+        count: i32,
+    ) -> Result<SeatHold, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        validate_participants(&mut errors, "count", count);
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        let expires_at = now + TimeDelta::minutes(self.hold_ttl_minutes as i64);
+        self.repository
+            .create_hold(departure_id, &user.id, count, expires_at, now)
+            .await
+            .map_err(map_booking_error)
+    }
+
+    pub async fn convert_seat_hold(
+        &self,
+        user: &CurrentUser,
+        id: i32,
+        party: Option<Vec<ParticipantsInput>>,
+        promo_code: Option<String>,
+    ) -> Result<Booking, ServiceError> {
+        if let Some(party) = &party {
+            let mut errors = ValidationErrors::new();
+            validate_party(&mut errors, "participants", party);
+            errors.into_result().map_err(ServiceError::Validation)?;
+        }
+        self.get_seat_hold(user, id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+
+        let promo_code = promo_code.map(|code| normalize_code(&code));
+        let now = Utc::now();
+        self.repository
+            .convert_hold(id, party.as_deref(), promo_code.as_deref(), now)
+            .await
+            .map_err(map_booking_error)
+    }
+
+    pub async fn release_seat_hold(
+        &self,
+        user: &CurrentUser,
+        id: i32,
+    ) -> Result<SeatHold, ServiceError> {
+        self.get_seat_hold(user, id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+
+        let now = Utc::now();
+        self.repository
+            .release_hold(id, now)
+            .await?
+            .ok_or_else(hold_inactive)
+    }
+
+    pub async fn expire_seat_holds(&self) -> Result<u64, ServiceError> {
+        Ok(self.repository.expire_holds(Utc::now()).await?)
+    }
+
+    // Expired holds already stop counting against capacity; the sweeper only moves
+    // them out of the active state.
+    pub fn spawn_hold_sweeper(&self) {
+        let booking_service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HOLD_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                match booking_service.expire_seat_holds().await {
+                    Ok(0) => {}
+                    Ok(expired) => info!("Released {} expired seat holds", expired),
+                    Err(error) => error!("Failed to release expired seat holds: {}", error),
+                }
+            }
+        });
+    }
+}
+
+fn hold_inactive() -> ServiceError {
+    ServiceError::Conflict("Seat hold has expired or was already used".to_string())
+}
+
+pub(crate) fn map_booking_error(error: BookingError) -> ServiceError {
+    match error {
+        BookingError::DepartureNotFound | BookingError::HoldNotFound => ServiceError::NotFound,
+        BookingError::DepartureUnavailable => {
+            let mut errors = ValidationErrors::new();
+            errors.add("departureId", "is not open for booking");
+            ServiceError::Validation(errors)
+        }
+        BookingError::InsufficientSeats { available } => {
+            ServiceError::Conflict(format!("Only {} seats are available", available))
+        }
+        BookingError::HoldInactive => hold_inactive(),
+        BookingError::HeldSeatsMismatch { seats } => {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "participants",
+                format!("must add up to the {} held seats", seats),
+            );
+            ServiceError::Validation(errors)
+        }
+        BookingError::Pricing(error) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("participants", error.to_string());
+            ServiceError::Validation(errors)
+        }
+        BookingError::Promotion(error) => map_promotion_error(error),
+        BookingError::Repository(error) => error.into(),
+    }
+}
+
+pub(crate) fn map_promotion_error(error: PromotionError) -> ServiceError {
+    let mut errors = ValidationErrors::new();
+    errors.add("promoCode", error.to_string());
+    ServiceError::Validation(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::models::booking::SeatHoldStatus;
+    use crate::models::pricing::adults;
+    use crate::repository::booking::postgres::PostgresBookingRepository;
+    use crate::repository::departure::postgres::PostgresDepartureRepository;
+    use crate::service::departure::DepartureService;
+    use crate::test_support::{insert_departure, user};
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn concurrent_bookings_never_oversell(pool: PgPool) {
+        const CAPACITY: usize = 3;
+        const BOOKINGS: usize = 8;
+        let departure_id = insert_departure(&pool, 1000, CAPACITY as i32).await;
+        let service =
+            BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 15);
+        let users: Vec<CurrentUser> = (0..BOOKINGS)
+            .map(|index| user(&format!("user-{}", index)))
+            .collect();
+
+        let results = join_all(
+            users
+                .iter()
+                .map(|user| service.book_tour(user, departure_id, adults(1), None)),
+        )
+        .await;
+
+        let booked = results.iter().filter(|result| result.is_ok()).count();
+        let sold_out = results
+            .iter()
+            .filter(|result| {
+                matches!(result, Err(ServiceError::Conflict(message)) if message == "Only 0 seats are available")
+            })
+            .count();
+        assert_eq!(booked, CAPACITY);
+        assert_eq!(sold_out, BOOKINGS - CAPACITY);
+
+        let seats_sold = sqlx::query_scalar::<_, i64>(
+            "SELECT SUM(participants)::bigint FROM bookings WHERE departure_id = $1 AND status <> 'cancelled'",
+        )
+        .bind(departure_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(seats_sold, CAPACITY as i64);
+    }
+
+    async fn insert_promotion(pool: &PgPool, max_redemptions: i32) {
+        sqlx::query(
+            r#"
+            INSERT INTO promotions (code, name, percent, max_redemptions)
+            VALUES ('LAST', 'Last seats', 10, $1)
+            "#,
+        )
+        .bind(max_redemptions)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn promo_code_error(result: &Result<Booking, ServiceError>) -> Option<&str> {
+        match result {
+            Err(ServiceError::Validation(errors)) => {
+                errors.fields()["promoCode"].first().map(String::as_str)
+            }
+            _ => None,
+        }
+    }
+
+    // Departures of the same tour on the following days, so bookings of them don't
+    // wait for each other's departure lock.
+    async fn later_departures(pool: &PgPool, departure_id: i32, count: usize) -> Vec<i32> {
+        sqlx::query_scalar::<_, i32>(
+            r#"
+            INSERT INTO departures (tour_id, starts_at, ends_at, capacity)
+            SELECT tour_id, starts_at + day * interval '1 day', ends_at + day * interval '1 day', capacity
+            FROM departures, generate_series(1, $2) AS day
+            WHERE id = $1
+            RETURNING id
+            "#,
+        )
+        .bind(departure_id)
+        .bind(count as i32)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn redemptions(pool: &PgPool) -> i64 {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM promotion_redemptions")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn concurrent_redemptions_never_exceed_the_code_cap(pool: PgPool) {
+        const MAX_REDEMPTIONS: i32 = 3;
+        const BOOKINGS: usize = 8;
+        let departure_id = insert_departure(&pool, 1000, 20).await;
+        insert_promotion(&pool, MAX_REDEMPTIONS).await;
+        let service =
+            BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 15);
+        for index in 0..MAX_REDEMPTIONS - 1 {
+            service
+                .book_tour(
+                    &user(&format!("early-{}", index)),
+                    departure_id,
+                    adults(1),
+                    Some("last".to_string()),
+                )
+                .await
+                .unwrap();
+        }
+        let users: Vec<CurrentUser> = (0..BOOKINGS)
+            .map(|index| user(&format!("user-{}", index)))
+            .collect();
+        let departure_ids = later_departures(&pool, departure_id, BOOKINGS).await;
+
+        // Everyone races for the last use, each on a departure of their own.
+        let results = join_all(
+            users
+                .iter()
+                .zip(&departure_ids)
+                .map(|(user, departure_id)| {
+                    service.book_tour(user, *departure_id, adults(1), Some("LAST".to_string()))
+                }),
+        )
+        .await;
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        let exhausted = results
+            .iter()
+            .filter(|result| promo_code_error(result) == Some("has been fully redeemed"))
+            .count();
+        assert_eq!(exhausted, BOOKINGS - 1);
+        assert_eq!(redemptions(&pool).await, i64::from(MAX_REDEMPTIONS));
+    }
+
+    async fn expire_now(pool: &PgPool, hold_id: i32) {
+        sqlx::query("UPDATE seat_holds SET expires_at = now() - interval '1 second' WHERE id = $1")
+            .bind(hold_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn active_holds_count_against_available_seats_until_they_expire(pool: PgPool) {
+        let departure_id = insert_departure(&pool, 1000, 4).await;
+        let service =
+            BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 15);
+        let departures =
+            DepartureService::new(Arc::new(PostgresDepartureRepository::new(pool.clone())));
+
+        let hold = service
+            .hold_seats(&user("holder"), departure_id, 3)
+            .await
+            .unwrap();
+        assert_eq!(
+            departures.get_available_seats(departure_id).await.unwrap(),
+            Some(1)
+        );
+        let result = service
+            .book_tour(&user("late"), departure_id, adults(2), None)
+            .await;
+        assert!(
+            matches!(&result, Err(ServiceError::Conflict(message)) if message == "Only 1 seats are available"),
+            "{:?}",
+            result
+        );
+
+        // Seats come back as soon as the hold expires, before the sweeper runs.
+        expire_now(&pool, hold.id).await;
+        assert_eq!(
+            departures.get_available_seats(departure_id).await.unwrap(),
+            Some(4)
+        );
+        assert_eq!(service.expire_seat_holds().await.unwrap(), 1);
+        let hold = service
+            .get_seat_hold(&user("holder"), hold.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hold.status, SeatHoldStatus::Expired);
+        assert!(matches!(
+            service
+                .convert_seat_hold(&user("holder"), hold.id, None, None)
+                .await,
+            Err(ServiceError::Conflict(_))
+        ));
+        service
+            .book_tour(&user("late"), departure_id, adults(4), None)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn released_and_converted_holds_free_or_keep_their_seats(pool: PgPool) {
+        let departure_id = insert_departure(&pool, 1000, 4).await;
+        let service =
+            BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 15);
+        let departures =
+            DepartureService::new(Arc::new(PostgresDepartureRepository::new(pool.clone())));
+
+        let released = service
+            .hold_seats(&user("holder"), departure_id, 2)
+            .await
+            .unwrap();
+        let converted = service
+            .hold_seats(&user("holder"), departure_id, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            departures.get_available_seats(departure_id).await.unwrap(),
+            Some(0)
+        );
+
+        service
+            .release_seat_hold(&user("holder"), released.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            departures.get_available_seats(departure_id).await.unwrap(),
+            Some(2)
+        );
+
+        let booking = service
+            .convert_seat_hold(&user("holder"), converted.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(booking.participants, 2);
+        assert_eq!(
+            departures.get_available_seats(departure_id).await.unwrap(),
+            Some(2)
+        );
+    }
+}
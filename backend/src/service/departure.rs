@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use crate::models::departure::{
+    CreateDepartureInput, Departure, RescheduleDepartureInput, MAX_DEPARTURES_PAGE_SIZE,
+};
+use crate::models::pagination::MAX_PAGE_SIZE;
+use crate::repository::departure::DepartureRepository;
+use crate::repository::RepositoryError;
+use crate::schema::validation::ValidationErrors;
+use crate::service::ServiceError;
+
+#[derive(Clone)]
+pub struct DepartureService {
+    repository: Arc<dyn DepartureRepository>,
+}
+
+impl DepartureService {
+    pub fn new(repository: Arc<dyn DepartureRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn get_departure(&self, id: i32) -> Result<Option<Departure>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn get_departures_by_ids(&self, ids: &[i32]) -> Result<Vec<Departure>, ServiceError> {
+        Ok(self.repository.find_by_ids(ids).await?)
+    }
+
+    pub async fn get_tour_departures(
+        &self,
+        tour_id: i32,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        first: Option<usize>,
+    ) -> Result<Vec<Departure>, ServiceError> {
+        if let (Some(from), Some(to)) = (from, to) {
+            if to < from {
+                let mut errors = ValidationErrors::new();
+                errors.add("to", "must not be before from");
+                return Err(ServiceError::Validation(errors));
+            }
+        }
+        let limit = first.unwrap_or(MAX_PAGE_SIZE).min(MAX_DEPARTURES_PAGE_SIZE);
+
+        Ok(self
+            .repository
+            .find_by_tour(tour_id, from, to, limit)
+            .await?)
+    }
+
+    pub async fn get_available_seats(&self, id: i32) -> Result<Option<i64>, ServiceError> {
+        Ok(self.repository.count_available_seats(id).await?)
+    }
+
+    pub async fn add_departure(
+        &self,
+        tour_id: i32,
+        currency: &str,
+        input: CreateDepartureInput,
+    ) -> Result<Departure, ServiceError> {
+        let now = Utc::now();
+        input
+            .validate(now, currency)
+            .map_err(ServiceError::Validation)?;
+
+        self.repository
+            .create(tour_id, input, currency, now)
+            .await
+            .map_err(map_write_error)
+    }
+
+    pub async fn reschedule_departure(
+        &self,
+        id: i32,
+        input: RescheduleDepartureInput,
+    ) -> Result<Departure, ServiceError> {
+        let now = Utc::now();
+        input.validate(now).map_err(ServiceError::Validation)?;
+
+        match self.repository.reschedule(id, input, now).await {
+            Ok(Some(departure)) => Ok(departure),
+            Ok(None) => Err(self.missing_or_cancelled(id).await),
+            Err(error) => Err(map_write_error(error)),
+        }
+    }
+
+    pub async fn cancel_departure(&self, id: i32) -> Result<Departure, ServiceError> {
+        let now = Utc::now();
+
+        match self.repository.cancel(id, now).await? {
+            Some(departure) => Ok(departure),
+            None => Err(self.missing_or_cancelled(id).await),
+        }
+    }
+
+    async fn missing_or_cancelled(&self, id: i32) -> ServiceError {
+        match self.repository.find_by_id(id).await {
+            Ok(Some(_)) => ServiceError::Conflict("Departure is cancelled".to_string()),
+            Ok(None) => ServiceError::NotFound,
+            Err(error) => error.into(),
+        }
+    }
+}
+
+fn map_write_error(error: RepositoryError) -> ServiceError {
+    match error {
+        RepositoryError::Conflict(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("startsAt", "already has a departure of this tour");
+            ServiceError::Validation(errors)
+        }
+        RepositoryError::ForeignKey(_) => ServiceError::NotFound,
+        error => error.into(),
+    }
+}
@@ -0,0 +1,717 @@
+use futures_util::{future, stream, Stream, StreamExt};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{error, info, warn};
+
+use crate::models::booking::BookingStatus;
+use crate::models::event::{
+    BookingNotification, DepartureNotification, EventAudience, ServerEvent, ServerEventKind,
+    TourChange, TourChangeOperation, TourNotification, WaitlistNotification,
+};
+use crate::models::user::CurrentUser;
+use crate::service::booking::BookingService;
+use crate::service::departure::DepartureService;
+use crate::service::tour::TourService;
+use crate::service::waitlist::WaitlistService;
+
+pub const TOUR_CHANGES_CHANNEL: &str = "tour_changes";
+pub const BOOKING_CHANGES_CHANNEL: &str = "booking_changes";
+pub const DEPARTURE_CHANGES_CHANNEL: &str = "departure_changes";
+pub const WAITLIST_CHANGES_CHANNEL: &str = "waitlist_changes";
+
+const EVENT_BUFFER_SIZE: usize = 256;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const REPLAY_BUFFER_CAPACITY: usize = 1024;
+const REPLAY_BUFFER_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Default)]
+struct ReplayBuffer {
+    last_id: u64,
+    events: VecDeque<ServerEvent>,
+}
+
+impl ReplayBuffer {
+    fn prune(&mut self, now: Instant) {
+        while self.events.len() > REPLAY_BUFFER_CAPACITY
+            || self
+                .events
+                .front()
+                .is_some_and(|event| now.duration_since(event.created_at) > REPLAY_BUFFER_TTL)
+        {
+            self.events.pop_front();
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventService {
+    tour_changes: broadcast::Sender<TourChange>,
+    server_events: broadcast::Sender<ServerEvent>,
+    replay_buffer: Arc<Mutex<ReplayBuffer>>,
+}
+
+impl Default for EventService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventService {
+    pub fn new() -> Self {
+        let (tour_changes, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        let (server_events, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        Self {
+            tour_changes,
+            server_events,
+            replay_buffer: Arc::new(Mutex::new(ReplayBuffer::default())),
+        }
+    }
+
+    // Subscribers that fall more than `EVENT_BUFFER_SIZE` events behind skip the
+    // events they missed instead of failing the stream.
+    pub fn subscribe_tour_changes(&self) -> impl Stream<Item = TourChange> + Send + 'static {
+        BroadcastStream::new(self.tour_changes.subscribe())
+            .filter_map(|change| future::ready(change.ok()))
+    }
+
+    // Replays buffered events newer than `last_event_id` before switching to live
+    // ones. Event ids are only meaningful to the instance that issued them.
+    pub fn subscribe_server_events(
+        &self,
+        current_user: Option<CurrentUser>,
+        last_event_id: Option<u64>,
+    ) -> impl Stream<Item = ServerEvent> + Send + 'static {
+        let (receiver, replayed, replayed_up_to) = {
+            let mut replay_buffer = self.replay_buffer.lock().unwrap();
+            replay_buffer.prune(Instant::now());
+
+            let receiver = self.server_events.subscribe();
+            let replayed: Vec<ServerEvent> = match last_event_id {
+                Some(last_event_id) => replay_buffer
+                    .events
+                    .iter()
+                    .filter(|event| event.id > last_event_id)
+                    .cloned()
+                    .collect(),
+                None => Vec::new(),
+            };
+            (receiver, replayed, replay_buffer.last_id)
+        };
+
+        let live = BroadcastStream::new(receiver)
+            .filter_map(|event| future::ready(event.ok()))
+            .filter(move |event| future::ready(event.id > replayed_up_to));
+
+        stream::iter(replayed)
+            .chain(live)
+            .filter(move |event| future::ready(event.audience.includes(current_user.as_ref())))
+    }
+
+    fn publish_server_event(
+        &self,
+        kind: ServerEventKind,
+        audience: EventAudience,
+        data: serde_json::Value,
+    ) {
+        let mut replay_buffer = self.replay_buffer.lock().unwrap();
+        let now = Instant::now();
+        replay_buffer.last_id += 1;
+
+        let event = ServerEvent {
+            id: replay_buffer.last_id,
+            kind,
+            audience,
+            data,
+            created_at: now,
+        };
+        replay_buffer.events.push_back(event.clone());
+        replay_buffer.prune(now);
+
+        // Sending only fails when nobody is subscribed.
+        let _ = self.server_events.send(event);
+    }
+
+    fn publish_tour_change(&self, change: TourChange) {
+        if let Some((kind, audience, data)) = ServerEvent::from_tour_change(&change) {
+            self.publish_server_event(kind, audience, data);
+        }
+
+        let _ = self.tour_changes.send(change);
+    }
+
+    // Changes arrive through Postgres NOTIFY rather than from the mutations
+    // themselves, so every instance sees writes made by any other instance.
+    pub fn spawn_listener(
+        &self,
+        database_pool: PgPool,
+        tour_service: TourService,
+        departure_service: DepartureService,
+        booking_service: BookingService,
+        waitlist_service: WaitlistService,
+    ) {
+        let event_service = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(error) = listen_for_changes(
+                    &database_pool,
+                    &tour_service,
+                    &departure_service,
+                    &booking_service,
+                    &waitlist_service,
+                    &event_service,
+                )
+                .await
+                {
+                    error!("Change listener failed: {}", error);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+}
+
+async fn listen_for_changes(
+    database_pool: &PgPool,
+    tour_service: &TourService,
+    departure_service: &DepartureService,
+    booking_service: &BookingService,
+    waitlist_service: &WaitlistService,
+    event_service: &EventService,
+) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(database_pool).await?;
+    listener
+        .listen_all([
+            TOUR_CHANGES_CHANNEL,
+            BOOKING_CHANGES_CHANNEL,
+            DEPARTURE_CHANGES_CHANNEL,
+            WAITLIST_CHANGES_CHANNEL,
+        ])
+        .await?;
+    info!("Listening for tour, booking, departure and waitlist changes");
+
+    loop {
+        let notification = listener.recv().await?;
+        match notification.channel() {
+            TOUR_CHANGES_CHANNEL => {
+                handle_tour_notification(notification.payload(), tour_service, event_service).await
+            }
+            BOOKING_CHANGES_CHANNEL => {
+                handle_booking_notification(
+                    notification.payload(),
+                    booking_service,
+                    waitlist_service,
+                    event_service,
+                )
+                .await
+            }
+            DEPARTURE_CHANGES_CHANNEL => {
+                handle_departure_notification(
+                    notification.payload(),
+                    departure_service,
+                    booking_service,
+                    event_service,
+                )
+                .await
+            }
+            WAITLIST_CHANGES_CHANNEL => {
+                handle_waitlist_notification(
+                    notification.payload(),
+                    waitlist_service,
+                    event_service,
+                )
+                .await
+            }
+            channel => warn!("Ignoring notification on unexpected channel {}", channel),
+        }
+    }
+}
+
+async fn handle_tour_notification(
+    payload: &str,
+    tour_service: &TourService,
+    event_service: &EventService,
+) {
+    let notification: TourNotification = match serde_json::from_str(payload) {
+        Ok(notification) => notification,
+        Err(error) => {
+            warn!("Ignoring malformed tour notification: {}", error);
+            return;
+        }
+    };
+
+    let tour = match notification.operation {
+        TourChangeOperation::Deleted => None,
+        _ => match tour_service.get_tour(notification.tour_id).await {
+            Ok(Some(tour)) => Some(tour),
+            Ok(None) => return,
+            Err(error) => {
+                error!("Failed to load tour {}: {}", notification.tour_id, error);
+                return;
+            }
+        },
+    };
+
+    event_service.publish_tour_change(TourChange {
+        operation: notification.operation,
+        tour_id: notification.tour_id,
+        tour,
+    });
+}
+
+async fn handle_booking_notification(
+    payload: &str,
+    booking_service: &BookingService,
+    waitlist_service: &WaitlistService,
+    event_service: &EventService,
+) {
+    let notification: BookingNotification = match serde_json::from_str(payload) {
+        Ok(notification) => notification,
+        Err(error) => {
+            warn!("Ignoring malformed booking notification: {}", error);
+            return;
+        }
+    };
+
+    let booking = match booking_service.get_booking(notification.booking_id).await {
+        Ok(Some(booking)) => booking,
+        Ok(None) => return,
+        Err(error) => {
+            error!(
+                "Failed to load booking {}: {}",
+                notification.booking_id, error
+            );
+            return;
+        }
+    };
+    if let Some((kind, audience, data)) = ServerEvent::from_booking(&booking) {
+        event_service.publish_server_event(kind, audience, data);
+    }
+
+    // Seats freed by a cancellation go to the waitlist right away rather than at
+    // the next promotion run.
+    if booking.status == BookingStatus::Cancelled {
+        if let Err(error) = waitlist_service.promote(booking.departure_id).await {
+            error!(
+                "Failed to promote waitlist of departure {}: {}",
+                booking.departure_id, error
+            );
+        }
+    }
+}
+
+async fn handle_waitlist_notification(
+    payload: &str,
+    waitlist_service: &WaitlistService,
+    event_service: &EventService,
+) {
+    let notification: WaitlistNotification = match serde_json::from_str(payload) {
+        Ok(notification) => notification,
+        Err(error) => {
+            warn!("Ignoring malformed waitlist notification: {}", error);
+            return;
+        }
+    };
+
+    let entry = match waitlist_service
+        .get_waitlist_entry(notification.entry_id)
+        .await
+    {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return,
+        Err(error) => {
+            error!(
+                "Failed to load waitlist entry {}: {}",
+                notification.entry_id, error
+            );
+            return;
+        }
+    };
+    let Some(claim_url) = waitlist_service.claim_url(&entry) else {
+        return;
+    };
+    if let Some((kind, audience, data)) = ServerEvent::from_waitlist_offer(&entry, &claim_url) {
+        event_service.publish_server_event(kind, audience, data);
+    }
+}
+
+async fn handle_departure_notification(
+    payload: &str,
+    departure_service: &DepartureService,
+    booking_service: &BookingService,
+    event_service: &EventService,
+) {
+    let notification: DepartureNotification = match serde_json::from_str(payload) {
+        Ok(notification) => notification,
+        Err(error) => {
+            warn!("Ignoring malformed departure notification: {}", error);
+            return;
+        }
+    };
+
+    let departure = match departure_service
+        .get_departure(notification.departure_id)
+        .await
+    {
+        Ok(Some(departure)) => departure,
+        Ok(None) => return,
+        Err(error) => {
+            error!(
+                "Failed to load departure {}: {}",
+                notification.departure_id, error
+            );
+            return;
+        }
+    };
+    let bookings = match booking_service
+        .get_bookings_affected_by_departure(&departure)
+        .await
+    {
+        Ok(bookings) => bookings,
+        Err(error) => {
+            error!(
+                "Failed to load bookings of departure {}: {}",
+                departure.id, error
+            );
+            return;
+        }
+    };
+
+    for booking in &bookings {
+        if let Some((kind, audience, data)) =
+            ServerEvent::from_departure_change(notification.operation, &departure, booking)
+        {
+            event_service.publish_server_event(kind, audience, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Request;
+    use chrono::{TimeDelta, Utc};
+    use futures_util::pin_mut;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::models::departure::RescheduleDepartureInput;
+    use crate::models::pricing::adults;
+    use crate::repository::booking::postgres::PostgresBookingRepository;
+    use crate::repository::departure::postgres::PostgresDepartureRepository;
+    use crate::repository::tour::memory::InMemoryTourRepository;
+    use crate::repository::tour::postgres::PostgresTourRepository;
+    use crate::repository::waitlist::postgres::PostgresWaitlistRepository;
+    use crate::test_support::{admin, application_data, insert_departure, schema, tour, user};
+
+    const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+    fn publish_public(event_service: &EventService, marker: &str) {
+        event_service.publish_server_event(
+            ServerEventKind::TourUpdated,
+            EventAudience::Public,
+            json!({ "marker": marker }),
+        );
+    }
+
+    // Event ids received up to and including `last_id`.
+    async fn received_ids(events: impl Stream<Item = ServerEvent>, last_id: u64) -> Vec<u64> {
+        pin_mut!(events);
+        let mut ids = Vec::new();
+        while ids.last() != Some(&last_id) {
+            let event = tokio::time::timeout(RECEIVE_TIMEOUT, events.next())
+                .await
+                .expect("an event before the timeout")
+                .unwrap();
+            ids.push(event.id);
+        }
+        ids
+    }
+
+    #[actix_web::test]
+    async fn server_events_resume_after_the_last_event_id() {
+        let event_service = EventService::new();
+        for marker in ["first", "second", "third"] {
+            publish_public(&event_service, marker);
+        }
+
+        let events = event_service.subscribe_server_events(None, Some(1));
+        pin_mut!(events);
+        let replayed = tokio::time::timeout(RECEIVE_TIMEOUT, events.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replayed.id, 2);
+        publish_public(&event_service, "fourth");
+
+        assert_eq!(received_ids(events, 4).await, [3, 4]);
+    }
+
+    #[actix_web::test]
+    async fn server_events_without_last_event_id_start_live() {
+        let event_service = EventService::new();
+        publish_public(&event_service, "missed");
+
+        let events = event_service.subscribe_server_events(None, None);
+        publish_public(&event_service, "live");
+
+        assert_eq!(received_ids(events, 2).await, [2]);
+    }
+
+    #[actix_web::test]
+    async fn server_events_only_reach_their_audience() {
+        let event_service = EventService::new();
+        event_service.publish_server_event(
+            ServerEventKind::TourUpdated,
+            EventAudience::Admins,
+            json!({}),
+        );
+        event_service.publish_server_event(
+            ServerEventKind::BookingConfirmed,
+            EventAudience::User("user-1".to_string()),
+            json!({}),
+        );
+        publish_public(&event_service, "last");
+
+        for (current_user, expected_ids) in [
+            (None, vec![3]),
+            (Some(user("user-1")), vec![2, 3]),
+            (Some(user("user-2")), vec![3]),
+            (Some(admin()), vec![1, 2, 3]),
+        ] {
+            let events = event_service.subscribe_server_events(current_user.clone(), Some(0));
+            assert_eq!(
+                received_ids(events, 3).await,
+                expected_ids,
+                "{:?}",
+                current_user
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn inactive_tour_changes_only_reach_admins() {
+        let mut inactive = tour(2, "Porto Wine", 3000);
+        inactive.is_active = false;
+        let changes = [
+            (
+                TourChangeOperation::Updated,
+                1,
+                Some(tour(1, "Old Lisbon", 1500)),
+            ),
+            (TourChangeOperation::Updated, 2, Some(inactive)),
+            (TourChangeOperation::Deleted, 3, None),
+        ];
+        let application_data = application_data(Arc::new(InMemoryTourRepository::new()));
+        let schema = schema(application_data.clone());
+
+        for (current_user, expected_ids) in [
+            (None, vec![1, 3]),
+            (Some(user("user-1")), vec![1, 3]),
+            (Some(admin()), vec![1, 2, 3]),
+        ] {
+            let mut request = Request::new("subscription { toursUpdated { tourId } }");
+            if let Some(current_user) = &current_user {
+                request = request.data(current_user.clone());
+            }
+            let responses = schema.execute_stream(request);
+            pin_mut!(responses);
+            // Polling once starts the subscription, so nothing is missed.
+            assert!(
+                tokio::time::timeout(Duration::from_millis(50), responses.next())
+                    .await
+                    .is_err()
+            );
+            for (operation, tour_id, tour) in changes.clone() {
+                application_data
+                    .event_service
+                    .publish_tour_change(TourChange {
+                        operation,
+                        tour_id,
+                        tour,
+                    });
+            }
+
+            let mut ids = Vec::new();
+            while ids.last() != Some(&3) {
+                let response = tokio::time::timeout(RECEIVE_TIMEOUT, responses.next())
+                    .await
+                    .expect("a change before the timeout")
+                    .unwrap();
+                assert!(response.errors.is_empty(), "{:?}", response.errors);
+                let data = response.data.into_json().unwrap();
+                ids.push(data["toursUpdated"]["tourId"].as_i64().unwrap());
+            }
+            assert_eq!(ids, expected_ids, "{:?}", current_user);
+        }
+    }
+
+    fn booking_service(pool: &PgPool) -> BookingService {
+        BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 15)
+    }
+
+    fn departure_service(pool: &PgPool) -> DepartureService {
+        DepartureService::new(Arc::new(PostgresDepartureRepository::new(pool.clone())))
+    }
+
+    fn listening_event_service(pool: &PgPool) -> EventService {
+        let event_service = EventService::new();
+        event_service.spawn_listener(
+            pool.clone(),
+            TourService::new(Arc::new(PostgresTourRepository::new(pool.clone()))),
+            departure_service(pool),
+            booking_service(pool),
+            WaitlistService::new(
+                Arc::new(PostgresWaitlistRepository::new(pool.clone())),
+                1,
+                "http://localhost/claim".to_string(),
+            ),
+        );
+        event_service
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn tour_notifications_reach_subscribers(pool: PgPool) {
+        let tour_id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO tours (title, slug, price_minor) VALUES ('Old Lisbon', 'old-lisbon', 1000) RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let event_service = listening_event_service(&pool);
+        let changes = event_service.subscribe_tour_changes();
+        let server_events = event_service.subscribe_server_events(None, None);
+        pin_mut!(changes);
+        pin_mut!(server_events);
+
+        // The listener connects in the background, so keep writing until it
+        // hears about one of the updates.
+        let mut change = None;
+        for attempt in 0..20 {
+            sqlx::query("UPDATE tours SET title = $2 WHERE id = $1")
+                .bind(tour_id)
+                .bind(format!("Old Lisbon {}", attempt))
+                .execute(&pool)
+                .await
+                .unwrap();
+            if let Ok(received) =
+                tokio::time::timeout(Duration::from_millis(250), changes.next()).await
+            {
+                change = received;
+                break;
+            }
+        }
+
+        let change = change.expect("a tour change from the listener");
+        assert_eq!(change.operation, TourChangeOperation::Updated);
+        assert_eq!(change.tour_id, tour_id);
+        assert!(change.tour.unwrap().title.starts_with("Old Lisbon "));
+        let event = tokio::time::timeout(RECEIVE_TIMEOUT, server_events.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.kind, ServerEventKind::TourUpdated);
+        assert_eq!(event.data["id"], tour_id);
+    }
+
+    async fn next_event_of_kind(
+        events: &mut (impl Stream<Item = ServerEvent> + Unpin),
+        kind: ServerEventKind,
+    ) -> ServerEvent {
+        loop {
+            let event = tokio::time::timeout(RECEIVE_TIMEOUT, events.next())
+                .await
+                .expect("an event before the timeout")
+                .unwrap();
+            if event.kind == kind {
+                return event;
+            }
+        }
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn departure_changes_notify_the_owners_of_its_bookings(pool: PgPool) {
+        let departure_id = insert_departure(&pool, 1000, 5).await;
+        let bookings = booking_service(&pool);
+        let departures = departure_service(&pool);
+        let mut booking_ids = Vec::new();
+        for user_id in ["owner", "other", "gone"] {
+            let booking = bookings
+                .book_tour(&user(user_id), departure_id, adults(1), None)
+                .await
+                .unwrap();
+            booking_ids.push(booking.id);
+        }
+        bookings
+            .cancel_booking(&user("gone"), booking_ids[2])
+            .await
+            .unwrap();
+        let event_service = listening_event_service(&pool);
+        let events = event_service.subscribe_server_events(Some(admin()), None);
+        pin_mut!(events);
+
+        // The listener connects in the background, so keep rescheduling until it
+        // hears about one of them. Each reschedule notifies both confirmed bookings.
+        let mut first = None;
+        for attempt in 0..20 {
+            let starts_at = Utc::now() + TimeDelta::days(8) + TimeDelta::minutes(attempt);
+            departures
+                .reschedule_departure(
+                    departure_id,
+                    RescheduleDepartureInput {
+                        starts_at,
+                        ends_at: starts_at + TimeDelta::hours(2),
+                    },
+                )
+                .await
+                .unwrap();
+            if let Ok(received) =
+                tokio::time::timeout(Duration::from_millis(250), events.next()).await
+            {
+                first = received;
+                break;
+            }
+        }
+        let first = first.expect("a departure change from the listener");
+        let second = next_event_of_kind(&mut events, ServerEventKind::DepartureRescheduled).await;
+        for event in [&first, &second] {
+            assert_eq!(event.kind, ServerEventKind::DepartureRescheduled);
+            assert_eq!(event.data["departure"]["id"], departure_id);
+        }
+        assert_eq!(
+            [first.audience, second.audience],
+            [
+                EventAudience::User("owner".to_string()),
+                EventAudience::User("other".to_string())
+            ]
+        );
+        assert_eq!(
+            [&first.data["booking"]["id"], &second.data["booking"]["id"]],
+            [booking_ids[0], booking_ids[1]]
+        );
+
+        let owner_events = event_service.subscribe_server_events(Some(user("owner")), None);
+        let gone_events = event_service.subscribe_server_events(Some(user("gone")), None);
+        pin_mut!(owner_events);
+        departures.cancel_departure(departure_id).await.unwrap();
+
+        let cancelled =
+            next_event_of_kind(&mut owner_events, ServerEventKind::DepartureCancelled).await;
+        assert_eq!(cancelled.data["booking"]["id"], booking_ids[0]);
+        assert_eq!(cancelled.data["booking"]["status"], "cancelled");
+        next_event_of_kind(&mut events, ServerEventKind::DepartureCancelled).await;
+        next_event_of_kind(&mut events, ServerEventKind::DepartureCancelled).await;
+        // Nothing was addressed to the user whose booking was already cancelled.
+        publish_public(&event_service, "last");
+        let last_id = event_service.replay_buffer.lock().unwrap().last_id;
+        let gone_ids = received_ids(gone_events, last_id).await;
+        assert_eq!(gone_ids, [last_id]);
+    }
+}
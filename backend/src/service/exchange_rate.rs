@@ -0,0 +1,290 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+use crate::models::money::{validate_currency, ExchangeRate, Money};
+use crate::repository::exchange_rate::ExchangeRateRepository;
+use crate::schema::validation::ValidationErrors;
+use crate::service::ServiceError;
+
+// The precision of `exchange_rates.rate`; finer rates are rejected rather than
+// rounded by the database.
+const MAX_RATE_DECIMAL_PLACES: u32 = 12;
+const MAX_RATE_INTEGER_DIGITS: u32 = 12;
+
+#[derive(Clone)]
+pub struct ExchangeRateService {
+    repository: Arc<dyn ExchangeRateRepository>,
+}
+
+impl ExchangeRateService {
+    pub fn new(repository: Arc<dyn ExchangeRateRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn get_exchange_rates(&self) -> Result<Vec<ExchangeRate>, ServiceError> {
+        Ok(self.repository.find_all().await?)
+    }
+
+    pub async fn set_exchange_rate(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+        rate: Decimal,
+    ) -> Result<ExchangeRate, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        validate_currency(&mut errors, "baseCurrency", base_currency);
+        validate_currency(&mut errors, "quoteCurrency", quote_currency);
+        if base_currency == quote_currency {
+            errors.add("quoteCurrency", "must differ from baseCurrency");
+        }
+        if rate <= Decimal::ZERO {
+            errors.add("rate", "must be greater than 0");
+        } else if rate.normalize().scale() > MAX_RATE_DECIMAL_PLACES {
+            errors.add(
+                "rate",
+                format!(
+                    "must have at most {} decimal places",
+                    MAX_RATE_DECIMAL_PLACES
+                ),
+            );
+        } else if rate >= Decimal::from(10_i64.pow(MAX_RATE_INTEGER_DIGITS)) {
+            errors.add("rate", "is out of range");
+        }
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        Ok(self
+            .repository
+            .upsert(base_currency, quote_currency, rate.normalize(), Utc::now())
+            .await?)
+    }
+
+    pub async fn delete_exchange_rate(
+        &self,
+        base_currency: &str,
+        quote_currency: &str,
+    ) -> Result<(), ServiceError> {
+        if self
+            .repository
+            .delete(base_currency, quote_currency)
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(ServiceError::NotFound)
+        }
+    }
+
+    // Rounds with `CONVERSION_ROUNDING`. A reverse rate is divided by rather than
+    // inverted first, which keeps its full precision. Returns `None` when no rate
+    // between the currencies is set.
+    pub async fn convert(
+        &self,
+        money: &Money,
+        currency: &str,
+    ) -> Result<Option<Money>, ServiceError> {
+        let invalid = |message: String| {
+            let mut errors = ValidationErrors::new();
+            errors.add("currency", message);
+            ServiceError::Validation(errors)
+        };
+
+        let mut errors = ValidationErrors::new();
+        validate_currency(&mut errors, "currency", currency);
+        errors.into_result().map_err(ServiceError::Validation)?;
+        if money.currency == currency {
+            return Ok(Some(money.clone()));
+        }
+
+        let Some((rate, is_reverse)) = self.repository.find_rate(&money.currency, currency).await?
+        else {
+            return Ok(None);
+        };
+        let amount = if is_reverse {
+            money.as_decimal().checked_div(rate)
+        } else {
+            money.as_decimal().checked_mul(rate)
+        };
+
+        amount
+            .ok_or_else(|| invalid("conversion is out of range".to_string()))
+            .and_then(|amount| {
+                Money::from_decimal_rounded(amount, currency)
+                    .map(Some)
+                    .map_err(|error| invalid(format!("conversion {}", error)))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::repository::RepositoryError;
+
+    // Rates set for `(base, quote)` pairs, looked up like the Postgres repository.
+    struct Rates(Vec<(&'static str, &'static str, &'static str)>);
+
+    #[async_trait]
+    impl ExchangeRateRepository for Rates {
+        async fn find_all(&self) -> Result<Vec<ExchangeRate>, RepositoryError> {
+            Ok(self
+                .0
+                .iter()
+                .map(|(base, quote, rate)| ExchangeRate {
+                    base_currency: base.to_string(),
+                    quote_currency: quote.to_string(),
+                    rate: rate.parse().unwrap(),
+                    updated_at: DateTime::UNIX_EPOCH,
+                })
+                .collect())
+        }
+
+        async fn find_rate(
+            &self,
+            base_currency: &str,
+            quote_currency: &str,
+        ) -> Result<Option<(Decimal, bool)>, RepositoryError> {
+            let find = |base: &str, quote: &str| {
+                self.0
+                    .iter()
+                    .find(|(rate_base, rate_quote, _)| *rate_base == base && *rate_quote == quote)
+                    .map(|(_, _, rate)| rate.parse().unwrap())
+            };
+
+            Ok(find(base_currency, quote_currency)
+                .map(|rate| (rate, false))
+                .or_else(|| find(quote_currency, base_currency).map(|rate| (rate, true))))
+        }
+
+        async fn upsert(
+            &self,
+            base_currency: &str,
+            quote_currency: &str,
+            rate: Decimal,
+            now: DateTime<Utc>,
+        ) -> Result<ExchangeRate, RepositoryError> {
+            Ok(ExchangeRate {
+                base_currency: base_currency.to_string(),
+                quote_currency: quote_currency.to_string(),
+                rate,
+                updated_at: now,
+            })
+        }
+
+        async fn delete(
+            &self,
+            _base_currency: &str,
+            _quote_currency: &str,
+        ) -> Result<bool, RepositoryError> {
+            Ok(false)
+        }
+    }
+
+    fn service() -> ExchangeRateService {
+        ExchangeRateService::new(Arc::new(Rates(vec![
+            ("USD", "JPY", "151.37"),
+            ("USD", "BHD", "0.376"),
+            ("USD", "EUR", "0.5"),
+            ("EUR", "USD", "2.2"),
+            ("EUR", "GBP", "3"),
+            ("USD", "KRW", "100000000000"),
+        ])))
+    }
+
+    fn invalid_currency(error: ServiceError) -> Vec<String> {
+        match error {
+            ServiceError::Validation(errors) => errors.fields()["currency"].clone(),
+            error => panic!("expected a validation error, got {:?}", error),
+        }
+    }
+
+    #[actix_web::test]
+    async fn conversions_round_to_the_target_minor_unit() {
+        let service = service();
+        let cases = [
+            // Forward rates.
+            (Money::new(1234, "USD"), "JPY", 1868),
+            (Money::new(1, "USD"), "JPY", 2),
+            (Money::new(1000, "USD"), "BHD", 3760),
+            (Money::new(1, "USD"), "BHD", 4),
+            // Midpoints go to the even neighbour.
+            (Money::new(25, "USD"), "EUR", 12),
+            (Money::new(75, "USD"), "EUR", 38),
+            // A rate set in both directions is used as is, never inverted.
+            (Money::new(1000, "EUR"), "USD", 2200),
+            // Reverse rates are divided by.
+            (Money::new(1000, "JPY"), "USD", 661),
+            (Money::new(100, "GBP"), "EUR", 33),
+            (Money::new(200, "GBP"), "EUR", 67),
+            (Money::new(150, "GBP"), "EUR", 50),
+            (Money::new(1, "BHD"), "USD", 0),
+            (Money::new(376, "BHD"), "USD", 100),
+            // Money already in the currency is returned unchanged.
+            (Money::new(1234, "USD"), "USD", 1234),
+        ];
+
+        for (money, currency, amount_minor) in cases {
+            assert_eq!(
+                service.convert(&money, currency).await.unwrap(),
+                Some(Money::new(amount_minor, currency)),
+                "{:?} to {}",
+                money,
+                currency
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn conversions_without_a_rate_are_none() {
+        let service = service();
+
+        assert_eq!(
+            service
+                .convert(&Money::new(1000, "JPY"), "EUR")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            service
+                .convert(&Money::new(1000, "CHF"), "USD")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[actix_web::test]
+    async fn conversions_to_unsupported_currencies_are_rejected() {
+        let service = service();
+
+        for currency in ["XBT", "usd", ""] {
+            let error = service
+                .convert(&Money::new(1000, "USD"), currency)
+                .await
+                .unwrap_err();
+            assert_eq!(
+                invalid_currency(error),
+                vec!["must be a supported ISO 4217 currency code".to_string()],
+                "{:?}",
+                currency
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn conversions_out_of_range_are_rejected() {
+        let error = service()
+            .convert(&Money::new(i64::MAX, "USD"), "KRW")
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            invalid_currency(error),
+            vec!["conversion is out of range".to_string()]
+        );
+    }
+}
@@ -0,0 +1,44 @@
+pub mod booking;
+pub mod cache;
+pub mod departure;
+pub mod events;
+pub mod exchange_rate;
+pub mod oauth;
+pub mod payment;
+pub mod place;
+pub mod pricing;
+pub mod promotion;
+pub mod schedule;
+pub mod tour;
+pub mod waitlist;
+
+use crate::repository::RepositoryError;
+use crate::schema::validation::ValidationErrors;
+use crate::service::payment::provider::PaymentProviderError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceError {
+    #[error("not found")]
+    NotFound,
+    #[error("validation failed")]
+    Validation(ValidationErrors),
+    #[error("{0}")]
+    Conflict(String),
+    #[error(transparent)]
+    Repository(RepositoryError),
+    #[error(transparent)]
+    PaymentProvider(PaymentProviderError),
+    #[error("payments are disabled")]
+    PaymentsDisabled,
+}
+
+impl From<RepositoryError> for ServiceError {
+    fn from(error: RepositoryError) -> Self {
+        match error {
+            RepositoryError::Conflict(_) => {
+                ServiceError::Conflict("Resource already exists".to_string())
+            }
+            error => ServiceError::Repository(error),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use oauth2::basic::BasicClient;
+use oauth2::{
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, RedirectUrl, Scope,
+    TokenResponse, TokenUrl,
+};
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+use url::Url;
+
+use crate::config::{get_required_env, ConfigError};
+
+const USER_INFO_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_FRONTEND_URL: &str = "http://localhost:3000";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OAuthProviderKind {
+    Github,
+    Google,
+}
+
+impl OAuthProviderKind {
+    pub fn session_key(self) -> &'static str {
+        match self {
+            OAuthProviderKind::Github => "github_user",
+            OAuthProviderKind::Google => "google_user",
+        }
+    }
+
+    // Where the `state` of a pending sign-in is kept until its callback.
+    pub fn state_session_key(self) -> &'static str {
+        match self {
+            OAuthProviderKind::Github => "github_oauth_state",
+            OAuthProviderKind::Google => "google_oauth_state",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            OAuthProviderKind::Github => "GitHub",
+            OAuthProviderKind::Google => "Google",
+        }
+    }
+
+    fn env_prefix(self) -> &'static str {
+        match self {
+            OAuthProviderKind::Github => "GITHUB",
+            OAuthProviderKind::Google => "GOOGLE",
+        }
+    }
+
+    fn default_urls(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            OAuthProviderKind::Github => (
+                "https://github.com/login/oauth/authorize",
+                "https://github.com/login/oauth/access_token",
+                "https://api.github.com/user",
+            ),
+            OAuthProviderKind::Google => (
+                "https://accounts.google.com/o/oauth2/auth",
+                "https://oauth2.googleapis.com/token",
+                "https://www.googleapis.com/oauth2/v2/userinfo",
+            ),
+        }
+    }
+
+    fn scopes(self) -> &'static [&'static str] {
+        match self {
+            OAuthProviderKind::Github => &["user:email", "read:user"],
+            OAuthProviderKind::Google => &["openid", "email", "profile"],
+        }
+    }
+
+    // The user info field stored in the session.
+    fn user_field(self) -> &'static str {
+        match self {
+            OAuthProviderKind::Github => "login",
+            OAuthProviderKind::Google => "email",
+        }
+    }
+
+    fn authorization_header(self, access_token: &str) -> String {
+        match self {
+            OAuthProviderKind::Github => format!("token {}", access_token),
+            OAuthProviderKind::Google => format!("Bearer {}", access_token),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OAuthError {
+    #[error("{0} sign-in is not configured")]
+    NotConfigured(&'static str),
+    #[error("authorization was denied: {0}")]
+    AuthorizationDenied(String),
+    #[error("callback state does not match the issued state")]
+    InvalidState,
+    #[error("token exchange failed: {0}")]
+    TokenExchange(String),
+    #[error("user info request failed: {0}")]
+    UserInfo(#[from] reqwest::Error),
+    #[error("user info response has no `{0}`")]
+    MissingUserField(&'static str),
+    #[error("failed to store the session: {0}")]
+    Session(String),
+}
+
+impl OAuthError {
+    // Stable values for the `error` query parameter of the sign-in page.
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::NotConfigured(_) => "provider_unavailable",
+            OAuthError::AuthorizationDenied(_) => "access_denied",
+            OAuthError::InvalidState => "invalid_state",
+            OAuthError::TokenExchange(_) => "token_exchange_failed",
+            OAuthError::UserInfo(_) => "user_info_unavailable",
+            OAuthError::MissingUserField(_) => "missing_user_info",
+            OAuthError::Session(_) => "session_failed",
+        }
+    }
+}
+
+struct OAuthProvider {
+    client: BasicClient,
+    user_info_url: Url,
+}
+
+#[derive(Clone)]
+pub struct OAuthService {
+    github: Option<Arc<OAuthProvider>>,
+    google: Option<Arc<OAuthProvider>>,
+    http_client: reqwest::Client,
+    frontend_url: Url,
+}
+
+impl OAuthService {
+    // Providers without a client id are disabled instead of failing startup.
+    // Endpoint URLs can be overridden (e.g. `GITHUB_TOKEN_URL`) to point at a
+    // stub server.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let http_client = reqwest::Client::builder()
+            .timeout(USER_INFO_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            github: load_provider(OAuthProviderKind::Github)?.map(Arc::new),
+            google: load_provider(OAuthProviderKind::Google)?.map(Arc::new),
+            http_client,
+            frontend_url: parse_url_env("FRONTEND_URL", Some(DEFAULT_FRONTEND_URL))?,
+        })
+    }
+
+    // Both providers talk to `base_url`: `/authorize`, `/token` and `/user`.
+    #[cfg(test)]
+    pub fn stub(base_url: &Url) -> Self {
+        let provider = || {
+            Arc::new(OAuthProvider {
+                client: BasicClient::new(
+                    ClientId::new("client-id".to_string()),
+                    Some(ClientSecret::new("client-secret".to_string())),
+                    AuthUrl::from_url(base_url.join("authorize").unwrap()),
+                    Some(TokenUrl::from_url(base_url.join("token").unwrap())),
+                ),
+                user_info_url: base_url.join("user").unwrap(),
+            })
+        };
+
+        Self {
+            github: Some(provider()),
+            google: Some(provider()),
+            http_client: reqwest::Client::new(),
+            frontend_url: Url::parse(DEFAULT_FRONTEND_URL).unwrap(),
+        }
+    }
+
+    // The returned state has to be kept by the caller and compared with the one
+    // the provider sends back to the callback.
+    pub fn authorize_url(&self, kind: OAuthProviderKind) -> Result<(Url, CsrfToken), OAuthError> {
+        let provider = self.get_provider(kind)?;
+        let mut request = provider.client.authorize_url(CsrfToken::new_random);
+        for scope in kind.scopes() {
+            request = request.add_scope(Scope::new(scope.to_string()));
+        }
+
+        let (mut authorize_url, state) = request.url();
+        authorize_url
+            .query_pairs_mut()
+            .append_pair("prompt", "consent");
+
+        Ok((authorize_url, state))
+    }
+
+    pub async fn fetch_user(
+        &self,
+        kind: OAuthProviderKind,
+        code: &str,
+    ) -> Result<String, OAuthError> {
+        let provider = self.get_provider(kind)?;
+        let token = provider
+            .client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
+            .await
+            .map_err(|error| OAuthError::TokenExchange(error.to_string()))?;
+
+        let user_info = self
+            .http_client
+            .get(provider.user_info_url.clone())
+            .header(
+                reqwest::header::AUTHORIZATION,
+                kind.authorization_header(token.access_token().secret()),
+            )
+            .header(reqwest::header::USER_AGENT, "actix-web-app")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?;
+
+        user_info[kind.user_field()]
+            .as_str()
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .ok_or(OAuthError::MissingUserField(kind.user_field()))
+    }
+
+    pub fn sign_in_error_url(&self, error: &OAuthError) -> String {
+        let mut url = self.frontend_url.clone();
+        url.set_path("/sign-in");
+        url.query_pairs_mut().append_pair("error", error.code());
+        url.to_string()
+    }
+
+    fn get_provider(&self, kind: OAuthProviderKind) -> Result<&OAuthProvider, OAuthError> {
+        match kind {
+            OAuthProviderKind::Github => self.github.as_deref(),
+            OAuthProviderKind::Google => self.google.as_deref(),
+        }
+        .ok_or(OAuthError::NotConfigured(kind.name()))
+    }
+}
+
+fn load_provider(kind: OAuthProviderKind) -> Result<Option<OAuthProvider>, ConfigError> {
+    let env_name = |suffix: &str| format!("{}_{}", kind.env_prefix(), suffix);
+
+    let Ok(client_id) = env::var(env_name("CLIENT_ID")) else {
+        warn!(
+            "{} is not set, {} sign-in is disabled",
+            env_name("CLIENT_ID"),
+            kind.name()
+        );
+        return Ok(None);
+    };
+    let client_secret = get_required_env(&env_name("CLIENT_SECRET"))?;
+
+    let (auth_url, token_url, user_info_url) = kind.default_urls();
+    let mut client = BasicClient::new(
+        ClientId::new(client_id),
+        Some(ClientSecret::new(client_secret)),
+        AuthUrl::from_url(parse_url_env(&env_name("AUTH_URL"), Some(auth_url))?),
+        Some(TokenUrl::from_url(parse_url_env(
+            &env_name("TOKEN_URL"),
+            Some(token_url),
+        )?)),
+    );
+    if env::var(env_name("REDIRECT_URL")).is_ok() {
+        let redirect_url = parse_url_env(&env_name("REDIRECT_URL"), None)?;
+        client = client.set_redirect_uri(RedirectUrl::from_url(redirect_url));
+    }
+
+    Ok(Some(OAuthProvider {
+        client,
+        user_info_url: parse_url_env(&env_name("USER_INFO_URL"), Some(user_info_url))?,
+    }))
+}
+
+fn parse_url_env(name: &str, default: Option<&str>) -> Result<Url, ConfigError> {
+    let value = match (env::var(name), default) {
+        (Ok(value), _) => value,
+        (Err(_), Some(default)) => default.to_string(),
+        (Err(_), None) => return Err(ConfigError::MissingVariable(name.to_string())),
+    };
+
+    Url::parse(&value).map_err(|error| ConfigError::InvalidVariable {
+        name: name.to_string(),
+        message: error.to_string(),
+    })
+}
@@ -0,0 +1,489 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::sync::Mutex;
+
+use crate::config::{get_required_env, ConfigError};
+use crate::models::payment::{PaymentStatus, PaymentWebhookEvent};
+use crate::service::payment::provider::{
+    CreateIntent, PaymentProvider, PaymentProviderError, ProviderOutcome,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Operation {
+    Create,
+    Capture,
+    Refund,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ScriptedOutcome {
+    Succeed,
+    Decline(String),
+    Error(String),
+}
+
+#[derive(Default)]
+struct FakeState {
+    next_id: u64,
+    script: HashMap<Operation, VecDeque<ScriptedOutcome>>,
+    intents: HashMap<String, ProviderOutcome>,
+    payments: HashMap<String, FakePayment>,
+}
+
+struct FakePayment {
+    status: PaymentStatus,
+    amount_minor: i64,
+    currency: String,
+}
+
+#[derive(Deserialize)]
+struct FakeWebhookPayload {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    payment_id: String,
+    failure_reason: Option<String>,
+}
+
+// An in-process provider for development and tests that never moves money. Every operation succeeds unless its
+// outcomes are scripted, e.g. FAKE_PAYMENT_SCRIPT="create=decline:card_declined,succeed;
+// capture=error:timeout" declines the first payment, and fails the first capture.
+// Scripted outcomes are used up in order. Webhooks are signed with the hex
+// HMAC-SHA256 of the body under FAKE_PAYMENT_WEBHOOK_SECRET.
+pub struct FakePaymentProvider {
+    webhook_secret: String,
+    state: Mutex<FakeState>,
+}
+
+impl FakePaymentProvider {
+    pub fn new(webhook_secret: String, script: &str) -> Result<Self, String> {
+        let state = FakeState {
+            script: parse_script(script)?,
+            ..FakeState::default()
+        };
+
+        Ok(Self {
+            webhook_secret,
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let webhook_secret = get_required_env("FAKE_PAYMENT_WEBHOOK_SECRET")?;
+        if webhook_secret.trim().is_empty() {
+            return Err(ConfigError::InvalidVariable {
+                name: "FAKE_PAYMENT_WEBHOOK_SECRET".to_string(),
+                message: "must not be empty".to_string(),
+            });
+        }
+        let script = env::var("FAKE_PAYMENT_SCRIPT").unwrap_or_default();
+
+        Self::new(webhook_secret, &script).map_err(|message| ConfigError::InvalidVariable {
+            name: "FAKE_PAYMENT_SCRIPT".to_string(),
+            message,
+        })
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn run(
+        state: &mut FakeState,
+        operation: Operation,
+        provider_payment_id: &str,
+        succeeded: PaymentStatus,
+        declined: PaymentStatus,
+    ) -> Result<ProviderOutcome, PaymentProviderError> {
+        let outcome = state
+            .script
+            .get_mut(&operation)
+            .and_then(VecDeque::pop_front)
+            .unwrap_or(ScriptedOutcome::Succeed);
+
+        let (status, failure_reason) = match outcome {
+            ScriptedOutcome::Succeed => (succeeded, None),
+            ScriptedOutcome::Decline(reason) => (declined, Some(reason)),
+            ScriptedOutcome::Error(message) => return Err(PaymentProviderError::Request(message)),
+        };
+        if let Some(payment) = state.payments.get_mut(provider_payment_id) {
+            payment.status = status;
+        }
+
+        Ok(ProviderOutcome {
+            provider_payment_id: provider_payment_id.to_string(),
+            status,
+            failure_reason,
+        })
+    }
+
+    fn expect_status(
+        state: &FakeState,
+        provider_payment_id: &str,
+        expected: PaymentStatus,
+    ) -> Result<(), PaymentProviderError> {
+        match state.payments.get(provider_payment_id) {
+            Some(payment) if payment.status == expected => Ok(()),
+            Some(payment) => Err(PaymentProviderError::Request(format!(
+                "payment {} is {}",
+                provider_payment_id,
+                payment.status.as_str()
+            ))),
+            None => Err(PaymentProviderError::Request(format!(
+                "no such payment: {}",
+                provider_payment_id
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for FakePaymentProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn create_intent(
+        &self,
+        intent: &CreateIntent,
+    ) -> Result<ProviderOutcome, PaymentProviderError> {
+        let mut state = self.state();
+        if let Some(outcome) = state.intents.get(&intent.idempotency_key) {
+            return Ok(outcome.clone());
+        }
+
+        state.next_id += 1;
+        let provider_payment_id = format!("fake_pi_{}", state.next_id);
+        state.payments.insert(
+            provider_payment_id.clone(),
+            FakePayment {
+                status: PaymentStatus::Pending,
+                amount_minor: intent.amount_minor,
+                currency: intent.currency.clone(),
+            },
+        );
+        let outcome = Self::run(
+            &mut state,
+            Operation::Create,
+            &provider_payment_id,
+            PaymentStatus::Authorized,
+            PaymentStatus::Failed,
+        )?;
+        state
+            .intents
+            .insert(intent.idempotency_key.clone(), outcome.clone());
+
+        Ok(outcome)
+    }
+
+    async fn capture(
+        &self,
+        provider_payment_id: &str,
+    ) -> Result<ProviderOutcome, PaymentProviderError> {
+        let mut state = self.state();
+        Self::expect_status(&state, provider_payment_id, PaymentStatus::Authorized)?;
+
+        Self::run(
+            &mut state,
+            Operation::Capture,
+            provider_payment_id,
+            PaymentStatus::Captured,
+            PaymentStatus::Failed,
+        )
+    }
+
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        amount_minor: i64,
+    ) -> Result<ProviderOutcome, PaymentProviderError> {
+        let mut state = self.state();
+        Self::expect_status(&state, provider_payment_id, PaymentStatus::Captured)?;
+        if let Some(payment) = state
+            .payments
+            .get(provider_payment_id)
+            .filter(|payment| amount_minor > payment.amount_minor)
+        {
+            return Err(PaymentProviderError::Request(format!(
+                "refund exceeds the captured {} {}",
+                payment.amount_minor, payment.currency
+            )));
+        }
+
+        // A declined refund leaves the payment captured.
+        Self::run(
+            &mut state,
+            Operation::Refund,
+            provider_payment_id,
+            PaymentStatus::Refunded,
+            PaymentStatus::Captured,
+        )
+    }
+
+    fn verify_webhook(
+        &self,
+        payload: &[u8],
+        signature: &str,
+    ) -> Result<PaymentWebhookEvent, PaymentProviderError> {
+        let signature =
+            hex::decode(signature.trim()).map_err(|_| PaymentProviderError::InvalidSignature)?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.webhook_secret.as_bytes())
+            .map_err(|_| PaymentProviderError::InvalidSignature)?;
+        mac.update(payload);
+        mac.verify_slice(&signature)
+            .map_err(|_| PaymentProviderError::InvalidSignature)?;
+
+        let payload: FakeWebhookPayload = serde_json::from_slice(payload)
+            .map_err(|error| PaymentProviderError::MalformedWebhook(error.to_string()))?;
+        let status = match payload.kind.as_str() {
+            "payment.authorized" => PaymentStatus::Authorized,
+            "payment.captured" => PaymentStatus::Captured,
+            "payment.refunded" => PaymentStatus::Refunded,
+            "payment.failed" => PaymentStatus::Failed,
+            other => {
+                return Err(PaymentProviderError::MalformedWebhook(format!(
+                    "unknown event type {}",
+                    other
+                )))
+            }
+        };
+
+        Ok(PaymentWebhookEvent {
+            id: payload.id,
+            provider_payment_id: payload.payment_id,
+            status,
+            failure_reason: payload.failure_reason,
+        })
+    }
+}
+
+fn parse_script(script: &str) -> Result<HashMap<Operation, VecDeque<ScriptedOutcome>>, String> {
+    let mut parsed = HashMap::new();
+    for entry in script
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (operation, outcomes) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected <operation>=<outcomes> in {:?}", entry))?;
+        let operation = match operation.trim() {
+            "create" => Operation::Create,
+            "capture" => Operation::Capture,
+            "refund" => Operation::Refund,
+            other => return Err(format!("unknown operation {:?}", other)),
+        };
+        let outcomes = outcomes
+            .split(',')
+            .map(|outcome| {
+                let (kind, detail) = outcome
+                    .trim()
+                    .split_once(':')
+                    .unwrap_or((outcome.trim(), ""));
+                match kind {
+                    "succeed" => Ok(ScriptedOutcome::Succeed),
+                    "decline" => Ok(ScriptedOutcome::Decline(or_default(detail, "declined"))),
+                    "error" => Ok(ScriptedOutcome::Error(or_default(detail, "unavailable"))),
+                    other => Err(format!("unknown outcome {:?}", other)),
+                }
+            })
+            .collect::<Result<VecDeque<_>, _>>()?;
+        parsed.insert(operation, outcomes);
+    }
+
+    Ok(parsed)
+}
+
+fn or_default(detail: &str, default: &str) -> String {
+    if detail.is_empty() {
+        default.to_string()
+    } else {
+        detail.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+
+    fn intent(key: &str, amount_minor: i64) -> CreateIntent {
+        CreateIntent {
+            idempotency_key: key.to_string(),
+            amount_minor,
+            currency: "EUR".to_string(),
+        }
+    }
+
+    fn sign(secret: &str, payload: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(payload);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[actix_web::test]
+    async fn scripted_outcomes_are_used_up_in_order() {
+        let provider = FakePaymentProvider::new(
+            SECRET.to_string(),
+            "create=decline:card_declined,succeed; capture=error:timeout",
+        )
+        .unwrap();
+
+        let declined = provider
+            .create_intent(&intent("payment-1", 1000))
+            .await
+            .unwrap();
+        assert_eq!(declined.status, PaymentStatus::Failed);
+        assert_eq!(declined.failure_reason.as_deref(), Some("card_declined"));
+
+        let authorized = provider
+            .create_intent(&intent("payment-2", 1000))
+            .await
+            .unwrap();
+        assert_eq!(authorized.status, PaymentStatus::Authorized);
+        assert_eq!(authorized.failure_reason, None);
+        let id = authorized.provider_payment_id;
+
+        let error = provider.capture(&id).await.unwrap_err();
+        assert!(matches!(error, PaymentProviderError::Request(message) if message == "timeout"));
+        // Once the script runs out, operations succeed.
+        assert_eq!(
+            provider.capture(&id).await.unwrap().status,
+            PaymentStatus::Captured
+        );
+        assert_eq!(
+            provider.refund(&id, 1000).await.unwrap().status,
+            PaymentStatus::Refunded
+        );
+    }
+
+    #[actix_web::test]
+    async fn declined_refunds_leave_the_payment_captured() {
+        let provider = FakePaymentProvider::new(SECRET.to_string(), "refund=decline").unwrap();
+        let id = provider
+            .create_intent(&intent("payment-1", 1000))
+            .await
+            .unwrap()
+            .provider_payment_id;
+        provider.capture(&id).await.unwrap();
+
+        let declined = provider.refund(&id, 1000).await.unwrap();
+        assert_eq!(declined.status, PaymentStatus::Captured);
+        assert_eq!(declined.failure_reason.as_deref(), Some("declined"));
+        assert_eq!(
+            provider.refund(&id, 1000).await.unwrap().status,
+            PaymentStatus::Refunded
+        );
+    }
+
+    #[actix_web::test]
+    async fn retried_intents_return_the_first_outcome() {
+        let provider = FakePaymentProvider::new(SECRET.to_string(), "create=decline").unwrap();
+
+        let first = provider
+            .create_intent(&intent("payment-1", 1000))
+            .await
+            .unwrap();
+        let retried = provider
+            .create_intent(&intent("payment-1", 1000))
+            .await
+            .unwrap();
+        assert_eq!(retried.provider_payment_id, first.provider_payment_id);
+        assert_eq!(retried.status, PaymentStatus::Failed);
+    }
+
+    #[actix_web::test]
+    async fn operations_out_of_order_are_rejected() {
+        let provider =
+            FakePaymentProvider::new(SECRET.to_string(), "create=decline,succeed").unwrap();
+        let failed = provider
+            .create_intent(&intent("payment-1", 1000))
+            .await
+            .unwrap()
+            .provider_payment_id;
+        let id = provider
+            .create_intent(&intent("payment-2", 1000))
+            .await
+            .unwrap()
+            .provider_payment_id;
+
+        assert!(provider.capture(&failed).await.is_err());
+        assert!(provider.capture("fake_pi_404").await.is_err());
+        assert!(provider.refund(&id, 1000).await.is_err());
+
+        provider.capture(&id).await.unwrap();
+        assert!(provider.capture(&id).await.is_err());
+        assert!(provider.refund(&id, 1001).await.is_err());
+
+        provider.refund(&id, 1000).await.unwrap();
+        assert!(provider.refund(&id, 1000).await.is_err());
+        assert!(provider.capture(&id).await.is_err());
+    }
+
+    #[test]
+    fn invalid_scripts_are_rejected() {
+        for script in [
+            "create",
+            "authorize=succeed",
+            "capture=explode",
+            "create=succeed;refund=later",
+        ] {
+            assert!(
+                FakePaymentProvider::new(SECRET.to_string(), script).is_err(),
+                "{}",
+                script
+            );
+        }
+    }
+
+    #[test]
+    fn webhooks_need_a_valid_signature() {
+        let provider = FakePaymentProvider::new(SECRET.to_string(), "").unwrap();
+        let payload =
+            br#"{"id":"evt_1","type":"payment.failed","payment_id":"fake_pi_1","failure_reason":"expired"}"#;
+
+        let event = provider
+            .verify_webhook(payload, &sign(SECRET, payload))
+            .unwrap();
+        assert_eq!(event.id, "evt_1");
+        assert_eq!(event.provider_payment_id, "fake_pi_1");
+        assert_eq!(event.status, PaymentStatus::Failed);
+        assert_eq!(event.failure_reason.as_deref(), Some("expired"));
+
+        let tampered = br#"{"id":"evt_1","type":"payment.captured","payment_id":"fake_pi_1","failure_reason":"expired"}"#;
+        for (payload, signature) in [
+            (&payload[..], sign("other-secret", payload)),
+            (&payload[..], "not-hex".to_string()),
+            (&payload[..], String::new()),
+            (&tampered[..], sign(SECRET, payload)),
+        ] {
+            assert!(matches!(
+                provider.verify_webhook(payload, &signature),
+                Err(PaymentProviderError::InvalidSignature)
+            ));
+        }
+    }
+
+    #[test]
+    fn signed_webhooks_must_be_known_events() {
+        let provider = FakePaymentProvider::new(SECRET.to_string(), "").unwrap();
+
+        for payload in [
+            &br#"{"id":"evt_1","type":"payment.disputed","payment_id":"fake_pi_1"}"#[..],
+            &br#"{"id":"evt_1","type":"payment.captured"}"#[..],
+            &b"not json"[..],
+        ] {
+            assert!(matches!(
+                provider.verify_webhook(payload, &sign(SECRET, payload)),
+                Err(PaymentProviderError::MalformedWebhook(_))
+            ));
+        }
+    }
+}
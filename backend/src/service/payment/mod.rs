@@ -0,0 +1,509 @@
+pub mod fake;
+pub mod provider;
+
+use chrono::Utc;
+use std::env;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::config::ConfigError;
+use crate::models::booking::BookingStatus;
+use crate::models::payment::{Payment, PaymentStatus, WebhookOutcome};
+use crate::models::user::CurrentUser;
+use crate::repository::payment::PaymentRepository;
+use crate::repository::RepositoryError;
+use crate::service::payment::fake::FakePaymentProvider;
+use crate::service::payment::provider::{CreateIntent, PaymentProvider, ProviderOutcome};
+use crate::service::ServiceError;
+
+// Loads the provider named by PAYMENT_PROVIDER, or none when it isn't set, which
+// disables payments. The fake provider accepts every payment, so it's only
+// available when `allow_fake` is set for development.
+pub fn provider_from_env(
+    allow_fake: bool,
+) -> Result<Option<Arc<dyn PaymentProvider>>, ConfigError> {
+    let Ok(name) = env::var("PAYMENT_PROVIDER") else {
+        return Ok(None);
+    };
+    let message = match name.as_str() {
+        "fake" if allow_fake => return Ok(Some(Arc::new(FakePaymentProvider::from_env()?))),
+        "fake" => "the fake provider needs --allow-fake-payments".to_string(),
+        other => format!("unknown provider {:?}", other),
+    };
+
+    Err(ConfigError::InvalidVariable {
+        name: "PAYMENT_PROVIDER".to_string(),
+        message,
+    })
+}
+
+#[derive(Clone)]
+pub struct PaymentService {
+    repository: Arc<dyn PaymentRepository>,
+    provider: Option<Arc<dyn PaymentProvider>>,
+}
+
+impl PaymentService {
+    pub fn new(
+        repository: Arc<dyn PaymentRepository>,
+        provider: Option<Arc<dyn PaymentProvider>>,
+    ) -> Self {
+        Self {
+            repository,
+            provider,
+        }
+    }
+
+    pub async fn get_payment(&self, id: i32) -> Result<Option<Payment>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn get_booking_payments(
+        &self,
+        booking_id: i32,
+    ) -> Result<Vec<Payment>, ServiceError> {
+        Ok(self.repository.find_by_booking(booking_id).await?)
+    }
+
+    pub async fn pay_booking(
+        &self,
+        user: &CurrentUser,
+        booking_id: i32,
+    ) -> Result<Payment, ServiceError> {
+        let provider = self.provider()?;
+        let booking = self
+            .repository
+            .find_payable_booking(booking_id)
+            .await?
+            .filter(|booking| booking.user_id == user.id || user.is_admin)
+            .ok_or(ServiceError::NotFound)?;
+        if booking.status != BookingStatus::Confirmed {
+            return Err(ServiceError::Conflict(
+                "Only confirmed bookings can be paid".to_string(),
+            ));
+        }
+        if booking.amount_minor <= 0 {
+            return Err(ServiceError::Conflict(
+                "Booking has nothing to pay".to_string(),
+            ));
+        }
+
+        let payment = self
+            .repository
+            .create(
+                booking_id,
+                provider.name(),
+                booking.amount_minor,
+                &booking.currency,
+                Utc::now(),
+            )
+            .await
+            .map_err(|error| match error {
+                RepositoryError::Conflict(_) => {
+                    ServiceError::Conflict("Booking already has a payment".to_string())
+                }
+                error => error.into(),
+            })?;
+
+        let intent = CreateIntent {
+            idempotency_key: format!("payment-{}", payment.id),
+            amount_minor: payment.amount_minor,
+            currency: payment.currency.clone(),
+        };
+        let outcome = match provider.create_intent(&intent).await {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                // Failing the payment lets the booking be paid again.
+                self.repository
+                    .transition(
+                        payment.id,
+                        PaymentStatus::Failed,
+                        Some(&error.to_string()),
+                        Utc::now(),
+                    )
+                    .await?;
+                return Err(ServiceError::PaymentProvider(error));
+            }
+        };
+
+        let payment = self
+            .repository
+            .set_provider_payment_id(payment.id, &outcome.provider_payment_id, Utc::now())
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+
+        self.apply_outcome(payment, outcome).await
+    }
+
+    pub async fn capture_payment(&self, id: i32) -> Result<Payment, ServiceError> {
+        let (payment, provider_payment_id) = self
+            .get_provider_payment(id, PaymentStatus::Authorized)
+            .await?;
+        let outcome = self
+            .provider()?
+            .capture(&provider_payment_id)
+            .await
+            .map_err(ServiceError::PaymentProvider)?;
+
+        self.apply_outcome(payment, outcome).await
+    }
+
+    pub async fn refund_payment(&self, id: i32) -> Result<Payment, ServiceError> {
+        let (payment, provider_payment_id) = self
+            .get_provider_payment(id, PaymentStatus::Captured)
+            .await?;
+        let outcome = self
+            .provider()?
+            .refund(&provider_payment_id, payment.amount_minor)
+            .await
+            .map_err(ServiceError::PaymentProvider)?;
+        if outcome.status != PaymentStatus::Refunded {
+            return Err(ServiceError::Conflict(format!(
+                "Refund was declined: {}",
+                outcome
+                    .failure_reason
+                    .as_deref()
+                    .unwrap_or("no reason given")
+            )));
+        }
+
+        self.apply_outcome(payment, outcome).await
+    }
+
+    pub async fn handle_webhook(
+        &self,
+        payload: &[u8],
+        signature: &str,
+    ) -> Result<WebhookOutcome, ServiceError> {
+        let provider = self.provider()?;
+        let event = provider
+            .verify_webhook(payload, signature)
+            .map_err(ServiceError::PaymentProvider)?;
+
+        let outcome = self
+            .repository
+            .apply_webhook_event(provider.name(), &event, Utc::now())
+            .await?;
+        match &outcome {
+            WebhookOutcome::Applied(payment) => info!(
+                "Payment {} is {} after webhook {}",
+                payment.id,
+                payment.status.as_str(),
+                event.id
+            ),
+            WebhookOutcome::Ignored => warn!(
+                "Ignored {} webhook {} moving payment {} to {}",
+                provider.name(),
+                event.id,
+                event.provider_payment_id,
+                event.status.as_str()
+            ),
+            WebhookOutcome::Duplicate => {}
+        }
+
+        Ok(outcome)
+    }
+
+    fn provider(&self) -> Result<&dyn PaymentProvider, ServiceError> {
+        self.provider
+            .as_deref()
+            .ok_or(ServiceError::PaymentsDisabled)
+    }
+
+    async fn get_provider_payment(
+        &self,
+        id: i32,
+        expected: PaymentStatus,
+    ) -> Result<(Payment, String), ServiceError> {
+        let provider = self.provider()?;
+        let payment = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        if payment.status != expected {
+            return Err(ServiceError::Conflict(format!(
+                "Payment is {}",
+                payment.status.as_str()
+            )));
+        }
+        let provider_payment_id = payment
+            .provider_payment_id
+            .clone()
+            .filter(|_| payment.provider == provider.name())
+            .ok_or_else(|| {
+                ServiceError::Conflict("Payment wasn't made with this provider".to_string())
+            })?;
+
+        Ok((payment, provider_payment_id))
+    }
+
+    async fn apply_outcome(
+        &self,
+        payment: Payment,
+        outcome: ProviderOutcome,
+    ) -> Result<Payment, ServiceError> {
+        if outcome.status == payment.status {
+            return Ok(payment);
+        }
+
+        let transitioned = self
+            .repository
+            .transition(
+                payment.id,
+                outcome.status,
+                outcome.failure_reason.as_deref(),
+                Utc::now(),
+            )
+            .await?;
+        match transitioned {
+            Some(payment) => Ok(payment),
+            // A webhook got there first.
+            None => self
+                .repository
+                .find_by_id(payment.id)
+                .await?
+                .ok_or(ServiceError::NotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::models::pricing::adults;
+    use crate::repository::booking::postgres::PostgresBookingRepository;
+    use crate::repository::payment::postgres::PostgresPaymentRepository;
+    use crate::service::booking::BookingService;
+    use crate::service::payment::provider::PaymentProviderError;
+    use crate::test_support::{insert_departure, user};
+
+    const SECRET: &str = "test-secret";
+
+    fn service(pool: &PgPool, script: &str) -> PaymentService {
+        PaymentService::new(
+            Arc::new(PostgresPaymentRepository::new(pool.clone())),
+            Some(Arc::new(
+                FakePaymentProvider::new(SECRET.to_string(), script).unwrap(),
+            )),
+        )
+    }
+
+    async fn confirmed_booking(pool: &PgPool, owner: &CurrentUser) -> i32 {
+        let departure_id = insert_departure(pool, 2500, 10).await;
+        BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 15)
+            .book_tour(owner, departure_id, adults(2), None)
+            .await
+            .unwrap()
+            .id
+    }
+
+    fn webhook(id: &str, kind: &str, payment_id: &str) -> (Vec<u8>, String) {
+        let payload = serde_json::json!({ "id": id, "type": kind, "payment_id": payment_id })
+            .to_string()
+            .into_bytes();
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(&payload);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        (payload, signature)
+    }
+
+    fn conflict(result: Result<Payment, ServiceError>) -> String {
+        match result {
+            Err(ServiceError::Conflict(message)) => message,
+            other => panic!(
+                "expected a conflict, got {:?}",
+                other.map(|payment| payment.status)
+            ),
+        }
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn payments_move_through_every_status(pool: PgPool) {
+        let owner = user("alice");
+        let booking_id = confirmed_booking(&pool, &owner).await;
+        let service = service(&pool, "create=decline:card_declined");
+
+        let declined = service.pay_booking(&owner, booking_id).await.unwrap();
+        assert_eq!(declined.status, PaymentStatus::Failed);
+        assert_eq!(declined.failure_reason.as_deref(), Some("card_declined"));
+        assert_eq!(declined.amount_minor, 5000);
+
+        // A failed payment doesn't block paying again.
+        let payment = service.pay_booking(&owner, booking_id).await.unwrap();
+        assert_eq!(payment.status, PaymentStatus::Authorized);
+        assert_eq!(
+            conflict(service.pay_booking(&owner, booking_id).await),
+            "Booking already has a payment"
+        );
+        assert_eq!(
+            conflict(service.refund_payment(payment.id).await),
+            "Payment is authorized"
+        );
+
+        let payment = service.capture_payment(payment.id).await.unwrap();
+        assert_eq!(payment.status, PaymentStatus::Captured);
+        assert_eq!(
+            conflict(service.capture_payment(payment.id).await),
+            "Payment is captured"
+        );
+
+        let payment = service.refund_payment(payment.id).await.unwrap();
+        assert_eq!(payment.status, PaymentStatus::Refunded);
+        assert_eq!(
+            conflict(service.capture_payment(payment.id).await),
+            "Payment is refunded"
+        );
+        assert_eq!(
+            conflict(service.refund_payment(payment.id).await),
+            "Payment is refunded"
+        );
+        assert_eq!(
+            conflict(service.capture_payment(declined.id).await),
+            "Payment is failed"
+        );
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn only_the_owner_can_pay(pool: PgPool) {
+        let booking_id = confirmed_booking(&pool, &user("alice")).await;
+
+        assert!(matches!(
+            service(&pool, "")
+                .pay_booking(&user("mallory"), booking_id)
+                .await,
+            Err(ServiceError::NotFound)
+        ));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn webhooks_apply_once_and_only_along_the_state_machine(pool: PgPool) {
+        let owner = user("alice");
+        let booking_id = confirmed_booking(&pool, &owner).await;
+        let service = service(&pool, "");
+        let payment = service.pay_booking(&owner, booking_id).await.unwrap();
+        let provider_payment_id = payment.provider_payment_id.clone().unwrap();
+
+        let (payload, signature) = webhook("evt_1", "payment.captured", &provider_payment_id);
+        let outcome = service.handle_webhook(&payload, &signature).await.unwrap();
+        assert!(
+            matches!(&outcome, WebhookOutcome::Applied(payment) if payment.status == PaymentStatus::Captured)
+        );
+        assert!(matches!(
+            service.handle_webhook(&payload, &signature).await.unwrap(),
+            WebhookOutcome::Duplicate
+        ));
+
+        // Captured payments can't go back to authorized or fail.
+        for (id, kind) in [("evt_2", "payment.authorized"), ("evt_3", "payment.failed")] {
+            let (payload, signature) = webhook(id, kind, &provider_payment_id);
+            assert!(matches!(
+                service.handle_webhook(&payload, &signature).await.unwrap(),
+                WebhookOutcome::Ignored
+            ));
+        }
+        let (payload, signature) = webhook("evt_4", "payment.captured", "fake_pi_404");
+        assert!(matches!(
+            service.handle_webhook(&payload, &signature).await.unwrap(),
+            WebhookOutcome::Ignored
+        ));
+
+        // Concurrent deliveries of one event are applied once.
+        let (payload, signature) = webhook("evt_5", "payment.refunded", &provider_payment_id);
+        let outcomes = join_all((0..5).map(|_| service.handle_webhook(&payload, &signature))).await;
+        let names: Vec<_> = outcomes
+            .into_iter()
+            .map(|outcome| outcome.unwrap().name())
+            .collect();
+        assert_eq!(names.iter().filter(|name| **name == "applied").count(), 1);
+        assert_eq!(names.iter().filter(|name| **name == "duplicate").count(), 4);
+
+        let payment = service.get_payment(payment.id).await.unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Refunded);
+        let recorded = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM payment_webhook_events")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(recorded, 5);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn webhooks_with_a_bad_signature_change_nothing(pool: PgPool) {
+        let owner = user("alice");
+        let booking_id = confirmed_booking(&pool, &owner).await;
+        let service = service(&pool, "");
+        let payment = service.pay_booking(&owner, booking_id).await.unwrap();
+
+        let (payload, _) = webhook(
+            "evt_1",
+            "payment.captured",
+            payment.provider_payment_id.as_deref().unwrap(),
+        );
+        let (_, other_signature) = webhook("evt_2", "payment.captured", "fake_pi_1");
+        let result = service.handle_webhook(&payload, &other_signature).await;
+        assert!(matches!(
+            result,
+            Err(ServiceError::PaymentProvider(
+                PaymentProviderError::InvalidSignature
+            ))
+        ));
+
+        let payment = service.get_payment(payment.id).await.unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Authorized);
+        let recorded = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM payment_webhook_events")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(recorded, 0);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn payments_are_rejected_while_disabled(pool: PgPool) {
+        let owner = user("alice");
+        let booking_id = confirmed_booking(&pool, &owner).await;
+        let payment = service(&pool, "")
+            .pay_booking(&owner, booking_id)
+            .await
+            .unwrap();
+        let disabled =
+            PaymentService::new(Arc::new(PostgresPaymentRepository::new(pool.clone())), None);
+
+        assert!(matches!(
+            disabled.pay_booking(&owner, booking_id).await,
+            Err(ServiceError::PaymentsDisabled)
+        ));
+        assert!(matches!(
+            disabled.capture_payment(payment.id).await,
+            Err(ServiceError::PaymentsDisabled)
+        ));
+        assert!(matches!(
+            disabled.refund_payment(payment.id).await,
+            Err(ServiceError::PaymentsDisabled)
+        ));
+        let (payload, signature) = webhook(
+            "evt_1",
+            "payment.captured",
+            payment.provider_payment_id.as_deref().unwrap(),
+        );
+        assert!(matches!(
+            disabled.handle_webhook(&payload, &signature).await,
+            Err(ServiceError::PaymentsDisabled)
+        ));
+
+        // Existing payments can still be read.
+        let payments = disabled.get_booking_payments(booking_id).await.unwrap();
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0].status, PaymentStatus::Authorized);
+    }
+}
@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+
+use crate::models::payment::{PaymentStatus, PaymentWebhookEvent};
+
+#[derive(Clone, Debug)]
+pub struct CreateIntent {
+    // Stable for a given payment, so a retried request doesn't charge twice.
+    pub idempotency_key: String,
+    pub amount_minor: i64,
+    pub currency: String,
+}
+
+// Where the provider says a payment ended up after a request.
+#[derive(Clone, Debug)]
+pub struct ProviderOutcome {
+    pub provider_payment_id: String,
+    pub status: PaymentStatus,
+    pub failure_reason: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentProviderError {
+    #[error("invalid webhook signature")]
+    InvalidSignature,
+    #[error("malformed webhook: {0}")]
+    MalformedWebhook(String),
+    #[error("payment provider request failed: {0}")]
+    Request(String),
+}
+
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Creates and authorizes a payment for the amount.
+    async fn create_intent(
+        &self,
+        intent: &CreateIntent,
+    ) -> Result<ProviderOutcome, PaymentProviderError>;
+
+    async fn capture(
+        &self,
+        provider_payment_id: &str,
+    ) -> Result<ProviderOutcome, PaymentProviderError>;
+
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        amount_minor: i64,
+    ) -> Result<ProviderOutcome, PaymentProviderError>;
+
+    // Checks that `payload` was sent by the provider and parses the event in it.
+    fn verify_webhook(
+        &self,
+        payload: &[u8],
+        signature: &str,
+    ) -> Result<PaymentWebhookEvent, PaymentProviderError>;
+}
@@ -0,0 +1,301 @@
+use chrono::Utc;
+use std::sync::Arc;
+
+use crate::models::pagination::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::models::place::{CreatePlaceInput, Place, UpdatePlaceInput};
+use crate::repository::place::PlaceRepository;
+use crate::repository::RepositoryError;
+use crate::schema::validation::ValidationErrors;
+use crate::service::ServiceError;
+
+pub const DEFAULT_MATCH_THRESHOLD: f32 = 0.6;
+
+#[derive(Clone, Copy, Debug)]
+pub struct BackfillOptions {
+    pub threshold: f32,
+    pub create_missing: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Debug)]
+pub enum BackfillOutcome {
+    Matched { place: Place, score: f32 },
+    Created { place: Place },
+    // Dry run with `create_missing`: a place named after the location would be
+    // created.
+    WouldCreate,
+    Unmatched,
+}
+
+#[derive(Debug)]
+pub struct BackfillEntry {
+    pub location: String,
+    pub outcome: BackfillOutcome,
+    pub tours_updated: u64,
+}
+
+#[derive(Clone)]
+pub struct PlaceService {
+    repository: Arc<dyn PlaceRepository>,
+}
+
+impl PlaceService {
+    pub fn new(repository: Arc<dyn PlaceRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn get_places(
+        &self,
+        query: Option<String>,
+        first: Option<usize>,
+    ) -> Result<Vec<Place>, ServiceError> {
+        let query = query
+            .as_deref()
+            .map(str::trim)
+            .filter(|query| !query.is_empty());
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+        Ok(self.repository.find(query, limit).await?)
+    }
+
+    pub async fn get_place(&self, id: i32) -> Result<Option<Place>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn get_places_by_ids(&self, ids: &[i32]) -> Result<Vec<Place>, ServiceError> {
+        Ok(self.repository.find_by_ids(ids).await?)
+    }
+
+    pub async fn get_place_by_slug(&self, slug: &str) -> Result<Option<Place>, ServiceError> {
+        Ok(self.repository.find_by_slug(slug).await?)
+    }
+
+    pub async fn create_place(&self, input: CreatePlaceInput) -> Result<Place, ServiceError> {
+        let input = input.normalize();
+        input.validate().map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        self.repository
+            .create(input, now)
+            .await
+            .map_err(map_slug_conflict)
+    }
+
+    pub async fn update_place(
+        &self,
+        id: i32,
+        input: UpdatePlaceInput,
+    ) -> Result<Place, ServiceError> {
+        let place = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let input = input.normalize();
+        input.validate(&place).map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        self.repository
+            .update(id, input, now)
+            .await
+            .map_err(map_slug_conflict)?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    pub async fn delete_place(&self, id: i32) -> Result<(), ServiceError> {
+        if self.repository.delete(id).await? {
+            Ok(())
+        } else {
+            Err(ServiceError::NotFound)
+        }
+    }
+
+    // Links tours that only have a free-text location to the closest existing
+    // place, optionally creating a place for locations that match nothing.
+    pub async fn backfill_tour_places(
+        &self,
+        options: BackfillOptions,
+    ) -> Result<Vec<BackfillEntry>, ServiceError> {
+        let mut entries = Vec::new();
+
+        // Locations come back trimmed, and tours are matched on their trimmed
+        // location so surrounding whitespace does not leave any behind.
+        for location in self.repository.find_unassigned_locations().await? {
+            let outcome = match self
+                .repository
+                .find_best_match(&location, options.threshold)
+                .await?
+            {
+                Some(place_match) => BackfillOutcome::Matched {
+                    place: place_match.place,
+                    score: place_match.score,
+                },
+                None if options.create_missing && options.dry_run => {
+                    let input = backfill_place_input(&location).normalize();
+                    match input.validate() {
+                        Ok(()) => BackfillOutcome::WouldCreate,
+                        Err(_) => BackfillOutcome::Unmatched,
+                    }
+                }
+                None if options.create_missing => {
+                    match self.create_place(backfill_place_input(&location)).await {
+                        Ok(place) => BackfillOutcome::Created { place },
+                        Err(ServiceError::Validation(_)) => BackfillOutcome::Unmatched,
+                        Err(error) => return Err(error),
+                    }
+                }
+                None => BackfillOutcome::Unmatched,
+            };
+
+            let tours_updated = match (&outcome, options.dry_run) {
+                (BackfillOutcome::Matched { place, .. }, false)
+                | (BackfillOutcome::Created { place }, false) => {
+                    let now = Utc::now();
+                    self.repository
+                        .assign_location(&location, place.id, now)
+                        .await?
+                }
+                _ => 0,
+            };
+
+            entries.push(BackfillEntry {
+                location,
+                outcome,
+                tours_updated,
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+fn backfill_place_input(location: &str) -> CreatePlaceInput {
+    CreatePlaceInput {
+        name: location.to_string(),
+        slug: None,
+        country_code: None,
+        region: None,
+        timezone: None,
+        latitude: None,
+        longitude: None,
+        aliases: Vec::new(),
+    }
+}
+
+fn map_slug_conflict(error: RepositoryError) -> ServiceError {
+    match error {
+        RepositoryError::Conflict(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("slug", "is already taken");
+            ServiceError::Validation(errors)
+        }
+        error => error.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::repository::place::postgres::PostgresPlaceRepository;
+
+    fn service(pool: &PgPool) -> PlaceService {
+        PlaceService::new(Arc::new(PostgresPlaceRepository::new(pool.clone())))
+    }
+
+    fn options(create_missing: bool, dry_run: bool) -> BackfillOptions {
+        BackfillOptions {
+            threshold: DEFAULT_MATCH_THRESHOLD,
+            create_missing,
+            dry_run,
+        }
+    }
+
+    async fn insert_tour(pool: &PgPool, slug: &str, location: &str) {
+        sqlx::query(
+            "INSERT INTO tours (title, slug, price_minor, location) VALUES ($1, $1, 1000, $2)",
+        )
+        .bind(slug)
+        .bind(location)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn unassigned_tours(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM tours WHERE place_id IS NULL")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn backfill_links_tours_whose_location_has_surrounding_whitespace(pool: PgPool) {
+        let service = service(&pool);
+        let lisbon = service
+            .create_place(backfill_place_input("Lisbon"))
+            .await
+            .unwrap();
+        insert_tour(&pool, "tidy", "Lisbon").await;
+        insert_tour(&pool, "padded", "  Lisbon ").await;
+
+        let entries = service
+            .backfill_tour_places(options(false, false))
+            .await
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].location, "Lisbon");
+        assert!(matches!(
+            &entries[0].outcome,
+            BackfillOutcome::Matched { place, .. } if place.id == lisbon.id
+        ));
+        assert_eq!(entries[0].tours_updated, 2);
+        assert_eq!(unassigned_tours(&pool).await, 0);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn backfill_dry_run_reports_places_it_would_create(pool: PgPool) {
+        let service = service(&pool);
+        insert_tour(&pool, "porto", "Porto").await;
+
+        let entries = service
+            .backfill_tour_places(options(true, true))
+            .await
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(entries[0].outcome, BackfillOutcome::WouldCreate));
+        assert_eq!(entries[0].tours_updated, 0);
+        assert!(service.get_places(None, None).await.unwrap().is_empty());
+        assert_eq!(unassigned_tours(&pool).await, 1);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn backfill_creates_missing_places_only_when_asked(pool: PgPool) {
+        let service = service(&pool);
+        insert_tour(&pool, "porto", " Porto").await;
+
+        let entries = service
+            .backfill_tour_places(options(false, false))
+            .await
+            .unwrap();
+        assert!(matches!(entries[0].outcome, BackfillOutcome::Unmatched));
+        assert_eq!(unassigned_tours(&pool).await, 1);
+
+        let entries = service
+            .backfill_tour_places(options(true, false))
+            .await
+            .unwrap();
+        assert!(matches!(
+            &entries[0].outcome,
+            BackfillOutcome::Created { place } if place.name == "Porto" && place.slug == "porto"
+        ));
+        assert_eq!(entries[0].tours_updated, 1);
+        assert_eq!(unassigned_tours(&pool).await, 0);
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+use crate::models::money::to_minor_units;
+use crate::models::pricing::{
+    validate_party, CreatePricingRuleInput, ParticipantsInput, PriceCategory, PricingRule, Quote,
+    TourPriceCategory, UpdatePricingRuleInput,
+};
+use crate::models::promotion::{normalize_code, PromotionError};
+use crate::repository::pricing::PricingRepository;
+use crate::repository::promotion::PromotionRepository;
+use crate::schema::validation::{validate_price, ValidationErrors};
+use crate::service::booking::map_promotion_error;
+use crate::service::schedule::map_tour_reference_error;
+use crate::service::ServiceError;
+
+#[derive(Clone)]
+pub struct PricingService {
+    repository: Arc<dyn PricingRepository>,
+    promotion_repository: Arc<dyn PromotionRepository>,
+}
+
+impl PricingService {
+    pub fn new(
+        repository: Arc<dyn PricingRepository>,
+        promotion_repository: Arc<dyn PromotionRepository>,
+    ) -> Self {
+        Self {
+            repository,
+            promotion_repository,
+        }
+    }
+
+    pub async fn get_tour_price_categories(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<TourPriceCategory>, ServiceError> {
+        Ok(self.repository.find_categories(tour_id).await?)
+    }
+
+    pub async fn set_tour_price_category(
+        &self,
+        tour_id: i32,
+        currency: &str,
+        category: PriceCategory,
+        price: Option<Decimal>,
+    ) -> Result<TourPriceCategory, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        if category == PriceCategory::Adult {
+            errors.add("category", "is priced by the departure or the tour");
+        }
+        if let Some(price) = price {
+            validate_price(&mut errors, "price", price, currency);
+        }
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        self.repository
+            .upsert_category(tour_id, category, to_minor_units(price, currency), now)
+            .await
+            .map_err(map_tour_reference_error)
+    }
+
+    pub async fn remove_tour_price_category(
+        &self,
+        tour_id: i32,
+        category: PriceCategory,
+    ) -> Result<(), ServiceError> {
+        if self.repository.delete_category(tour_id, category).await? {
+            Ok(())
+        } else {
+            Err(ServiceError::NotFound)
+        }
+    }
+
+    pub async fn get_tour_pricing_rules(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<PricingRule>, ServiceError> {
+        Ok(self.repository.find_rules(tour_id).await?)
+    }
+
+    pub async fn get_pricing_rule(&self, id: i32) -> Result<Option<PricingRule>, ServiceError> {
+        Ok(self.repository.find_rule(id).await?)
+    }
+
+    pub async fn create_pricing_rule(
+        &self,
+        tour_id: i32,
+        currency: &str,
+        input: CreatePricingRuleInput,
+    ) -> Result<PricingRule, ServiceError> {
+        let input = input.normalize();
+        input.validate(currency).map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        self.repository
+            .create_rule(tour_id, input, currency, now)
+            .await
+            .map_err(map_tour_reference_error)
+    }
+
+    pub async fn update_pricing_rule(
+        &self,
+        id: i32,
+        currency: &str,
+        input: UpdatePricingRuleInput,
+    ) -> Result<PricingRule, ServiceError> {
+        let mut rule = self
+            .repository
+            .find_rule(id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let input = input.normalize();
+        input.validate(currency).map_err(ServiceError::Validation)?;
+        input.apply(&mut rule, currency);
+
+        let now = Utc::now();
+        self.repository
+            .update_rule(&rule, now)
+            .await?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    pub async fn delete_pricing_rule(&self, id: i32) -> Result<(), ServiceError> {
+        if self.repository.delete_rule(id).await? {
+            Ok(())
+        } else {
+            Err(ServiceError::NotFound)
+        }
+    }
+
+    // Prices the party as booking the departure now would. Per-user limits of the
+    // promo code are only checked for a known `user_id`.
+    pub async fn quote(
+        &self,
+        departure_id: i32,
+        party: &[ParticipantsInput],
+        promo_code: Option<&str>,
+        user_id: Option<&str>,
+    ) -> Result<Quote, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        validate_party(&mut errors, "participants", party);
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        let price_list = self
+            .repository
+            .find_price_list(departure_id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+
+        let now = Utc::now();
+        let mut quote = price_list.quote(party, now).map_err(|error| {
+            let mut errors = ValidationErrors::new();
+            errors.add("participants", error.to_string());
+            ServiceError::Validation(errors)
+        })?;
+
+        if let Some(promo_code) = promo_code {
+            let promotion = self
+                .promotion_repository
+                .find_by_code(&normalize_code(promo_code))
+                .await?
+                .ok_or_else(|| map_promotion_error(PromotionError::Invalid))?;
+            let counts = self
+                .promotion_repository
+                .count_redemptions(promotion.id, user_id)
+                .await?;
+            promotion
+                .apply(&mut quote, counts, now)
+                .map_err(map_promotion_error)?;
+        }
+
+        Ok(quote)
+    }
+}
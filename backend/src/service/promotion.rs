@@ -0,0 +1,119 @@
+use chrono::Utc;
+use std::sync::Arc;
+
+use crate::models::pagination::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::models::promotion::{
+    CreatePromotionInput, Promotion, PromotionRedemption, UpdatePromotionInput,
+};
+use crate::repository::promotion::PromotionRepository;
+use crate::repository::RepositoryError;
+use crate::schema::validation::ValidationErrors;
+use crate::service::ServiceError;
+
+#[derive(Clone)]
+pub struct PromotionService {
+    repository: Arc<dyn PromotionRepository>,
+}
+
+impl PromotionService {
+    pub fn new(repository: Arc<dyn PromotionRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn get_promotions(&self) -> Result<Vec<Promotion>, ServiceError> {
+        Ok(self.repository.find_all().await?)
+    }
+
+    pub async fn get_promotion(&self, id: i32) -> Result<Option<Promotion>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn create_promotion(
+        &self,
+        input: CreatePromotionInput,
+    ) -> Result<Promotion, ServiceError> {
+        let input = input.normalize();
+        input.validate().map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        self.repository
+            .create(input, now)
+            .await
+            .map_err(map_write_error)
+    }
+
+    pub async fn update_promotion(
+        &self,
+        id: i32,
+        input: UpdatePromotionInput,
+    ) -> Result<Promotion, ServiceError> {
+        let mut promotion = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let input = input.normalize();
+        input
+            .validate(&promotion)
+            .map_err(ServiceError::Validation)?;
+        input.apply(&mut promotion);
+
+        let now = Utc::now();
+        self.repository
+            .update(&promotion, now)
+            .await
+            .map_err(map_write_error)?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    // Redeemed promotions are kept for reporting; deactivating them stops new
+    // redemptions instead.
+    pub async fn delete_promotion(&self, id: i32) -> Result<(), ServiceError> {
+        match self.repository.delete(id).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ServiceError::NotFound),
+            Err(RepositoryError::ForeignKey(_)) => Err(ServiceError::Conflict(
+                "Promotion has been redeemed, deactivate it instead".to_string(),
+            )),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    // Bookings with the promotion that aren't cancelled.
+    pub async fn get_redemption_count(&self, promotion_id: i32) -> Result<i64, ServiceError> {
+        Ok(self
+            .repository
+            .count_redemptions(promotion_id, None)
+            .await?
+            .total)
+    }
+
+    pub async fn get_redemptions(
+        &self,
+        promotion_id: i32,
+        first: Option<usize>,
+    ) -> Result<Vec<PromotionRedemption>, ServiceError> {
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+        Ok(self
+            .repository
+            .find_redemptions(promotion_id, limit)
+            .await?)
+    }
+}
+
+fn map_write_error(error: RepositoryError) -> ServiceError {
+    match error {
+        RepositoryError::Conflict(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("code", "is already taken");
+            ServiceError::Validation(errors)
+        }
+        RepositoryError::ForeignKey(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("tourIds", "must reference existing tours");
+            ServiceError::Validation(errors)
+        }
+        error => error.into(),
+    }
+}
@@ -0,0 +1,316 @@
+use chrono::{DateTime, Days, TimeDelta, Utc};
+use chrono_tz::Tz;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::models::recurrence::local_to_utc;
+use crate::models::schedule::{
+    validate_blackout_dates, BlackoutCalendar, BlackoutDate, BlackoutDateInput,
+    CreateDepartureScheduleInput, DepartureSchedule, MaterializableSchedule,
+    MaterializationSummary, UpdateDepartureScheduleInput,
+};
+use crate::repository::schedule::{ScheduleRepository, ScheduledDepartures};
+use crate::repository::RepositoryError;
+use crate::schema::validation::{validate_title, ValidationErrors};
+use crate::service::ServiceError;
+
+pub const DEFAULT_HORIZON_DAYS: u64 = 90;
+const MATERIALIZE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone)]
+pub struct ScheduleService {
+    repository: Arc<dyn ScheduleRepository>,
+    horizon_days: u64,
+}
+
+impl ScheduleService {
+    pub fn new(repository: Arc<dyn ScheduleRepository>, horizon_days: u64) -> Self {
+        Self {
+            repository,
+            horizon_days,
+        }
+    }
+
+    pub async fn get_tour_schedules(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<DepartureSchedule>, ServiceError> {
+        Ok(self.repository.find_by_tour(tour_id).await?)
+    }
+
+    pub async fn get_schedule(&self, id: i32) -> Result<Option<DepartureSchedule>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn create_schedule(
+        &self,
+        tour_id: i32,
+        currency: &str,
+        input: CreateDepartureScheduleInput,
+    ) -> Result<DepartureSchedule, ServiceError> {
+        let input = input.normalize();
+        input.validate(currency).map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        let schedule = self
+            .repository
+            .create(tour_id, input, currency, now)
+            .await
+            .map_err(map_tour_reference_error)?;
+        self.materialize(Some(&[tour_id])).await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn update_schedule(
+        &self,
+        id: i32,
+        currency: &str,
+        input: UpdateDepartureScheduleInput,
+    ) -> Result<DepartureSchedule, ServiceError> {
+        let mut schedule = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let input = input.normalize();
+        input
+            .validate(&schedule, currency)
+            .map_err(ServiceError::Validation)?;
+        input.apply(&mut schedule, currency);
+
+        let now = Utc::now();
+        let schedule = self
+            .repository
+            .update(&schedule, now)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        self.materialize(Some(&[schedule.tour_id])).await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn delete_schedule(&self, id: i32) -> Result<(), ServiceError> {
+        let now = Utc::now();
+        if self.repository.delete(id, now).await? {
+            Ok(())
+        } else {
+            Err(ServiceError::NotFound)
+        }
+    }
+
+    pub async fn get_calendars(&self) -> Result<Vec<BlackoutCalendar>, ServiceError> {
+        Ok(self.repository.find_calendars().await?)
+    }
+
+    pub async fn get_calendar(&self, id: i32) -> Result<Option<BlackoutCalendar>, ServiceError> {
+        Ok(self.repository.find_calendar(id).await?)
+    }
+
+    pub async fn get_tour_calendars(
+        &self,
+        tour_id: i32,
+    ) -> Result<Vec<BlackoutCalendar>, ServiceError> {
+        Ok(self.repository.find_calendars_by_tour(tour_id).await?)
+    }
+
+    pub async fn get_blackout_dates(
+        &self,
+        calendar_id: i32,
+    ) -> Result<Vec<BlackoutDate>, ServiceError> {
+        Ok(self.repository.find_blackout_dates(calendar_id).await?)
+    }
+
+    pub async fn get_blackout_date(&self, id: i32) -> Result<Option<BlackoutDate>, ServiceError> {
+        Ok(self.repository.find_blackout_date(id).await?)
+    }
+
+    pub async fn create_calendar(&self, name: &str) -> Result<BlackoutCalendar, ServiceError> {
+        let name = name.trim();
+        let mut errors = ValidationErrors::new();
+        validate_title(&mut errors, "name", name);
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        let now = Utc::now();
+        self.repository
+            .create_calendar(name, now)
+            .await
+            .map_err(|error| match error {
+                RepositoryError::Conflict(_) => {
+                    let mut errors = ValidationErrors::new();
+                    errors.add("name", "is already taken");
+                    ServiceError::Validation(errors)
+                }
+                error => error.into(),
+            })
+    }
+
+    pub async fn delete_calendar(&self, id: i32) -> Result<(), ServiceError> {
+        let tour_ids = self.repository.find_tour_ids_by_calendar(id).await?;
+        if !self.repository.delete_calendar(id).await? {
+            return Err(ServiceError::NotFound);
+        }
+        self.materialize(Some(&tour_ids)).await?;
+
+        Ok(())
+    }
+
+    pub async fn add_blackout_dates(
+        &self,
+        calendar_id: i32,
+        dates: Vec<BlackoutDateInput>,
+    ) -> Result<Vec<BlackoutDate>, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        validate_blackout_dates(&mut errors, &dates);
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        let dates = self
+            .repository
+            .add_blackout_dates(calendar_id, &dates)
+            .await
+            .map_err(|error| match error {
+                RepositoryError::ForeignKey(_) => ServiceError::NotFound,
+                error => error.into(),
+            })?;
+        let tour_ids = self
+            .repository
+            .find_tour_ids_by_calendar(calendar_id)
+            .await?;
+        self.materialize(Some(&tour_ids)).await?;
+
+        Ok(dates)
+    }
+
+    pub async fn remove_blackout_date(&self, id: i32) -> Result<BlackoutDate, ServiceError> {
+        let date = self
+            .repository
+            .remove_blackout_date(id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        let tour_ids = self
+            .repository
+            .find_tour_ids_by_calendar(date.calendar_id)
+            .await?;
+        self.materialize(Some(&tour_ids)).await?;
+
+        Ok(date)
+    }
+
+    pub async fn set_tour_calendars(
+        &self,
+        tour_id: i32,
+        calendar_ids: &[i32],
+    ) -> Result<Vec<BlackoutCalendar>, ServiceError> {
+        self.repository
+            .set_tour_calendars(tour_id, calendar_ids)
+            .await
+            .map_err(|error| match error {
+                RepositoryError::ForeignKey(constraint)
+                    if constraint.ends_with("calendar_id_fkey") =>
+                {
+                    let mut errors = ValidationErrors::new();
+                    errors.add("calendarIds", "must reference existing calendars");
+                    ServiceError::Validation(errors)
+                }
+                error => map_tour_reference_error(error),
+            })?;
+        self.materialize(Some(&[tour_id])).await?;
+
+        self.get_tour_calendars(tour_id).await
+    }
+
+    // Expands the schedules of `tour_ids`, or of every tour, into departures from
+    // now until the end of the horizon.
+    pub async fn materialize(
+        &self,
+        tour_ids: Option<&[i32]>,
+    ) -> Result<MaterializationSummary, ServiceError> {
+        let schedules = self.repository.find_materializable(tour_ids).await?;
+        let now = Utc::now();
+
+        let mut summary = MaterializationSummary::default();
+        for schedule in &schedules {
+            summary += self.materialize_schedule(schedule, now).await?;
+        }
+
+        Ok(summary)
+    }
+
+    async fn materialize_schedule(
+        &self,
+        materializable: &MaterializableSchedule,
+        now: DateTime<Utc>,
+    ) -> Result<MaterializationSummary, ServiceError> {
+        let schedule = &materializable.schedule;
+        let timezone = materializable.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+        let until = now + TimeDelta::days(self.horizon_days as i64);
+
+        // Local dates are widened by a day on each side so every departure in the
+        // UTC window is considered whatever the offset.
+        let first_date = now.with_timezone(&timezone).date_naive() - Days::new(1);
+        let last_date = until.with_timezone(&timezone).date_naive() + Days::new(1);
+        let blackout_dates = self
+            .repository
+            .find_tour_blackout_dates(schedule.tour_id, first_date, last_date)
+            .await?;
+        let duration = TimeDelta::minutes(i64::from(schedule.duration_minutes));
+
+        let departures = match (schedule.is_active, schedule.recurrence()) {
+            (true, Some(recurrence)) => recurrence
+                .occurrences(schedule.starts_on, first_date, last_date)
+                .into_iter()
+                .filter(|date| !schedule.exception_dates.contains(date))
+                .filter(|date| {
+                    !blackout_dates
+                        .iter()
+                        .any(|blackout| blackout.contains(*date))
+                })
+                .map(|date| {
+                    let starts_at = local_to_utc(date, schedule.start_time, timezone);
+                    (starts_at, starts_at + duration)
+                })
+                .filter(|(starts_at, _)| *starts_at > now && *starts_at <= until)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(self
+            .repository
+            .reconcile(
+                ScheduledDepartures {
+                    schedule,
+                    from: now,
+                    until,
+                    departures,
+                },
+                now,
+            )
+            .await?)
+    }
+
+    pub fn spawn_materializer(&self) {
+        let schedule_service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MATERIALIZE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match schedule_service.materialize(None).await {
+                    Ok(summary) => info!(
+                        "Materialized departures: {} created, {} updated, {} removed",
+                        summary.created, summary.updated, summary.removed
+                    ),
+                    Err(error) => error!("Failed to materialize departures: {}", error),
+                }
+            }
+        });
+    }
+}
+
+pub(crate) fn map_tour_reference_error(error: RepositoryError) -> ServiceError {
+    match error {
+        RepositoryError::ForeignKey(_) => ServiceError::NotFound,
+        error => error.into(),
+    }
+}
@@ -0,0 +1,266 @@
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::geo::{
+    validate_latitude, validate_longitude, BoundingBox, NearbyTour, MAX_RADIUS_KM,
+};
+use crate::models::pagination::{Page, PageRequest, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::models::search::{validate_language, Suggestion, SuggestionKind, TourSearchResult};
+use crate::models::tour::{
+    CreateTourInput, Tour, TourCursor, TourFilter, TourSort, UpdateTourInput,
+};
+use crate::models::user::CurrentUser;
+use crate::repository::tour::TourRepository;
+use crate::repository::RepositoryError;
+use crate::schema::validation::ValidationErrors;
+use crate::service::cache::TtlCache;
+use crate::service::ServiceError;
+
+const DEFAULT_SUGGESTION_LIMIT: usize = 10;
+const MAX_SUGGESTION_LIMIT: usize = 25;
+const SUGGESTION_CACHE_TTL: Duration = Duration::from_secs(30);
+const SUGGESTION_CACHE_CAPACITY: usize = 1024;
+
+type SuggestionKey = (String, Option<SuggestionKind>, usize);
+
+#[derive(Clone)]
+pub struct TourService {
+    repository: Arc<dyn TourRepository>,
+    suggestion_cache: Arc<TtlCache<SuggestionKey, Vec<Suggestion>>>,
+}
+
+impl TourService {
+    pub fn new(repository: Arc<dyn TourRepository>) -> Self {
+        Self {
+            repository,
+            suggestion_cache: Arc::new(TtlCache::new(
+                SUGGESTION_CACHE_TTL,
+                SUGGESTION_CACHE_CAPACITY,
+            )),
+        }
+    }
+
+    pub async fn get_tours(&self, user: Option<&CurrentUser>) -> Result<Vec<Tour>, ServiceError> {
+        let tours = self.repository.find_all().await?;
+
+        Ok(tours
+            .into_iter()
+            .filter(|tour| tour.is_listed_for(user))
+            .collect())
+    }
+
+    pub async fn get_tours_page(
+        &self,
+        user: Option<&CurrentUser>,
+        mut filter: TourFilter,
+        sort: TourSort,
+        page_request: PageRequest<TourCursor>,
+    ) -> Result<Page<Tour>, ServiceError> {
+        filter.validate().map_err(ServiceError::Validation)?;
+        filter
+            .restrict_to_listed(user)
+            .map_err(ServiceError::Validation)?;
+
+        let cursors = [&page_request.after, &page_request.before];
+        if cursors
+            .into_iter()
+            .flatten()
+            .any(|cursor| cursor.sort != sort)
+        {
+            let mut errors = ValidationErrors::new();
+            errors.add("cursor", "does not match the requested sort order");
+            return Err(ServiceError::Validation(errors));
+        }
+
+        Ok(self
+            .repository
+            .find_page(&filter, sort, &page_request)
+            .await?)
+    }
+
+    pub async fn search_tours(
+        &self,
+        user: Option<&CurrentUser>,
+        query: &str,
+        language: Option<&str>,
+        first: Option<usize>,
+    ) -> Result<Vec<TourSearchResult>, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        if query.trim().is_empty() {
+            errors.add("query", "must not be empty");
+        }
+        if let Some(language) = language {
+            validate_language(&mut errors, "language", language);
+        }
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        let include_inactive = user.is_some_and(|user| user.is_admin);
+        Ok(self
+            .repository
+            .search(query, language, include_inactive, limit)
+            .await?)
+    }
+
+    pub async fn suggest(
+        &self,
+        prefix: &str,
+        kind: Option<SuggestionKind>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Suggestion>, ServiceError> {
+        let prefix = prefix.split_whitespace().collect::<Vec<_>>().join(" ");
+        if prefix.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let limit = limit
+            .unwrap_or(DEFAULT_SUGGESTION_LIMIT)
+            .min(MAX_SUGGESTION_LIMIT);
+        let key = (prefix.to_lowercase(), kind, limit);
+        if let Some(suggestions) = self.suggestion_cache.get(&key) {
+            return Ok(suggestions);
+        }
+
+        let suggestions = self.repository.suggest(&prefix, kind, limit).await?;
+        self.suggestion_cache.insert(key, suggestions.clone());
+
+        Ok(suggestions)
+    }
+
+    pub async fn get_nearby_tours(
+        &self,
+        user: Option<&CurrentUser>,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        mut filter: TourFilter,
+        first: Option<usize>,
+    ) -> Result<Vec<NearbyTour>, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        validate_latitude(&mut errors, "lat", latitude);
+        validate_longitude(&mut errors, "lng", longitude);
+        if !(radius_km > 0.0 && radius_km <= MAX_RADIUS_KM) {
+            errors.add(
+                "radiusKm",
+                format!("must be between 0 and {}", MAX_RADIUS_KM),
+            );
+        }
+        errors.into_result().map_err(ServiceError::Validation)?;
+        filter.validate().map_err(ServiceError::Validation)?;
+        filter
+            .restrict_to_listed(user)
+            .map_err(ServiceError::Validation)?;
+
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        Ok(self
+            .repository
+            .find_nearby(latitude, longitude, radius_km, &filter, limit)
+            .await?)
+    }
+
+    pub async fn get_tours_in_bounding_box(
+        &self,
+        user: Option<&CurrentUser>,
+        bounding_box: BoundingBox,
+        mut filter: TourFilter,
+        first: Option<usize>,
+    ) -> Result<Vec<Tour>, ServiceError> {
+        bounding_box.validate().map_err(ServiceError::Validation)?;
+        filter.validate().map_err(ServiceError::Validation)?;
+        filter
+            .restrict_to_listed(user)
+            .map_err(ServiceError::Validation)?;
+
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        Ok(self
+            .repository
+            .find_in_bounding_box(&bounding_box, &filter, limit)
+            .await?)
+    }
+
+    pub async fn get_tour(&self, id: i32) -> Result<Option<Tour>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn get_tours_by_ids(&self, ids: &[i32]) -> Result<Vec<Tour>, ServiceError> {
+        Ok(self.repository.find_by_ids(ids).await?)
+    }
+
+    pub async fn get_tour_by_slug(&self, slug: &str) -> Result<Option<Tour>, ServiceError> {
+        Ok(self.repository.find_by_slug(slug).await?)
+    }
+
+    pub async fn create_tour(&self, mut input: CreateTourInput) -> Result<Tour, ServiceError> {
+        input.validate().map_err(ServiceError::Validation)?;
+        if input.slug.is_none() {
+            input.slug = Some(self.generate_slug(&input.title).await?);
+        }
+
+        let now = Utc::now();
+        self.repository
+            .create(input, now)
+            .await
+            .map_err(map_write_error)
+    }
+
+    pub async fn update_tour(&self, id: i32, input: UpdateTourInput) -> Result<Tour, ServiceError> {
+        let tour = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or(ServiceError::NotFound)?;
+        input.validate(&tour).map_err(ServiceError::Validation)?;
+        let input = input.normalize(&tour);
+        let changes_currency = input
+            .currency
+            .as_ref()
+            .is_some_and(|currency| *currency != tour.currency);
+        if changes_currency && self.repository.has_price_overrides(id).await? {
+            return Err(ServiceError::Conflict(
+                "Departure, schedule, category and pricing rule amounts must be cleared before changing the currency"
+                    .to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        self.repository
+            .update(id, input, now)
+            .await
+            .map_err(map_write_error)?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    // Titles are not unique, so repeated titles get a numeric suffix.
+    async fn generate_slug(&self, title: &str) -> Result<String, ServiceError> {
+        let base = match slug::slugify(title) {
+            slug if slug.is_empty() => "tour".to_string(),
+            slug => slug,
+        };
+
+        let mut slug = base.clone();
+        let mut suffix = 2;
+        while self.repository.find_by_slug(&slug).await?.is_some() {
+            slug = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        Ok(slug)
+    }
+}
+
+fn map_write_error(error: RepositoryError) -> ServiceError {
+    match error {
+        RepositoryError::Conflict(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("slug", "is already taken");
+            ServiceError::Validation(errors)
+        }
+        RepositoryError::ForeignKey(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("placeId", "must reference an existing place");
+            ServiceError::Validation(errors)
+        }
+        error => error.into(),
+    }
+}
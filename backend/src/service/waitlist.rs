@@ -0,0 +1,357 @@
+use chrono::{TimeDelta, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::models::booking::{validate_participants, Booking};
+use crate::models::pagination::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::models::pricing::{validate_party, ParticipantsInput};
+use crate::models::promotion::normalize_code;
+use crate::models::user::CurrentUser;
+use crate::models::waitlist::{WaitlistEntry, WaitlistStatus};
+use crate::repository::booking::BookingError;
+use crate::repository::waitlist::{WaitlistError, WaitlistRepository};
+use crate::repository::RepositoryError;
+use crate::schema::validation::ValidationErrors;
+use crate::service::booking::map_booking_error;
+use crate::service::ServiceError;
+
+pub const DEFAULT_OFFER_TTL_MINUTES: u64 = 60;
+pub const DEFAULT_CLAIM_URL: &str = "http://localhost:3000/waitlist/claim";
+const PROMOTE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct WaitlistService {
+    repository: Arc<dyn WaitlistRepository>,
+    offer_ttl_minutes: u64,
+    claim_url: String,
+}
+
+impl WaitlistService {
+    pub fn new(
+        repository: Arc<dyn WaitlistRepository>,
+        offer_ttl_minutes: u64,
+        claim_url: String,
+    ) -> Self {
+        Self {
+            repository,
+            offer_ttl_minutes,
+            claim_url,
+        }
+    }
+
+    pub async fn get_waitlist_entry(&self, id: i32) -> Result<Option<WaitlistEntry>, ServiceError> {
+        Ok(self.repository.find_by_id(id).await?)
+    }
+
+    pub async fn get_user_waitlist_entries(
+        &self,
+        user: &CurrentUser,
+        first: Option<usize>,
+    ) -> Result<Vec<WaitlistEntry>, ServiceError> {
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+        Ok(self.repository.find_by_user(&user.id, limit).await?)
+    }
+
+    pub async fn get_position(&self, entry: &WaitlistEntry) -> Result<Option<i64>, ServiceError> {
+        if entry.status != WaitlistStatus::Waiting {
+            return Ok(None);
+        }
+
+        Ok(self.repository.find_position(entry.id).await?)
+    }
+
+    // The link sent with an offer; only set while the offer is outstanding.
+    pub fn claim_url(&self, entry: &WaitlistEntry) -> Option<String> {
+        entry
+            .claim_token
+            .as_ref()
+            .filter(|_| entry.status == WaitlistStatus::Offered)
+            .map(|claim_token| format!("{}?token={}", self.claim_url, claim_token))
+    }
+
+    pub async fn join_waitlist(
+        &self,
+        user: &CurrentUser,
+        departure_id: i32,
+        participants: i32,
+    ) -> Result<WaitlistEntry, ServiceError> {
+        let mut errors = ValidationErrors::new();
+        validate_participants(&mut errors, "participants", participants);
+        errors.into_result().map_err(ServiceError::Validation)?;
+
+        self.repository
+            .join(departure_id, &user.id, participants, Utc::now())
+            .await
+            .map_err(map_waitlist_error)
+    }
+
+    // Entries of other users are reported as missing, like bookings.
+    pub async fn leave_waitlist(
+        &self,
+        user: &CurrentUser,
+        id: i32,
+    ) -> Result<WaitlistEntry, ServiceError> {
+        self.repository
+            .find_by_id(id)
+            .await?
+            .filter(|entry| entry.user_id == user.id || user.is_admin)
+            .ok_or(ServiceError::NotFound)?;
+
+        let entry = self
+            .repository
+            .leave(id, Utc::now())
+            .await?
+            .ok_or_else(|| {
+                ServiceError::Conflict("Waitlist entry is no longer open".to_string())
+            })?;
+        self.promote(entry.departure_id).await?;
+
+        Ok(entry)
+    }
+
+    // Only the user the offer was made to can claim it, even with the token.
+    pub async fn claim_offer(
+        &self,
+        user: &CurrentUser,
+        claim_token: &str,
+        party: Option<Vec<ParticipantsInput>>,
+        promo_code: Option<String>,
+    ) -> Result<Booking, ServiceError> {
+        if let Some(party) = &party {
+            let mut errors = ValidationErrors::new();
+            validate_party(&mut errors, "participants", party);
+            errors.into_result().map_err(ServiceError::Validation)?;
+        }
+
+        let entry = self
+            .repository
+            .find_by_claim_token(claim_token)
+            .await?
+            .filter(|entry| entry.user_id == user.id)
+            .ok_or(ServiceError::NotFound)?;
+
+        self.repository
+            .claim(
+                entry.id,
+                party.as_deref(),
+                promo_code.map(|code| normalize_code(&code)).as_deref(),
+                Utc::now(),
+            )
+            .await
+            .map_err(map_waitlist_error)
+    }
+
+    pub async fn promote(&self, departure_id: i32) -> Result<Vec<WaitlistEntry>, ServiceError> {
+        let now = Utc::now();
+        let offer_expires_at = now + TimeDelta::minutes(self.offer_ttl_minutes as i64);
+
+        self.repository
+            .promote(departure_id, offer_expires_at, now)
+            .await
+            .map_err(map_waitlist_error)
+    }
+
+    // Expires unclaimed offers, then offers any free seats to the next entries, so
+    // an offer that runs out cascades to whoever is waiting behind it.
+    pub async fn run_promotions(&self) -> Result<usize, ServiceError> {
+        let now = Utc::now();
+        self.repository.expire_offers(now).await?;
+
+        let mut offered = 0;
+        for departure_id in self.repository.find_departures_with_waiting(now).await? {
+            offered += self.promote(departure_id).await?.len();
+        }
+
+        Ok(offered)
+    }
+
+    pub fn spawn_promoter(&self) {
+        let waitlist_service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PROMOTE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match waitlist_service.run_promotions().await {
+                    Ok(0) => {}
+                    Ok(offered) => info!("Offered seats to {} waitlisted users", offered),
+                    Err(error) => error!("Failed to promote waitlisted users: {}", error),
+                }
+            }
+        });
+    }
+}
+
+fn map_waitlist_error(error: WaitlistError) -> ServiceError {
+    match error {
+        WaitlistError::SeatsAvailable => {
+            ServiceError::Conflict("Seats are available, book the departure instead".to_string())
+        }
+        WaitlistError::OfferInactive => {
+            ServiceError::Conflict("Offer has expired or was already claimed".to_string())
+        }
+        WaitlistError::Booking(BookingError::Repository(RepositoryError::Conflict(_))) => {
+            ServiceError::Conflict("Already on the waitlist for this departure".to_string())
+        }
+        WaitlistError::Booking(error) => map_booking_error(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::models::pricing::adults;
+    use crate::repository::booking::postgres::PostgresBookingRepository;
+    use crate::repository::waitlist::postgres::PostgresWaitlistRepository;
+    use crate::service::booking::BookingService;
+    use crate::test_support::{insert_departure, user};
+
+    fn services(pool: &PgPool) -> (BookingService, WaitlistService) {
+        (
+            BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 15),
+            WaitlistService::new(
+                Arc::new(PostgresWaitlistRepository::new(pool.clone())),
+                60,
+                "http://localhost/claim".to_string(),
+            ),
+        )
+    }
+
+    // A departure with `capacity` seats, all of them booked by one user.
+    async fn sold_out_departure(
+        pool: &PgPool,
+        bookings: &BookingService,
+        capacity: i32,
+    ) -> (i32, i32) {
+        let departure_id = insert_departure(pool, 1000, capacity).await;
+        let booking = bookings
+            .book_tour(&user("booker"), departure_id, adults(capacity), None)
+            .await
+            .unwrap();
+
+        (departure_id, booking.id)
+    }
+
+    async fn status(waitlist: &WaitlistService, entry: &WaitlistEntry) -> WaitlistStatus {
+        waitlist
+            .get_waitlist_entry(entry.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .status
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn freed_seats_are_offered_in_order_skipping_parties_too_large(pool: PgPool) {
+        let (bookings, waitlist) = services(&pool);
+        let (departure_id, booking_id) = sold_out_departure(&pool, &bookings, 3).await;
+        let mut entries = Vec::new();
+        for (user_id, participants) in [("first", 2), ("large", 3), ("second", 1), ("third", 1)] {
+            entries.push(
+                waitlist
+                    .join_waitlist(&user(user_id), departure_id, participants)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        bookings
+            .cancel_booking(&user("booker"), booking_id)
+            .await
+            .unwrap();
+        let offered = waitlist.promote(departure_id).await.unwrap();
+
+        let offered_users: Vec<&str> = offered.iter().map(|entry| entry.user_id.as_str()).collect();
+        assert_eq!(offered_users, ["first", "second"]);
+        assert!(offered
+            .iter()
+            .all(|entry| waitlist.claim_url(entry).is_some()));
+        assert_eq!(
+            status(&waitlist, &entries[1]).await,
+            WaitlistStatus::Waiting
+        );
+        assert_eq!(
+            status(&waitlist, &entries[3]).await,
+            WaitlistStatus::Waiting
+        );
+        assert_eq!(waitlist.get_position(&entries[1]).await.unwrap(), Some(1));
+        assert!(waitlist.promote(departure_id).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn expired_offers_cascade_to_the_next_entry(pool: PgPool) {
+        let (bookings, waitlist) = services(&pool);
+        let (departure_id, booking_id) = sold_out_departure(&pool, &bookings, 2).await;
+        let first = waitlist
+            .join_waitlist(&user("first"), departure_id, 2)
+            .await
+            .unwrap();
+        let second = waitlist
+            .join_waitlist(&user("second"), departure_id, 2)
+            .await
+            .unwrap();
+        bookings
+            .cancel_booking(&user("booker"), booking_id)
+            .await
+            .unwrap();
+        let offer = waitlist.promote(departure_id).await.unwrap().remove(0);
+        assert_eq!(offer.id, first.id);
+
+        // Let the offer and the seats held for it run out.
+        sqlx::query(
+            "UPDATE waitlist_entries SET offer_expires_at = now() - interval '1 second' WHERE id = $1",
+        )
+        .bind(first.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE seat_holds SET expires_at = now() - interval '1 second' WHERE id = $1")
+            .bind(offer.hold_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(waitlist.run_promotions().await.unwrap(), 1);
+
+        assert_eq!(status(&waitlist, &first).await, WaitlistStatus::Expired);
+        assert_eq!(status(&waitlist, &second).await, WaitlistStatus::Offered);
+        let expired_claim = waitlist
+            .claim_offer(
+                &user("first"),
+                offer.claim_token.as_deref().unwrap(),
+                None,
+                None,
+            )
+            .await;
+        assert!(
+            matches!(&expired_claim, Err(ServiceError::Conflict(message)) if message == "Offer has expired or was already claimed"),
+            "{:?}",
+            expired_claim
+        );
+
+        let claim_token = waitlist
+            .get_waitlist_entry(second.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .claim_token
+            .unwrap();
+        assert!(matches!(
+            waitlist
+                .claim_offer(&user("first"), &claim_token, None, None)
+                .await,
+            Err(ServiceError::NotFound)
+        ));
+        let booking = waitlist
+            .claim_offer(&user("second"), &claim_token, None, None)
+            .await
+            .unwrap();
+        assert_eq!(booking.participants, 2);
+        assert_eq!(status(&waitlist, &second).await, WaitlistStatus::Claimed);
+    }
+}
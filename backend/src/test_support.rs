@@ -0,0 +1,151 @@
+use actix_web::web;
+use async_graphql::Schema;
+use chrono::{TimeZone, Utc};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::sync::Arc;
+use url::Url;
+
+use crate::config::ApplicationData;
+use crate::models::tour::Tour;
+use crate::models::user::CurrentUser;
+use crate::repository::booking::postgres::PostgresBookingRepository;
+use crate::repository::departure::postgres::PostgresDepartureRepository;
+use crate::repository::exchange_rate::postgres::PostgresExchangeRateRepository;
+use crate::repository::payment::postgres::PostgresPaymentRepository;
+use crate::repository::place::postgres::PostgresPlaceRepository;
+use crate::repository::pricing::postgres::PostgresPricingRepository;
+use crate::repository::promotion::postgres::PostgresPromotionRepository;
+use crate::repository::schedule::postgres::PostgresScheduleRepository;
+use crate::repository::tour::TourRepository;
+use crate::repository::waitlist::postgres::PostgresWaitlistRepository;
+use crate::schema::graphql::{ApplicationSchema, MutationRoot, QueryRoot, SubscriptionRoot};
+use crate::service::booking::BookingService;
+use crate::service::departure::DepartureService;
+use crate::service::events::EventService;
+use crate::service::exchange_rate::ExchangeRateService;
+use crate::service::oauth::OAuthService;
+use crate::service::payment::fake::FakePaymentProvider;
+use crate::service::payment::PaymentService;
+use crate::service::place::PlaceService;
+use crate::service::pricing::PricingService;
+use crate::service::promotion::PromotionService;
+use crate::service::schedule::ScheduleService;
+use crate::service::tour::TourService;
+use crate::service::waitlist::WaitlistService;
+
+// Everything but tours is backed by a pool that never connects, so tests built
+// on this fail loudly if they reach the database.
+pub fn application_data(tour_repository: Arc<dyn TourRepository>) -> web::Data<ApplicationData> {
+    let unused_url = Url::parse("http://localhost/unused/").unwrap();
+    application_data_with_oauth(tour_repository, OAuthService::stub(&unused_url))
+}
+
+pub fn application_data_with_oauth(
+    tour_repository: Arc<dyn TourRepository>,
+    oauth_service: OAuthService,
+) -> web::Data<ApplicationData> {
+    let pool = PgPoolOptions::new()
+        .connect_lazy("postgres://localhost:1/unused")
+        .unwrap();
+    let promotion_repository = Arc::new(PostgresPromotionRepository::new(pool.clone()));
+
+    web::Data::new(ApplicationData::new(
+        TourService::new(tour_repository),
+        PlaceService::new(Arc::new(PostgresPlaceRepository::new(pool.clone()))),
+        DepartureService::new(Arc::new(PostgresDepartureRepository::new(pool.clone()))),
+        ScheduleService::new(Arc::new(PostgresScheduleRepository::new(pool.clone())), 1),
+        BookingService::new(Arc::new(PostgresBookingRepository::new(pool.clone())), 1),
+        WaitlistService::new(
+            Arc::new(PostgresWaitlistRepository::new(pool.clone())),
+            1,
+            "http://localhost/claim".to_string(),
+        ),
+        PaymentService::new(
+            Arc::new(PostgresPaymentRepository::new(pool.clone())),
+            Some(Arc::new(
+                FakePaymentProvider::new("test-secret".to_string(), "").unwrap(),
+            )),
+        ),
+        ExchangeRateService::new(Arc::new(PostgresExchangeRateRepository::new(pool.clone()))),
+        PricingService::new(
+            Arc::new(PostgresPricingRepository::new(pool)),
+            promotion_repository.clone(),
+        ),
+        PromotionService::new(promotion_repository),
+        EventService::new(),
+        oauth_service,
+    ))
+}
+
+pub fn schema(application_data: web::Data<ApplicationData>) -> ApplicationSchema {
+    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(application_data)
+        .finish()
+}
+
+pub fn admin() -> CurrentUser {
+    CurrentUser {
+        id: "admin-id".to_string(),
+        is_admin: true,
+    }
+}
+
+pub fn tour(id: i32, title: &str, price_minor: i64) -> Tour {
+    let created_at =
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::hours(id.into());
+
+    Tour {
+        id,
+        title: title.to_string(),
+        slug: slug::slugify(title),
+        description: None,
+        start_date: None,
+        end_date: None,
+        price_minor: Some(price_minor),
+        currency: "USD".to_string(),
+        rating: None,
+        created_at: Some(created_at),
+        updated_at: Some(created_at),
+        location: Some("Lisbon".to_string()),
+        latitude: None,
+        longitude: None,
+        image_url: None,
+        is_active: true,
+        max_participants: Some(10),
+        language: "english".to_string(),
+        place_id: None,
+        timezone: "UTC".to_string(),
+    }
+}
+
+pub fn user(id: &str) -> CurrentUser {
+    CurrentUser {
+        id: id.to_string(),
+        is_admin: false,
+    }
+}
+
+// A tour priced at `price_minor` USD with one departure a week from now.
+pub async fn insert_departure(pool: &PgPool, price_minor: i64, capacity: i32) -> i32 {
+    let tour_id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO tours (title, slug, price_minor) VALUES ('Old Lisbon', 'old-lisbon', $1) RETURNING id",
+    )
+    .bind(price_minor)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO departures (tour_id, starts_at, ends_at, capacity)
+        VALUES ($1, now() + interval '7 days', now() + interval '7 days 2 hours', $2)
+        RETURNING id
+        "#,
+    )
+    .bind(tour_id)
+    .bind(capacity)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}